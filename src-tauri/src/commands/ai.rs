@@ -5,10 +5,11 @@ use crate::error::AppError;
 
 #[tauri::command]
 pub async fn ai_chat(
+    app: AppHandle,
     state: State<'_, AppState>,
     request: AiChatRequest,
 ) -> Result<AiChatResponse, AppError> {
-    state.ai_service.chat(request).await
+    state.ai_service.chat(&app, request).await
 }
 
 #[tauri::command]
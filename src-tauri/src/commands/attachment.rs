@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use tauri_plugin_opener::OpenerExt;
+use crate::state::AppState;
+use crate::models::attachment::Attachment;
+use crate::error::AppError;
+use super::todo::run_db;
+
+#[tauri::command]
+pub async fn add_attachment(
+    state: State<'_, AppState>,
+    todo_id: String,
+    path: String,
+) -> Result<Attachment, AppError> {
+    let service = state.attachment_service.clone();
+
+    run_db(move || service.add(&todo_id, &PathBuf::from(path))).await
+}
+
+#[tauri::command]
+pub async fn get_todo_attachments(
+    state: State<'_, AppState>,
+    todo_id: String,
+) -> Result<Vec<Attachment>, AppError> {
+    let service = state.attachment_service.clone();
+
+    run_db(move || service.list(&todo_id)).await
+}
+
+#[tauri::command]
+pub async fn remove_attachment(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), AppError> {
+    let service = state.attachment_service.clone();
+
+    run_db(move || service.remove(&id)).await
+}
+
+/// 用系统默认程序打开附件
+#[tauri::command]
+pub async fn open_attachment(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), AppError> {
+    let service = state.attachment_service.clone();
+    let path = run_db(move || service.open_path(&id)).await?;
+
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError::OpenFailed(e.to_string()))
+}
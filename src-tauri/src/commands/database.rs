@@ -0,0 +1,49 @@
+use tauri::{AppHandle, Emitter, State};
+use crate::state::AppState;
+use crate::db::MigrationReport;
+use crate::models::backup::{BackupInfo, BackupKind};
+use crate::error::AppError;
+use super::todo::run_db;
+
+/// 返回数据库 schema 版本与待执行迁移（dry-run，不修改数据库）
+#[tauri::command]
+pub async fn get_schema_status(
+    state: State<'_, AppState>,
+) -> Result<MigrationReport, AppError> {
+    let db = state.db.clone();
+
+    run_db(move || db.migration_report()).await
+}
+
+/// 立即创建一个手动备份
+#[tauri::command]
+pub async fn create_backup(
+    state: State<'_, AppState>,
+) -> Result<BackupInfo, AppError> {
+    let service = state.backup_service.clone();
+
+    run_db(move || service.create(BackupKind::Manual)).await
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    state: State<'_, AppState>,
+) -> Result<Vec<BackupInfo>, AppError> {
+    let service = state.backup_service.clone();
+
+    run_db(move || service.list()).await
+}
+
+/// 从备份恢复，完成后发出 `database-restored` 事件，前端应重新加载全部数据
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    file_name: String,
+) -> Result<MigrationReport, AppError> {
+    let service = state.backup_service.clone();
+
+    let report = run_db(move || service.restore(&file_name)).await?;
+    app.emit("database-restored", &report)?;
+    Ok(report)
+}
@@ -0,0 +1,70 @@
+use tauri::{AppHandle, State};
+use crate::state::AppState;
+use crate::models::focus::{FocusConfig, FocusSession, FocusStatus};
+use crate::error::AppError;
+use super::todo::run_db;
+
+/// 默认返回的专注记录数量
+const DEFAULT_SESSION_LIMIT: u32 = 50;
+
+/// 开始新的一轮专注，未提供 `config` 时使用设置中的时长
+#[tauri::command]
+pub async fn start_focus(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    todo_id: Option<String>,
+    config: Option<FocusConfig>,
+) -> Result<FocusStatus, AppError> {
+    let service = state.focus_service.clone();
+
+    run_db(move || service.start(app, todo_id.as_deref(), config)).await
+}
+
+#[tauri::command]
+pub async fn pause_focus(
+    state: State<'_, AppState>,
+) -> Result<FocusStatus, AppError> {
+    state.focus_service.pause()
+}
+
+#[tauri::command]
+pub async fn resume_focus(
+    state: State<'_, AppState>,
+) -> Result<FocusStatus, AppError> {
+    state.focus_service.resume()
+}
+
+#[tauri::command]
+pub async fn stop_focus(
+    state: State<'_, AppState>,
+) -> Result<Option<FocusSession>, AppError> {
+    let service = state.focus_service.clone();
+
+    run_db(move || service.stop()).await
+}
+
+#[tauri::command]
+pub async fn log_focus_interruption(
+    state: State<'_, AppState>,
+    note: Option<String>,
+) -> Result<FocusStatus, AppError> {
+    state.focus_service.log_interruption(note.as_deref())
+}
+
+#[tauri::command]
+pub async fn get_focus_status(
+    state: State<'_, AppState>,
+) -> Result<Option<FocusStatus>, AppError> {
+    Ok(state.focus_service.status())
+}
+
+#[tauri::command]
+pub async fn get_focus_sessions(
+    state: State<'_, AppState>,
+    todo_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<FocusSession>, AppError> {
+    let service = state.focus_service.clone();
+
+    run_db(move || service.sessions(todo_id.as_deref(), limit.unwrap_or(DEFAULT_SESSION_LIMIT))).await
+}
@@ -0,0 +1,48 @@
+use tauri::State;
+use crate::state::AppState;
+use crate::models::list::{CreateListRequest, TodoList, UpdateListRequest};
+use crate::models::history::Actor;
+use crate::error::AppError;
+use super::todo::run_db;
+
+#[tauri::command]
+pub async fn get_lists(
+    state: State<'_, AppState>,
+    include_archived: Option<bool>,
+) -> Result<Vec<TodoList>, AppError> {
+    let repo = state.list_repo.clone();
+
+    run_db(move || repo.get_all(include_archived.unwrap_or(false))).await
+}
+
+#[tauri::command]
+pub async fn create_list(
+    state: State<'_, AppState>,
+    list: CreateListRequest,
+) -> Result<TodoList, AppError> {
+    let repo = state.list_repo.clone();
+
+    run_db(move || repo.create(list)).await
+}
+
+#[tauri::command]
+pub async fn update_list(
+    state: State<'_, AppState>,
+    id: String,
+    updates: UpdateListRequest,
+) -> Result<TodoList, AppError> {
+    let repo = state.list_repo.clone();
+
+    run_db(move || repo.update(&id, updates)).await
+}
+
+/// 删除列表，其中的任务移回收件箱，返回移动的任务数
+#[tauri::command]
+pub async fn delete_list(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<u32, AppError> {
+    let repo = state.list_repo.clone();
+
+    run_db(move || repo.delete(&id, &Actor::User)).await
+}
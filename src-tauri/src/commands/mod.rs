@@ -1,3 +1,13 @@
 pub mod todo;
+pub mod tag;
+pub mod list;
+pub mod workflow;
+pub mod reminder;
+pub mod attachment;
+pub mod time_entry;
+pub mod focus;
+pub mod report;
+pub mod undo;
 pub mod settings;
 pub mod ai;
+pub mod database;
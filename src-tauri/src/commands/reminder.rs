@@ -0,0 +1,61 @@
+use tauri::State;
+use crate::state::AppState;
+use crate::models::reminder::{CreateReminderRequest, Reminder};
+use crate::error::AppError;
+use super::todo::run_db;
+
+/// 默认返回的即将触发提醒数量
+const DEFAULT_UPCOMING_LIMIT: u32 = 50;
+
+#[tauri::command]
+pub async fn get_todo_reminders(
+    state: State<'_, AppState>,
+    todo_id: String,
+) -> Result<Vec<Reminder>, AppError> {
+    let repo = state.reminder_repo.clone();
+
+    run_db(move || repo.get_for_todo(&todo_id)).await
+}
+
+#[tauri::command]
+pub async fn get_upcoming_reminders(
+    state: State<'_, AppState>,
+    limit: Option<u32>,
+) -> Result<Vec<Reminder>, AppError> {
+    let repo = state.reminder_repo.clone();
+
+    run_db(move || repo.get_upcoming(limit.unwrap_or(DEFAULT_UPCOMING_LIMIT))).await
+}
+
+#[tauri::command]
+pub async fn add_todo_reminder(
+    state: State<'_, AppState>,
+    todo_id: String,
+    reminder: CreateReminderRequest,
+) -> Result<Reminder, AppError> {
+    let repo = state.reminder_repo.clone();
+
+    run_db(move || repo.create(&todo_id, reminder)).await
+}
+
+#[tauri::command]
+pub async fn delete_reminder(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), AppError> {
+    let repo = state.reminder_repo.clone();
+
+    run_db(move || repo.delete(&id)).await
+}
+
+/// 稍后提醒：`minutes` 分钟后再次触发 `todo-reminder` 事件
+#[tauri::command]
+pub async fn snooze_reminder(
+    state: State<'_, AppState>,
+    id: String,
+    minutes: u32,
+) -> Result<Reminder, AppError> {
+    let repo = state.reminder_repo.clone();
+
+    run_db(move || repo.snooze(&id, minutes)).await
+}
@@ -0,0 +1,27 @@
+use tauri::State;
+use crate::state::AppState;
+use crate::models::report::{EstimateReport, EstimateReportQuery, StatisticsQuery, StatisticsReport};
+use crate::error::AppError;
+use super::todo::run_db;
+
+/// 预估与实际工作量的对比，`query` 为空时统计全部已完成的任务
+#[tauri::command]
+pub async fn get_estimate_report(
+    state: State<'_, AppState>,
+    query: Option<EstimateReportQuery>,
+) -> Result<EstimateReport, AppError> {
+    let repo = state.report_repo.clone();
+
+    run_db(move || repo.estimate_accuracy(&query.unwrap_or_default())).await
+}
+
+/// 新建与完成的趋势、连续完成天数、逾期与分组统计，`query` 为空时统计最近 30 天
+#[tauri::command]
+pub async fn get_statistics_report(
+    state: State<'_, AppState>,
+    query: Option<StatisticsQuery>,
+) -> Result<StatisticsReport, AppError> {
+    let repo = state.report_repo.clone();
+
+    run_db(move || repo.statistics(&query.unwrap_or_default())).await
+}
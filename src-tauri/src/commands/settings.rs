@@ -1,4 +1,4 @@
-use tauri::State;
+use tauri::{AppHandle, State};
 use crate::state::AppState;
 use crate::models::settings::Settings;
 use crate::error::AppError;
@@ -74,6 +74,7 @@ pub struct FunctionCallTestResult {
 
 #[tauri::command]
 pub async fn test_function_calling(
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<FunctionCallTestResult, AppError> {
     use crate::models::ai::*;
@@ -101,23 +102,23 @@ pub async fn test_function_calling(
     let test_request = AiChatRequest {
         message: "请帮我添加一个测试任务：测试函数调用功能".to_string(),
         history: None,
+        conversation_id: None,
+        list_id: None,
     };
 
     let todo_repo = state.todo_repo.clone();
     let before_count = run_db(move || {
-        let todos = todo_repo.get_all(None)?;
-        Ok::<usize, AppError>(todos.len())
+        Ok::<u32, AppError>(todo_repo.get_statistics(None)?.total)
     }).await?;
 
-    match state.ai_service.chat(test_request).await {
+    match state.ai_service.chat(&app, test_request).await {
         Ok(response) => {
             let todo_repo = state.todo_repo.clone();
             let after_count = run_db(move || {
-                let todos = todo_repo.get_all(None)?;
-                Ok::<usize, AppError>(todos.len())
+                Ok::<u32, AppError>(todo_repo.get_statistics(None)?.total)
             }).await?;
 
-            let todos_created = (after_count - before_count) as u32;
+            let todos_created = after_count.saturating_sub(before_count);
 
             let api_format = if let Some(ref results) = response.function_results {
                 if !results.is_empty() {
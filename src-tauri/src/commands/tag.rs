@@ -0,0 +1,58 @@
+use tauri::State;
+use crate::state::AppState;
+use crate::models::tag::Tag;
+use crate::models::history::Actor;
+use crate::error::AppError;
+use super::todo::run_db;
+
+#[tauri::command]
+pub async fn get_tags(
+    state: State<'_, AppState>,
+) -> Result<Vec<Tag>, AppError> {
+    let repo = state.tag_repo.clone();
+
+    run_db(move || repo.get_all()).await
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    state: State<'_, AppState>,
+    id: String,
+    name: String,
+) -> Result<Tag, AppError> {
+    let repo = state.tag_repo.clone();
+
+    run_db(move || repo.rename(&id, &name, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn merge_tags(
+    state: State<'_, AppState>,
+    source_ids: Vec<String>,
+    target_id: String,
+) -> Result<Tag, AppError> {
+    let repo = state.tag_repo.clone();
+
+    run_db(move || repo.merge(&source_ids, &target_id, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn set_tag_color(
+    state: State<'_, AppState>,
+    id: String,
+    color: Option<String>,
+) -> Result<Tag, AppError> {
+    let repo = state.tag_repo.clone();
+
+    run_db(move || repo.set_color(&id, color)).await
+}
+
+#[tauri::command]
+pub async fn delete_tag(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), AppError> {
+    let repo = state.tag_repo.clone();
+
+    run_db(move || repo.delete(&id, &Actor::User)).await
+}
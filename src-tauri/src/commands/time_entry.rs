@@ -0,0 +1,88 @@
+use tauri::State;
+use crate::state::AppState;
+use crate::models::history::Actor;
+use crate::models::time_entry::{CreateTimeEntryRequest, TimeEntry, TimeReport, TimeReportQuery, UpdateTimeEntryRequest};
+use crate::error::AppError;
+use super::todo::run_db;
+
+/// 开始为任务计时，其他任务正在计时时会先被停止
+#[tauri::command]
+pub async fn start_timer(
+    state: State<'_, AppState>,
+    todo_id: String,
+    note: Option<String>,
+) -> Result<TimeEntry, AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.start_timer(&todo_id, note.as_deref(), &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn stop_timer(
+    state: State<'_, AppState>,
+) -> Result<Option<TimeEntry>, AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.stop_timer()).await
+}
+
+#[tauri::command]
+pub async fn get_running_timer(
+    state: State<'_, AppState>,
+) -> Result<Option<TimeEntry>, AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.get_running()).await
+}
+
+#[tauri::command]
+pub async fn get_todo_time_entries(
+    state: State<'_, AppState>,
+    todo_id: String,
+) -> Result<Vec<TimeEntry>, AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.get_for_todo(&todo_id)).await
+}
+
+#[tauri::command]
+pub async fn add_time_entry(
+    state: State<'_, AppState>,
+    todo_id: String,
+    entry: CreateTimeEntryRequest,
+) -> Result<TimeEntry, AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.create(&todo_id, entry)).await
+}
+
+#[tauri::command]
+pub async fn update_time_entry(
+    state: State<'_, AppState>,
+    id: String,
+    entry: UpdateTimeEntryRequest,
+) -> Result<TimeEntry, AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.update(&id, entry)).await
+}
+
+#[tauri::command]
+pub async fn delete_time_entry(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.delete(&id)).await
+}
+
+#[tauri::command]
+pub async fn get_time_report(
+    state: State<'_, AppState>,
+    query: Option<TimeReportQuery>,
+) -> Result<TimeReport, AppError> {
+    let repo = state.time_entry_repo.clone();
+
+    run_db(move || repo.report(&query.unwrap_or_default())).await
+}
@@ -1,10 +1,12 @@
 use tauri::State;
 use crate::state::AppState;
 use crate::models::todo::*;
+use crate::models::date_expr::ParsedDate;
+use crate::models::history::{Actor, TodoEvent};
 use crate::error::AppError;
 
 // 在专用的阻塞线程池中执行数据库操作，避免阻塞主异步运行时线程
-async fn run_db<F, T>(f: F) -> Result<T, AppError>
+pub(super) async fn run_db<F, T>(f: F) -> Result<T, AppError>
 where
     F: FnOnce() -> Result<T, AppError> + Send + 'static,
     T: Send + 'static,
@@ -21,21 +23,59 @@ pub async fn create_todo(
     priority: Option<Priority>,
     due_date: Option<String>,
     tags: Option<Vec<String>>,
+    parent_id: Option<String>,
+    list_id: Option<String>,
 ) -> Result<Todo, AppError> {
     let repo = state.todo_repo.clone();
-    let request = CreateTodoRequest { text, priority, due_date, tags };
+    let settings_repo = state.settings_repo.clone();
 
-    run_db(move || repo.create(request)).await
+    run_db(move || {
+        // 快速添加：没有单独指定截止日期时从内容中识别，例如"明天下午三点交报告"
+        let (text, due_date) = match due_date {
+            Some(due_date) => (text, Some(due_date)),
+            None => match settings_repo.timezone()?.parse_expression(&text) {
+                Some(parsed) if !parsed.remainder.is_empty() => (parsed.remainder, Some(parsed.due_date)),
+                _ => (text, None),
+            },
+        };
+        let request = CreateTodoRequest { text, priority, due_date, tags, parent_id, list_id, ..Default::default() };
+
+        repo.create(request, &Actor::User)
+    }).await
+}
+
+/// 预览日期表达式的解析结果（按用户时区），输入中没有可识别的日期时返回 `None`
+#[tauri::command]
+pub async fn parse_date_expression(
+    state: State<'_, AppState>,
+    text: String,
+) -> Result<Option<ParsedDate>, AppError> {
+    let settings_repo = state.settings_repo.clone();
+
+    run_db(move || Ok(settings_repo.timezone()?.parse_expression(&text))).await
 }
 
+/// 分页查询任务，`page` 为空时返回全部任务（默认排序）
 #[tauri::command]
 pub async fn get_todos(
     state: State<'_, AppState>,
     filter: Option<TodoFilter>,
-) -> Result<Vec<Todo>, AppError> {
+    page: Option<TodoPageRequest>,
+) -> Result<TodoPage, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.get_page(filter, page.unwrap_or_default())).await
+}
+
+#[tauri::command]
+pub async fn search_todos(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<TodoSearchResult>, AppError> {
     let repo = state.todo_repo.clone();
 
-    run_db(move || repo.get_all(filter)).await
+    run_db(move || repo.search(&query, limit.unwrap_or(50))).await
 }
 
 #[tauri::command]
@@ -56,7 +96,7 @@ pub async fn update_todo(
 ) -> Result<Todo, AppError> {
     let repo = state.todo_repo.clone();
 
-    run_db(move || repo.update(&id, updates)).await
+    run_db(move || repo.update(&id, updates, &Actor::User)).await
 }
 
 #[tauri::command]
@@ -66,7 +106,7 @@ pub async fn delete_todo(
 ) -> Result<(), AppError> {
     let repo = state.todo_repo.clone();
 
-    run_db(move || repo.delete(&id)).await
+    run_db(move || repo.delete(&id, &Actor::User)).await
 }
 
 #[tauri::command]
@@ -76,7 +116,173 @@ pub async fn batch_create_todos(
 ) -> Result<Vec<Todo>, AppError> {
     let repo = state.todo_repo.clone();
 
-    run_db(move || repo.batch_create(todos)).await
+    run_db(move || repo.batch_create(todos, &Actor::User)).await
+}
+
+/// 对 `selector` 选中的任务批量应用同一组修改，整批在一个事务中完成
+#[tauri::command]
+pub async fn bulk_update_todos(
+    state: State<'_, AppState>,
+    selector: TodoSelector,
+    updates: UpdateTodoRequest,
+) -> Result<Vec<Todo>, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.bulk_update(&selector, updates, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn bulk_delete_todos(
+    state: State<'_, AppState>,
+    selector: TodoSelector,
+) -> Result<u32, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.bulk_delete(&selector, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn bulk_set_status(
+    state: State<'_, AppState>,
+    selector: TodoSelector,
+    status: TodoStatus,
+) -> Result<Vec<Todo>, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.bulk_set_status(&selector, status, &Actor::User)).await
+}
+
+/// 把一个或多个任务按给定顺序移动到 `anchor_id` 之前或之后（手动排序）
+#[tauri::command]
+pub async fn reorder_todos(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    anchor_id: String,
+    placement: Placement,
+) -> Result<Vec<Todo>, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.reorder(&ids, &anchor_id, placement, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn get_todo_children(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<Todo>, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.get_children(&id)).await
+}
+
+#[tauri::command]
+pub async fn get_todo_ancestors(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<Todo>, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.get_ancestors(&id)).await
+}
+
+#[tauri::command]
+pub async fn get_todo_tree(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<TodoTree, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.get_tree(&id)).await
+}
+
+/// 把任务移动到另一个任务下，`parent_id` 为空时移为顶层任务
+#[tauri::command]
+pub async fn set_todo_parent(
+    state: State<'_, AppState>,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.set_parent(&id, parent_id.as_deref(), &Actor::User)).await
+}
+
+/// 让 `id` 依赖 `depends_on_id`：后者完成前前者处于阻塞状态
+#[tauri::command]
+pub async fn add_todo_dependency(
+    state: State<'_, AppState>,
+    id: String,
+    depends_on_id: String,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.add_dependency(&id, &depends_on_id, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn remove_todo_dependency(
+    state: State<'_, AppState>,
+    id: String,
+    depends_on_id: String,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.remove_dependency(&id, &depends_on_id, &Actor::User)).await
+}
+
+/// 在任务清单末尾添加一项，返回修改后的任务
+#[tauri::command]
+pub async fn add_checklist_item(
+    state: State<'_, AppState>,
+    todo_id: String,
+    text: String,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.add_checklist_item(&todo_id, &text, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn update_checklist_item(
+    state: State<'_, AppState>,
+    id: String,
+    updates: UpdateChecklistItemRequest,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.update_checklist_item(&id, updates, &Actor::User)).await
+}
+
+/// 切换清单项的完成状态，返回所属任务
+#[tauri::command]
+pub async fn toggle_checklist_item(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.toggle_checklist_item(&id, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn delete_checklist_item(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.delete_checklist_item(&id, &Actor::User)).await
+}
+
+/// 跳过重复任务的本次，截止日期顺延到下一次
+#[tauri::command]
+pub async fn skip_todo_occurrence(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.skip_occurrence(&id, &Actor::User)).await
 }
 
 #[tauri::command]
@@ -85,14 +291,54 @@ pub async fn delete_completed_todos(
 ) -> Result<u32, AppError> {
     let repo = state.todo_repo.clone();
 
-    run_db(move || repo.delete_completed()).await
+    run_db(move || repo.delete_completed(&Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn get_trash(
+    state: State<'_, AppState>,
+) -> Result<Vec<Todo>, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.get_trash()).await
+}
+
+#[tauri::command]
+pub async fn restore_todo(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Todo, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.restore(&id, &Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn empty_trash(
+    state: State<'_, AppState>,
+) -> Result<u32, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.empty_trash(&Actor::User)).await
+}
+
+#[tauri::command]
+pub async fn get_todo_history(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<TodoEvent>, AppError> {
+    let repo = state.history_repo.clone();
+
+    run_db(move || repo.get_for_todo(&id)).await
 }
 
+/// 任务统计，`list_id` 为空时统计全部未归档的列表，空字符串表示只统计收件箱
 #[tauri::command]
 pub async fn get_todo_statistics(
     state: State<'_, AppState>,
+    list_id: Option<String>,
 ) -> Result<TodoStatistics, AppError> {
     let repo = state.todo_repo.clone();
 
-    run_db(move || repo.get_statistics()).await
+    run_db(move || repo.get_statistics(list_id.as_deref())).await
 }
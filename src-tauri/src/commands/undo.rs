@@ -0,0 +1,46 @@
+use tauri::{AppHandle, Emitter, State};
+use crate::state::AppState;
+use crate::models::undo::{UndoStack, UndoStep};
+use crate::error::AppError;
+use super::todo::run_db;
+
+#[tauri::command]
+pub async fn get_undo_stack(
+    state: State<'_, AppState>,
+) -> Result<UndoStack, AppError> {
+    let repo = state.undo_repo.clone();
+
+    run_db(move || repo.get_stack()).await
+}
+
+/// 撤销最近一步（一次用户操作，或 AI 一轮对话中的全部修改）
+#[tauri::command]
+pub async fn undo(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<UndoStep>, AppError> {
+    let repo = state.undo_repo.clone();
+
+    let step = run_db(move || repo.undo()).await?;
+    emit_stack_changed(&app, &state).await?;
+    Ok(step)
+}
+
+#[tauri::command]
+pub async fn redo(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<UndoStep>, AppError> {
+    let repo = state.undo_repo.clone();
+
+    let step = run_db(move || repo.redo()).await?;
+    emit_stack_changed(&app, &state).await?;
+    Ok(step)
+}
+
+async fn emit_stack_changed(app: &AppHandle, state: &State<'_, AppState>) -> Result<(), AppError> {
+    let repo = state.undo_repo.clone();
+    let stack = run_db(move || repo.get_stack()).await?;
+    app.emit("undo-stack-changed", &stack)?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use tauri::State;
+use crate::state::AppState;
+use crate::models::history::Actor;
+use crate::models::workflow::{Board, CreateWorkflowStateRequest, UpdateWorkflowStateRequest, WorkflowState};
+use crate::error::AppError;
+use super::todo::run_db;
+
+#[tauri::command]
+pub async fn get_workflow_states(
+    state: State<'_, AppState>,
+) -> Result<Vec<WorkflowState>, AppError> {
+    let repo = state.workflow_repo.clone();
+
+    run_db(move || repo.get_all()).await
+}
+
+#[tauri::command]
+pub async fn create_workflow_state(
+    state: State<'_, AppState>,
+    workflow_state: CreateWorkflowStateRequest,
+) -> Result<WorkflowState, AppError> {
+    let repo = state.workflow_repo.clone();
+
+    run_db(move || repo.create(workflow_state)).await
+}
+
+#[tauri::command]
+pub async fn update_workflow_state(
+    state: State<'_, AppState>,
+    id: String,
+    updates: UpdateWorkflowStateRequest,
+) -> Result<WorkflowState, AppError> {
+    let repo = state.workflow_repo.clone();
+
+    run_db(move || repo.update(&id, updates)).await
+}
+
+/// 删除状态，其中的任务移入同一类别的默认状态，返回移动的任务数
+#[tauri::command]
+pub async fn delete_workflow_state(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<u32, AppError> {
+    let repo = state.workflow_repo.clone();
+
+    run_db(move || repo.delete(&id, &Actor::User)).await
+}
+
+/// 按给定顺序排列看板的列
+#[tauri::command]
+pub async fn reorder_workflow_states(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<Vec<WorkflowState>, AppError> {
+    let repo = state.workflow_repo.clone();
+
+    run_db(move || repo.reorder(&ids)).await
+}
+
+/// 设置从 `id` 允许变为的状态，空列表表示不限制
+#[tauri::command]
+pub async fn set_workflow_transitions(
+    state: State<'_, AppState>,
+    id: String,
+    to_ids: Vec<String>,
+) -> Result<WorkflowState, AppError> {
+    let repo = state.workflow_repo.clone();
+
+    run_db(move || repo.set_transitions(&id, &to_ids)).await
+}
+
+/// 看板，`list_id` 为空时包含全部未归档的列表，空字符串表示只看收件箱
+#[tauri::command]
+pub async fn get_board(
+    state: State<'_, AppState>,
+    list_id: Option<String>,
+) -> Result<Board, AppError> {
+    let repo = state.todo_repo.clone();
+
+    run_db(move || repo.get_board(list_id.as_deref())).await
+}
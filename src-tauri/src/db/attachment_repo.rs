@@ -0,0 +1,119 @@
+use crate::db::{todo_repo, Database};
+use crate::error::AppError;
+use crate::models::attachment::Attachment;
+use chrono::Utc;
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const ATTACHMENT_COLUMNS: &str = "id, todo_id, file_name, mime_type, size_bytes, hash, created_at";
+
+fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        file_name: row.get(2)?,
+        mime_type: row.get(3)?,
+        size_bytes: row.get::<_, i64>(4)? as u64,
+        hash: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn load_attachment(conn: &Connection, id: &str) -> Result<Attachment, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM attachments WHERE id = ?1", ATTACHMENT_COLUMNS),
+        [id],
+        row_to_attachment,
+    )
+    .map_err(|_| AppError::AttachmentNotFound(id.to_string()))
+}
+
+/// 数据库中仍被引用的文件哈希。也用于读取备份文件，旧版本的备份没有附件表时返回空集合。
+pub(crate) fn referenced_hashes(conn: &Connection) -> Result<HashSet<String>, AppError> {
+    let has_table: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'attachments')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_table {
+        return Ok(HashSet::new());
+    }
+
+    let mut stmt = conn.prepare("SELECT DISTINCT hash FROM attachments")?;
+    let hashes = stmt.query_map([], |row| row.get(0))?;
+    Ok(hashes.collect::<Result<_, _>>()?)
+}
+
+/// 附件元数据。文件的复制与清理由 `AttachmentService` 负责。
+pub struct AttachmentRepository {
+    db: Arc<Database>,
+}
+
+impl AttachmentRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 任务的全部附件，按添加时间排序
+    pub fn get_for_todo(&self, todo_id: &str) -> Result<Vec<Attachment>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM attachments WHERE todo_id = ?1 ORDER BY created_at, id",
+                ATTACHMENT_COLUMNS
+            ))?;
+
+            let attachments = stmt.query_map([todo_id], row_to_attachment)?;
+
+            let mut result = Vec::new();
+            for attachment in attachments {
+                result.push(attachment?);
+            }
+            Ok(result)
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Result<Attachment, AppError> {
+        self.db.with_conn(|conn| load_attachment(conn, id))
+    }
+
+    /// 记录已保存到附件目录中的文件，任务必须存在且不在回收站中
+    pub fn create(
+        &self,
+        todo_id: &str,
+        file_name: &str,
+        mime_type: &str,
+        size_bytes: u64,
+        hash: &str,
+    ) -> Result<Attachment, AppError> {
+        self.db.with_conn(|conn| {
+            let todo = todo_repo::load_todo(conn, todo_id)?;
+            if todo.deleted_at.is_some() {
+                return Err(AppError::TodoNotFound(todo_id.to_string()));
+            }
+
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                &format!("INSERT INTO attachments ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", ATTACHMENT_COLUMNS),
+                (&id, todo_id, file_name, mime_type, size_bytes as i64, hash, Utc::now().to_rfc3339()),
+            )?;
+
+            load_attachment(conn, &id)
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), AppError> {
+        self.db.with_conn(|conn| {
+            let affected = conn.execute("DELETE FROM attachments WHERE id = ?1", [id])?;
+            if affected == 0 {
+                return Err(AppError::AttachmentNotFound(id.to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn referenced_hashes(&self) -> Result<HashSet<String>, AppError> {
+        self.db.with_conn(referenced_hashes)
+    }
+}
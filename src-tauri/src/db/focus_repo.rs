@@ -0,0 +1,100 @@
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::focus::FocusSession;
+use rusqlite::Connection;
+use std::sync::Arc;
+
+const FOCUS_SESSION_COLUMNS: &str = "focus_sessions.id, focus_sessions.todo_id, todos.text, focus_sessions.started_at,
+    focus_sessions.ended_at, focus_sessions.planned_seconds, focus_sessions.focused_seconds,
+    focus_sessions.completed, focus_sessions.interruptions";
+
+fn row_to_session(row: &rusqlite::Row) -> rusqlite::Result<FocusSession> {
+    let interruptions: String = row.get(8)?;
+
+    Ok(FocusSession {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        todo_text: row.get(2)?,
+        started_at: row.get(3)?,
+        ended_at: row.get(4)?,
+        planned_seconds: row.get::<_, i64>(5)? as u64,
+        focused_seconds: row.get::<_, i64>(6)? as u64,
+        completed: row.get::<_, i32>(7)? != 0,
+        interruptions: serde_json::from_str(&interruptions).unwrap_or_default(),
+    })
+}
+
+fn load_session(conn: &Connection, id: &str) -> Result<FocusSession, AppError> {
+    Ok(conn.query_row(
+        &format!(
+            "SELECT {} FROM focus_sessions LEFT JOIN todos ON todos.id = focus_sessions.todo_id
+             WHERE focus_sessions.id = ?1",
+            FOCUS_SESSION_COLUMNS
+        ),
+        [id],
+        row_to_session,
+    )?)
+}
+
+/// 已结束的专注阶段。计时本身由 `FocusService` 在内存中进行，阶段结束时才写入数据库。
+pub struct FocusRepository {
+    db: Arc<Database>,
+}
+
+impl FocusRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 保存一个已结束的专注阶段，`session.id` 由调用方生成
+    pub fn save(&self, session: &FocusSession) -> Result<FocusSession, AppError> {
+        self.db.with_conn(|conn| {
+            // 专注期间任务可能已被永久删除
+            let todo_id: Option<String> = match session.todo_id {
+                Some(ref todo_id) => conn
+                    .query_row("SELECT id FROM todos WHERE id = ?1", [todo_id], |row| row.get(0))
+                    .ok(),
+                None => None,
+            };
+
+            conn.execute(
+                "INSERT INTO focus_sessions
+                    (id, todo_id, started_at, ended_at, planned_seconds, focused_seconds, completed, interruptions)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                (
+                    &session.id,
+                    &todo_id,
+                    &session.started_at,
+                    &session.ended_at,
+                    session.planned_seconds as i64,
+                    session.focused_seconds as i64,
+                    if session.completed { 1 } else { 0 },
+                    serde_json::to_string(&session.interruptions)?,
+                ),
+            )?;
+
+            load_session(conn, &session.id)
+        })
+    }
+
+    /// 最近的专注记录，最新的在前；指定任务时只返回该任务的记录
+    pub fn get_recent(&self, todo_id: Option<&str>, limit: u32) -> Result<Vec<FocusSession>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM focus_sessions LEFT JOIN todos ON todos.id = focus_sessions.todo_id
+                 WHERE ?1 IS NULL OR focus_sessions.todo_id = ?1
+                 ORDER BY focus_sessions.started_at DESC
+                 LIMIT ?2",
+                FOCUS_SESSION_COLUMNS
+            ))?;
+
+            let sessions = stmt.query_map((todo_id, limit), row_to_session)?;
+
+            let mut result = Vec::new();
+            for session in sessions {
+                result.push(session?);
+            }
+            Ok(result)
+        })
+    }
+}
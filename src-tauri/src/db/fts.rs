@@ -0,0 +1,194 @@
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+
+/// 插入到 CJK 字符之间的分隔符（零宽空格）。
+///
+/// FTS5 的 unicode61 分词器会把连续的汉字当作一个词，导致"周报"无法匹配"提交周报"。
+/// 写入索引前在每个 CJK 字符两侧插入零宽空格，使每个汉字成为独立的 token，
+/// 查询时再把汉字串转换为短语查询，从而实现按字连续匹配。
+const SEGMENT_MARK: char = '\u{200B}';
+
+/// 在连接上注册 `fts_segment(text)` SQL 函数，FTS 同步触发器依赖它
+pub fn register_functions(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.create_scalar_function(
+        "fts_segment",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC | FunctionFlags::SQLITE_INNOCUOUS,
+        |ctx| {
+            let text: Option<String> = ctx.get(0)?;
+            Ok(text.map(|t| segment(&t)))
+        },
+    )
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // 平假名、片假名
+        | 0x3400..=0x4DBF    // CJK 扩展 A
+        | 0x4E00..=0x9FFF    // CJK 统一汉字
+        | 0xAC00..=0xD7AF    // 韩文音节
+        | 0xF900..=0xFAFF    // CJK 兼容汉字
+        | 0x20000..=0x2FA1F  // CJK 扩展 B 及之后
+    )
+}
+
+/// 为写入 FTS 索引的文本分词：在相邻的 CJK 字符之间插入分隔符
+pub fn segment(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut prev: Option<char> = None;
+
+    for c in text.chars() {
+        if let Some(p) = prev {
+            if (is_cjk(p) || is_cjk(c)) && !p.is_whitespace() && !c.is_whitespace() {
+                out.push(SEGMENT_MARK);
+            }
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+
+    out
+}
+
+/// 去掉 `snippet()`/`highlight()` 结果中的分隔符，还原为原始文本
+pub fn desegment(text: &str) -> String {
+    text.replace(SEGMENT_MARK, "")
+}
+
+/// 把用户输入的搜索词转换为安全的 FTS5 MATCH 表达式。
+///
+/// - 空格分隔的词之间是 AND 关系
+/// - `"..."` 包裹的内容按短语匹配
+/// - 以 `*` 结尾的词按前缀匹配，例如 `rep*` 匹配 `report`
+///
+/// 所有词都会被引号包裹，用户输入中的 FTS5 运算符不会生效。
+/// 输入中没有任何有效词时返回 `None`。
+pub fn build_match_query(input: &str) -> Option<String> {
+    let mut terms = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut term = String::new();
+        if c == '"' {
+            chars.next();
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                term.push(c);
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                term.push(c);
+                chars.next();
+            }
+        }
+
+        let prefix = term.ends_with('*');
+        let body = term.trim_end_matches('*').trim();
+        if body.is_empty() {
+            continue;
+        }
+
+        let quoted = format!("\"{}\"", segment(body).replace('"', "\"\""));
+        terms.push(if prefix { format!("{}*", quoted) } else { quoted });
+    }
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 把期望结果中的 `|` 换成分隔符，便于阅读
+    fn marked(text: &str) -> String {
+        text.replace('|', &SEGMENT_MARK.to_string())
+    }
+
+    #[test]
+    fn segment_splits_cjk_characters() {
+        let cases = [
+            ("提交周报", "提|交|周|报"),
+            ("weekly report", "weekly report"),
+            ("写report草稿", "写|report|草|稿"),
+            ("周报 v2", "周|报 v2"),
+            ("カタカナ", "カ|タ|カ|ナ"),
+            ("", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(segment(input), marked(expected), "{}", input);
+            assert_eq!(desegment(&segment(input)), input);
+        }
+    }
+
+    #[test]
+    fn build_match_query_quotes_every_term() {
+        let cases = [
+            ("周报", Some(r#""周|报""#)),
+            ("weekly report", Some(r#""weekly" "report""#)),
+            // 短语与前缀
+            (r#""weekly report" draft"#, Some(r#""weekly report" "draft""#)),
+            ("rep*", Some(r#""rep"*"#)),
+            ("周*", Some(r#""周"*"#)),
+            // 未闭合的引号取到末尾，词中的引号把词断开
+            (r#""weekly rep"#, Some(r#""weekly rep""#)),
+            (r#"ab"cd"#, Some(r#""ab" "cd""#)),
+            // FTS5 运算符按普通词处理
+            ("a OR b", Some(r#""a" "OR" "b""#)),
+            ("NEAR(a b)", Some(r#""NEAR(a" "b)""#)),
+            ("-draft", Some(r#""-draft""#)),
+            ("col:value ^start", Some(r#""col:value" "^start""#)),
+            // 中英混合
+            ("写report 草稿*", Some(r#""写|report" "草|稿"*"#)),
+            ("", None),
+            ("   ", None),
+            ("* **", None),
+            (r#""" """#, None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(build_match_query(input), expected.map(marked), "{}", input);
+        }
+    }
+
+    #[test]
+    fn built_queries_run_against_fts5() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE VIRTUAL TABLE docs USING fts5(text);").unwrap();
+        let rows = ["提交周报", "周末报告", "weekly report draft", "写report草稿", "a OR b", "NEAR me"];
+        for row in rows {
+            conn.execute("INSERT INTO docs (text) VALUES (?1)", [segment(row)]).unwrap();
+        }
+
+        let search = |input: &str| -> Vec<String> {
+            let query = build_match_query(input).unwrap();
+            let mut stmt = conn.prepare("SELECT text FROM docs WHERE docs MATCH ?1 ORDER BY rowid").unwrap();
+            let found = stmt.query_map([query], |row| row.get::<_, String>(0)).unwrap();
+            found.map(|text| desegment(&text.unwrap())).collect()
+        };
+
+        // 汉字按字连续匹配，不会匹配到分开的字
+        assert_eq!(search("周报"), vec!["提交周报"]);
+        assert_eq!(search("报告"), vec!["周末报告"]);
+        assert_eq!(search("rep*"), vec!["weekly report draft", "写report草稿"]);
+        assert_eq!(search(r#""report draft""#), vec!["weekly report draft"]);
+        assert!(search(r#""draft report""#).is_empty());
+        assert_eq!(search("写report"), vec!["写report草稿"]);
+        // 运算符按字面文本匹配，不会产生语法错误
+        assert_eq!(search("a OR b"), vec!["a OR b"]);
+        assert_eq!(search("NEAR(me)"), vec!["NEAR me"]);
+        assert_eq!(search("-draft"), vec!["weekly report draft"]);
+    }
+}
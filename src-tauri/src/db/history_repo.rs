@@ -0,0 +1,218 @@
+use crate::db::{undo_repo, Database};
+use crate::error::AppError;
+use crate::models::history::*;
+use crate::models::todo::Todo;
+use chrono::Utc;
+use rusqlite::Connection;
+use std::sync::Arc;
+
+/// 一次 repository 调用内写入事件的上下文。
+///
+/// 同一上下文写入的所有事件属于同一个撤销步骤；AI 对话的一轮中所有工具调用共享同一个步骤。
+/// `step_id` 为 `None` 时事件不可撤销（自动任务、撤销/重做操作本身）。
+pub(crate) struct EventContext<'a> {
+    pub actor: &'a Actor,
+    pub step_id: Option<String>,
+}
+
+impl<'a> EventContext<'a> {
+    pub fn new(actor: &'a Actor) -> Self {
+        Self {
+            actor,
+            step_id: actor.undo_step_id(),
+        }
+    }
+
+    /// 不产生撤销步骤的上下文
+    pub fn untracked(actor: &'a Actor) -> Self {
+        Self { actor, step_id: None }
+    }
+}
+
+/// 写入一条任务变更事件。
+///
+/// `before`/`after` 为变更前后的完整快照：创建时 `before` 为 `None`，永久删除时 `after` 为 `None`。
+/// 应与对应的修改在同一个事务中调用。
+pub(crate) fn record_event(
+    conn: &Connection,
+    ctx: &EventContext,
+    action: TodoAction,
+    before: Option<&Todo>,
+    after: Option<&Todo>,
+) -> Result<(), AppError> {
+    let actor = ctx.actor;
+    let todo_id = match after.or(before) {
+        Some(todo) => todo.id.clone(),
+        None => return Ok(()),
+    };
+
+    let before_json = before.map(serde_json::to_value).transpose()?;
+    let after_json = after.map(serde_json::to_value).transpose()?;
+    let changes = diff_snapshots(before_json.as_ref(), after_json.as_ref());
+
+    // 没有任何字段变化的修改不记录
+    if action == TodoAction::Updated && changes.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now().to_rfc3339();
+
+    if let Some(ref step_id) = ctx.step_id {
+        undo_repo::register_step(conn, step_id, actor, &now)?;
+    }
+
+    conn.execute(
+        "INSERT INTO todo_events (todo_id, action, actor_kind, actor, changes, before, after, created_at, step_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        (
+            &todo_id,
+            action.as_str(),
+            actor.kind(),
+            serde_json::to_string(actor)?,
+            serde_json::to_string(&changes)?,
+            before_json.map(|v| v.to_string()),
+            after_json.map(|v| v.to_string()),
+            &now,
+            &ctx.step_id,
+        ),
+    )?;
+
+    Ok(())
+}
+
+fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<TodoEvent> {
+    let actor_json: String = row.get(3)?;
+    let changes_json: String = row.get(4)?;
+
+    Ok(TodoEvent {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        action: TodoAction::parse(&row.get::<_, String>(2)?),
+        actor: serde_json::from_str(&actor_json).unwrap_or(Actor::User),
+        changes: serde_json::from_str(&changes_json).unwrap_or_default(),
+        created_at: row.get(5)?,
+        todo_text: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+    })
+}
+
+pub struct HistoryRepository {
+    db: Arc<Database>,
+}
+
+impl HistoryRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 某个任务的全部变更记录，按时间先后排列
+    pub fn get_for_todo(&self, todo_id: &str) -> Result<Vec<TodoEvent>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, todo_id, action, actor, changes, created_at, json_extract(COALESCE(after, before), '$.text')
+                 FROM todo_events WHERE todo_id = ?1 ORDER BY id ASC",
+            )?;
+
+            let events = stmt.query_map([todo_id], row_to_event)?;
+
+            let mut result = Vec::new();
+            for event in events {
+                result.push(event?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// 最近的变更记录，最新的在前
+    pub fn get_recent(&self, limit: u32) -> Result<Vec<TodoEvent>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, todo_id, action, actor, changes, created_at, json_extract(COALESCE(after, before), '$.text')
+                 FROM todo_events ORDER BY id DESC LIMIT ?1",
+            )?;
+
+            let events = stmt.query_map([limit], row_to_event)?;
+
+            let mut result = Vec::new();
+            for event in events {
+                result.push(event?);
+            }
+            Ok(result)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TestDb, TodoRepository};
+    use crate::models::todo::{CreateTodoRequest, Priority, UpdateTodoRequest};
+    use serde_json::json;
+
+    fn ai_actor() -> Actor {
+        Actor::Ai { conversation_id: Some("conv-1".into()), tool_call_id: Some("call-1".into()), turn_id: None }
+    }
+
+    #[test]
+    fn updates_record_only_changed_fields() {
+        let test = TestDb::new();
+        let repo = HistoryRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = todos.create(CreateTodoRequest { text: "write report".into(), ..Default::default() }, &Actor::User).unwrap();
+
+        let request = UpdateTodoRequest { priority: Some(Priority::High), ..Default::default() };
+        todos.update(&todo.id, request, &ai_actor()).unwrap();
+        // 没有变化的修改不记录
+        let request = UpdateTodoRequest { priority: Some(Priority::High), ..Default::default() };
+        todos.update(&todo.id, request, &Actor::User).unwrap();
+
+        let events = repo.get_for_todo(&todo.id).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].action, TodoAction::Created);
+        assert_eq!(events[0].actor, Actor::User);
+        assert_eq!(events[0].changes.get("text").unwrap(), &json!({ "before": null, "after": "write report" }));
+
+        let update = &events[1];
+        assert_eq!(update.action, TodoAction::Updated);
+        assert_eq!(update.actor, ai_actor());
+        assert_eq!(update.changes.keys().collect::<Vec<_>>(), vec!["priority"]);
+        assert_eq!(update.changes["priority"], json!({ "before": "low", "after": "high" }));
+        assert!(update.summary().ends_with("AI修改了任务「write report」（priority: low → high）"));
+    }
+
+    #[test]
+    fn history_outlives_the_todo() {
+        let test = TestDb::new();
+        let repo = HistoryRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = todos.create(CreateTodoRequest { text: "old".into(), ..Default::default() }, &Actor::Import).unwrap();
+
+        todos.delete(&todo.id, &Actor::User).unwrap();
+        todos.empty_trash(&Actor::automation("trash")).unwrap();
+
+        let events = repo.get_for_todo(&todo.id).unwrap();
+        let actions: Vec<TodoAction> = events.iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec![TodoAction::Created, TodoAction::Deleted, TodoAction::Purged]);
+        assert_eq!(events[0].actor, Actor::Import);
+        assert_eq!(events[2].actor, Actor::automation("trash"));
+        assert!(events.iter().all(|e| e.todo_text == "old"));
+
+        let recent = repo.get_recent(1).unwrap();
+        assert_eq!(recent[0].id, events[2].id);
+    }
+
+    #[test]
+    fn events_cannot_be_rewritten() {
+        let test = TestDb::new();
+        let todos = TodoRepository::new(test.db.clone());
+        todos.create(CreateTodoRequest { text: "a".into(), ..Default::default() }, &Actor::User).unwrap();
+
+        test.db
+            .with_conn(|conn| {
+                assert!(conn.execute("UPDATE todo_events SET actor_kind = 'ai'", []).is_err());
+                assert!(conn.execute("DELETE FROM todo_events", []).is_err());
+                Ok(())
+            })
+            .unwrap();
+    }
+}
+
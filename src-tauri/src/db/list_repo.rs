@@ -0,0 +1,340 @@
+use crate::db::{history_repo, tag_repo, todo_repo, Database};
+use crate::db::history_repo::EventContext;
+use crate::error::AppError;
+use crate::models::history::{Actor, TodoAction};
+use crate::models::list::{CreateListRequest, TodoList, UpdateListRequest};
+use crate::models::todo::Priority;
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const LIST_COLUMNS: &str = "lists.id, lists.name, lists.color, lists.icon, lists.archived, lists.default_priority,
+    (SELECT COUNT(*) FROM todos WHERE todos.list_id = lists.id AND todos.deleted_at IS NULL),
+    (SELECT COUNT(*) FROM todos WHERE todos.list_id = lists.id AND todos.deleted_at IS NULL AND todos.completed = 1),
+    lists.created_at, lists.updated_at";
+
+fn row_to_list(row: &rusqlite::Row) -> rusqlite::Result<TodoList> {
+    Ok(TodoList {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        icon: row.get(3)?,
+        archived: row.get::<_, i32>(4)? != 0,
+        default_priority: Priority::from_i32(row.get(5)?),
+        todo_count: row.get(6)?,
+        completed_count: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+/// 在给定连接上读取单个列表，供其他 repository 在事务内使用
+pub(crate) fn load_list(conn: &Connection, id: &str) -> Result<TodoList, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM lists WHERE lists.id = ?1", LIST_COLUMNS),
+        [id],
+        row_to_list,
+    )
+    .map_err(|_| AppError::ListNotFound(id.to_string()))
+}
+
+/// 空字符串表示清除该字段
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+pub struct ListRepository {
+    db: Arc<Database>,
+}
+
+impl ListRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 列出全部列表，按名称排序；`include_archived` 为 false 时跳过已归档的列表
+    pub fn get_all(&self, include_archived: bool) -> Result<Vec<TodoList>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM lists WHERE ?1 OR lists.archived = 0 ORDER BY lists.name COLLATE NOCASE",
+                LIST_COLUMNS
+            ))?;
+
+            let lists = stmt.query_map([include_archived], row_to_list)?;
+
+            let mut result = Vec::new();
+            for list in lists {
+                result.push(list?);
+            }
+            Ok(result)
+        })
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Result<TodoList, AppError> {
+        self.db.with_conn(|conn| load_list(conn, id))
+    }
+
+    /// 按名称（忽略大小写）查找列表，供 AI 通过名称指定列表
+    pub fn find_by_name(&self, name: &str) -> Result<TodoList, AppError> {
+        self.db.with_conn(|conn| {
+            let id: Option<String> = conn
+                .query_row("SELECT id FROM lists WHERE name = ?1", [name.trim()], |row| row.get(0))
+                .optional()?;
+
+            match id {
+                Some(id) => load_list(conn, &id),
+                None => Err(AppError::ListNotFound(name.to_string())),
+            }
+        })
+    }
+
+    pub fn create(&self, request: CreateListRequest) -> Result<TodoList, AppError> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(AppError::InvalidArgument("List name cannot be empty".into()));
+        }
+        if let Some(ref color) = request.color {
+            tag_repo::validate_color(color)?;
+        }
+
+        self.db.with_conn(|conn| {
+            Self::ensure_name_available(conn, name, None)?;
+
+            let id = Uuid::new_v4().to_string();
+            let now = Utc::now().to_rfc3339();
+            conn.execute(
+                "INSERT INTO lists (id, name, color, icon, archived, default_priority, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?6)",
+                (
+                    &id,
+                    name,
+                    &request.color,
+                    request.icon.as_deref().and_then(non_empty),
+                    request.default_priority.unwrap_or_default().as_i32(),
+                    &now,
+                ),
+            )?;
+
+            load_list(conn, &id)
+        })
+    }
+
+    pub fn update(&self, id: &str, request: UpdateListRequest) -> Result<TodoList, AppError> {
+        self.db.with_conn(|conn| {
+            let existing = load_list(conn, id)?;
+
+            let name = match request.name {
+                Some(ref name) => {
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(AppError::InvalidArgument("List name cannot be empty".into()));
+                    }
+                    Self::ensure_name_available(conn, name, Some(id))?;
+                    name.to_string()
+                }
+                None => existing.name,
+            };
+
+            let color = match request.color {
+                Some(ref color) => non_empty(color),
+                None => existing.color,
+            };
+            if let Some(ref color) = color {
+                tag_repo::validate_color(color)?;
+            }
+
+            let icon = match request.icon {
+                Some(ref icon) => non_empty(icon),
+                None => existing.icon,
+            };
+            let archived = request.archived.unwrap_or(existing.archived);
+            let default_priority = request.default_priority.unwrap_or(existing.default_priority);
+
+            conn.execute(
+                "UPDATE lists SET name = ?1, color = ?2, icon = ?3, archived = ?4, default_priority = ?5, updated_at = ?6
+                 WHERE id = ?7",
+                (
+                    &name,
+                    &color,
+                    &icon,
+                    if archived { 1 } else { 0 },
+                    default_priority.as_i32(),
+                    Utc::now().to_rfc3339(),
+                    id,
+                ),
+            )?;
+
+            load_list(conn, id)
+        })
+    }
+
+    /// 删除列表，其中的任务（包括回收站中的）移回收件箱，返回移动的任务数
+    pub fn delete(&self, id: &str, actor: &Actor) -> Result<u32, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            load_list(&tx, id)?;
+
+            let todo_ids: Vec<String> = {
+                let mut stmt = tx.prepare("SELECT id FROM todos WHERE list_id = ?1")?;
+                let rows = stmt.query_map([id], |row| row.get(0))?;
+                rows.collect::<Result<_, _>>()?
+            };
+
+            let now = Utc::now().to_rfc3339();
+            let ctx = EventContext::new(actor);
+            for todo_id in &todo_ids {
+                let before = todo_repo::load_todo(&tx, todo_id)?;
+                tx.execute(
+                    "UPDATE todos SET list_id = NULL, updated_at = ?1 WHERE id = ?2",
+                    (&now, todo_id),
+                )?;
+                let after = todo_repo::load_todo(&tx, todo_id)?;
+                history_repo::record_event(&tx, &ctx, TodoAction::Updated, Some(&before), Some(&after))?;
+            }
+
+            tx.execute("DELETE FROM lists WHERE id = ?1", [id])?;
+            tx.commit()?;
+            Ok(todo_ids.len() as u32)
+        })
+    }
+
+    fn ensure_name_available(conn: &Connection, name: &str, except_id: Option<&str>) -> Result<(), AppError> {
+        let conflict: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM lists WHERE name = ?1 AND id IS NOT ?2)",
+            (name, except_id),
+            |row| row.get(0),
+        )?;
+
+        if conflict {
+            return Err(AppError::InvalidArgument(format!("List '{}' already exists", name)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TestDb, TodoRepository};
+    use crate::models::todo::{CreateTodoRequest, Todo, TodoFilter, UpdateTodoRequest};
+
+    fn create_list(repo: &ListRepository, name: &str) -> TodoList {
+        repo.create(CreateListRequest { name: name.into(), ..Default::default() }).unwrap()
+    }
+
+    fn create_in(todos: &TodoRepository, text: &str, list: Option<&TodoList>) -> Todo {
+        let request = CreateTodoRequest {
+            text: text.into(),
+            list_id: list.map(|l| l.id.clone()),
+            ..Default::default()
+        };
+        todos.create(request, &Actor::User).unwrap()
+    }
+
+    fn texts_in(todos: &TodoRepository, list_id: Option<&str>) -> Vec<String> {
+        let filter = TodoFilter { list_id: list_id.map(String::from), ..Default::default() };
+        let mut texts: Vec<String> = todos.get_all(Some(filter)).unwrap().into_iter().map(|t| t.text).collect();
+        texts.sort();
+        texts
+    }
+
+    #[test]
+    fn filters_and_statistics_are_scoped_to_the_list() {
+        let test = TestDb::new();
+        let repo = ListRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let work = create_list(&repo, "Work");
+        let shopping = create_list(&repo, "Shopping");
+        let report = create_in(&todos, "report", Some(&work));
+        create_in(&todos, "milk", Some(&shopping));
+        create_in(&todos, "inbox item", None);
+        todos.update(&report.id, UpdateTodoRequest { completed: Some(true), ..Default::default() }, &Actor::User).unwrap();
+
+        assert_eq!(texts_in(&todos, Some(&work.id)), vec!["report"]);
+        assert_eq!(texts_in(&todos, Some("")), vec!["inbox item"]);
+        assert_eq!(texts_in(&todos, None), vec!["inbox item", "milk", "report"]);
+
+        let stats = todos.get_statistics(Some(&work.id)).unwrap();
+        assert_eq!((stats.total, stats.completed), (1, 1));
+        let work = repo.get_by_id(&work.id).unwrap();
+        assert_eq!((work.todo_count, work.completed_count), (1, 1));
+    }
+
+    #[test]
+    fn archived_lists_are_hidden_from_unscoped_queries() {
+        let test = TestDb::new();
+        let repo = ListRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let old = create_list(&repo, "Old project");
+        create_in(&todos, "archived item", Some(&old));
+        create_in(&todos, "inbox item", None);
+
+        repo.update(&old.id, UpdateListRequest { archived: Some(true), ..Default::default() }).unwrap();
+
+        assert_eq!(texts_in(&todos, None), vec!["inbox item"]);
+        assert_eq!(todos.get_statistics(None).unwrap().total, 1);
+        assert_eq!(texts_in(&todos, Some(&old.id)), vec!["archived item"]);
+        assert!(repo.get_all(false).unwrap().is_empty());
+        assert_eq!(repo.get_all(true).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn new_todos_use_list_defaults() {
+        let test = TestDb::new();
+        let repo = ListRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let urgent = repo
+            .create(CreateListRequest { name: "Urgent".into(), default_priority: Some(Priority::High), ..Default::default() })
+            .unwrap();
+
+        let parent = create_in(&todos, "parent", Some(&urgent));
+        let child = todos
+            .create(CreateTodoRequest { text: "child".into(), parent_id: Some(parent.id.clone()), ..Default::default() }, &Actor::User)
+            .unwrap();
+        let explicit = todos
+            .create(
+                CreateTodoRequest {
+                    text: "explicit".into(),
+                    list_id: Some(urgent.id.clone()),
+                    priority: Some(Priority::Low),
+                    ..Default::default()
+                },
+                &Actor::User,
+            )
+            .unwrap();
+
+        assert_eq!(parent.priority, Priority::High);
+        assert_eq!(child.list_id.as_deref(), Some(urgent.id.as_str()));
+        assert_eq!(child.priority, Priority::High);
+        assert_eq!(explicit.priority, Priority::Low);
+    }
+
+    #[test]
+    fn deleting_a_list_moves_its_todos_to_the_inbox() {
+        let test = TestDb::new();
+        let repo = ListRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let work = create_list(&repo, "Work");
+        let kept = create_in(&todos, "kept", Some(&work));
+        let trashed = create_in(&todos, "trashed", Some(&work));
+        todos.delete(&trashed.id, &Actor::User).unwrap();
+
+        assert!(matches!(
+            repo.create(CreateListRequest { name: " Work ".into(), ..Default::default() }),
+            Err(AppError::InvalidArgument(_))
+        ));
+
+        assert_eq!(repo.delete(&work.id, &Actor::User).unwrap(), 2);
+        assert!(matches!(repo.get_by_id(&work.id), Err(AppError::ListNotFound(_))));
+        assert_eq!(todos.get_by_id(&kept.id).unwrap().list_id, None);
+        assert_eq!(todos.get_by_id(&trashed.id).unwrap().list_id, None);
+        assert_eq!(texts_in(&todos, Some("")), vec!["kept"]);
+    }
+}
+
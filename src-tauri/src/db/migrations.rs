@@ -0,0 +1,1105 @@
+use crate::error::AppError;
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use std::collections::HashMap;
+use serde::Serialize;
+
+/// 一个带编号的 schema 迁移。
+///
+/// 已发布的迁移不能再修改，新的表结构变更只能追加新的迁移，版本号必须严格递增。
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub up: fn(&Connection) -> Result<(), AppError>,
+}
+
+/// 按版本号排序的全部迁移
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        up: m0001_initial_schema,
+    },
+    Migration {
+        version: 2,
+        name: "todos_fts",
+        up: m0002_todos_fts,
+    },
+    Migration {
+        version: 3,
+        name: "normalized_tags",
+        up: m0003_normalized_tags,
+    },
+    Migration {
+        version: 4,
+        name: "todos_soft_delete",
+        up: m0004_todos_soft_delete,
+    },
+    Migration {
+        version: 5,
+        name: "todo_events",
+        up: m0005_todo_events,
+    },
+    Migration {
+        version: 6,
+        name: "undo_steps",
+        up: m0006_undo_steps,
+    },
+    Migration {
+        version: 7,
+        name: "todos_position",
+        up: m0007_todos_position,
+    },
+    Migration {
+        version: 8,
+        name: "todos_hierarchy",
+        up: m0008_todos_hierarchy,
+    },
+    Migration {
+        version: 9,
+        name: "todo_dependencies",
+        up: m0009_todo_dependencies,
+    },
+    Migration {
+        version: 10,
+        name: "lists",
+        up: m0010_lists,
+    },
+    Migration {
+        version: 11,
+        name: "todo_recurrence",
+        up: m0011_todo_recurrence,
+    },
+    Migration {
+        version: 12,
+        name: "reminders",
+        up: m0012_reminders,
+    },
+    Migration {
+        version: 13,
+        name: "todo_due_at",
+        up: m0013_todo_due_at,
+    },
+    Migration {
+        version: 14,
+        name: "todo_notes",
+        up: m0014_todo_notes,
+    },
+    Migration {
+        version: 15,
+        name: "attachments",
+        up: m0015_attachments,
+    },
+    Migration {
+        version: 16,
+        name: "time_entries",
+        up: m0016_time_entries,
+    },
+    Migration {
+        version: 17,
+        name: "focus_sessions",
+        up: m0017_focus_sessions,
+    },
+    Migration {
+        version: 18,
+        name: "todo_estimates",
+        up: m0018_todo_estimates,
+    },
+    Migration {
+        version: 19,
+        name: "todo_status_timestamps",
+        up: m0019_todo_status_timestamps,
+    },
+    Migration {
+        version: 20,
+        name: "workflow_states",
+        up: m0020_workflow_states,
+    },
+    Migration {
+        version: 21,
+        name: "detachable_undo_events",
+        up: m0021_detachable_undo_events,
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationInfo {
+    pub version: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    /// 执行前数据库所处的版本
+    pub current_version: u32,
+    /// 当前程序支持的最新版本
+    pub latest_version: u32,
+    /// 尚未应用的迁移
+    pub pending: Vec<MigrationInfo>,
+    /// 为 true 时只生成报告，没有修改数据库
+    pub dry_run: bool,
+}
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// 读取数据库当前的 schema 版本，没有 `schema_version` 表时视为 0
+pub fn current_version(conn: &Connection) -> Result<u32, AppError> {
+    let has_table: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if !has_table {
+        return Ok(0);
+    }
+
+    let version: Option<u32> = conn.query_row(
+        "SELECT MAX(version) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+
+    Ok(version.unwrap_or(0))
+}
+
+/// 检查并应用所有未执行的迁移。
+///
+/// 所有待执行的迁移在同一个事务中完成，任何一步失败都会整体回滚。
+/// `dry_run` 为 true 时只返回报告，不会写入数据库。
+/// 如果数据库由更新版本的程序写入（版本号高于 `latest_version()`），拒绝打开。
+pub fn run(conn: &Connection, dry_run: bool) -> Result<MigrationReport, AppError> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(AppError::SchemaTooNew {
+            found: current,
+            supported: latest,
+        });
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+
+    let report = MigrationReport {
+        current_version: current,
+        latest_version: latest,
+        pending: pending
+            .iter()
+            .map(|m| MigrationInfo {
+                version: m.version,
+                name: m.name.to_string(),
+            })
+            .collect(),
+        dry_run,
+    };
+
+    if dry_run || pending.is_empty() {
+        return Ok(report);
+    }
+
+    // IMMEDIATE 事务：在开始时就拿到写锁，避免与其他连接交错执行迁移
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    for migration in pending {
+        log::info!("Applying migration {:04} {}", migration.version, migration.name);
+
+        (migration.up)(&tx)?;
+
+        tx.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (?1, ?2, ?3)",
+            (migration.version, migration.name, Utc::now().to_rfc3339()),
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(report)
+}
+
+// ===== 迁移定义 =====
+
+/// 初始表结构。使用 IF NOT EXISTS 以兼容引入迁移机制之前创建的数据库。
+fn m0001_initial_schema(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS todos (
+            id TEXT PRIMARY KEY,
+            text TEXT NOT NULL,
+            completed INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'pending',
+            priority INTEGER NOT NULL DEFAULT 0,
+            due_date TEXT,
+            tags TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_todos_status ON todos(status);
+        CREATE INDEX IF NOT EXISTS idx_todos_completed ON todos(completed);
+        CREATE INDEX IF NOT EXISTS idx_todos_created_at ON todos(created_at);
+
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// todos 的 FTS5 全文索引，通过触发器与 todos 表保持同步。
+/// 索引内容经过 `fts_segment()` 分词处理，见 `db::fts`。
+fn m0002_todos_fts(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE todos_fts USING fts5(
+            id UNINDEXED,
+            text,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER todos_fts_insert AFTER INSERT ON todos BEGIN
+            INSERT INTO todos_fts (id, text) VALUES (new.id, fts_segment(new.text));
+        END;
+
+        CREATE TRIGGER todos_fts_delete AFTER DELETE ON todos BEGIN
+            DELETE FROM todos_fts WHERE id = old.id;
+        END;
+
+        CREATE TRIGGER todos_fts_update AFTER UPDATE OF text ON todos BEGIN
+            UPDATE todos_fts SET text = fts_segment(new.text) WHERE id = old.id;
+        END;
+
+        INSERT INTO todos_fts (id, text) SELECT id, fts_segment(text) FROM todos;",
+    )?;
+
+    Ok(())
+}
+
+/// 把 `todos.tags` 中的 JSON 数组拆分到 `tags` 与 `todo_tags` 表，随后删除旧列
+fn m0003_normalized_tags(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE tags (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            color TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE todo_tags (
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (todo_id, tag_id)
+        );
+
+        CREATE INDEX idx_todo_tags_tag_id ON todo_tags(tag_id);",
+    )?;
+
+    let rows: Vec<(String, Option<String>)> = {
+        let mut stmt = conn.prepare("SELECT id, tags FROM todos")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let now = Utc::now().to_rfc3339();
+    // 标签名忽略大小写去重，保留第一次出现时的写法
+    let mut tag_ids: HashMap<String, String> = HashMap::new();
+
+    for (todo_id, tags_json) in rows {
+        let tags: Vec<String> = tags_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        for name in tags {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+
+            let tag_id = match tag_ids.get(&name.to_lowercase()) {
+                Some(id) => id.clone(),
+                None => {
+                    let id = uuid::Uuid::new_v4().to_string();
+                    conn.execute(
+                        "INSERT INTO tags (id, name, color, created_at) VALUES (?1, ?2, NULL, ?3)",
+                        (&id, name, &now),
+                    )?;
+                    tag_ids.insert(name.to_lowercase(), id.clone());
+                    id
+                }
+            };
+
+            conn.execute(
+                "INSERT OR IGNORE INTO todo_tags (todo_id, tag_id) VALUES (?1, ?2)",
+                (&todo_id, &tag_id),
+            )?;
+        }
+    }
+
+    conn.execute_batch("ALTER TABLE todos DROP COLUMN tags;")?;
+
+    Ok(())
+}
+
+/// 回收站：`deleted_at` 非空的任务视为已删除
+fn m0004_todos_soft_delete(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN deleted_at TEXT;
+
+        CREATE INDEX idx_todos_deleted_at ON todos(deleted_at);",
+    )?;
+
+    Ok(())
+}
+
+/// 任务变更历史。只允许追加，更新与删除会被触发器拒绝。
+/// `todo_id` 不设外键，任务被永久删除后历史记录仍然保留。
+fn m0005_todo_events(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE todo_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            todo_id TEXT NOT NULL,
+            action TEXT NOT NULL,
+            actor_kind TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            changes TEXT NOT NULL,
+            before TEXT,
+            after TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_todo_events_todo_id ON todo_events(todo_id);
+        CREATE INDEX idx_todo_events_created_at ON todo_events(created_at);
+
+        CREATE TRIGGER todo_events_no_update BEFORE UPDATE ON todo_events BEGIN
+            SELECT RAISE(ABORT, 'todo_events is append-only');
+        END;
+
+        CREATE TRIGGER todo_events_no_delete BEFORE DELETE ON todo_events BEGIN
+            SELECT RAISE(ABORT, 'todo_events is append-only');
+        END;",
+    )?;
+
+    Ok(())
+}
+
+/// 撤销/重做：事件按 `step_id` 归组，`undo_steps.undone_at` 非空的步骤位于重做栈
+fn m0006_undo_steps(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todo_events ADD COLUMN step_id TEXT;
+
+        CREATE INDEX idx_todo_events_step_id ON todo_events(step_id);
+
+        CREATE TABLE undo_steps (
+            id TEXT PRIMARY KEY,
+            actor TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            undone_at TEXT
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// 手动排序：`position` 为稀疏的浮点数，移动任务时取相邻两个位置的中间值。
+/// 已有任务按创建时间先后依次分配位置。
+fn m0007_todos_position(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN position REAL NOT NULL DEFAULT 0;
+
+        CREATE INDEX idx_todos_position ON todos(position);",
+    )?;
+
+    // 间隔与 `todo_repo::POSITION_GAP` 一致，迁移中写死以免常量改动影响已发布的迁移
+    conn.execute_batch(
+        "UPDATE todos SET position = 1024.0 * (
+            SELECT ordered.rn FROM (
+                SELECT id, ROW_NUMBER() OVER (ORDER BY created_at, id) AS rn FROM todos
+            ) AS ordered
+            WHERE ordered.id = todos.id
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// 子任务：`parent_id` 指向父任务。删除与恢复的级联规则由 `TodoRepository` 处理并记录历史，
+/// 外键的级联删除只作为兜底。
+fn m0008_todos_hierarchy(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN parent_id TEXT REFERENCES todos(id) ON DELETE CASCADE;
+        ALTER TABLE todos ADD COLUMN auto_complete INTEGER NOT NULL DEFAULT 0;
+
+        CREATE INDEX idx_todos_parent_id ON todos(parent_id);",
+    )?;
+
+    Ok(())
+}
+
+/// 任务依赖：`todo_id` 必须在 `depends_on_id` 完成后才能开始。环路检测由 `TodoRepository` 负责。
+fn m0009_todo_dependencies(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE todo_dependencies (
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            depends_on_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (todo_id, depends_on_id)
+        );
+
+        CREATE INDEX idx_todo_dependencies_depends_on ON todo_dependencies(depends_on_id);",
+    )?;
+
+    Ok(())
+}
+
+/// 任务列表（项目）。不属于任何列表的任务（`list_id` 为 NULL）显示在收件箱中。
+fn m0010_lists(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE lists (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            color TEXT,
+            icon TEXT,
+            archived INTEGER NOT NULL DEFAULT 0,
+            default_priority INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        ALTER TABLE todos ADD COLUMN list_id TEXT REFERENCES lists(id) ON DELETE SET NULL;
+
+        CREATE INDEX idx_todos_list_id ON todos(list_id);",
+    )?;
+
+    Ok(())
+}
+
+/// 重复任务：同一序列中的任务共享 `series_id`，`occurrence` 为序号
+fn m0011_todo_recurrence(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN recurrence TEXT;
+        ALTER TABLE todos ADD COLUMN series_id TEXT;
+        ALTER TABLE todos ADD COLUMN occurrence INTEGER NOT NULL DEFAULT 1;
+
+        CREATE INDEX idx_todos_series ON todos(series_id, occurrence);",
+    )?;
+
+    Ok(())
+}
+
+/// `overdue_notices` 记录已发出逾期通知的任务及当时的截止日期，截止日期改变后会再次通知
+fn m0012_reminders(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE reminders (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            offset_minutes INTEGER,
+            remind_at TEXT,
+            snoozed_until TEXT,
+            fired_at TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_reminders_todo ON reminders(todo_id);
+        CREATE INDEX idx_reminders_pending ON reminders(fired_at, remind_at);
+
+        CREATE TABLE overdue_notices (
+            todo_id TEXT PRIMARY KEY REFERENCES todos(id) ON DELETE CASCADE,
+            due_date TEXT NOT NULL,
+            notified_at TEXT NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// `todos.due_at` 保存截止时刻（UTC），用于排序和按截止时间过滤。
+///
+/// 同时规范化已有的 `due_date`：以前可以保存任意文本，规则见 `m0013_normalize_due_date`，
+/// 无法识别的清空并记录日志。此时还没有时区设置，按系统时区计算。
+fn m0013_todo_due_at(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN due_at TEXT;
+
+        CREATE INDEX idx_todos_due_at ON todos(due_at);",
+    )?;
+
+    let rows: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, due_date, created_at FROM todos WHERE due_date IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    for (id, value, created_at) in rows {
+        let normalized = m0013_normalize_due_date(&value, &created_at);
+        if normalized.is_none() && !value.trim().is_empty() {
+            log::warn!("Dropping unrecognized due date of todo {}: {}", id, value);
+        }
+
+        let (due_date, due_at) = normalized.unzip();
+        conn.execute(
+            "UPDATE todos SET due_date = ?1, due_at = ?2 WHERE id = ?3",
+            (due_date, due_at, &id),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 把 v12 及之前保存的截止日期换算为 `(due_date, due_at)`。
+///
+/// 规则在这里写死，不调用 `DueDate`、`date_expr`，以免之后的解析改动影响已发布的迁移：
+/// 接受 `YYYY-MM-DD`、RFC 3339、不带时区的 `YYYY-MM-DD[T ]HH:MM[:SS]`（系统时区），
+/// 以及以任务创建日期为基准的今天、明天、后天（today、tomorrow）。
+fn m0013_normalize_due_date(value: &str, created_at: &str) -> Option<(String, String)> {
+    let value = value.trim();
+    let utc = |instant: DateTime<Utc>| instant.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let all_day = |date: NaiveDate| {
+        let end = Local.from_local_datetime(&date.checked_add_days(Days::new(1))?.and_time(NaiveTime::MIN)).earliest()?;
+        Some((date.format("%Y-%m-%d").to_string(), utc(end.with_timezone(&Utc))))
+    };
+    let at = |instant: DateTime<FixedOffset>| {
+        let due_date = match instant.offset().local_minus_utc() {
+            0 => instant.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            _ => instant.format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+        };
+        Some((due_date, utc(instant.with_timezone(&Utc))))
+    };
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return all_day(date);
+    }
+    if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+        return at(instant);
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(local) = NaiveDateTime::parse_from_str(value, format) {
+            return at(Local.from_local_datetime(&local).earliest()?.fixed_offset());
+        }
+    }
+
+    let days = match value.to_lowercase().as_str() {
+        "今天" | "today" => 0,
+        "明天" | "tomorrow" => 1,
+        "后天" => 2,
+        _ => return None,
+    };
+    let created = DateTime::parse_from_rfc3339(created_at).ok()?.with_timezone(&Local).date_naive();
+    all_day(created.checked_add_days(Days::new(days))?)
+}
+
+/// 任务备注（Markdown）与清单项。全文索引重建为 `text`、`notes`、`checklist` 三列，
+/// `checklist` 为任务全部清单项文本的拼接，由清单项上的触发器维护。
+fn m0014_todo_notes(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN notes TEXT;
+
+        CREATE TABLE checklist_items (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            text TEXT NOT NULL,
+            done INTEGER NOT NULL DEFAULT 0,
+            position REAL NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_checklist_items_todo ON checklist_items(todo_id, position);
+
+        DROP TRIGGER todos_fts_insert;
+        DROP TRIGGER todos_fts_delete;
+        DROP TRIGGER todos_fts_update;
+        DROP TABLE todos_fts;
+
+        CREATE VIRTUAL TABLE todos_fts USING fts5(
+            id UNINDEXED,
+            text,
+            notes,
+            checklist,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER todos_fts_insert AFTER INSERT ON todos BEGIN
+            INSERT INTO todos_fts (id, text, notes, checklist)
+            VALUES (new.id, fts_segment(new.text), fts_segment(new.notes), NULL);
+        END;
+
+        CREATE TRIGGER todos_fts_delete AFTER DELETE ON todos BEGIN
+            DELETE FROM todos_fts WHERE id = old.id;
+        END;
+
+        CREATE TRIGGER todos_fts_update AFTER UPDATE OF text, notes ON todos BEGIN
+            UPDATE todos_fts SET text = fts_segment(new.text), notes = fts_segment(new.notes) WHERE id = old.id;
+        END;
+
+        CREATE TRIGGER checklist_fts_insert AFTER INSERT ON checklist_items BEGIN
+            UPDATE todos_fts SET checklist = (
+                SELECT fts_segment(group_concat(text, char(10))) FROM checklist_items WHERE todo_id = new.todo_id
+            ) WHERE id = new.todo_id;
+        END;
+
+        CREATE TRIGGER checklist_fts_update AFTER UPDATE OF text ON checklist_items BEGIN
+            UPDATE todos_fts SET checklist = (
+                SELECT fts_segment(group_concat(text, char(10))) FROM checklist_items WHERE todo_id = new.todo_id
+            ) WHERE id = new.todo_id;
+        END;
+
+        CREATE TRIGGER checklist_fts_delete AFTER DELETE ON checklist_items BEGIN
+            UPDATE todos_fts SET checklist = (
+                SELECT fts_segment(group_concat(text, char(10))) FROM checklist_items WHERE todo_id = old.todo_id
+            ) WHERE id = old.todo_id;
+        END;
+
+        INSERT INTO todos_fts (id, text, notes, checklist) SELECT id, fts_segment(text), NULL, NULL FROM todos;",
+    )?;
+
+    Ok(())
+}
+
+/// 附件元数据，文件本身按 `hash` 保存在附件目录中
+fn m0015_attachments(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE attachments (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            file_name TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            hash TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_attachments_todo ON attachments(todo_id);
+        CREATE INDEX idx_attachments_hash ON attachments(hash);",
+    )?;
+
+    Ok(())
+}
+
+/// 计时记录。部分唯一索引保证同一时间最多只有一个进行中的计时。
+fn m0016_time_entries(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE time_entries (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_time_entries_todo ON time_entries(todo_id, started_at);
+        CREATE INDEX idx_time_entries_started ON time_entries(started_at);
+        CREATE UNIQUE INDEX idx_time_entries_running ON time_entries((ended_at IS NULL)) WHERE ended_at IS NULL;",
+    )?;
+
+    Ok(())
+}
+
+/// 已结束的专注阶段。任务被永久删除后保留记录，继续计入统计。
+fn m0017_focus_sessions(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE focus_sessions (
+            id TEXT PRIMARY KEY,
+            todo_id TEXT REFERENCES todos(id) ON DELETE SET NULL,
+            started_at TEXT NOT NULL,
+            ended_at TEXT NOT NULL,
+            planned_seconds INTEGER NOT NULL,
+            focused_seconds INTEGER NOT NULL,
+            completed INTEGER NOT NULL,
+            interruptions TEXT NOT NULL DEFAULT '[]'
+        );
+
+        CREATE INDEX idx_focus_sessions_todo ON focus_sessions(todo_id);
+        CREATE INDEX idx_focus_sessions_started ON focus_sessions(started_at);",
+    )?;
+
+    Ok(())
+}
+
+/// 任务的预估工作量：分钟数与故事点数，都可以为空
+fn m0018_todo_estimates(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN estimate_minutes INTEGER;
+        ALTER TABLE todos ADD COLUMN story_points REAL;",
+    )?;
+
+    Ok(())
+}
+
+/// 状态变化的时间：`started_at`、`completed_at`、`cancelled_at`。
+/// 以前 `completed` 与 `status` 可能不一致，任一方表示已完成时统一为已完成。
+/// 已有任务的时间取自任务历史，没有历史记录时取最后修改时间。
+fn m0019_todo_status_timestamps(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN started_at TEXT;
+        ALTER TABLE todos ADD COLUMN completed_at TEXT;
+        ALTER TABLE todos ADD COLUMN cancelled_at TEXT;
+
+        UPDATE todos SET status = 'completed' WHERE completed = 1 AND status != 'completed';
+        UPDATE todos SET completed = 1 WHERE status = 'completed' AND completed = 0;
+
+        UPDATE todos SET started_at = (
+            SELECT MIN(created_at) FROM todo_events
+            WHERE todo_id = todos.id AND json_extract(changes, '$.status.after') = 'in_progress'
+        );
+        UPDATE todos SET started_at = updated_at WHERE status = 'in_progress' AND started_at IS NULL;
+        UPDATE todos SET completed_at = COALESCE((
+            SELECT MAX(created_at) FROM todo_events
+            WHERE todo_id = todos.id AND (json_extract(changes, '$.completed.after') = 1
+                OR json_extract(changes, '$.status.after') = 'completed')
+        ), updated_at) WHERE status = 'completed';
+        UPDATE todos SET cancelled_at = COALESCE((
+            SELECT MAX(created_at) FROM todo_events
+            WHERE todo_id = todos.id AND json_extract(changes, '$.status.after') = 'cancelled'
+        ), updated_at) WHERE status = 'cancelled';
+
+        CREATE INDEX idx_todos_completed_at ON todos(completed_at);",
+    )?;
+
+    Ok(())
+}
+
+/// 自定义工作流状态（看板列）。每个状态属于一个类别（open/active/done），决定任务的 `status`；
+/// 已取消的任务不属于任何状态。预置与原有状态对应的三个状态，并按 `status` 设置已有任务的状态。
+/// `workflow_transitions` 为空的状态可以变为任意状态。
+fn m0020_workflow_states(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "CREATE TABLE workflow_states (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            color TEXT,
+            category TEXT NOT NULL CHECK (category IN ('open', 'active', 'done')),
+            position REAL NOT NULL,
+            wip_limit INTEGER,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE workflow_transitions (
+            from_state_id TEXT NOT NULL REFERENCES workflow_states(id) ON DELETE CASCADE,
+            to_state_id TEXT NOT NULL REFERENCES workflow_states(id) ON DELETE CASCADE,
+            PRIMARY KEY (from_state_id, to_state_id)
+        );
+
+        ALTER TABLE todos ADD COLUMN state_id TEXT REFERENCES workflow_states(id) ON DELETE SET NULL;
+
+        CREATE INDEX idx_todos_state_id ON todos(state_id);",
+    )?;
+
+    // 时间格式与之后新建的状态一致
+    let now = Utc::now().to_rfc3339();
+    for (id, name, category, position) in [
+        ("pending", "待办", "open", 1024.0),
+        ("in_progress", "进行中", "active", 2048.0),
+        ("completed", "已完成", "done", 3072.0),
+    ] {
+        conn.execute(
+            "INSERT INTO workflow_states (id, name, category, position, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            (id, name, category, position, &now),
+        )?;
+    }
+    conn.execute(
+        "UPDATE todos SET state_id = status WHERE status IN ('pending', 'in_progress', 'completed')",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// 历史仍然只允许追加，但允许把事件的 `step_id` 清空：任务被永久删除后，
+/// 只把它的事件移出撤销步骤，同一步骤中其他任务的修改仍可撤销。
+fn m0021_detachable_undo_events(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(
+        "DROP TRIGGER todo_events_no_update;
+
+        CREATE TRIGGER todo_events_no_update
+        BEFORE UPDATE OF id, todo_id, action, actor_kind, actor, changes, before, after, created_at ON todo_events BEGIN
+            SELECT RAISE(ABORT, 'todo_events is append-only');
+        END;
+
+        CREATE TRIGGER todo_events_step_detach_only
+        BEFORE UPDATE OF step_id ON todo_events WHEN NEW.step_id IS NOT NULL BEGIN
+            SELECT RAISE(ABORT, 'todo_events.step_id can only be cleared');
+        END;",
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fts;
+
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        fts::register_functions(&conn).unwrap();
+        conn
+    }
+
+    /// 与 `run` 相同地应用到 `version` 为止的迁移，用来构造旧版本的数据库
+    fn migrate_to(conn: &Connection, version: u32) {
+        conn.execute_batch(
+            "CREATE TABLE schema_version (version INTEGER PRIMARY KEY, name TEXT NOT NULL, applied_at TEXT NOT NULL);",
+        )
+        .unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            (migration.up)(conn).unwrap();
+            conn.execute(
+                "INSERT INTO schema_version (version, name, applied_at) VALUES (?1, ?2, ?3)",
+                (migration.version, migration.name, Utc::now().to_rfc3339()),
+            )
+            .unwrap();
+        }
+    }
+
+    /// 引入迁移机制之前由 `init_schema` 创建的表结构
+    const BASELINE_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS todos (
+            id TEXT PRIMARY KEY,
+            text TEXT NOT NULL,
+            completed INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'pending',
+            priority INTEGER NOT NULL DEFAULT 0,
+            due_date TEXT,
+            tags TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_todos_status ON todos(status);
+        CREATE INDEX IF NOT EXISTS idx_todos_completed ON todos(completed);
+        CREATE INDEX IF NOT EXISTS idx_todos_created_at ON todos(created_at);
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );";
+
+    /// 全部表、索引、触发器的名称以及每个表的列，用于比较两个数据库的结构
+    fn schema(conn: &Connection) -> Vec<(String, String, Vec<String>)> {
+        let mut stmt = conn
+            .prepare("SELECT type, name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%' ORDER BY type, name")
+            .unwrap();
+        let objects: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        objects
+            .into_iter()
+            .map(|(kind, name)| {
+                let columns = if kind == "table" {
+                    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1) ORDER BY cid").unwrap();
+                    stmt.query_map([&name], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+                } else {
+                    Vec::new()
+                };
+                (kind, name, columns)
+            })
+            .collect()
+    }
+
+    fn applied_versions(conn: &Connection) -> Vec<u32> {
+        let mut stmt = conn.prepare("SELECT version FROM schema_version ORDER BY version").unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn versions_strictly_increase() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+        assert_eq!(MIGRATIONS[0].version, 1);
+        assert_eq!(latest_version(), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn applies_all_migrations_to_empty_database() {
+        let conn = open();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+
+        let report = run(&conn, false).unwrap();
+        assert_eq!(report.current_version, 0);
+        assert_eq!(report.latest_version, latest_version());
+        assert_eq!(report.pending.len(), MIGRATIONS.len());
+        assert!(!report.dry_run);
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert_eq!(applied_versions(&conn), MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>());
+        conn.execute_batch("PRAGMA foreign_key_check;").unwrap();
+    }
+
+    #[test]
+    fn applies_all_migrations_to_baseline_schema() {
+        let conn = open();
+        conn.execute_batch(BASELINE_SCHEMA).unwrap();
+        conn.execute(
+            "INSERT INTO todos (id, text, completed, status, priority, due_date, tags, created_at, updated_at)
+             VALUES ('a', '写周报', 1, 'completed', 2, '2025-03-01', '[\"工作\", \"Work\", \"work \", \"周报\"]',
+                     '2025-02-01T08:00:00+00:00', '2025-02-02T08:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+
+        run(&conn, false).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        // 与新建的数据库结构相同
+        let fresh = open();
+        run(&fresh, false).unwrap();
+        assert_eq!(schema(&conn), schema(&fresh));
+
+        let (text, status, completed_at): (String, String, Option<String>) = conn
+            .query_row("SELECT text, status, completed_at FROM todos WHERE id = 'a'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((text.as_str(), status.as_str()), ("写周报", "completed"));
+        assert!(completed_at.is_some());
+        // 标签拆分到 todo_tags，忽略大小写去重
+        let mut tags: Vec<String> = {
+            let mut stmt = conn
+                .prepare("SELECT tags.name FROM todo_tags JOIN tags ON tags.id = todo_tags.tag_id WHERE todo_id = 'a'")
+                .unwrap();
+            stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+        };
+        tags.sort();
+        assert_eq!(tags, ["Work", "周报", "工作"]);
+    }
+
+    #[test]
+    fn rerun_is_a_no_op() {
+        let conn = open();
+        run(&conn, false).unwrap();
+        let before = schema(&conn);
+
+        let report = run(&conn, false).unwrap();
+        assert_eq!(report.current_version, latest_version());
+        assert!(report.pending.is_empty());
+        assert_eq!(schema(&conn), before);
+        assert_eq!(applied_versions(&conn).len(), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn dry_run_does_not_write() {
+        let conn = open();
+        let report = run(&conn, true).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.pending.len(), MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        assert!(schema(&conn).is_empty());
+
+        migrate_to(&conn, 12);
+        let report = run(&conn, true).unwrap();
+        assert_eq!(report.current_version, 12);
+        assert_eq!(report.pending.first().map(|m| m.version), Some(13));
+        assert_eq!(current_version(&conn).unwrap(), 12);
+        assert_eq!(applied_versions(&conn), (1..=12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn newer_schema_is_rejected() {
+        let conn = open();
+        run(&conn, false).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (?1, 'from_the_future', ?2)",
+            (latest_version() + 1, Utc::now().to_rfc3339()),
+        )
+        .unwrap();
+
+        for dry_run in [true, false] {
+            match run(&conn, dry_run) {
+                Err(AppError::SchemaTooNew { found, supported }) => {
+                    assert_eq!((found, supported), (latest_version() + 1, latest_version()));
+                }
+                other => panic!("expected SchemaTooNew, got {:?}", other.map(|r| r.current_version)),
+            }
+        }
+    }
+
+    #[test]
+    fn workflow_seed_timestamps_match_rust_format() {
+        let conn = open();
+        run(&conn, false).unwrap();
+        let mut stmt = conn.prepare("SELECT created_at, updated_at FROM workflow_states").unwrap();
+        let rows: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        for (created_at, updated_at) in rows {
+            assert_eq!(created_at, updated_at);
+            assert!(created_at.ends_with("+00:00"), "{}", created_at);
+            DateTime::parse_from_rfc3339(&created_at).unwrap();
+        }
+    }
+
+    fn end_of_local_day(date: &str) -> String {
+        let next = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap().succ_opt().unwrap();
+        let end = Local.from_local_datetime(&next.and_time(NaiveTime::MIN)).earliest().unwrap();
+        end.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    }
+
+    #[test]
+    fn todo_due_at_normalizes_v12_due_dates() {
+        let conn = open();
+        migrate_to(&conn, 12);
+
+        let created_at = "2025-02-10T12:00:00+00:00";
+        let tomorrow = DateTime::parse_from_rfc3339(created_at).unwrap().with_timezone(&Local).date_naive().succ_opt().unwrap();
+        let local = Local.from_local_datetime(&NaiveDateTime::parse_from_str("2025-03-01 15:00", "%Y-%m-%d %H:%M").unwrap())
+            .earliest()
+            .unwrap();
+        let local_due = match local.offset().local_minus_utc() {
+            0 => local.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            _ => local.format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+        };
+
+        let cases: Vec<(Option<&str>, Option<String>, Option<String>)> = vec![
+            (Some("2025-03-01"), Some("2025-03-01".into()), Some(end_of_local_day("2025-03-01"))),
+            (Some(" 2025-03-01T15:00:00+08:00 "), Some("2025-03-01T15:00:00+08:00".into()), Some("2025-03-01T07:00:00Z".into())),
+            (Some("2025-03-01T07:00:00.500Z"), Some("2025-03-01T07:00:00Z".into()), Some("2025-03-01T07:00:00Z".into())),
+            (Some("2025-03-01 15:00"), Some(local_due), Some(local.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string())),
+            (Some("明天"), Some(tomorrow.to_string()), Some(end_of_local_day(&tomorrow.to_string()))),
+            (Some("Tomorrow"), Some(tomorrow.to_string()), Some(end_of_local_day(&tomorrow.to_string()))),
+            (Some("下周三"), None, None),
+            (Some("2025-02-30"), None, None),
+            (Some(""), None, None),
+            (None, None, None),
+        ];
+        for (i, (due_date, _, _)) in cases.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO todos (id, text, due_date, created_at, updated_at) VALUES (?1, 't', ?2, ?3, ?3)",
+                (i.to_string(), due_date, created_at),
+            )
+            .unwrap();
+        }
+
+        let report = run(&conn, false).unwrap();
+        assert_eq!(report.current_version, 12);
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        for (i, (input, due_date, due_at)) in cases.into_iter().enumerate() {
+            let row: (Option<String>, Option<String>) = conn
+                .query_row("SELECT due_date, due_at FROM todos WHERE id = ?1", [i.to_string()], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .unwrap();
+            assert_eq!(row, (due_date, due_at), "input: {:?}", input);
+        }
+    }
+}
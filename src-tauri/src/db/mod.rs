@@ -1,16 +1,44 @@
+pub mod fts;
+pub mod migrations;
 pub mod todo_repo;
+pub mod tag_repo;
+pub mod list_repo;
+pub mod reminder_repo;
+pub mod attachment_repo;
+pub mod time_entry_repo;
+pub mod focus_repo;
+pub mod report_repo;
+pub mod workflow_repo;
+pub mod history_repo;
+pub mod undo_repo;
 pub mod settings_repo;
 
 pub use todo_repo::TodoRepository;
+pub use tag_repo::TagRepository;
+pub use list_repo::ListRepository;
+pub use reminder_repo::ReminderRepository;
+pub use attachment_repo::AttachmentRepository;
+pub use time_entry_repo::TimeEntryRepository;
+pub use focus_repo::FocusRepository;
+pub use report_repo::ReportRepository;
+pub use workflow_repo::WorkflowRepository;
+pub use history_repo::HistoryRepository;
+pub use undo_repo::UndoRepository;
 pub use settings_repo::SettingsRepository;
+pub use migrations::MigrationReport;
 
 use crate::error::AppError;
-use rusqlite::Connection;
+use rusqlite::backup::{Backup, Progress};
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use std::collections::HashSet;
+use std::path::Path;
 use std::sync::{Condvar, Mutex};
+use std::time::Duration;
 
 pub struct Database {
     pool: Mutex<Vec<Connection>>,
     available: Condvar,
+    pool_size: usize,
 }
 
 impl Database {
@@ -26,12 +54,19 @@ impl Database {
             // 启用 WAL 模式以提高并发性能
             conn.execute_batch("PRAGMA journal_mode=WAL;")?;
 
+            // 外键约束是连接级别的设置，需要在每个连接上开启
+            conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+
+            // 注册 FTS 同步触发器使用的自定义函数
+            fts::register_functions(&conn)?;
+
             connections.push(conn);
         }
 
         Ok(Self {
             pool: Mutex::new(connections),
             available: Condvar::new(),
+            pool_size: POOL_SIZE,
         })
     }
 
@@ -57,54 +92,120 @@ impl Database {
         // 归还连接
         let mut guard = self.pool.lock().unwrap();
         guard.push(conn);
-        self.available.notify_one();
+        // 同时可能有 `with_exclusive_conn` 在等待全部连接归还，需要唤醒所有等待者
+        self.available.notify_all();
 
         result
     }
 
-    pub fn init_schema(&self) -> Result<(), AppError> {
+    /// 等待所有连接归还后独占连接池执行闭包，期间其他 `with_conn` 调用会被阻塞。
+    /// 用于恢复备份等需要替换整个数据库内容的操作。
+    fn with_exclusive_conn<F, T>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, AppError>,
+    {
+        let mut guard = self.pool.lock().unwrap();
+        while guard.len() < self.pool_size {
+            guard = self.available.wait(guard).unwrap();
+        }
+
+        let conn = guard.last_mut().expect("connection pool unexpectedly empty");
+        f(conn)
+    }
+
+    /// 应用所有未执行的 schema 迁移，应在启动时调用一次
+    pub fn migrate(&self) -> Result<MigrationReport, AppError> {
+        self.with_conn(|conn| migrations::run(conn, false))
+    }
+
+    /// 只检查迁移状态，不修改数据库（dry-run）
+    pub fn migration_report(&self) -> Result<MigrationReport, AppError> {
+        self.with_conn(|conn| migrations::run(conn, true))
+    }
+
+    /// 使用 SQLite 备份 API 将当前数据库完整复制到 `dest`。
+    /// 备份分批复制页面，期间不阻塞其他连接的写入；如果源数据库在复制过程中被修改，
+    /// SQLite 会自动从头重新复制，因此得到的始终是一致的快照。
+    pub fn backup_to(&self, dest: &Path) -> Result<(), AppError> {
+        const PAGES_PER_STEP: i32 = 256;
+
         self.with_conn(|conn| {
-            // 创建 todos 表
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS todos (
-                    id TEXT PRIMARY KEY,
-                    text TEXT NOT NULL,
-                    completed INTEGER NOT NULL DEFAULT 0,
-                    status TEXT NOT NULL DEFAULT 'pending',
-                    priority INTEGER NOT NULL DEFAULT 0,
-                    due_date TEXT,
-                    tags TEXT,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                )",
-                [],
-            )?;
-
-            // 创建索引
-            conn.execute(
-                "CREATE INDEX IF NOT EXISTS idx_todos_status ON todos(status)",
-                [],
-            )?;
-            conn.execute(
-                "CREATE INDEX IF NOT EXISTS idx_todos_completed ON todos(completed)",
-                [],
-            )?;
-            conn.execute(
-                "CREATE INDEX IF NOT EXISTS idx_todos_created_at ON todos(created_at)",
-                [],
-            )?;
-
-            // 创建 settings 表
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS settings (
-                    key TEXT PRIMARY KEY,
-                    value TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                )",
-                [],
-            )?;
+            let mut target = Connection::open(dest)?;
+            let backup = Backup::new(conn, &mut target)?;
+            backup.run_to_completion(PAGES_PER_STEP, Duration::from_millis(10), None)?;
+            drop(backup);
 
+            // 备份文件不使用 WAL，保证它始终是一个独立的文件
+            target.execute_batch("PRAGMA journal_mode=DELETE;")?;
             Ok(())
         })
     }
+
+    /// 用 `src` 中的备份替换当前数据库内容，随后执行迁移使 schema 保持最新。
+    /// 恢复期间独占连接池。
+    pub fn restore_from(&self, src: &Path) -> Result<MigrationReport, AppError> {
+        self.with_exclusive_conn(|conn| {
+            conn.restore(DatabaseName::Main, src, None::<fn(Progress)>)?;
+            migrations::run(conn, false)
+        })
+    }
+}
+
+/// 对数据库文件执行 `PRAGMA integrity_check`，返回发现的问题（为空表示完好）。
+/// FTS5 校验倒排索引需要写权限，因此以读写方式打开，但不会创建不存在的文件。
+pub fn integrity_check(path: &Path) -> Result<Vec<String>, AppError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+    let mut problems = Vec::new();
+    for row in rows {
+        let row = row?;
+        if row != "ok" {
+            problems.push(row);
+        }
+    }
+    Ok(problems)
+}
+
+/// 备份文件中附件引用的文件哈希，备份与恢复时据此复制附件文件
+pub fn attachment_hashes_of(path: &Path) -> Result<HashSet<String>, AppError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    attachment_repo::referenced_hashes(&conn)
+}
+
+/// 备份文件的 schema 版本，用于在恢复前拒绝由更新版本应用写入的备份
+pub fn schema_version_of(path: &Path) -> Result<u32, AppError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    migrations::current_version(&conn)
+}
+
+/// 测试用的临时数据库：已执行全部迁移，drop 时删除所在目录
+#[cfg(test)]
+pub(crate) struct TestDb {
+    pub db: std::sync::Arc<Database>,
+    dir: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TestDb {
+    pub fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("aideo-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(dir.join("aideo.db").to_str().unwrap()).unwrap();
+        db.migrate().unwrap();
+        Self { db: std::sync::Arc::new(db), dir }
+    }
+
+    /// 临时目录，测试可在其中放置备份、附件等文件
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
 }
@@ -0,0 +1,406 @@
+use crate::db::{settings_repo, todo_repo, Database};
+use crate::error::AppError;
+use crate::models::due_date::{instant_sql, DueDate, UserTimezone};
+use crate::models::reminder::{CreateReminderRequest, Reminder, ReminderNotification};
+use crate::models::todo::Todo;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const REMINDER_COLUMNS: &str = "reminders.id, reminders.todo_id, reminders.offset_minutes, reminders.remind_at,
+    reminders.snoozed_until, reminders.fired_at, reminders.created_at";
+
+/// 提醒实际触发的时间：稍后提醒优先于计划时间
+const FIRE_AT_SQL: &str = "COALESCE(reminders.snoozed_until, reminders.remind_at)";
+
+/// 只有未完成、未取消且不在回收站中的任务才会提醒
+const OPEN_TODO_SQL: &str = "todos.completed = 0 AND todos.status != 'cancelled' AND todos.deleted_at IS NULL";
+
+fn row_to_reminder(row: &rusqlite::Row) -> rusqlite::Result<Reminder> {
+    Ok(Reminder {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        offset_minutes: row.get(2)?,
+        remind_at: row.get(3)?,
+        snoozed_until: row.get(4)?,
+        fired_at: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn load_reminder(conn: &Connection, id: &str) -> Result<Reminder, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM reminders WHERE reminders.id = ?1", REMINDER_COLUMNS),
+        [id],
+        row_to_reminder,
+    )
+    .map_err(|_| AppError::ReminderNotFound(id.to_string()))
+}
+
+/// 相对提醒的计划时间，任务没有截止时间时为 `None`。
+/// 提醒相关的时间都以 `instant_sql` 的格式保存，可以直接按字符串比较。
+fn offset_remind_at(due_date: Option<&DueDate>, offset_minutes: i64, tz: &UserTimezone) -> Option<String> {
+    let base = due_date?.reminder_base(tz)?;
+    Some(instant_sql(&(base - Duration::minutes(offset_minutes))))
+}
+
+/// 任务截止日期改变后重新计算其相对提醒的时间，并清除稍后提醒。
+/// 新的时间仍在将来时，已触发的提醒会再次触发。应与修改截止日期在同一个事务中调用。
+pub(crate) fn reschedule_for_todo(conn: &Connection, todo_id: &str, due_date: Option<&DueDate>) -> Result<(), AppError> {
+    let now = instant_sql(&Utc::now());
+    let tz = settings_repo::load_timezone(conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, offset_minutes FROM reminders WHERE todo_id = ?1 AND offset_minutes IS NOT NULL")?;
+    let reminders = stmt
+        .query_map([todo_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (id, offset_minutes) in reminders {
+        let remind_at = offset_remind_at(due_date, offset_minutes, &tz);
+        conn.execute(
+            "UPDATE reminders SET remind_at = ?1, snoozed_until = NULL,
+                fired_at = CASE WHEN ?1 > ?2 THEN NULL ELSE fired_at END
+             WHERE id = ?3",
+            (&remind_at, &now, &id),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 把 `from_id` 的相对提醒复制到 `to_id`（重复任务的下一次），绝对时间提醒只属于原任务
+pub(crate) fn copy_reminders(conn: &Connection, from_id: &str, to_id: &str, due_date: Option<&DueDate>) -> Result<(), AppError> {
+    let now = Utc::now().to_rfc3339();
+    let tz = settings_repo::load_timezone(conn)?;
+
+    let mut stmt = conn.prepare("SELECT offset_minutes FROM reminders WHERE todo_id = ?1 AND offset_minutes IS NOT NULL")?;
+    let offsets = stmt
+        .query_map([from_id], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for offset_minutes in offsets {
+        conn.execute(
+            "INSERT INTO reminders (id, todo_id, offset_minutes, remind_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                Uuid::new_v4().to_string(),
+                to_id,
+                offset_minutes,
+                offset_remind_at(due_date, offset_minutes, &tz),
+                &now,
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+pub struct ReminderRepository {
+    db: Arc<Database>,
+}
+
+impl ReminderRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 任务的全部提醒，按触发时间排序
+    pub fn get_for_todo(&self, todo_id: &str) -> Result<Vec<Reminder>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM reminders WHERE reminders.todo_id = ?1 ORDER BY {} IS NULL, {}, reminders.created_at",
+                REMINDER_COLUMNS, FIRE_AT_SQL, FIRE_AT_SQL
+            ))?;
+
+            let reminders = stmt.query_map([todo_id], row_to_reminder)?;
+
+            let mut result = Vec::new();
+            for reminder in reminders {
+                result.push(reminder?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// 尚未触发的提醒，按触发时间排序
+    pub fn get_upcoming(&self, limit: u32) -> Result<Vec<Reminder>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM reminders JOIN todos ON todos.id = reminders.todo_id
+                 WHERE reminders.fired_at IS NULL AND {} IS NOT NULL AND {}
+                 ORDER BY {} LIMIT ?1",
+                REMINDER_COLUMNS, FIRE_AT_SQL, OPEN_TODO_SQL, FIRE_AT_SQL
+            ))?;
+
+            let reminders = stmt.query_map([limit], row_to_reminder)?;
+
+            let mut result = Vec::new();
+            for reminder in reminders {
+                result.push(reminder?);
+            }
+            Ok(result)
+        })
+    }
+
+    pub fn create(&self, todo_id: &str, request: CreateReminderRequest) -> Result<Reminder, AppError> {
+        self.db.with_conn(|conn| {
+            let todo = todo_repo::load_todo(conn, todo_id)?;
+            if todo.deleted_at.is_some() {
+                return Err(AppError::TodoNotFound(todo_id.to_string()));
+            }
+
+            let tz = settings_repo::load_timezone(conn)?;
+            let remind_at = match (request.offset_minutes, request.remind_at.as_deref()) {
+                (Some(offset), None) if offset >= 0 => offset_remind_at(todo.due_date.as_ref(), offset, &tz),
+                (Some(offset), None) => {
+                    return Err(AppError::InvalidArgument(format!("Reminder offset must not be negative: {}", offset)));
+                }
+                (None, Some(value)) => match DueDate::resolve(value, &tz).ok().and_then(|at| at.reminder_base(&tz)) {
+                    Some(remind_at) => Some(instant_sql(&remind_at)),
+                    None => return Err(AppError::InvalidArgument(format!("Invalid reminder time: {}", value))),
+                },
+                _ => {
+                    return Err(AppError::InvalidArgument(
+                        "Exactly one of offsetMinutes and remindAt is required".into(),
+                    ));
+                }
+            };
+
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO reminders (id, todo_id, offset_minutes, remind_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                (&id, todo_id, request.offset_minutes, &remind_at, Utc::now().to_rfc3339()),
+            )?;
+
+            load_reminder(conn, &id)
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), AppError> {
+        self.db.with_conn(|conn| {
+            let affected = conn.execute("DELETE FROM reminders WHERE id = ?1", [id])?;
+            if affected == 0 {
+                return Err(AppError::ReminderNotFound(id.to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    /// 稍后提醒：从现在起 `minutes` 分钟后再次触发
+    pub fn snooze(&self, id: &str, minutes: u32) -> Result<Reminder, AppError> {
+        if minutes == 0 {
+            return Err(AppError::InvalidArgument("Snooze duration must be positive".into()));
+        }
+
+        self.db.with_conn(|conn| {
+            let snoozed_until = instant_sql(&(Utc::now() + Duration::minutes(minutes as i64)));
+            let affected = conn.execute(
+                "UPDATE reminders SET snoozed_until = ?1, fired_at = NULL WHERE id = ?2",
+                (&snoozed_until, id),
+            )?;
+            if affected == 0 {
+                return Err(AppError::ReminderNotFound(id.to_string()));
+            }
+
+            load_reminder(conn, id)
+        })
+    }
+
+    /// 取出在 `now` 之前到期且尚未触发的提醒并标记为已触发。
+    /// 应用关闭期间错过的提醒会在下次启动后的第一次检查中返回。
+    pub fn take_due(&self, now: DateTime<Utc>) -> Result<Vec<ReminderNotification>, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let now = instant_sql(&now);
+
+            let due = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT {} FROM reminders JOIN todos ON todos.id = reminders.todo_id
+                     WHERE reminders.fired_at IS NULL AND {} <= ?1 AND {}
+                     ORDER BY {}",
+                    REMINDER_COLUMNS, FIRE_AT_SQL, OPEN_TODO_SQL, FIRE_AT_SQL
+                ))?;
+                let rows = stmt.query_map([&now], row_to_reminder)?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            };
+
+            let mut result = Vec::with_capacity(due.len());
+            for mut reminder in due {
+                tx.execute("UPDATE reminders SET fired_at = ?1 WHERE id = ?2", (&now, &reminder.id))?;
+                reminder.fired_at = Some(now.clone());
+
+                let todo = todo_repo::load_todo(&tx, &reminder.todo_id)?;
+                result.push(ReminderNotification { reminder, todo });
+            }
+
+            tx.commit()?;
+            Ok(result)
+        })
+    }
+
+    /// 取出在 `now` 时已经逾期、且当前截止日期尚未通知过的任务，并记录为已通知
+    pub fn take_overdue(&self, now: DateTime<Utc>) -> Result<Vec<Todo>, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let overdue = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT todos.id, todos.due_date FROM todos
+                     LEFT JOIN overdue_notices ON overdue_notices.todo_id = todos.id
+                     WHERE todos.due_at IS NOT NULL AND todos.due_at <= ?1 AND {}
+                       AND (overdue_notices.due_date IS NULL OR overdue_notices.due_date != todos.due_date)
+                     ORDER BY todos.due_at",
+                    OPEN_TODO_SQL
+                ))?;
+                let rows = stmt.query_map([instant_sql(&now)], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            };
+
+            let notified_at = now.to_rfc3339();
+            let mut result = Vec::new();
+            for (id, due_date) in overdue {
+                tx.execute(
+                    "INSERT INTO overdue_notices (todo_id, due_date, notified_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(todo_id) DO UPDATE SET due_date = excluded.due_date, notified_at = excluded.notified_at",
+                    (&id, &due_date, &notified_at),
+                )?;
+                result.push(todo_repo::load_todo(&tx, &id)?);
+            }
+
+            tx.commit()?;
+            Ok(result)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TestDb, TodoRepository};
+    use crate::models::history::Actor;
+    use crate::models::todo::{CreateTodoRequest, UpdateTodoRequest};
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    fn create_due(todos: &TodoRepository, due_date: &str) -> Todo {
+        let request = CreateTodoRequest { text: "report".into(), due_date: Some(due_date.into()), ..Default::default() };
+        todos.create(request, &Actor::User).unwrap()
+    }
+
+    fn set_due(todos: &TodoRepository, id: &str, due_date: &str) {
+        let request = UpdateTodoRequest { due_date: Some(due_date.into()), ..Default::default() };
+        todos.update(id, request, &Actor::User).unwrap();
+    }
+
+    fn offset(minutes: i64) -> CreateReminderRequest {
+        CreateReminderRequest { offset_minutes: Some(minutes), remind_at: None }
+    }
+
+    #[test]
+    fn due_reminders_fire_once() {
+        let test = TestDb::new();
+        let repo = ReminderRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = create_due(&todos, "2099-03-12T18:00:00Z");
+        let reminder = repo.create(&todo.id, offset(30)).unwrap();
+        assert_eq!(reminder.remind_at.as_deref(), Some("2099-03-12T17:30:00Z"));
+
+        assert!(repo.take_due(at("2099-03-12T17:29:59Z")).unwrap().is_empty());
+
+        let fired = repo.take_due(at("2099-03-12T17:30:00Z")).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].reminder.id, reminder.id);
+        assert_eq!(fired[0].todo.id, todo.id);
+
+        assert!(repo.take_due(at("2099-03-12T19:00:00Z")).unwrap().is_empty());
+        assert!(repo.get_upcoming(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn closed_todos_do_not_fire() {
+        let test = TestDb::new();
+        let repo = ReminderRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = create_due(&todos, "2099-03-12T18:00:00Z");
+        repo.create(&todo.id, offset(0)).unwrap();
+
+        todos.update(&todo.id, UpdateTodoRequest { completed: Some(true), ..Default::default() }, &Actor::User).unwrap();
+
+        assert!(repo.take_due(at("2099-03-13T00:00:00Z")).unwrap().is_empty());
+        assert!(repo.take_overdue(at("2099-03-13T00:00:00Z")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn snoozed_reminders_fire_again() {
+        let test = TestDb::new();
+        let repo = ReminderRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = create_due(&todos, "2099-03-12T18:00:00Z");
+        let reminder = repo.create(&todo.id, offset(0)).unwrap();
+        assert_eq!(repo.take_due(at("2099-03-12T18:00:00Z")).unwrap().len(), 1);
+
+        assert!(matches!(repo.snooze(&reminder.id, 0), Err(AppError::InvalidArgument(_))));
+        let snoozed = repo.snooze(&reminder.id, 10).unwrap();
+        assert!(snoozed.fired_at.is_none());
+
+        // 稍后提醒从现在起计时，优先于原来的计划时间
+        assert!(repo.take_due(Utc::now()).unwrap().is_empty());
+        let fired = repo.take_due(Utc::now() + Duration::minutes(11)).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].reminder.snoozed_until, snoozed.snoozed_until);
+        assert!(repo.take_due(Utc::now() + Duration::minutes(12)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn changing_the_due_date_reschedules_offset_reminders() {
+        let test = TestDb::new();
+        let repo = ReminderRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = create_due(&todos, "2099-03-12T18:00:00Z");
+        let relative = repo.create(&todo.id, offset(60)).unwrap();
+        let absolute = repo
+            .create(&todo.id, CreateReminderRequest { offset_minutes: None, remind_at: Some("2099-03-10T08:00:00Z".into()) })
+            .unwrap();
+        assert_eq!(repo.take_due(at("2099-03-12T17:00:00Z")).unwrap().len(), 2);
+
+        set_due(&todos, &todo.id, "2099-03-14T18:00:00Z");
+
+        let reminders = repo.get_for_todo(&todo.id).unwrap();
+        let relative = reminders.iter().find(|r| r.id == relative.id).unwrap();
+        let absolute = reminders.iter().find(|r| r.id == absolute.id).unwrap();
+        assert_eq!(relative.remind_at.as_deref(), Some("2099-03-14T17:00:00Z"));
+        assert!(relative.fired_at.is_none());
+        assert_eq!(absolute.remind_at.as_deref(), Some("2099-03-10T08:00:00Z"));
+        assert!(absolute.fired_at.is_some());
+
+        let fired = repo.take_due(at("2099-03-14T17:00:00Z")).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].reminder.id, relative.id);
+
+        // 清除截止日期后相对提醒不再有触发时间
+        set_due(&todos, &todo.id, "");
+        let reminders = repo.get_for_todo(&todo.id).unwrap();
+        assert!(reminders.iter().find(|r| r.id == relative.id).unwrap().remind_at.is_none());
+    }
+
+    #[test]
+    fn overdue_notices_repeat_only_after_the_due_date_changes() {
+        let test = TestDb::new();
+        let repo = ReminderRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = create_due(&todos, "2099-03-12T18:00:00Z");
+
+        assert!(repo.take_overdue(at("2099-03-12T17:59:59Z")).unwrap().is_empty());
+        let overdue = repo.take_overdue(at("2099-03-12T18:00:00Z")).unwrap();
+        assert_eq!(overdue.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec![todo.id.as_str()]);
+        assert!(repo.take_overdue(at("2099-03-13T00:00:00Z")).unwrap().is_empty());
+
+        set_due(&todos, &todo.id, "2099-03-14T18:00:00Z");
+        assert!(repo.take_overdue(at("2099-03-14T00:00:00Z")).unwrap().is_empty());
+        assert_eq!(repo.take_overdue(at("2099-03-15T00:00:00Z")).unwrap().len(), 1);
+        assert!(repo.take_overdue(at("2099-03-16T00:00:00Z")).unwrap().is_empty());
+    }
+}
+
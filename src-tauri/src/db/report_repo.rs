@@ -0,0 +1,739 @@
+use crate::db::{settings_repo, todo_repo, Database};
+use crate::error::AppError;
+use crate::models::due_date::instant_sql;
+use crate::models::report::*;
+use crate::models::time_entry::{resolve_range, start_of_day};
+use crate::models::todo::{Priority, TodoStatus};
+use chrono::{DateTime, Datelike, Days, Duration, Months, NaiveDate, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+/// 有预估的已完成任务及其实际工作量的依据：计时秒数、第一次开始进行的时间、第一次计时的时间与完成时间
+const ESTIMATED_TODOS_SQL: &str = "SELECT todos.id, todos.text, todos.priority, todos.list_id, lists.name,
+    (SELECT json_group_array(tags.name) FROM todo_tags JOIN tags ON tags.id = todo_tags.tag_id
+        WHERE todo_tags.todo_id = todos.id),
+    todos.estimate_minutes, todos.story_points,
+    (SELECT SUM(MAX(0, CAST(strftime('%s', COALESCE(ended_at, ?1)) AS INTEGER) - CAST(strftime('%s', started_at) AS INTEGER)))
+        FROM time_entries WHERE time_entries.todo_id = todos.id),
+    todos.started_at,
+    (SELECT MIN(started_at) FROM time_entries WHERE time_entries.todo_id = todos.id),
+    todos.completed_at
+    FROM todos LEFT JOIN lists ON lists.id = todos.list_id
+    WHERE todos.deleted_at IS NULL AND todos.completed = 1
+        AND (todos.estimate_minutes IS NOT NULL OR todos.story_points IS NOT NULL)";
+
+/// 统计报告中的全部任务，`{}` 为未指定列表时排除已归档列表的条件
+const STATISTICS_TODOS_SQL: &str = "SELECT todos.priority, todos.list_id, lists.name,
+    (SELECT json_group_array(tags.name) FROM todo_tags JOIN tags ON tags.id = todo_tags.tag_id
+        WHERE todo_tags.todo_id = todos.id),
+    todos.created_at, todos.status, todos.due_at,
+    todos.completed_at, todos.started_at
+    FROM todos LEFT JOIN lists ON lists.id = todos.list_id
+    WHERE todos.deleted_at IS NULL AND CASE
+        WHEN :list_id IS NULL THEN {}
+        WHEN :list_id = '' THEN todos.list_id IS NULL
+        ELSE todos.list_id = :list_id END";
+
+/// 未指定统计范围时统计的天数
+const DEFAULT_STATISTICS_DAYS: u64 = 30;
+
+/// 趋势中时间段数的上限，超过时需要使用更粗的粒度
+const MAX_TREND_POINTS: usize = 1000;
+
+fn parse_instant(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc))
+}
+
+fn priority_label(priority: &Priority) -> &'static str {
+    match priority {
+        Priority::Low => "低",
+        Priority::Medium => "中",
+        Priority::High => "高",
+    }
+}
+
+fn bucket_start(date: NaiveDate, granularity: Granularity) -> NaiveDate {
+    match granularity {
+        Granularity::Day => date,
+        Granularity::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+        Granularity::Month => date.with_day(1).unwrap_or(date),
+    }
+}
+
+fn next_bucket(start: NaiveDate, granularity: Granularity) -> Option<NaiveDate> {
+    match granularity {
+        Granularity::Day => start.checked_add_days(Days::new(1)),
+        Granularity::Week => start.checked_add_days(Days::new(7)),
+        Granularity::Month => start.checked_add_months(Months::new(1)),
+    }
+}
+
+/// 连续有任务完成的天数，返回（截至今天的，最长的）
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let mut current = 0;
+    let mut day = if days.contains(&today) { Some(today) } else { today.pred_opt() };
+    while let Some(d) = day.filter(|d| days.contains(d)) {
+        current += 1;
+        day = d.pred_opt();
+    }
+    (current, longest)
+}
+
+fn breakdown<'a>(groups: &'a mut BTreeMap<String, StatisticsBreakdown>, key: &str, label: &str) -> &'a mut StatisticsBreakdown {
+    groups.entry(key.to_string()).or_insert_with(|| StatisticsBreakdown {
+        key: key.to_string(),
+        label: label.to_string(),
+        created: 0,
+        completed: 0,
+        open: 0,
+        overdue: 0,
+    })
+}
+
+/// 按范围内的新建与完成数、当前未完成数从多到少排列
+fn sorted_breakdowns(groups: BTreeMap<String, StatisticsBreakdown>) -> Vec<StatisticsBreakdown> {
+    let mut result: Vec<StatisticsBreakdown> = groups.into_values().collect();
+    result.sort_by_key(|g| std::cmp::Reverse(g.created + g.completed + g.open));
+    result
+}
+
+/// 累加一组任务的预估与实际工作量
+struct Accumulator {
+    label: String,
+    estimated_count: u32,
+    estimated_minutes: f64,
+    actual_minutes: f64,
+    abs_error_percent: f64,
+    pointed_count: u32,
+    story_points: f64,
+    pointed_actual_minutes: f64,
+}
+
+impl Accumulator {
+    fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            estimated_count: 0,
+            estimated_minutes: 0.0,
+            actual_minutes: 0.0,
+            abs_error_percent: 0.0,
+            pointed_count: 0,
+            story_points: 0.0,
+            pointed_actual_minutes: 0.0,
+        }
+    }
+
+    fn add(&mut self, item: &EstimateItem) {
+        if let Some(estimate) = item.estimate_minutes {
+            let estimate = estimate as f64;
+            self.estimated_count += 1;
+            self.estimated_minutes += estimate;
+            self.actual_minutes += item.actual_minutes;
+            self.abs_error_percent += (item.actual_minutes - estimate).abs() / estimate * 100.0;
+        }
+        if let Some(points) = item.story_points {
+            self.pointed_count += 1;
+            self.story_points += points;
+            self.pointed_actual_minutes += item.actual_minutes;
+        }
+    }
+
+    fn finish(self, key: String) -> EstimateAccuracy {
+        let ratio = (self.estimated_minutes > 0.0).then(|| self.actual_minutes / self.estimated_minutes);
+        let mean_abs_error_percent = (self.estimated_count > 0).then(|| self.abs_error_percent / self.estimated_count as f64);
+        let minutes_per_point = (self.story_points > 0.0).then(|| self.pointed_actual_minutes / self.story_points);
+
+        EstimateAccuracy {
+            key,
+            label: self.label,
+            estimated_count: self.estimated_count,
+            estimated_minutes: self.estimated_minutes,
+            actual_minutes: self.actual_minutes,
+            ratio,
+            mean_abs_error_percent,
+            pointed_count: self.pointed_count,
+            story_points: self.story_points,
+            minutes_per_point,
+        }
+    }
+}
+
+/// 按分组键累加，结果按任务数从多到少排列
+fn group_by<F>(items: &[EstimateItem], keys: F) -> Vec<EstimateAccuracy>
+where
+    F: Fn(&EstimateItem) -> Vec<(String, String)>,
+{
+    let mut groups: BTreeMap<String, Accumulator> = BTreeMap::new();
+    for item in items {
+        for (key, label) in keys(item) {
+            groups.entry(key).or_insert_with(|| Accumulator::new(&label)).add(item);
+        }
+    }
+
+    let mut result: Vec<EstimateAccuracy> = groups.into_iter().map(|(key, acc)| acc.finish(key)).collect();
+    result.sort_by_key(|g| std::cmp::Reverse(g.estimated_count + g.pointed_count));
+    result
+}
+
+/// 跨任务的统计报告
+pub struct ReportRepository {
+    db: Arc<Database>,
+}
+
+impl ReportRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 比较预估与实际工作量，按标签、优先级与列表分组。
+    /// 实际工作量优先使用计时记录，没有计时时使用从开始进行到完成经过的时间，两者都没有的任务不计入。
+    pub fn estimate_accuracy(&self, query: &EstimateReportQuery) -> Result<EstimateReport, AppError> {
+        self.db.with_conn(|conn| {
+            let tz = settings_repo::load_timezone(conn)?;
+            let (from, to) = resolve_range(query.period, query.from.as_deref(), query.to.as_deref(), &tz)?;
+            let now = instant_sql(&Utc::now());
+
+            let mut stmt = conn.prepare(ESTIMATED_TODOS_SQL)?;
+            let rows = stmt.query_map([&now], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i32>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<u32>>(6)?,
+                    row.get::<_, Option<f64>>(7)?,
+                    row.get::<_, Option<i64>>(8)?,
+                    row.get::<_, Option<String>>(9)?,
+                    row.get::<_, Option<String>>(10)?,
+                    row.get::<_, Option<String>>(11)?,
+                ))
+            })?;
+
+            let mut items = Vec::new();
+            let mut list_names = BTreeMap::new();
+            for row in rows {
+                let (todo_id, text, priority, list_id, list_name, tags, estimate_minutes, story_points, logged, started, first_entry, completed) = row?;
+
+                let completed_at = match completed.as_deref().and_then(parse_instant) {
+                    Some(completed_at) => completed_at,
+                    None => continue,
+                };
+                if from.is_some_and(|from| completed_at < from) || to.is_some_and(|to| completed_at >= to) {
+                    continue;
+                }
+
+                // 第一次开始进行或第一次计时，取较早的一个
+                let started = [started, first_entry].iter().flatten().filter_map(|s| parse_instant(s)).min();
+                let (actual_minutes, source) = match (logged.filter(|s| *s > 0), started) {
+                    (Some(seconds), _) => (seconds as f64 / 60.0, ActualSource::Logged),
+                    (None, Some(started)) if started < completed_at => {
+                        ((completed_at - started).num_seconds() as f64 / 60.0, ActualSource::Elapsed)
+                    }
+                    _ => continue,
+                };
+
+                if let (Some(list_id), Some(list_name)) = (&list_id, list_name) {
+                    list_names.insert(list_id.clone(), list_name);
+                }
+                items.push(EstimateItem {
+                    todo_id,
+                    text,
+                    priority: Priority::from_i32(priority),
+                    list_id,
+                    tags: serde_json::from_str(&tags).unwrap_or_default(),
+                    estimate_minutes,
+                    story_points,
+                    actual_minutes,
+                    source,
+                    completed_at: instant_sql(&completed_at),
+                });
+            }
+            items.sort_by(|a, b| b.completed_at.cmp(&a.completed_at));
+
+            let overall = group_by(&items, |_| vec![(String::new(), "全部".to_string())])
+                .pop()
+                .unwrap_or_else(|| Accumulator::new("全部").finish(String::new()));
+            let by_tag = group_by(&items, |item| item.tags.iter().map(|tag| (tag.clone(), tag.clone())).collect());
+            let by_priority = group_by(&items, |item| {
+                vec![(item.priority.as_str().to_string(), priority_label(&item.priority).to_string())]
+            });
+            let by_list = group_by(&items, |item| {
+                let key = item.list_id.clone().unwrap_or_default();
+                let label = list_names.get(&key).cloned().unwrap_or_else(|| "收件箱".to_string());
+                vec![(key, label)]
+            });
+
+            Ok(EstimateReport {
+                from: from.map(|f| instant_sql(&f)),
+                to: to.map(|t| instant_sql(&t)),
+                overall,
+                by_tag,
+                by_priority,
+                by_list,
+                items,
+            })
+        })
+    }
+
+    /// 统计范围内的新建与完成趋势、连续完成天数、逾期数、平均周期，以及按优先级、标签和列表的分组。
+    /// 全部数据在一次查询中取出后在内存中汇总；连续完成天数不受统计范围限制。
+    pub fn statistics(&self, query: &StatisticsQuery) -> Result<StatisticsReport, AppError> {
+        self.db.with_conn(|conn| {
+            let tz = settings_repo::load_timezone(conn)?;
+            let today = tz.today();
+            let (from, to) = resolve_range(query.period, query.from.as_deref(), query.to.as_deref(), &tz)?;
+            let invalid_range = || AppError::InvalidArgument("Invalid statistics range".into());
+            let to = match to {
+                Some(to) => to,
+                None => today.succ_opt().and_then(|d| start_of_day(d, &tz)).ok_or_else(invalid_range)?,
+            };
+            let last_day = tz.from_utc(to - Duration::seconds(1)).date_naive();
+            let from = match from {
+                Some(from) => from,
+                None => last_day
+                    .checked_sub_days(Days::new(DEFAULT_STATISTICS_DAYS - 1))
+                    .and_then(|d| start_of_day(d, &tz))
+                    .ok_or_else(invalid_range)?,
+            };
+            if from >= to {
+                return Err(invalid_range());
+            }
+
+            // 预先生成全部时间段，没有任务的时间段计为 0
+            let mut trend = BTreeMap::new();
+            let mut bucket = Some(bucket_start(tz.from_utc(from).date_naive(), query.granularity));
+            while let Some(start) = bucket.filter(|start| *start <= last_day) {
+                if trend.len() >= MAX_TREND_POINTS {
+                    return Err(AppError::InvalidArgument(format!(
+                        "Statistics range has more than {} {:?} periods, use a coarser granularity",
+                        MAX_TREND_POINTS, query.granularity
+                    )));
+                }
+                trend.insert(start, (0u32, 0u32));
+                bucket = next_bucket(start, query.granularity);
+            }
+
+            let now = Utc::now();
+            let list_id = query.list_id.as_deref().map(str::trim);
+            let mut stmt = conn.prepare(&STATISTICS_TODOS_SQL.replace("{}", todo_repo::NOT_ARCHIVED_SQL))?;
+            let rows = stmt.query_map(rusqlite::named_params! { ":list_id": list_id }, |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                    row.get::<_, Option<String>>(8)?,
+                ))
+            })?;
+
+            let in_range = |instant: &DateTime<Utc>| *instant >= from && *instant < to;
+            let mut created = 0;
+            let mut completed = 0;
+            let mut completed_late = 0;
+            let mut overdue = 0;
+            let mut cycle_seconds = Vec::new();
+            let mut completion_days = BTreeSet::new();
+            let mut by_priority = BTreeMap::new();
+            let mut by_tag = BTreeMap::new();
+            let mut by_list = BTreeMap::new();
+
+            for row in rows {
+                let (priority, list_id, list_name, tags, created_at, status, due_at, completed_at, started_at) = row?;
+                let created_at = parse_instant(&created_at);
+                let completed_at = completed_at.as_deref().and_then(parse_instant);
+                let due_at = due_at.as_deref().and_then(parse_instant);
+
+                let created_in_range = created_at.as_ref().is_some_and(in_range);
+                let completed_in_range = completed_at.as_ref().is_some_and(in_range);
+                let open = matches!(TodoStatus::from_str(&status), TodoStatus::Pending | TodoStatus::InProgress);
+                let is_overdue = open && due_at.is_some_and(|due| due <= now);
+
+                if let Some(created_at) = created_at.filter(|_| created_in_range) {
+                    created += 1;
+                    let start = bucket_start(tz.from_utc(created_at).date_naive(), query.granularity);
+                    if let Some(point) = trend.get_mut(&start) {
+                        point.0 += 1;
+                    }
+                }
+                if let Some(completed_at) = completed_at {
+                    completion_days.insert(tz.from_utc(completed_at).date_naive());
+                }
+                if let Some(completed_at) = completed_at.filter(|_| completed_in_range) {
+                    completed += 1;
+                    let start = bucket_start(tz.from_utc(completed_at).date_naive(), query.granularity);
+                    if let Some(point) = trend.get_mut(&start) {
+                        point.1 += 1;
+                    }
+                    if due_at.is_some_and(|due| due < completed_at) {
+                        completed_late += 1;
+                    }
+                    let started_at = started_at.as_deref().and_then(parse_instant).filter(|s| *s <= completed_at);
+                    if let Some(started_at) = started_at.or(created_at) {
+                        cycle_seconds.push((completed_at - started_at).num_seconds().max(0));
+                    }
+                }
+                if is_overdue {
+                    overdue += 1;
+                }
+                if !(created_in_range || completed_in_range || open) {
+                    continue;
+                }
+
+                let priority = Priority::from_i32(priority);
+                let list_key = list_id.unwrap_or_default();
+                let list_label = list_name.unwrap_or_else(|| "收件箱".to_string());
+                let tags: Vec<String> = serde_json::from_str(&tags).unwrap_or_default();
+                let count = |group: &mut StatisticsBreakdown| {
+                    group.created += created_in_range as u32;
+                    group.completed += completed_in_range as u32;
+                    group.open += open as u32;
+                    group.overdue += is_overdue as u32;
+                };
+                count(breakdown(&mut by_priority, priority.as_str(), priority_label(&priority)));
+                count(breakdown(&mut by_list, &list_key, &list_label));
+                for tag in &tags {
+                    count(breakdown(&mut by_tag, tag, tag));
+                }
+            }
+
+            let (current_streak, longest_streak) = streaks(&completion_days, today);
+            let average_cycle_hours = (!cycle_seconds.is_empty())
+                .then(|| cycle_seconds.iter().sum::<i64>() as f64 / cycle_seconds.len() as f64 / 3600.0);
+
+            // 优先级按从高到低的固定顺序排列
+            let by_priority = [Priority::High, Priority::Medium, Priority::Low]
+                .iter()
+                .filter_map(|p| by_priority.remove(p.as_str()))
+                .collect();
+
+            Ok(StatisticsReport {
+                from: instant_sql(&from),
+                to: instant_sql(&to),
+                granularity: query.granularity,
+                created,
+                completed,
+                completed_late,
+                overdue,
+                current_streak,
+                longest_streak,
+                average_cycle_hours,
+                trend: trend
+                    .into_iter()
+                    .map(|(start, (created, completed))| TrendPoint {
+                        start: start.format("%Y-%m-%d").to_string(),
+                        created,
+                        completed,
+                    })
+                    .collect(),
+                by_priority,
+                by_tag: sorted_breakdowns(by_tag),
+                by_list: sorted_breakdowns(by_list),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{ListRepository, TestDb, TodoRepository};
+    use crate::models::history::Actor;
+    use crate::models::list::CreateListRequest;
+    use crate::models::todo::{CreateTodoRequest, Todo, UpdateTodoRequest};
+
+    /// 统计按用户时区划分日期，测试固定使用 UTC
+    fn setup() -> (TestDb, ReportRepository, TodoRepository) {
+        let test = TestDb::new();
+        test.db
+            .with_conn(|conn| {
+                conn.execute("INSERT INTO settings (key, value, updated_at) VALUES ('timezone', 'UTC', '')", [])?;
+                Ok(())
+            })
+            .unwrap();
+        let reports = ReportRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        (test, reports, todos)
+    }
+
+    /// 直接改写任务的时间字段，`None` 表示清除
+    fn set_times(test: &TestDb, id: &str, times: &[(&str, Option<&str>)]) {
+        test.db
+            .with_conn(|conn| {
+                for (column, value) in times {
+                    conn.execute(&format!("UPDATE todos SET {} = ?1 WHERE id = ?2", column), (value, id))?;
+                }
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    /// 创建任务并在 `completed_at` 完成
+    fn completed(test: &TestDb, todos: &TodoRepository, request: CreateTodoRequest, completed_at: &str, started_at: Option<&str>) -> Todo {
+        let todo = todos.create(request, &Actor::User).unwrap();
+        todos.update(&todo.id, UpdateTodoRequest { completed: Some(true), ..Default::default() }, &Actor::User).unwrap();
+        set_times(test, &todo.id, &[("completed_at", Some(completed_at)), ("started_at", started_at)]);
+        todo
+    }
+
+    fn log_time(test: &TestDb, todo_id: &str, started_at: &str, ended_at: &str) {
+        test.db
+            .with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO time_entries (id, todo_id, started_at, ended_at, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?3, ?3)",
+                    (uuid::Uuid::new_v4().to_string(), todo_id, started_at, ended_at),
+                )?;
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    fn estimated(text: &str, minutes: u32, tags: &[&str]) -> CreateTodoRequest {
+        CreateTodoRequest {
+            text: text.into(),
+            estimate_minutes: Some(minutes),
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn estimate_accuracy_compares_logged_and_elapsed_time() {
+        let (test, reports, todos) = setup();
+        let work = ListRepository::new(test.db.clone())
+            .create(CreateListRequest { name: "Work".into(), ..Default::default() })
+            .unwrap();
+
+        let logged = completed(
+            &test,
+            &todos,
+            CreateTodoRequest { priority: Some(Priority::High), ..estimated("logged", 60, &["dev"]) },
+            "2024-05-10T12:00:00Z",
+            None,
+        );
+        log_time(&test, &logged.id, "2024-05-10T09:00:00Z", "2024-05-10T09:30:00Z");
+        log_time(&test, &logged.id, "2024-05-10T10:00:00Z", "2024-05-10T11:00:00Z");
+        completed(
+            &test,
+            &todos,
+            estimated("elapsed", 30, &["dev", "review"]),
+            "2024-05-11T09:30:00Z",
+            Some("2024-05-11T09:00:00Z"),
+        );
+        let pointed = completed(
+            &test,
+            &todos,
+            CreateTodoRequest {
+                text: "pointed".into(),
+                story_points: Some(2.0),
+                list_id: Some(work.id.clone()),
+                ..Default::default()
+            },
+            "2024-05-12T12:00:00Z",
+            None,
+        );
+        log_time(&test, &pointed.id, "2024-05-12T08:00:00Z", "2024-05-12T10:00:00Z");
+        // 没有计时也没有开始时间，无法确定实际工作量
+        completed(&test, &todos, estimated("unknown", 45, &["dev"]), "2024-05-12T12:00:00Z", None);
+        let earlier = completed(&test, &todos, estimated("earlier", 10, &["dev"]), "2024-04-01T12:00:00Z", None);
+        log_time(&test, &earlier.id, "2024-04-01T08:00:00Z", "2024-04-01T09:00:00Z");
+        // 未完成的任务不计入
+        let open = todos.create(estimated("open", 10, &["dev"]), &Actor::User).unwrap();
+        log_time(&test, &open.id, "2024-05-10T08:00:00Z", "2024-05-10T09:00:00Z");
+
+        let query = EstimateReportQuery { from: Some("2024-05-01".into()), to: Some("2024-05-31".into()), ..Default::default() };
+        let report = reports.estimate_accuracy(&query).unwrap();
+
+        let items: Vec<(&str, f64, ActualSource)> =
+            report.items.iter().map(|i| (i.text.as_str(), i.actual_minutes, i.source)).collect();
+        assert_eq!(
+            items,
+            vec![
+                ("pointed", 120.0, ActualSource::Logged),
+                ("elapsed", 30.0, ActualSource::Elapsed),
+                ("logged", 90.0, ActualSource::Logged),
+            ]
+        );
+
+        let overall = &report.overall;
+        assert_eq!(overall.estimated_count, 2);
+        assert_eq!((overall.estimated_minutes, overall.actual_minutes), (90.0, 120.0));
+        assert_eq!(overall.ratio, Some(120.0 / 90.0));
+        assert_eq!(overall.mean_abs_error_percent, Some(25.0));
+        assert_eq!(overall.pointed_count, 1);
+        assert_eq!(overall.minutes_per_point, Some(60.0));
+
+        let tags: Vec<(&str, u32)> = report.by_tag.iter().map(|g| (g.key.as_str(), g.estimated_count)).collect();
+        assert_eq!(tags, vec![("dev", 2), ("review", 1)]);
+        let high = report.by_priority.iter().find(|g| g.key == "high").unwrap();
+        assert_eq!((high.label.as_str(), high.ratio), ("高", Some(1.5)));
+        let lists: Vec<(&str, &str)> = report.by_list.iter().map(|g| (g.key.as_str(), g.label.as_str())).collect();
+        assert_eq!(lists, vec![("", "收件箱"), (work.id.as_str(), "Work")]);
+    }
+
+    #[test]
+    fn estimate_accuracy_without_range_includes_all_completed_todos() {
+        let (test, reports, todos) = setup();
+        let todo = completed(&test, &todos, estimated("a", 60, &[]), "2020-01-01T12:00:00Z", None);
+        log_time(&test, &todo.id, "2020-01-01T08:00:00Z", "2020-01-01T09:00:00Z");
+
+        let report = reports.estimate_accuracy(&EstimateReportQuery::default()).unwrap();
+        assert_eq!((report.from, report.to), (None, None));
+        assert_eq!(report.overall.ratio, Some(1.0));
+        assert_eq!(report.overall.mean_abs_error_percent, Some(0.0));
+        assert!(report.by_tag.is_empty());
+
+        let empty = reports
+            .estimate_accuracy(&EstimateReportQuery { from: Some("2024-01-01".into()), ..Default::default() })
+            .unwrap();
+        assert_eq!(empty.overall.estimated_count, 0);
+        assert_eq!(empty.overall.ratio, None);
+    }
+
+    fn week_query(granularity: Granularity) -> StatisticsQuery {
+        StatisticsQuery {
+            from: Some("2024-05-06".into()),
+            to: Some("2024-05-12".into()),
+            granularity,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn statistics_count_trends_and_breakdowns_in_range() {
+        let (test, reports, todos) = setup();
+        let work = ListRepository::new(test.db.clone())
+            .create(CreateListRequest { name: "Work".into(), ..Default::default() })
+            .unwrap();
+        let tagged = |text: &str, tags: &[&str]| CreateTodoRequest {
+            text: text.into(),
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            ..Default::default()
+        };
+
+        let fast = completed(
+            &test,
+            &todos,
+            CreateTodoRequest { priority: Some(Priority::High), ..tagged("fast", &["dev"]) },
+            "2024-05-07T12:00:00Z",
+            Some("2024-05-06T12:00:00Z"),
+        );
+        set_times(&test, &fast.id, &[("created_at", Some("2024-05-06T08:00:00Z"))]);
+        let late = completed(
+            &test,
+            &todos,
+            CreateTodoRequest { due_date: Some("2024-05-07T12:00:00Z".into()), ..tagged("late", &["dev"]) },
+            "2024-05-08T00:00:00Z",
+            None,
+        );
+        set_times(&test, &late.id, &[("created_at", Some("2024-05-07T12:00:00Z"))]);
+        let overdue = todos
+            .create(CreateTodoRequest { due_date: Some("2024-05-09".into()), ..tagged("overdue", &["home"]) }, &Actor::User)
+            .unwrap();
+        set_times(&test, &overdue.id, &[("created_at", Some("2024-05-08T10:00:00Z"))]);
+        let cancelled = todos
+            .create(CreateTodoRequest { list_id: Some(work.id.clone()), ..tagged("cancelled", &[]) }, &Actor::User)
+            .unwrap();
+        todos
+            .update(&cancelled.id, UpdateTodoRequest { status: Some(TodoStatus::Cancelled), ..Default::default() }, &Actor::User)
+            .unwrap();
+        set_times(&test, &cancelled.id, &[("created_at", Some("2024-05-09T10:00:00Z"))]);
+        let old = completed(&test, &todos, tagged("old", &["dev"]), "2024-04-02T12:00:00Z", None);
+        set_times(&test, &old.id, &[("created_at", Some("2024-04-01T12:00:00Z"))]);
+
+        let report = reports.statistics(&week_query(Granularity::Day)).unwrap();
+
+        assert_eq!((report.from.as_str(), report.to.as_str()), ("2024-05-06T00:00:00Z", "2024-05-13T00:00:00Z"));
+        assert_eq!((report.created, report.completed, report.completed_late, report.overdue), (4, 2, 1, 1));
+        assert_eq!(report.average_cycle_hours, Some(18.0));
+        assert_eq!((report.current_streak, report.longest_streak), (0, 2));
+
+        let trend: Vec<(&str, u32, u32)> = report.trend.iter().map(|p| (p.start.as_str(), p.created, p.completed)).collect();
+        assert_eq!(
+            trend,
+            vec![
+                ("2024-05-06", 1, 0),
+                ("2024-05-07", 1, 1),
+                ("2024-05-08", 1, 1),
+                ("2024-05-09", 1, 0),
+                ("2024-05-10", 0, 0),
+                ("2024-05-11", 0, 0),
+                ("2024-05-12", 0, 0),
+            ]
+        );
+
+        let counts = |g: &StatisticsBreakdown| (g.key.clone(), g.created, g.completed, g.open, g.overdue);
+        assert_eq!(
+            report.by_priority.iter().map(counts).collect::<Vec<_>>(),
+            vec![("high".to_string(), 1, 1, 0, 0), ("low".to_string(), 3, 1, 1, 1)]
+        );
+        assert_eq!(
+            report.by_tag.iter().map(counts).collect::<Vec<_>>(),
+            vec![("dev".to_string(), 2, 2, 0, 0), ("home".to_string(), 1, 0, 1, 1)]
+        );
+        assert_eq!(
+            report.by_list.iter().map(counts).collect::<Vec<_>>(),
+            vec![(String::new(), 3, 2, 1, 1), (work.id.clone(), 1, 0, 0, 0)]
+        );
+
+        let weekly = reports.statistics(&week_query(Granularity::Week)).unwrap();
+        assert_eq!(weekly.trend.len(), 1);
+        assert_eq!((weekly.trend[0].created, weekly.trend[0].completed), (4, 2));
+
+        let inbox = reports.statistics(&StatisticsQuery { list_id: Some(String::new()), ..week_query(Granularity::Day) }).unwrap();
+        assert_eq!((inbox.created, inbox.completed), (3, 2));
+        let scoped = reports.statistics(&StatisticsQuery { list_id: Some(work.id.clone()), ..week_query(Granularity::Day) }).unwrap();
+        assert_eq!((scoped.created, scoped.completed, scoped.overdue), (1, 0, 0));
+    }
+
+    #[test]
+    fn statistics_reject_empty_and_oversized_ranges() {
+        let (_test, reports, _) = setup();
+
+        let reversed = StatisticsQuery { from: Some("2024-05-12".into()), to: Some("2024-05-06".into()), ..Default::default() };
+        assert!(matches!(reports.statistics(&reversed), Err(AppError::InvalidArgument(_))));
+
+        let long = |granularity| StatisticsQuery {
+            from: Some("2000-01-01".into()),
+            to: Some("2024-12-31".into()),
+            granularity,
+            ..Default::default()
+        };
+        assert!(matches!(reports.statistics(&long(Granularity::Day)), Err(AppError::InvalidArgument(_))));
+        assert_eq!(reports.statistics(&long(Granularity::Month)).unwrap().trend.len(), 300);
+
+        // 默认统计最近 30 天，截至今天
+        assert_eq!(reports.statistics(&StatisticsQuery::default()).unwrap().trend.len(), 30);
+    }
+
+    #[test]
+    fn streaks_count_from_today_or_yesterday() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        let days = |offsets: &[u64]| offsets.iter().map(|d| today - Days::new(*d)).collect::<BTreeSet<_>>();
+
+        assert_eq!(streaks(&days(&[]), today), (0, 0));
+        assert_eq!(streaks(&days(&[0, 1, 2]), today), (3, 3));
+        // 今天还没有完成时从昨天算起
+        assert_eq!(streaks(&days(&[1, 2]), today), (2, 2));
+        assert_eq!(streaks(&days(&[2, 3]), today), (0, 2));
+        assert_eq!(streaks(&days(&[0, 3, 4, 5, 6]), today), (1, 4));
+    }
+}
+
@@ -1,9 +1,22 @@
-use crate::db::Database;
+use crate::db::{todo_repo, Database};
 use crate::error::AppError;
+use crate::models::due_date::UserTimezone;
 use crate::models::settings::Settings;
 use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
 use std::sync::Arc;
 
+/// 用户设置的时区，未设置或无法识别时使用系统时区
+pub(crate) fn load_timezone(conn: &Connection) -> Result<UserTimezone, AppError> {
+    let name: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key = 'timezone'", [], |row| row.get(0))
+        .optional()?;
+
+    Ok(name
+        .and_then(|name| UserTimezone::parse(&name).ok())
+        .unwrap_or(UserTimezone::System))
+}
+
 pub struct SettingsRepository {
     db: Arc<Database>,
 }
@@ -72,14 +85,53 @@ impl SettingsRepository {
                 settings.system_prompt = value;
             }
 
+            if let Ok(value) = conn.query_row(
+                "SELECT value FROM settings WHERE key = 'trash_retention_days'",
+                [],
+                |row| row.get::<_, String>(0),
+            ) {
+                if let Ok(days) = value.parse::<u32>() {
+                    settings.trash_retention_days = days;
+                }
+            }
+
+            if let Ok(value) = conn.query_row(
+                "SELECT value FROM settings WHERE key = 'timezone'",
+                [],
+                |row| row.get::<_, String>(0),
+            ) {
+                settings.timezone = value;
+            }
+
+            if let Ok(value) = conn.query_row(
+                "SELECT value FROM settings WHERE key = 'focus'",
+                [],
+                |row| row.get::<_, String>(0),
+            ) {
+                if let Ok(focus) = serde_json::from_str(&value) {
+                    settings.focus = focus;
+                }
+            }
+
             Ok(settings)
         })
     }
 
+    /// 用户时区，用于解释截止日期与"明天"等相对日期
+    pub fn timezone(&self) -> Result<UserTimezone, AppError> {
+        self.db.with_conn(load_timezone)
+    }
+
     pub fn save(&self, settings: &Settings) -> Result<(), AppError> {
         let now = Utc::now().to_rfc3339();
+        let timezone = UserTimezone::parse(&settings.timezone)?;
+        settings.focus.validate()?;
 
         self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let conn = &*tx;
+            let timezone_changed = load_timezone(conn)? != timezone;
+
             // 保存各个设置项
             self.upsert_setting(conn, "api_key", settings.api_key.as_deref().unwrap_or(""), &now)?;
             self.upsert_setting(conn, "api_base_url", &settings.api_base_url, &now)?;
@@ -87,14 +139,23 @@ impl SettingsRepository {
             self.upsert_setting(conn, "temperature", &settings.temperature.to_string(), &now)?;
             self.upsert_setting(conn, "max_tokens", &settings.max_tokens.to_string(), &now)?;
             self.upsert_setting(conn, "system_prompt", &settings.system_prompt, &now)?;
+            self.upsert_setting(conn, "trash_retention_days", &settings.trash_retention_days.to_string(), &now)?;
+            self.upsert_setting(conn, "timezone", settings.timezone.trim(), &now)?;
+            self.upsert_setting(conn, "focus", &serde_json::to_string(&settings.focus)?, &now)?;
+
+            // 全天任务的截止时刻取决于时区
+            if timezone_changed {
+                todo_repo::refresh_all_day_deadlines(conn, &timezone)?;
+            }
 
+            tx.commit()?;
             Ok(())
         })
     }
 
     fn upsert_setting(
         &self,
-        conn: &rusqlite::Connection,
+        conn: &Connection,
         key: &str,
         value: &str,
         updated_at: &str,
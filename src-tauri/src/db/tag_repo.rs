@@ -0,0 +1,367 @@
+use crate::db::{history_repo, todo_repo, Database};
+use crate::db::history_repo::EventContext;
+use crate::error::AppError;
+use crate::models::history::{Actor, TodoAction};
+use crate::models::tag::Tag;
+use crate::models::todo::Todo;
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const TAG_COLUMNS: &str = "tags.id, tags.name, tags.color, tags.created_at,
+    (SELECT COUNT(*) FROM todo_tags JOIN todos ON todos.id = todo_tags.todo_id
+     WHERE todo_tags.tag_id = tags.id AND todos.deleted_at IS NULL)";
+
+fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        created_at: row.get(3)?,
+        todo_count: row.get(4)?,
+    })
+}
+
+/// 按名称（忽略大小写）查找标签，不存在时创建，返回标签 ID
+pub(crate) fn ensure_tag(conn: &Connection, name: &str) -> Result<String, AppError> {
+    let existing: Option<String> = conn
+        .query_row("SELECT id FROM tags WHERE name = ?1", [name], |row| row.get(0))
+        .optional()?;
+
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO tags (id, name, color, created_at) VALUES (?1, ?2, NULL, ?3)",
+        (&id, name, Utc::now().to_rfc3339()),
+    )?;
+    Ok(id)
+}
+
+/// 用给定的标签名列表替换任务的全部标签，空白和重复的名称会被忽略
+pub(crate) fn set_todo_tags(conn: &Connection, todo_id: &str, tags: &[String]) -> Result<(), AppError> {
+    conn.execute("DELETE FROM todo_tags WHERE todo_id = ?1", [todo_id])?;
+
+    for name in tags {
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+
+        let tag_id = ensure_tag(conn, name)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO todo_tags (todo_id, tag_id) VALUES (?1, ?2)",
+            (todo_id, &tag_id),
+        )?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_color(color: &str) -> Result<(), AppError> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidArgument(format!(
+            "Invalid color '{}', expected #RRGGBB",
+            color
+        )))
+    }
+}
+
+pub struct TagRepository {
+    db: Arc<Database>,
+}
+
+impl TagRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 列出全部标签及其使用次数，按名称排序
+    pub fn get_all(&self) -> Result<Vec<Tag>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM tags ORDER BY tags.name COLLATE NOCASE",
+                TAG_COLUMNS
+            ))?;
+
+            let tags = stmt.query_map([], row_to_tag)?;
+
+            let mut result = Vec::new();
+            for tag in tags {
+                result.push(tag?);
+            }
+            Ok(result)
+        })
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Result<Tag, AppError> {
+        self.db.with_conn(|conn| self.get_by_id_internal(conn, id))
+    }
+
+    fn get_by_id_internal(&self, conn: &Connection, id: &str) -> Result<Tag, AppError> {
+        conn.query_row(
+            &format!("SELECT {} FROM tags WHERE tags.id = ?1", TAG_COLUMNS),
+            [id],
+            row_to_tag,
+        )
+        .map_err(|_| AppError::TagNotFound(id.to_string()))
+    }
+
+    /// 重命名标签。新名称已被其他标签占用时返回错误，此时应使用 `merge`。
+    pub fn rename(&self, id: &str, new_name: &str, actor: &Actor) -> Result<Tag, AppError> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(AppError::InvalidArgument("Tag name cannot be empty".into()));
+        }
+
+        self.db.with_conn(|conn| {
+            // 冲突检查与修改在同一事务中，避免检查后被其他写入抢占名称
+            let tx = conn.unchecked_transaction()?;
+            self.get_by_id_internal(&tx, id)?;
+
+            let conflict: Option<String> = tx
+                .query_row(
+                    "SELECT id FROM tags WHERE name = ?1 AND id != ?2",
+                    (new_name, id),
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            if conflict.is_some() {
+                return Err(AppError::InvalidArgument(format!(
+                    "Tag '{}' already exists, merge the tags instead",
+                    new_name
+                )));
+            }
+
+            let affected = Self::affected_todos(&tx, &[id.to_string()])?;
+            tx.execute("UPDATE tags SET name = ?1 WHERE id = ?2", (new_name, id))?;
+            Self::finish_todo_changes(&tx, affected, actor)?;
+
+            let tag = self.get_by_id_internal(&tx, id)?;
+            tx.commit()?;
+            Ok(tag)
+        })
+    }
+
+    /// 将 `source_ids` 中的标签合并到 `target_id`：所有任务改用目标标签，源标签被删除
+    pub fn merge(&self, source_ids: &[String], target_id: &str, actor: &Actor) -> Result<Tag, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            self.get_by_id_internal(&tx, target_id)?;
+            let affected = Self::affected_todos(&tx, source_ids)?;
+
+            for source_id in source_ids.iter().filter(|s| s.as_str() != target_id) {
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM tags WHERE id = ?1)",
+                    [source_id],
+                    |row| row.get(0),
+                )?;
+                if !exists {
+                    return Err(AppError::TagNotFound(source_id.clone()));
+                }
+
+                tx.execute(
+                    "INSERT OR IGNORE INTO todo_tags (todo_id, tag_id)
+                     SELECT todo_id, ?1 FROM todo_tags WHERE tag_id = ?2",
+                    (target_id, source_id),
+                )?;
+                tx.execute("DELETE FROM tags WHERE id = ?1", [source_id])?;
+            }
+
+            Self::finish_todo_changes(&tx, affected, actor)?;
+
+            let tag = self.get_by_id_internal(&tx, target_id)?;
+            tx.commit()?;
+            Ok(tag)
+        })
+    }
+
+    /// 设置标签颜色（`#RRGGBB`），传入 `None` 清除颜色
+    pub fn set_color(&self, id: &str, color: Option<String>) -> Result<Tag, AppError> {
+        if let Some(ref c) = color {
+            validate_color(c)?;
+        }
+
+        self.db.with_conn(|conn| {
+            let rows = conn.execute("UPDATE tags SET color = ?1 WHERE id = ?2", (&color, id))?;
+            if rows == 0 {
+                return Err(AppError::TagNotFound(id.to_string()));
+            }
+
+            self.get_by_id_internal(conn, id)
+        })
+    }
+
+    /// 删除标签，并从所有任务上移除
+    pub fn delete(&self, id: &str, actor: &Actor) -> Result<(), AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let affected = Self::affected_todos(&tx, &[id.to_string()])?;
+
+            // todo_tags 通过外键级联删除
+            let rows = tx.execute("DELETE FROM tags WHERE id = ?1", [id])?;
+            if rows == 0 {
+                return Err(AppError::TagNotFound(id.to_string()));
+            }
+
+            Self::finish_todo_changes(&tx, affected, actor)?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// 使用了这些标签的任务在修改前的快照
+    fn affected_todos(conn: &Connection, tag_ids: &[String]) -> Result<Vec<Todo>, AppError> {
+        let mut todos: Vec<Todo> = Vec::new();
+        for tag_id in tag_ids {
+            let ids: Vec<String> = {
+                let mut stmt = conn.prepare("SELECT todo_id FROM todo_tags WHERE tag_id = ?1")?;
+                let rows = stmt.query_map([tag_id], |row| row.get(0))?;
+                rows.collect::<Result<_, _>>()?
+            };
+
+            for id in ids {
+                if !todos.iter().any(|t| t.id == id) {
+                    todos.push(todo_repo::load_todo(conn, &id)?);
+                }
+            }
+        }
+        Ok(todos)
+    }
+
+    /// 更新受影响任务的 `updated_at`，并为每个任务记录标签变更事件
+    fn finish_todo_changes(conn: &Connection, before: Vec<Todo>, actor: &Actor) -> Result<(), AppError> {
+        let now = Utc::now().to_rfc3339();
+        let ctx = EventContext::new(actor);
+        for todo in before {
+            conn.execute("UPDATE todos SET updated_at = ?1 WHERE id = ?2", (&now, &todo.id))?;
+            let after = todo_repo::load_todo(conn, &todo.id)?;
+            history_repo::record_event(conn, &ctx, TodoAction::Updated, Some(&todo), Some(&after))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TestDb, TodoRepository, UndoRepository};
+    use crate::models::todo::CreateTodoRequest;
+
+    fn create(todos: &TodoRepository, text: &str, tags: &[&str]) -> Todo {
+        let tags = tags.iter().map(|t| t.to_string()).collect();
+        todos.create(CreateTodoRequest { text: text.into(), tags: Some(tags), ..Default::default() }, &Actor::User).unwrap()
+    }
+
+    fn tag_id(repo: &TagRepository, name: &str) -> String {
+        repo.get_all().unwrap().into_iter().find(|t| t.name == name).unwrap().id
+    }
+
+    fn sorted_tags(todos: &TodoRepository, id: &str) -> Vec<String> {
+        let mut tags = todos.get_by_id(id).unwrap().tags;
+        tags.sort();
+        tags
+    }
+
+    #[test]
+    fn rename_rejects_names_of_other_tags() {
+        let test = TestDb::new();
+        let repo = TagRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        create(&todos, "a", &["work", "home"]);
+        let work = tag_id(&repo, "work");
+
+        assert!(matches!(repo.rename(&work, "HOME", &Actor::User), Err(AppError::InvalidArgument(_))));
+        assert!(matches!(repo.rename(&work, "  ", &Actor::User), Err(AppError::InvalidArgument(_))));
+        // 只改大小写不算冲突
+        assert_eq!(repo.rename(&work, "Work", &Actor::User).unwrap().name, "Work");
+    }
+
+    #[test]
+    fn rename_can_be_undone() {
+        let test = TestDb::new();
+        let repo = TagRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let undo = UndoRepository::new(test.db.clone());
+        let todo = create(&todos, "a", &["work"]);
+        let work = tag_id(&repo, "work");
+
+        let renamed = repo.rename(&work, "工作", &Actor::User).unwrap();
+        assert_eq!(renamed.todo_count, 1);
+        assert_eq!(todos.get_by_id(&todo.id).unwrap().tags, vec!["工作"]);
+
+        let step = undo.undo().unwrap().unwrap();
+        assert_eq!(step.todo_count, 1);
+        assert_eq!(todos.get_by_id(&todo.id).unwrap().tags, vec!["work"]);
+        undo.redo().unwrap().unwrap();
+        assert_eq!(todos.get_by_id(&todo.id).unwrap().tags, vec!["工作"]);
+    }
+
+    #[test]
+    fn merge_moves_todos_to_the_target() {
+        let test = TestDb::new();
+        let repo = TagRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let only_source = create(&todos, "a", &["job"]);
+        let both = create(&todos, "b", &["job", "work", "home"]);
+        let unrelated = create(&todos, "c", &["home"]);
+        let job = tag_id(&repo, "job");
+        let work = tag_id(&repo, "work");
+
+        let merged = repo.merge(&[job.clone(), work.clone()], &work, &Actor::User).unwrap();
+
+        assert_eq!(merged.todo_count, 2);
+        assert!(matches!(repo.get_by_id(&job), Err(AppError::TagNotFound(_))));
+        assert_eq!(sorted_tags(&todos, &only_source.id), vec!["work"]);
+        assert_eq!(sorted_tags(&todos, &both.id), vec!["home", "work"]);
+        assert_eq!(sorted_tags(&todos, &unrelated.id), vec!["home"]);
+    }
+
+    #[test]
+    fn merge_with_missing_source_changes_nothing() {
+        let test = TestDb::new();
+        let repo = TagRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let todo = create(&todos, "a", &["job"]);
+        create(&todos, "b", &["work"]);
+        let job = tag_id(&repo, "job");
+        let work = tag_id(&repo, "work");
+
+        assert!(matches!(
+            repo.merge(&[job.clone(), "missing".into()], &work, &Actor::User),
+            Err(AppError::TagNotFound(_))
+        ));
+        assert!(repo.get_by_id(&job).is_ok());
+        assert_eq!(todos.get_by_id(&todo.id).unwrap().tags, vec!["job"]);
+    }
+
+    #[test]
+    fn delete_removes_the_tag_from_todos() {
+        let test = TestDb::new();
+        let repo = TagRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let undo = UndoRepository::new(test.db.clone());
+        let todo = create(&todos, "a", &["work", "home"]);
+        let work = tag_id(&repo, "work");
+
+        repo.delete(&work, &Actor::User).unwrap();
+
+        assert!(matches!(repo.get_by_id(&work), Err(AppError::TagNotFound(_))));
+        assert_eq!(todos.get_by_id(&todo.id).unwrap().tags, vec!["home"]);
+        assert!(matches!(repo.delete(&work, &Actor::User), Err(AppError::TagNotFound(_))));
+
+        // 撤销时按名称重建标签
+        undo.undo().unwrap().unwrap();
+        assert_eq!(sorted_tags(&todos, &todo.id), vec!["home", "work"]);
+    }
+}
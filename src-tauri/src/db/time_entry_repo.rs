@@ -0,0 +1,377 @@
+use crate::db::history_repo::EventContext;
+use crate::db::todo_repo::{self, TodoRepository};
+use crate::db::{settings_repo, Database};
+use crate::error::AppError;
+use crate::models::due_date::{instant_sql, DueDate, UserTimezone};
+use crate::models::history::Actor;
+use crate::models::time_entry::*;
+use crate::models::todo::{TodoStatus, UpdateTodoRequest};
+use chrono::{DateTime, Utc};
+use rusqlite::{named_params, Connection, OptionalExtension};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const TIME_ENTRY_COLUMNS: &str = "time_entries.id, time_entries.todo_id, todos.text, time_entries.started_at,
+    time_entries.ended_at, time_entries.note, time_entries.created_at, time_entries.updated_at";
+
+/// 计时记录落在 `[:from, :to)` 内的秒数（Unix 时间戳），进行中的计时算到 `:now`
+const CLIPPED_SECONDS_SQL: &str = "MAX(0,
+    MIN(COALESCE(CAST(strftime('%s', time_entries.ended_at) AS INTEGER), :now), :to)
+    - MAX(CAST(strftime('%s', time_entries.started_at) AS INTEGER), :from))";
+
+/// 汇总的过滤条件：`:todo_id`、`:tag` 为 NULL 时不限制
+const REPORT_FILTER_SQL: &str = "todos.deleted_at IS NULL
+    AND (:todo_id IS NULL OR time_entries.todo_id = :todo_id)
+    AND (:tag IS NULL OR EXISTS (SELECT 1 FROM todo_tags JOIN tags ON tags.id = todo_tags.tag_id
+        WHERE todo_tags.todo_id = todos.id AND tags.name = :tag))";
+
+fn row_to_time_entry(row: &rusqlite::Row) -> rusqlite::Result<TimeEntry> {
+    let started_at: String = row.get(3)?;
+    let ended_at: Option<String> = row.get(4)?;
+
+    Ok(TimeEntry {
+        id: row.get(0)?,
+        todo_id: row.get(1)?,
+        todo_text: row.get(2)?,
+        duration_seconds: duration_seconds(&started_at, ended_at.as_deref()),
+        started_at,
+        ended_at,
+        note: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// 计时持续的秒数，进行中的计时算到当前时间
+fn duration_seconds(started_at: &str, ended_at: Option<&str>) -> i64 {
+    let parse = |value: &str| DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc));
+    let end = match ended_at {
+        Some(ended_at) => parse(ended_at),
+        None => Some(Utc::now()),
+    };
+
+    match (parse(started_at), end) {
+        (Some(start), Some(end)) => (end - start).num_seconds().max(0),
+        _ => 0,
+    }
+}
+
+fn load_time_entry(conn: &Connection, id: &str) -> Result<TimeEntry, AppError> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM time_entries JOIN todos ON todos.id = time_entries.todo_id WHERE time_entries.id = ?1",
+            TIME_ENTRY_COLUMNS
+        ),
+        [id],
+        row_to_time_entry,
+    )
+    .map_err(|_| AppError::TimeEntryNotFound(id.to_string()))
+}
+
+fn load_running(conn: &Connection) -> Result<Option<TimeEntry>, AppError> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM time_entries JOIN todos ON todos.id = time_entries.todo_id
+                 WHERE time_entries.ended_at IS NULL",
+                TIME_ENTRY_COLUMNS
+            ),
+            [],
+            row_to_time_entry,
+        )
+        .optional()?)
+}
+
+fn normalize_note(note: Option<&str>) -> Option<String> {
+    note.map(str::trim).filter(|n| !n.is_empty()).map(String::from)
+}
+
+/// 解析计时记录的时刻（RFC 3339 或"今天下午两点"），必须包含具体时间
+fn entry_instant(value: &str, tz: &UserTimezone) -> Result<String, AppError> {
+    match DueDate::resolve(value, tz)? {
+        DueDate::At(at) => Ok(instant_sql(&at.with_timezone(&Utc))),
+        DueDate::AllDay(_) => Err(AppError::InvalidArgument(format!("Time entry needs a time of day: {}", value))),
+    }
+}
+
+fn check_order(started_at: &str, ended_at: Option<&str>) -> Result<(), AppError> {
+    match ended_at {
+        Some(ended_at) if ended_at <= started_at => Err(AppError::InvalidArgument(
+            "Time entry must end after it starts".into(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 计时记录：开始/停止计时、手动添加与修改记录，以及按任务或标签汇总
+pub struct TimeEntryRepository {
+    db: Arc<Database>,
+    /// 开始计时时通过它把任务改为进行中，以便记录历史
+    todos: TodoRepository,
+}
+
+impl TimeEntryRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { todos: TodoRepository::new(db.clone()), db }
+    }
+
+    /// 任务的全部计时记录，最近的在前
+    pub fn get_for_todo(&self, todo_id: &str) -> Result<Vec<TimeEntry>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM time_entries JOIN todos ON todos.id = time_entries.todo_id
+                 WHERE time_entries.todo_id = ?1
+                 ORDER BY time_entries.started_at DESC",
+                TIME_ENTRY_COLUMNS
+            ))?;
+
+            let entries = stmt.query_map([todo_id], row_to_time_entry)?;
+
+            let mut result = Vec::new();
+            for entry in entries {
+                result.push(entry?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// 正在进行的计时
+    pub fn get_running(&self) -> Result<Option<TimeEntry>, AppError> {
+        self.db.with_conn(load_running)
+    }
+
+    /// 开始为任务计时。已有其他任务在计时时先将其停止，该任务已在计时时直接返回当前计时。
+    /// 待办状态的任务会变为进行中（工作流不允许时保持原状态）；已完成或已取消的任务不能计时。
+    /// 状态变化记入历史但不单独作为撤销步骤：计时记录不在撤销范围内，
+    /// 只撤销状态会留下一个仍在为待办任务计时的计时器，需要时应停止计时后手动修改状态。
+    pub fn start_timer(&self, todo_id: &str, note: Option<&str>, actor: &Actor) -> Result<TimeEntry, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let todo = todo_repo::load_todo(&tx, todo_id)?;
+            if todo.deleted_at.is_some() {
+                return Err(AppError::TodoNotFound(todo_id.to_string()));
+            }
+            if todo.completed || todo.status == TodoStatus::Cancelled {
+                return Err(AppError::InvalidArgument(format!(
+                    "Cannot start a timer on a finished todo: {}",
+                    todo.text
+                )));
+            }
+
+            // 计时记录与任务的开始时间取同一时刻，各自按所在表的格式写入
+            let now = Utc::now();
+            let started_at = instant_sql(&now);
+            if let Some(running) = load_running(&tx)? {
+                if running.todo_id == todo_id {
+                    return Ok(running);
+                }
+                tx.execute(
+                    "UPDATE time_entries SET ended_at = ?1, updated_at = ?1 WHERE id = ?2",
+                    (&started_at, &running.id),
+                )?;
+            }
+
+            let id = Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO time_entries (id, todo_id, started_at, note, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?3, ?3)",
+                (&id, todo_id, &started_at, normalize_note(note)),
+            )?;
+
+            if todo.status == TodoStatus::Pending {
+                let request = UpdateTodoRequest { status: Some(TodoStatus::InProgress), ..Default::default() };
+                match self.todos.update_at(&tx, todo_id, &request, &EventContext::untracked(actor), &now) {
+                    Ok(_) | Err(AppError::StateTransitionNotAllowed { .. } | AppError::WipLimitReached { .. }) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let entry = load_time_entry(&tx, &id)?;
+            tx.commit()?;
+            Ok(entry)
+        })
+    }
+
+    /// 停止正在进行的计时，没有计时时返回 `None`
+    pub fn stop_timer(&self) -> Result<Option<TimeEntry>, AppError> {
+        self.db.with_conn(|conn| {
+            let now = instant_sql(&Utc::now());
+            let id: Option<String> = conn
+                .query_row(
+                    "UPDATE time_entries SET ended_at = ?1, updated_at = ?1 WHERE ended_at IS NULL RETURNING id",
+                    [&now],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            id.map(|id| load_time_entry(conn, &id)).transpose()
+        })
+    }
+
+    /// 手动添加一条已结束的计时记录
+    pub fn create(&self, todo_id: &str, request: CreateTimeEntryRequest) -> Result<TimeEntry, AppError> {
+        self.db.with_conn(|conn| {
+            let todo = todo_repo::load_todo(conn, todo_id)?;
+            if todo.deleted_at.is_some() {
+                return Err(AppError::TodoNotFound(todo_id.to_string()));
+            }
+
+            let tz = settings_repo::load_timezone(conn)?;
+            let started_at = entry_instant(&request.started_at, &tz)?;
+            let ended_at = entry_instant(&request.ended_at, &tz)?;
+            check_order(&started_at, Some(&ended_at))?;
+
+            let id = Uuid::new_v4().to_string();
+            let now = instant_sql(&Utc::now());
+            conn.execute(
+                "INSERT INTO time_entries (id, todo_id, started_at, ended_at, note, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                (&id, todo_id, &started_at, &ended_at, normalize_note(request.note.as_deref()), &now),
+            )?;
+
+            load_time_entry(conn, &id)
+        })
+    }
+
+    pub fn update(&self, id: &str, request: UpdateTimeEntryRequest) -> Result<TimeEntry, AppError> {
+        self.db.with_conn(|conn| {
+            let existing = load_time_entry(conn, id)?;
+            let tz = settings_repo::load_timezone(conn)?;
+
+            let started_at = match request.started_at {
+                Some(ref value) => entry_instant(value, &tz)?,
+                None => existing.started_at.clone(),
+            };
+            let ended_at = match request.ended_at {
+                Some(ref value) => Some(entry_instant(value, &tz)?),
+                None => existing.ended_at.clone(),
+            };
+            check_order(&started_at, ended_at.as_deref())?;
+            let note = match request.note {
+                Some(ref note) => normalize_note(Some(note)),
+                None => existing.note.clone(),
+            };
+
+            conn.execute(
+                "UPDATE time_entries SET started_at = ?1, ended_at = ?2, note = ?3, updated_at = ?4 WHERE id = ?5",
+                (&started_at, &ended_at, &note, instant_sql(&Utc::now()), id),
+            )?;
+
+            load_time_entry(conn, id)
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), AppError> {
+        self.db.with_conn(|conn| {
+            let affected = conn.execute("DELETE FROM time_entries WHERE id = ?1", [id])?;
+            if affected == 0 {
+                return Err(AppError::TimeEntryNotFound(id.to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    /// 按任务或标签汇总计时，不包含回收站中的任务
+    pub fn report(&self, query: &TimeReportQuery) -> Result<TimeReport, AppError> {
+        self.db.with_conn(|conn| {
+            let tz = settings_repo::load_timezone(conn)?;
+
+            let (from, to) = resolve_range(query.period, query.from.as_deref(), query.to.as_deref(), &tz)?;
+
+            let (key, label, joins) = match query.group_by {
+                TimeGroup::Todo => ("todos.id", "todos.text", ""),
+                TimeGroup::Tag => (
+                    "tags.name",
+                    "tags.name",
+                    " JOIN todo_tags ON todo_tags.todo_id = todos.id JOIN tags ON tags.id = todo_tags.tag_id",
+                ),
+            };
+
+            let params = named_params! {
+                ":now": Utc::now().timestamp(),
+                ":from": from.map(|f| f.timestamp()).unwrap_or(i64::MIN),
+                ":to": to.map(|t| t.timestamp()).unwrap_or(i64::MAX),
+                ":todo_id": query.todo_id,
+                ":tag": query.tag,
+            };
+
+            let total_seconds: i64 = conn.query_row(
+                &format!(
+                    "SELECT COALESCE(SUM({}), 0) FROM time_entries JOIN todos ON todos.id = time_entries.todo_id WHERE {}",
+                    CLIPPED_SECONDS_SQL, REPORT_FILTER_SQL
+                ),
+                params,
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {key}, {label}, SUM({clipped}) AS seconds, COUNT(*)
+                 FROM time_entries JOIN todos ON todos.id = time_entries.todo_id{joins}
+                 WHERE {conditions} AND {clipped} > 0
+                 GROUP BY {key}
+                 ORDER BY seconds DESC, {label}",
+                key = key,
+                label = label,
+                clipped = CLIPPED_SECONDS_SQL,
+                joins = joins,
+                conditions = REPORT_FILTER_SQL,
+            ))?;
+            let groups = stmt
+                .query_map(params, |row| {
+                    Ok(TimeTotal {
+                        key: row.get(0)?,
+                        label: row.get(1)?,
+                        seconds: row.get(2)?,
+                        entry_count: row.get(3)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(TimeReport {
+                from: from.map(|f| instant_sql(&f)),
+                to: to.map(|t| instant_sql(&t)),
+                total_seconds,
+                groups,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{HistoryRepository, TestDb, UndoRepository};
+    use crate::models::todo::CreateTodoRequest;
+
+    #[test]
+    fn start_timer_moves_pending_todo_to_in_progress_without_an_undo_step() {
+        let test = TestDb::new();
+        let todos = TodoRepository::new(test.db.clone());
+        let repo = TimeEntryRepository::new(test.db.clone());
+        let undo = UndoRepository::new(test.db.clone());
+        let todo = todos.create(CreateTodoRequest { text: "写周报".into(), ..Default::default() }, &Actor::User).unwrap();
+        let stack_before: Vec<String> = undo.get_stack().unwrap().undo.into_iter().map(|s| s.id).collect();
+
+        let entry = repo.start_timer(&todo.id, None, &Actor::User).unwrap();
+        let started = todos.get_by_id(&todo.id).unwrap();
+        assert_eq!(started.status, TodoStatus::InProgress);
+        let todo_started_at = DateTime::parse_from_rfc3339(started.started_at.as_deref().unwrap()).unwrap();
+        let entry_started_at = DateTime::parse_from_rfc3339(&entry.started_at).unwrap();
+        assert_eq!(todo_started_at.timestamp(), entry_started_at.timestamp());
+
+        // 状态变化记入历史，但撤销栈不变，计时器与任务状态保持一致
+        let history = HistoryRepository::new(test.db.clone()).get_for_todo(&todo.id).unwrap();
+        assert!(history.iter().any(|e| e.changes.contains_key("status")));
+        let stack_after: Vec<String> = undo.get_stack().unwrap().undo.into_iter().map(|s| s.id).collect();
+        assert_eq!(stack_after, stack_before);
+        let running = repo.get_running().unwrap().unwrap();
+        assert_eq!(running.id, entry.id);
+        assert_eq!(running.ended_at, None);
+
+        let stopped = repo.stop_timer().unwrap().unwrap();
+        assert_eq!(stopped.id, entry.id);
+        assert!(repo.get_running().unwrap().is_none());
+        assert_eq!(todos.get_by_id(&todo.id).unwrap().status, TodoStatus::InProgress);
+    }
+}
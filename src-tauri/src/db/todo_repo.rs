@@ -1,228 +1,1990 @@
-use crate::db::Database;
+use crate::db::{fts, history_repo, list_repo, reminder_repo, settings_repo, tag_repo, undo_repo, workflow_repo, Database};
+use crate::db::history_repo::EventContext;
 use crate::error::AppError;
+use crate::models::history::{Actor, TodoAction};
+use crate::models::due_date::{self, DueDate, UserTimezone};
+use crate::models::recurrence::{self, RecurrenceRule};
+use crate::models::tag::TagMatch;
 use crate::models::todo::*;
-use chrono::Utc;
+use crate::models::workflow::{Board, BoardColumn, StateCategory};
+use chrono::{DateTime, NaiveTime, Utc};
+use rusqlite::OptionalExtension;
+use rusqlite::types::Value as SqlValue;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// 查询 Todo 时统一使用的列，顺序与 `row_to_todo` 对应
+const TODO_COLUMNS: &str = "todos.id, todos.text, todos.completed, todos.status, todos.priority, todos.due_date,
+    (SELECT json_group_array(name) FROM (
+        SELECT tags.name FROM todo_tags JOIN tags ON tags.id = todo_tags.tag_id
+        WHERE todo_tags.todo_id = todos.id ORDER BY tags.name COLLATE NOCASE
+    )),
+    todos.created_at, todos.updated_at, todos.deleted_at, todos.position, todos.parent_id, todos.auto_complete,
+    (SELECT COUNT(*) FROM todos AS children WHERE children.parent_id = todos.id AND children.deleted_at IS NULL),
+    (SELECT COUNT(*) FROM todos AS children WHERE children.parent_id = todos.id AND children.deleted_at IS NULL
+        AND children.completed = 1),
+    (SELECT json_group_array(depends_on_id) FROM todo_dependencies WHERE todo_dependencies.todo_id = todos.id),
+    (SELECT json_group_array(blockers.id) FROM todo_dependencies
+        JOIN todos AS blockers ON blockers.id = todo_dependencies.depends_on_id
+        WHERE todo_dependencies.todo_id = todos.id
+        AND blockers.deleted_at IS NULL AND blockers.completed = 0 AND blockers.status != 'cancelled'),
+    todos.list_id, todos.recurrence, todos.series_id, todos.occurrence, todos.notes,
+    (SELECT json_group_array(json_object(
+        'id', id, 'text', text, 'done', json(CASE WHEN done = 1 THEN 'true' ELSE 'false' END)
+    )) FROM (
+        SELECT id, text, done FROM checklist_items WHERE checklist_items.todo_id = todos.id ORDER BY position
+    )),
+    todos.estimate_minutes, todos.story_points, todos.started_at, todos.completed_at, todos.cancelled_at,
+    todos.state_id";
+
+/// `TODO_COLUMNS` 的列数，附加在其后的查询列从这个下标开始
+const TODO_COLUMN_COUNT: usize = 29;
+
+/// 全文检索的相关度，标题中的匹配权重高于备注与清单（第一个权重对应不参与索引的 `id` 列）
+const FTS_RANK_SQL: &str = "bm25(todos_fts, 0.0, 4.0, 1.0, 1.0)";
+
+/// 任务存在未完成的依赖
+const BLOCKED_SQL: &str = "EXISTS (SELECT 1 FROM todo_dependencies
+    JOIN todos AS blockers ON blockers.id = todo_dependencies.depends_on_id
+    WHERE todo_dependencies.todo_id = todos.id
+    AND blockers.deleted_at IS NULL AND blockers.completed = 0 AND blockers.status != 'cancelled')";
+
+/// 现在就可以开始的任务：未完成、未取消、没有未完成的依赖与子任务
+const ACTIONABLE_SQL: &str = "(todos.completed = 0 AND todos.status != 'cancelled'
+    AND NOT EXISTS (SELECT 1 FROM todo_dependencies
+        JOIN todos AS blockers ON blockers.id = todo_dependencies.depends_on_id
+        WHERE todo_dependencies.todo_id = todos.id
+        AND blockers.deleted_at IS NULL AND blockers.completed = 0 AND blockers.status != 'cancelled')
+    AND NOT EXISTS (SELECT 1 FROM todos AS children
+        WHERE children.parent_id = todos.id AND children.deleted_at IS NULL
+        AND children.completed = 0 AND children.status != 'cancelled'))";
+
+/// 以 `?1` 为根的子树（不含根本身），`depth` 从 1 开始，包括回收站中的任务
+const DESCENDANTS_CTE: &str = "WITH RECURSIVE subtree(id, depth) AS (
+        SELECT id, 0 FROM todos WHERE id = ?1
+        UNION ALL
+        SELECT todos.id, subtree.depth + 1 FROM todos JOIN subtree ON todos.parent_id = subtree.id
+    )";
+
+/// 新任务之间的位置间隔
+pub(crate) const POSITION_GAP: f64 = 1024.0;
+
+/// 相邻位置之差小于该值时重新分配所有位置，避免浮点精度耗尽
+const MIN_POSITION_GAP: f64 = 1e-6;
+
+/// 未指定列表时排除已归档列表中的任务
+pub(crate) const NOT_ARCHIVED_SQL: &str = "(todos.list_id IS NULL
+    OR NOT EXISTS (SELECT 1 FROM lists WHERE lists.id = todos.list_id AND lists.archived = 1))";
+
+/// 逾期：截止时刻不晚于 `?`（当前时间）且未完成、未取消
+const OVERDUE_SQL: &str = "(todos.due_at IS NOT NULL AND todos.due_at <= ?
+    AND todos.completed = 0 AND todos.status != 'cancelled')";
+
+/// 标签过滤条件：任务拥有名称为 `?` 的标签（名称比较忽略大小写）
+const HAS_TAG_SQL: &str = "EXISTS (SELECT 1 FROM todo_tags JOIN tags ON tags.id = todo_tags.tag_id
+    WHERE todo_tags.todo_id = todos.id AND tags.name = ?)";
+
+fn row_to_todo(row: &rusqlite::Row) -> rusqlite::Result<Todo> {
+    let tags_json: String = row.get(6)?;
+    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    let depends_on: Vec<String> = serde_json::from_str(&row.get::<_, String>(15)?).unwrap_or_default();
+    let blocked_by: Vec<String> = serde_json::from_str(&row.get::<_, String>(16)?).unwrap_or_default();
+    let checklist: Vec<ChecklistItem> = serde_json::from_str(&row.get::<_, String>(22)?).unwrap_or_default();
+
+    Ok(Todo {
+        id: row.get(0)?,
+        text: row.get(1)?,
+        completed: row.get::<_, i32>(2)? != 0,
+        status: TodoStatus::from_str(&row.get::<_, String>(3)?),
+        priority: Priority::from_i32(row.get(4)?),
+        due_date: row.get::<_, Option<String>>(5)?.as_deref().and_then(DueDate::parse),
+        tags,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        deleted_at: row.get(9)?,
+        position: row.get(10)?,
+        parent_id: row.get(11)?,
+        auto_complete: row.get::<_, i32>(12)? != 0,
+        child_count: row.get(13)?,
+        completed_child_count: row.get(14)?,
+        depends_on,
+        blocked: !blocked_by.is_empty(),
+        blocked_by,
+        list_id: row.get(17)?,
+        recurrence: row.get(18)?,
+        series_id: row.get(19)?,
+        occurrence: row.get(20)?,
+        notes: row.get(21)?,
+        checklist,
+        estimate_minutes: row.get(23)?,
+        story_points: row.get(24)?,
+        started_at: row.get(25)?,
+        completed_at: row.get(26)?,
+        cancelled_at: row.get(27)?,
+        state_id: row.get(28)?,
+    })
+}
+
+/// 备注为空白时不保存
+fn normalize_notes(notes: Option<&str>) -> Option<String> {
+    notes.filter(|n| !n.trim().is_empty()).map(String::from)
+}
+
+/// 预估分钟数，0 视为未预估
+fn normalize_estimate_minutes(minutes: Option<u32>) -> Option<u32> {
+    minutes.filter(|m| *m > 0)
+}
+
+/// 故事点数必须是非负的有限数，0 视为未预估
+fn normalize_story_points(points: Option<f64>) -> Result<Option<f64>, AppError> {
+    match points {
+        Some(points) if !points.is_finite() || points < 0.0 => {
+            Err(AppError::InvalidArgument(format!("Invalid story points: {}", points)))
+        }
+        Some(0.0) => Ok(None),
+        points => Ok(points),
+    }
+}
+
+/// 确定新状态。`completed` 是状态的简写：`true` 对应已完成，`false` 把已完成的任务重新打开为待办；
+/// 与 `status` 同时提供但不一致，或状态变化不被允许时返回错误。
+fn resolve_status(existing: &Todo, status: Option<&TodoStatus>, completed: Option<bool>) -> Result<TodoStatus, AppError> {
+    let status = match (status, completed) {
+        (Some(status), Some(completed)) if completed != (*status == TodoStatus::Completed) => {
+            return Err(AppError::InvalidArgument(format!(
+                "completed = {} conflicts with status {}",
+                completed,
+                status.as_str()
+            )));
+        }
+        (Some(status), _) => status.clone(),
+        (None, Some(true)) => TodoStatus::Completed,
+        (None, Some(false)) if existing.status == TodoStatus::Completed => TodoStatus::Pending,
+        (None, _) => existing.status.clone(),
+    };
+
+    if !existing.status.can_transition_to(&status) {
+        return Err(AppError::InvalidTransition { from: existing.status.clone(), to: status });
+    }
+    Ok(status)
+}
+
+/// 根据修改请求确定新的任务状态与工作流状态。指定 `state_id` 时任务状态由工作流状态的类别决定；
+/// 只修改任务状态时，仍属于同一类别的工作流状态保持不变，否则移入对应类别的默认状态，取消时离开工作流。
+/// 工作流状态变化需符合允许的转换与 WIP 限制。
+pub(crate) fn resolve_state(
+    conn: &rusqlite::Connection,
+    existing: &Todo,
+    request: &UpdateTodoRequest,
+) -> Result<(TodoStatus, Option<String>), AppError> {
+    let target = match request.state_id.as_deref() {
+        Some(state_id) => {
+            let state = workflow_repo::load_state(conn, state_id)?;
+            if request.status.as_ref().is_some_and(|status| *status != state.category.status()) {
+                return Err(AppError::InvalidArgument(format!(
+                    "status conflicts with state '{}' ({})",
+                    state.name,
+                    state.category.as_str()
+                )));
+            }
+            Some(state)
+        }
+        None => None,
+    };
+
+    let requested = target.as_ref().map(|state| state.category.status()).or_else(|| request.status.clone());
+    let status = resolve_status(existing, requested.as_ref(), request.completed)?;
+
+    let state = match (target, StateCategory::from_status(&status)) {
+        (Some(state), _) => Some(state),
+        (None, None) => None,
+        (None, Some(category)) => {
+            let current = existing.state_id.as_deref().and_then(|id| workflow_repo::load_state(conn, id).ok());
+            match current {
+                Some(current) if current.category == category => Some(current),
+                _ => Some(workflow_repo::default_state(conn, category)?),
+            }
+        }
+    };
+    if let Some(ref state) = state {
+        if existing.state_id.as_deref() != Some(state.id.as_str()) {
+            workflow_repo::check_move(conn, existing.state_id.as_deref(), state, &existing.id)?;
+        }
+    }
+
+    Ok((status, state.map(|state| state.id)))
+}
+
+/// 变为 `status` 后的 `started_at`、`completed_at`、`cancelled_at`：
+/// 第一次进行时记录开始时间，完成和取消时记录相应时间，离开已完成或已取消状态时清除。
+fn status_timestamps(existing: &Todo, status: &TodoStatus, now: &str) -> (Option<String>, Option<String>, Option<String>) {
+    if *status == existing.status {
+        return (existing.started_at.clone(), existing.completed_at.clone(), existing.cancelled_at.clone());
+    }
+
+    let started_at = match status {
+        TodoStatus::InProgress => existing.started_at.clone().or_else(|| Some(now.to_string())),
+        _ => existing.started_at.clone(),
+    };
+    let completed_at = (*status == TodoStatus::Completed).then(|| now.to_string());
+    let cancelled_at = (*status == TodoStatus::Cancelled).then(|| now.to_string());
+    (started_at, completed_at, cancelled_at)
+}
+
+/// 清单项文本不能为空
+fn checklist_text(text: &str) -> Result<String, AppError> {
+    match text.trim() {
+        "" => Err(AppError::InvalidArgument("Checklist item text must not be empty".into())),
+        text => Ok(text.to_string()),
+    }
+}
+
+/// 在任务清单末尾追加一项，返回清单项 ID
+fn insert_checklist_item(conn: &rusqlite::Connection, todo_id: &str, text: &str, done: bool, now: &str) -> Result<String, AppError> {
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO checklist_items (id, todo_id, text, done, position, created_at)
+         VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(position), 0) + 1 FROM checklist_items WHERE todo_id = ?2), ?5)",
+        (&id, todo_id, text, if done { 1 } else { 0 }, now),
+    )?;
+    Ok(id)
+}
+
+/// 校验重复规则并转换为规范形式，空字符串表示不重复
+fn normalize_recurrence(rule: Option<&str>) -> Result<Option<String>, AppError> {
+    match rule.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(rule) => Ok(Some(RecurrenceRule::parse(rule)?.to_string())),
+    }
+}
+
+/// 按用户时区解析截止日期（ISO 日期或"明天下午三点"等自然语言），空字符串表示没有截止日期
+fn normalize_due_date(due_date: Option<&str>, tz: &UserTimezone) -> Result<Option<DueDate>, AppError> {
+    match due_date.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(due_date) => Ok(Some(DueDate::resolve(due_date, tz)?)),
+    }
+}
+
+/// 写入 `todos.due_date` 与 `todos.due_at` 的值
+fn due_columns(due_date: Option<&DueDate>, tz: &UserTimezone) -> (Option<String>, Option<String>) {
+    (
+        due_date.map(|d| d.to_string()),
+        due_date.and_then(|d| d.deadline_sql(tz)),
+    )
+}
+
+/// 时区设置改变后重新计算全天任务的截止时刻及其相对提醒，应与保存设置在同一个事务中调用
+pub(crate) fn refresh_all_day_deadlines(conn: &rusqlite::Connection, tz: &UserTimezone) -> Result<(), AppError> {
+    let rows: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, due_date FROM todos WHERE due_date IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    for (id, value) in rows {
+        let due_date = match DueDate::parse(&value) {
+            Some(due_date) if due_date.is_all_day() => due_date,
+            _ => continue,
+        };
+        conn.execute(
+            "UPDATE todos SET due_at = ?1 WHERE id = ?2",
+            (due_date.deadline_sql(tz), &id),
+        )?;
+        reminder_repo::reschedule_for_todo(conn, &id, Some(&due_date))?;
+    }
+
+    Ok(())
+}
+
+/// 截止时间过滤的边界，格式与 `todos.due_at` 相同。
+/// 日期在用户时区中不存在（夏令时切换）时返回错误。
+fn due_bound(instant: Option<DateTime<Utc>>, value: &str) -> Result<String, AppError> {
+    instant
+        .map(|instant| due_date::instant_sql(&instant))
+        .ok_or_else(|| AppError::InvalidArgument(format!("Unrecognized due date: {}", value)))
+}
+
+/// 由 `TodoFilter` 生成的查询片段：`joins` 追加在 `FROM todos` 之后，
+/// `conditions` 以 ` AND ...` 的形式追加在 `WHERE todos.deleted_at IS NULL` 之后。
+struct FilterSql {
+    joins: String,
+    conditions: String,
+    params: Vec<Box<dyn rusqlite::ToSql>>,
+    /// 包含全文检索条件，可以按 bm25 排序
+    ranked: bool,
+}
+
+impl FilterSql {
+    fn new(filter: Option<&TodoFilter>, tz: &UserTimezone) -> Result<Self, AppError> {
+        let mut sql = FilterSql {
+            joins: String::new(),
+            conditions: String::new(),
+            params: Vec::new(),
+            ranked: false,
+        };
+
+        let default_filter = TodoFilter::default();
+        let f = filter.unwrap_or(&default_filter);
+
+        match f.list_id.as_deref().map(str::trim) {
+            Some("") => sql.conditions.push_str(" AND todos.list_id IS NULL"),
+            Some(list_id) => {
+                sql.conditions.push_str(" AND todos.list_id = ?");
+                sql.params.push(Box::new(list_id.to_string()));
+            }
+            None => {
+                sql.conditions.push_str(" AND ");
+                sql.conditions.push_str(NOT_ARCHIVED_SQL);
+            }
+        }
+
+        if let Some(ref status) = f.status {
+            sql.conditions.push_str(" AND todos.status = ?");
+            sql.params.push(Box::new(status.as_str().to_string()));
+        }
+        if let Some(ref state_id) = f.state_id {
+            sql.conditions.push_str(" AND todos.state_id = ?");
+            sql.params.push(Box::new(state_id.clone()));
+        }
+        if let Some(completed) = f.completed {
+            sql.conditions.push_str(" AND todos.completed = ?");
+            sql.params.push(Box::new(if completed { 1 } else { 0 }));
+        }
+        if let Some(ref priority) = f.priority {
+            sql.conditions.push_str(" AND todos.priority = ?");
+            sql.params.push(Box::new(priority.as_i32()));
+        }
+        if let Some(query) = f.search.as_deref().and_then(fts::build_match_query) {
+            // 全文检索：连接 FTS 表并按相关度排序
+            sql.joins.push_str(" JOIN todos_fts ON todos_fts.id = todos.id");
+            sql.conditions.push_str(" AND todos_fts MATCH ?");
+            sql.params.push(Box::new(query));
+            sql.ranked = true;
+        }
+        if let Some(ref tag) = f.tag {
+            sql.conditions.push_str(" AND ");
+            sql.conditions.push_str(HAS_TAG_SQL);
+            sql.params.push(Box::new(tag.trim().to_string()));
+        }
+        if let Some(ref tags) = f.tags {
+            let clauses: Vec<&str> = tags.iter()
+                .filter(|t| !t.trim().is_empty())
+                .map(|t| {
+                    sql.params.push(Box::new(t.trim().to_string()));
+                    HAS_TAG_SQL
+                })
+                .collect();
+
+            if !clauses.is_empty() {
+                let joiner = match f.tag_match.unwrap_or_default() {
+                    TagMatch::All => " AND ",
+                    TagMatch::Any => " OR ",
+                };
+                sql.conditions.push_str(&format!(" AND ({})", clauses.join(joiner)));
+            }
+        }
+        if let Some(actionable) = f.actionable {
+            sql.conditions.push_str(if actionable { " AND " } else { " AND NOT " });
+            sql.conditions.push_str(ACTIONABLE_SQL);
+        }
+        // 截止时间范围：日期包含当天，全天任务的截止时刻是当天结束
+        if let Some(value) = f.due_before.as_deref().filter(|v| !v.trim().is_empty()) {
+            let bound = DueDate::resolve(value, tz)?.deadline(tz);
+            sql.conditions.push_str(" AND todos.due_at <= ?");
+            sql.params.push(Box::new(due_bound(bound, value)?));
+        }
+        if let Some(value) = f.due_after.as_deref().filter(|v| !v.trim().is_empty()) {
+            match DueDate::resolve(value, tz)? {
+                DueDate::AllDay(date) => {
+                    let start = tz.from_local(date.and_time(NaiveTime::MIN)).map(|dt| dt.with_timezone(&Utc));
+                    sql.conditions.push_str(" AND todos.due_at > ?");
+                    sql.params.push(Box::new(due_bound(start, value)?));
+                }
+                DueDate::At(at) => {
+                    sql.conditions.push_str(" AND todos.due_at >= ?");
+                    sql.params.push(Box::new(due_date::instant_sql(&at.with_timezone(&Utc))));
+                }
+            }
+        }
+        if let Some(overdue) = f.overdue {
+            sql.conditions.push_str(if overdue { " AND " } else { " AND NOT " });
+            sql.conditions.push_str(OVERDUE_SQL);
+            sql.params.push(Box::new(due_date::instant_sql(&Utc::now())));
+        }
+
+        Ok(sql)
+    }
+
+    fn params_refs(&self) -> Vec<&dyn rusqlite::ToSql> {
+        self.params.iter().map(|p| p.as_ref()).collect()
+    }
+}
+
+/// 单页最多返回的任务数
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// 排序使用的列（SQL 表达式均不为 NULL）及方向。
+/// 末尾总是追加 `todos.id` 使顺序唯一，这样就可以用上一页最后一行的排序值作为游标（keyset 分页）。
+fn sort_columns(sort: SortKey, direction: SortDirection, ranked: bool) -> Vec<(&'static str, SortDirection)> {
+    use SortDirection::*;
+
+    let mut columns = match sort {
+        SortKey::Default if ranked => vec![(FTS_RANK_SQL, Asc), ("todos.created_at", Desc)],
+        SortKey::Default => vec![("todos.completed", Asc), ("todos.created_at", Desc)],
+        SortKey::Priority => vec![("todos.priority", direction), ("todos.created_at", Desc)],
+        // 没有截止日期的任务总是排在最后
+        SortKey::DueDate => vec![("(todos.due_at IS NULL)", Asc), ("COALESCE(todos.due_at, '')", direction)],
+        SortKey::CreatedAt => vec![("todos.created_at", direction)],
+        SortKey::UpdatedAt => vec![("todos.updated_at", direction)],
+        SortKey::Text => vec![("todos.text COLLATE NOCASE", direction)],
+        SortKey::Manual => vec![("todos.position", direction)],
+    };
+
+    columns.push(("todos.id", Asc));
+    columns
+}
+
+/// 分页游标：上一页最后一行的排序值。排序方式不同的游标视为无效。
+#[derive(Serialize, Deserialize)]
+struct PageCursor {
+    sort: SortKey,
+    direction: SortDirection,
+    values: Vec<Value>,
+}
+
+impl PageCursor {
+    fn decode(cursor: &str, sort: SortKey, direction: SortDirection, column_count: usize) -> Result<Self, AppError> {
+        let invalid = || AppError::InvalidArgument(format!("Invalid page cursor: {}", cursor));
+
+        let decoded: PageCursor = serde_json::from_str(cursor).map_err(|_| invalid())?;
+        if decoded.sort != sort || decoded.direction != direction || decoded.values.len() != column_count {
+            return Err(invalid());
+        }
+        Ok(decoded)
+    }
+
+    fn sql_values(&self) -> Result<Vec<SqlValue>, AppError> {
+        self.values
+            .iter()
+            .map(|v| match v {
+                Value::Number(n) if n.is_i64() => Ok(SqlValue::Integer(n.as_i64().unwrap_or_default())),
+                Value::Number(n) => Ok(SqlValue::Real(n.as_f64().unwrap_or_default())),
+                Value::String(s) => Ok(SqlValue::Text(s.clone())),
+                _ => Err(AppError::InvalidArgument("Invalid page cursor value".into())),
+            })
+            .collect()
+    }
+}
+
+fn sql_to_json(value: SqlValue) -> Value {
+    match value {
+        SqlValue::Integer(i) => Value::from(i),
+        SqlValue::Real(f) => Value::from(f),
+        SqlValue::Text(s) => Value::from(s),
+        SqlValue::Null | SqlValue::Blob(_) => Value::Null,
+    }
+}
+
+fn build_tree(todo: Todo, by_parent: &mut HashMap<String, Vec<Todo>>) -> TodoTree {
+    let children = by_parent.remove(&todo.id).unwrap_or_default();
+    TodoTree {
+        children: children.into_iter().map(|child| build_tree(child, by_parent)).collect(),
+        todo,
+    }
+}
+
+/// 在给定连接上读取单个任务（包括回收站中的任务），供其他 repository 在事务内使用
+pub(crate) fn load_todo(conn: &rusqlite::Connection, id: &str) -> Result<Todo, AppError> {
+    let todo = conn.query_row(
+        &format!("SELECT {} FROM todos WHERE todos.id = ?1", TODO_COLUMNS),
+        [id],
+        row_to_todo,
+    ).map_err(|_| AppError::TodoNotFound(id.to_string()))?;
+
+    Ok(todo)
+}
+
+/// 把任务行完整写为 `target` 快照（包括标签），用于撤销/重做，绕过业务校验直接覆盖数据。
+/// `None` 表示任务在快照时还不存在（撤销创建），此时只移入回收站：
+/// 提醒、附件、计时记录与依赖不在快照中，永久删除后重做无法找回。
+pub(crate) fn write_snapshot(conn: &rusqlite::Connection, id: &str, target: Option<&Todo>) -> Result<(), AppError> {
+    let todo = match target {
+        Some(todo) => todo,
+        None => {
+            conn.execute(
+                "UPDATE todos SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                (Utc::now().to_rfc3339(), id),
+            )?;
+            return Ok(());
+        }
+    };
+
+    let tz = settings_repo::load_timezone(conn)?;
+    let (due_date, due_at) = due_columns(todo.due_date.as_ref(), &tz);
+
+    // 快照中的列表或工作流状态可能已被删除，此时任务写回收件箱、不属于任何状态
+    conn.execute(
+        "INSERT INTO todos (id, text, completed, status, priority, due_date, due_at, created_at, updated_at, deleted_at,
+                            position, parent_id, auto_complete, list_id, recurrence, series_id, occurrence, notes,
+                            estimate_minutes, story_points, started_at, completed_at, cancelled_at, state_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, (SELECT id FROM lists WHERE id = ?14), ?15, ?16, ?17, ?18,
+                 ?19, ?20, ?21, ?22, ?23, (SELECT id FROM workflow_states WHERE id = ?24))
+         ON CONFLICT(id) DO UPDATE SET
+            text = excluded.text,
+            completed = excluded.completed,
+            status = excluded.status,
+            priority = excluded.priority,
+            due_date = excluded.due_date,
+            due_at = excluded.due_at,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            deleted_at = excluded.deleted_at,
+            position = excluded.position,
+            parent_id = excluded.parent_id,
+            auto_complete = excluded.auto_complete,
+            list_id = excluded.list_id,
+            recurrence = excluded.recurrence,
+            series_id = excluded.series_id,
+            occurrence = excluded.occurrence,
+            notes = excluded.notes,
+            estimate_minutes = excluded.estimate_minutes,
+            story_points = excluded.story_points,
+            started_at = excluded.started_at,
+            completed_at = excluded.completed_at,
+            cancelled_at = excluded.cancelled_at,
+            state_id = excluded.state_id",
+        rusqlite::params![
+            id,
+            &todo.text,
+            if todo.completed { 1 } else { 0 },
+            todo.status.as_str(),
+            todo.priority.as_i32(),
+            due_date,
+            due_at,
+            &todo.created_at,
+            &todo.updated_at,
+            &todo.deleted_at,
+            todo.position,
+            &todo.parent_id,
+            if todo.auto_complete { 1 } else { 0 },
+            &todo.list_id,
+            &todo.recurrence,
+            &todo.series_id,
+            todo.occurrence,
+            &todo.notes,
+            todo.estimate_minutes,
+            todo.story_points,
+            &todo.started_at,
+            &todo.completed_at,
+            &todo.cancelled_at,
+            &todo.state_id,
+        ],
+    )?;
+
+    tag_repo::set_todo_tags(conn, id, &todo.tags)?;
+
+    conn.execute("DELETE FROM checklist_items WHERE todo_id = ?1", [id])?;
+    for (index, item) in todo.checklist.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO checklist_items (id, todo_id, text, done, position, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (&item.id, id, &item.text, if item.done { 1 } else { 0 }, (index + 1) as f64, &todo.updated_at),
+        )?;
+    }
+    reminder_repo::reschedule_for_todo(conn, id, todo.due_date.as_ref())?;
+
+    // 快照中的依赖任务可能已被永久删除，跳过不存在的任务
+    conn.execute("DELETE FROM todo_dependencies WHERE todo_id = ?1", [id])?;
+    for depends_on_id in &todo.depends_on {
+        conn.execute(
+            "INSERT OR IGNORE INTO todo_dependencies (todo_id, depends_on_id, created_at)
+             SELECT ?1, ?2, ?3 WHERE EXISTS (SELECT 1 FROM todos WHERE id = ?2)",
+            (id, depends_on_id, &todo.updated_at),
+        )?;
+    }
+
+    Ok(())
+}
+
 pub struct TodoRepository {
     db: Arc<Database>,
 }
 
-impl TodoRepository {
-    pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+impl TodoRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 创建任务（包括 `request.children` 中嵌套的子任务），返回顶层任务
+    pub fn create(&self, request: CreateTodoRequest, actor: &Actor) -> Result<Todo, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let id = self.create_internal(&tx, request, &EventContext::new(actor), &mut Vec::new())?;
+            let todo = self.get_by_id_internal(&tx, &id)?;
+            tx.commit()?;
+            Ok(todo)
+        })
+    }
+
+    /// 在一个事务中创建多个任务，任何一个失败都会整体回滚。
+    /// 返回创建的全部任务（包括嵌套的子任务），父任务总在其子任务之前。
+    pub fn batch_create(&self, requests: Vec<CreateTodoRequest>, actor: &Actor) -> Result<Vec<Todo>, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let ctx = EventContext::new(actor);
+            let mut ids = Vec::with_capacity(requests.len());
+            for request in requests {
+                self.create_internal(&tx, request, &ctx, &mut ids)?;
+            }
+
+            // 子任务创建后重新读取，使父任务的子任务计数是最新的
+            let todos = ids.iter()
+                .map(|id| self.get_by_id_internal(&tx, id))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            tx.commit()?;
+            Ok(todos)
+        })
+    }
+
+    /// 创建任务及其嵌套子任务，新建任务的 ID 依次追加到 `created`，返回顶层任务的 ID
+    fn create_internal(
+        &self,
+        conn: &rusqlite::Connection,
+        request: CreateTodoRequest,
+        ctx: &EventContext,
+        created: &mut Vec<String>,
+    ) -> Result<String, AppError> {
+        let now = Utc::now().to_rfc3339();
+        let id = Uuid::new_v4().to_string();
+        let tags = request.tags.unwrap_or_default();
+
+        let parent = match request.parent_id {
+            Some(ref parent_id) => Some(self.get_active(conn, parent_id)?),
+            None => None,
+        };
+
+        // 未指定列表的子任务沿用父任务的列表
+        let list_id = match request.list_id.as_deref().map(str::trim) {
+            Some("") => None,
+            Some(list_id) => Some(list_id.to_string()),
+            None => parent.and_then(|p| p.list_id),
+        };
+        let list = list_id.as_deref().map(|list_id| list_repo::load_list(conn, list_id)).transpose()?;
+        let priority = request.priority.or_else(|| list.map(|l| l.default_priority)).unwrap_or_default();
+
+        // 重复任务没有截止日期时，以今天起的第一次作为截止日期
+        let recurrence = normalize_recurrence(request.recurrence.as_deref())?;
+        let tz = settings_repo::load_timezone(conn)?;
+        let mut due_date = normalize_due_date(request.due_date.as_deref(), &tz)?;
+        if let (Some(rule), None) = (&recurrence, &due_date) {
+            due_date = RecurrenceRule::parse(rule)?.first_on_or_after(tz.today()).map(DueDate::AllDay);
+        }
+        let (due_date, due_at) = due_columns(due_date.as_ref(), &tz);
+        let story_points = normalize_story_points(request.story_points)?;
+        let (series_id, occurrence) = match request.series {
+            Some((ref series_id, occurrence)) => (Some(series_id.clone()), occurrence),
+            None if recurrence.is_some() => (Some(id.clone()), 1),
+            None => (None, 1),
+        };
+
+        // 新任务追加在手动排序的末尾，批量创建时保持给定的顺序
+        let position: f64 = conn.query_row(
+            "SELECT COALESCE(MAX(position), 0) + ?1 FROM todos",
+            [POSITION_GAP],
+            |row| row.get(0),
+        )?;
+
+        let affected_rows = conn.execute(
+            "INSERT INTO todos (id, text, completed, status, priority, due_date, due_at, created_at, updated_at, position,
+                                parent_id, auto_complete, list_id, recurrence, series_id, occurrence, notes,
+                                estimate_minutes, story_points, state_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
+            rusqlite::params![
+                &id,
+                &request.text,
+                0,
+                TodoStatus::Pending.as_str(),
+                priority.as_i32(),
+                &due_date,
+                &due_at,
+                &now,
+                &now,
+                position,
+                &request.parent_id,
+                if request.auto_complete { 1 } else { 0 },
+                &list_id,
+                &recurrence,
+                &series_id,
+                occurrence,
+                normalize_notes(request.notes.as_deref()),
+                normalize_estimate_minutes(request.estimate_minutes),
+                story_points,
+                workflow_repo::default_state(conn, StateCategory::Open)?.id,
+            ],
+        )?;
+
+        if affected_rows != 1 {
+            return Err(AppError::InvalidArgument(format!(
+                "Expected 1 row to be inserted, but {} were affected",
+                affected_rows
+            )));
+        }
+
+        tag_repo::set_todo_tags(conn, &id, &tags)?;
+        for text in &request.checklist {
+            insert_checklist_item(conn, &id, &checklist_text(text)?, false, &now)?;
+        }
+
+        // 新任务不可能已被其他任务依赖，因此添加依赖不会形成环路
+        for depends_on_id in &request.depends_on {
+            self.get_active(conn, depends_on_id)?;
+            Self::insert_dependency(conn, &id, depends_on_id, &now)?;
+        }
+
+        // 从数据库读取刚插入的记录以确保数据一致性
+        let todo = self.get_by_id_internal(conn, &id)?;
+        history_repo::record_event(conn, ctx, TodoAction::Created, None, Some(&todo))?;
+        created.push(id.clone());
+
+        let mut previous: Option<String> = None;
+        for mut child in request.children {
+            child.parent_id = Some(id.clone());
+            if request.sequential {
+                child.depends_on.extend(previous.take());
+            }
+            previous = Some(self.create_internal(conn, child, ctx, created)?);
+        }
+
+        Ok(id)
+    }
+
+    fn insert_dependency(conn: &rusqlite::Connection, todo_id: &str, depends_on_id: &str, now: &str) -> Result<(), AppError> {
+        conn.execute(
+            "INSERT OR IGNORE INTO todo_dependencies (todo_id, depends_on_id, created_at) VALUES (?1, ?2, ?3)",
+            (todo_id, depends_on_id, now),
+        )?;
+        Ok(())
+    }
+
+    /// 添加依赖：`todo_id` 需要在 `depends_on_id` 完成后才能开始。
+    /// 如果 `depends_on_id` 已经（直接或间接）依赖 `todo_id`，会形成环路，返回错误。
+    pub fn add_dependency(&self, todo_id: &str, depends_on_id: &str, actor: &Actor) -> Result<Todo, AppError> {
+        if todo_id == depends_on_id {
+            return Err(AppError::InvalidArgument("A todo cannot depend on itself".into()));
+        }
+
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let before = self.get_active(&tx, todo_id)?;
+            self.get_active(&tx, depends_on_id)?;
+
+            let creates_cycle: bool = tx.query_row(
+                "WITH RECURSIVE upstream(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT todo_dependencies.depends_on_id FROM todo_dependencies
+                    JOIN upstream ON todo_dependencies.todo_id = upstream.id
+                )
+                SELECT EXISTS (SELECT 1 FROM upstream WHERE id = ?2)",
+                (depends_on_id, todo_id),
+                |row| row.get(0),
+            )?;
+            if creates_cycle {
+                return Err(AppError::DependencyCycle {
+                    todo_id: todo_id.to_string(),
+                    depends_on_id: depends_on_id.to_string(),
+                });
+            }
+
+            let now = Utc::now().to_rfc3339();
+            Self::insert_dependency(&tx, todo_id, depends_on_id, &now)?;
+            tx.execute("UPDATE todos SET updated_at = ?1 WHERE id = ?2", (&now, todo_id))?;
+
+            let after = self.get_by_id_internal(&tx, todo_id)?;
+            history_repo::record_event(&tx, &EventContext::new(actor), TodoAction::Updated, Some(&before), Some(&after))?;
+
+            tx.commit()?;
+            Ok(after)
+        })
+    }
+
+    pub fn remove_dependency(&self, todo_id: &str, depends_on_id: &str, actor: &Actor) -> Result<Todo, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let before = self.get_active(&tx, todo_id)?;
+            let rows = tx.execute(
+                "DELETE FROM todo_dependencies WHERE todo_id = ?1 AND depends_on_id = ?2",
+                (todo_id, depends_on_id),
+            )?;
+            if rows == 0 {
+                return Ok(before);
+            }
+            tx.execute(
+                "UPDATE todos SET updated_at = ?1 WHERE id = ?2",
+                (Utc::now().to_rfc3339(), todo_id),
+            )?;
+
+            let after = self.get_by_id_internal(&tx, todo_id)?;
+            history_repo::record_event(&tx, &EventContext::new(actor), TodoAction::Updated, Some(&before), Some(&after))?;
+
+            tx.commit()?;
+            Ok(after)
+        })
+    }
+
+    /// 在任务清单末尾添加一项，返回修改后的任务
+    pub fn add_checklist_item(&self, todo_id: &str, text: &str, actor: &Actor) -> Result<Todo, AppError> {
+        let text = checklist_text(text)?;
+
+        self.change_checklist(todo_id, actor, |conn, now| {
+            insert_checklist_item(conn, todo_id, &text, false, now)?;
+            Ok(())
+        })
+    }
+
+    /// 修改清单项的文本或完成状态，返回所属任务
+    pub fn update_checklist_item(&self, item_id: &str, request: UpdateChecklistItemRequest, actor: &Actor) -> Result<Todo, AppError> {
+        let text = request.text.as_deref().map(checklist_text).transpose()?;
+        let todo_id = self.checklist_item_todo_id(item_id)?;
+
+        self.change_checklist(&todo_id, actor, |conn, _| {
+            conn.execute(
+                "UPDATE checklist_items SET text = COALESCE(?1, text), done = COALESCE(?2, done) WHERE id = ?3",
+                (&text, request.done.map(|done| if done { 1 } else { 0 }), item_id),
+            )?;
+            Ok(())
+        })
+    }
+
+    /// 切换清单项的完成状态，返回所属任务
+    pub fn toggle_checklist_item(&self, item_id: &str, actor: &Actor) -> Result<Todo, AppError> {
+        let todo_id = self.checklist_item_todo_id(item_id)?;
+
+        self.change_checklist(&todo_id, actor, |conn, _| {
+            conn.execute("UPDATE checklist_items SET done = 1 - done WHERE id = ?1", [item_id])?;
+            Ok(())
+        })
+    }
+
+    /// 删除清单项，返回所属任务
+    pub fn delete_checklist_item(&self, item_id: &str, actor: &Actor) -> Result<Todo, AppError> {
+        let todo_id = self.checklist_item_todo_id(item_id)?;
+
+        self.change_checklist(&todo_id, actor, |conn, _| {
+            conn.execute("DELETE FROM checklist_items WHERE id = ?1", [item_id])?;
+            Ok(())
+        })
+    }
+
+    fn checklist_item_todo_id(&self, item_id: &str) -> Result<String, AppError> {
+        self.db.with_conn(|conn| {
+            conn.query_row("SELECT todo_id FROM checklist_items WHERE id = ?1", [item_id], |row| row.get(0))
+                .optional()?
+                .ok_or_else(|| AppError::ChecklistItemNotFound(item_id.to_string()))
+        })
+    }
+
+    /// 在事务中修改任务的清单：任务必须不在回收站中，修改后更新 `updated_at` 并记录历史，
+    /// 因此清单的修改也可以撤销。
+    fn change_checklist<F>(&self, todo_id: &str, actor: &Actor, change: F) -> Result<Todo, AppError>
+    where
+        F: FnOnce(&rusqlite::Connection, &str) -> Result<(), AppError>,
+    {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let before = self.get_active(&tx, todo_id)?;
+            let now = Utc::now().to_rfc3339();
+            change(&tx, &now)?;
+            tx.execute("UPDATE todos SET updated_at = ?1 WHERE id = ?2", (&now, todo_id))?;
+
+            let after = self.get_by_id_internal(&tx, todo_id)?;
+            history_repo::record_event(&tx, &EventContext::new(actor), TodoAction::Updated, Some(&before), Some(&after))?;
+
+            tx.commit()?;
+            Ok(after)
+        })
+    }
+
+    /// 满足过滤条件的全部任务，使用默认排序
+    pub fn get_all(&self, filter: Option<TodoFilter>) -> Result<Vec<Todo>, AppError> {
+        self.db.with_conn(|conn| {
+            let (todos, _) = self.query_page(conn, filter.as_ref(), &TodoPageRequest::default())?;
+            Ok(todos)
+        })
+    }
+
+    /// 看板：按工作流状态分列的任务，列内按手动排序，没有状态的任务放在 `unassigned` 中。
+    /// `list_id` 的含义与 `TodoFilter::list_id` 相同。
+    pub fn get_board(&self, list_id: Option<&str>) -> Result<Board, AppError> {
+        let filter = TodoFilter {
+            list_id: list_id.map(String::from),
+            ..Default::default()
+        };
+        let page = TodoPageRequest {
+            sort: Some(SortKey::Manual),
+            ..Default::default()
+        };
+
+        self.db.with_conn(|conn| {
+            let states = workflow_repo::load_states(conn)?;
+            let (todos, _) = self.query_page(conn, Some(&filter), &page)?;
+
+            let mut by_state: HashMap<String, Vec<Todo>> = HashMap::new();
+            let mut unassigned = Vec::new();
+            for todo in todos {
+                match todo.state_id.clone() {
+                    Some(state_id) if states.iter().any(|s| s.id == state_id) => by_state.entry(state_id).or_default().push(todo),
+                    _ => unassigned.push(todo),
+                }
+            }
+
+            let columns = states
+                .into_iter()
+                .map(|state| {
+                    let todos = by_state.remove(&state.id).unwrap_or_default();
+                    let over_limit = state.wip_limit.is_some_and(|limit| todos.len() as u32 > limit);
+                    BoardColumn { state, todos, over_limit }
+                })
+                .collect();
+            Ok(Board { columns, unassigned })
+        })
+    }
+
+    /// 分页查询，返回当前页、总数与下一页游标
+    pub fn get_page(&self, filter: Option<TodoFilter>, page: TodoPageRequest) -> Result<TodoPage, AppError> {
+        self.db.with_conn(|conn| {
+            let (items, next_cursor) = self.query_page(conn, filter.as_ref(), &page)?;
+
+            let tz = settings_repo::load_timezone(conn)?;
+            let filter_sql = FilterSql::new(filter.as_ref(), &tz)?;
+            let total: u32 = conn.query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM todos{} WHERE todos.deleted_at IS NULL{}",
+                    filter_sql.joins, filter_sql.conditions
+                ),
+                filter_sql.params_refs().as_slice(),
+                |row| row.get(0),
+            )?;
+
+            Ok(TodoPage { items, total, next_cursor })
+        })
+    }
+
+    fn query_page(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: Option<&TodoFilter>,
+        page: &TodoPageRequest,
+    ) -> Result<(Vec<Todo>, Option<String>), AppError> {
+        let tz = settings_repo::load_timezone(conn)?;
+        let mut filter_sql = FilterSql::new(filter, &tz)?;
+
+        let sort = page.sort.unwrap_or_default();
+        let direction = page.direction.unwrap_or_else(|| sort.default_direction());
+        let columns = sort_columns(sort, direction, filter_sql.ranked);
+
+        // 回收站中的任务不出现在普通列表中
+        let mut sql = format!(
+            "SELECT {}, {} FROM todos{} WHERE todos.deleted_at IS NULL{}",
+            TODO_COLUMNS,
+            columns.iter().map(|(expr, _)| *expr).collect::<Vec<_>>().join(", "),
+            filter_sql.joins,
+            filter_sql.conditions
+        );
+
+        // 游标条件：(c1, c2, ...) 在排序意义上位于上一页最后一行之后
+        if let Some(ref cursor) = page.cursor {
+            let values = PageCursor::decode(cursor, sort, direction, columns.len())?.sql_values()?;
+
+            let mut alternatives = Vec::new();
+            for i in 0..columns.len() {
+                let mut terms = Vec::new();
+                for (j, (expr, _)) in columns.iter().enumerate().take(i) {
+                    terms.push(format!("{} = ?", expr));
+                    filter_sql.params.push(Box::new(values[j].clone()));
+                }
+
+                let (expr, dir) = columns[i];
+                let op = match dir {
+                    SortDirection::Asc => ">",
+                    SortDirection::Desc => "<",
+                };
+                terms.push(format!("{} {} ?", expr, op));
+                filter_sql.params.push(Box::new(values[i].clone()));
+
+                alternatives.push(format!("({})", terms.join(" AND ")));
+            }
+            sql.push_str(&format!(" AND ({})", alternatives.join(" OR ")));
+        }
+
+        let order: Vec<String> = columns
+            .iter()
+            .map(|(expr, dir)| match dir {
+                SortDirection::Asc => format!("{} ASC", expr),
+                SortDirection::Desc => format!("{} DESC", expr),
+            })
+            .collect();
+        sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+
+        // 多取一行用于判断是否还有下一页
+        let limit = page.limit.map(|l| l.clamp(1, MAX_PAGE_SIZE));
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit + 1));
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(filter_sql.params_refs().as_slice(), |row| {
+            let todo = row_to_todo(row)?;
+            let mut sort_values = Vec::with_capacity(columns.len());
+            for i in 0..columns.len() {
+                sort_values.push(sql_to_json(row.get(TODO_COLUMN_COUNT + i)?));
+            }
+            Ok((todo, sort_values))
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+
+        let mut next_cursor = None;
+        if let Some(limit) = limit {
+            if result.len() > limit as usize {
+                result.truncate(limit as usize);
+                if let Some((_, values)) = result.last() {
+                    let cursor = PageCursor { sort, direction, values: values.clone() };
+                    next_cursor = Some(serde_json::to_string(&cursor)?);
+                }
+            }
+        }
+
+        Ok((result.into_iter().map(|(todo, _)| todo).collect(), next_cursor))
+    }
+
+    /// 解析批量操作的目标任务 ID。
+    /// 显式给出的 ID 必须全部存在且不在回收站中，否则返回错误，保证批量操作要么全部生效要么全部不生效。
+    fn select_ids(&self, conn: &rusqlite::Connection, selector: &TodoSelector) -> Result<Vec<String>, AppError> {
+        match selector {
+            TodoSelector::Ids(ids) => {
+                let mut result: Vec<String> = Vec::new();
+                for id in ids {
+                    let todo = self.get_by_id_internal(conn, id)?;
+                    if todo.deleted_at.is_some() {
+                        return Err(AppError::TodoNotFound(id.clone()));
+                    }
+                    if !result.contains(id) {
+                        result.push(id.clone());
+                    }
+                }
+                Ok(result)
+            }
+            TodoSelector::Filter(filter) => {
+                let tz = settings_repo::load_timezone(conn)?;
+                let filter_sql = FilterSql::new(Some(filter), &tz)?;
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT todos.id FROM todos{} WHERE todos.deleted_at IS NULL{} ORDER BY todos.created_at ASC",
+                    filter_sql.joins, filter_sql.conditions
+                ))?;
+
+                let ids = stmt.query_map(filter_sql.params_refs().as_slice(), |row| row.get(0))?;
+                Ok(ids.collect::<Result<_, _>>()?)
+            }
+        }
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Result<Todo, AppError> {
+        self.db.with_conn(|conn| self.get_by_id_internal(conn, id))
+    }
+
+    pub fn update(&self, id: &str, request: UpdateTodoRequest, actor: &Actor) -> Result<Todo, AppError> {
+        // 在单个事务中执行读取与更新，避免竞态
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let updated = self.update_internal(&tx, id, &request, &EventContext::new(actor))?;
+            tx.commit()?;
+            Ok(updated)
+        })
+    }
+
+    fn update_internal(
+        &self,
+        conn: &rusqlite::Connection,
+        id: &str,
+        request: &UpdateTodoRequest,
+        ctx: &EventContext,
+    ) -> Result<Todo, AppError> {
+        self.update_at(conn, id, request, ctx, &Utc::now())
+    }
+
+    /// 以 `now` 作为修改时间更新任务，供需要与其他表共用同一时刻的调用方（如开始计时）使用
+    pub(crate) fn update_at(
+        &self,
+        conn: &rusqlite::Connection,
+        id: &str,
+        request: &UpdateTodoRequest,
+        ctx: &EventContext,
+        now: &DateTime<Utc>,
+    ) -> Result<Todo, AppError> {
+        let now = now.to_rfc3339();
+
+        // 先获取现有数据，回收站中的任务需要先恢复才能修改
+        let existing = self.get_by_id_internal(conn, id)?;
+        if existing.deleted_at.is_some() {
+            return Err(AppError::TodoNotFound(id.to_string()));
+        }
+
+        let text = request.text.clone().unwrap_or(existing.text.clone());
+        let (status, state_id) = resolve_state(conn, &existing, request)?;
+        let (started_at, completed_at, cancelled_at) = status_timestamps(&existing, &status, &now);
+        let priority = request.priority.clone().unwrap_or(existing.priority.clone());
+        let tz = settings_repo::load_timezone(conn)?;
+        let due_date = match request.due_date {
+            Some(ref due_date) => normalize_due_date(Some(due_date), &tz)?,
+            None => existing.due_date.clone(),
+        };
+        let (due_date_sql, due_at) = due_columns(due_date.as_ref(), &tz);
+        let auto_complete = request.auto_complete.unwrap_or(existing.auto_complete);
+        let list_id = match request.list_id.as_deref().map(str::trim) {
+            Some("") => None,
+            Some(list_id) => Some(list_repo::load_list(conn, list_id)?.id),
+            None => existing.list_id.clone(),
+        };
+        let recurrence = match request.recurrence {
+            Some(ref rule) => normalize_recurrence(Some(rule))?,
+            None => existing.recurrence.clone(),
+        };
+        let notes = match request.notes {
+            Some(ref notes) => normalize_notes(Some(notes)),
+            None => existing.notes.clone(),
+        };
+        let estimate_minutes = match request.estimate_minutes {
+            Some(minutes) => normalize_estimate_minutes(Some(minutes)),
+            None => existing.estimate_minutes,
+        };
+        let story_points = match request.story_points {
+            Some(points) => normalize_story_points(Some(points))?,
+            None => existing.story_points,
+        };
+        let checklist = request.add_checklist.iter().map(|text| checklist_text(text)).collect::<Result<Vec<_>, _>>()?;
+        // 首次设置重复规则时以本任务作为序列的第一次
+        let series_id = match recurrence {
+            Some(_) => existing.series_id.clone().or_else(|| Some(id.to_string())),
+            None => existing.series_id.clone(),
+        };
+
+        conn.execute(
+            "UPDATE todos SET text = ?1, completed = ?2, status = ?3, priority = ?4, due_date = ?5, due_at = ?6,
+                auto_complete = ?7, list_id = ?8, recurrence = ?9, series_id = ?10, notes = ?11, estimate_minutes = ?12,
+                story_points = ?13, started_at = ?14, completed_at = ?15, cancelled_at = ?16, state_id = ?17, updated_at = ?18
+             WHERE id = ?19",
+            rusqlite::params![
+                &text,
+                if status == TodoStatus::Completed { 1 } else { 0 },
+                status.as_str(),
+                priority.as_i32(),
+                &due_date_sql,
+                &due_at,
+                if auto_complete { 1 } else { 0 },
+                &list_id,
+                &recurrence,
+                &series_id,
+                &notes,
+                estimate_minutes,
+                story_points,
+                &started_at,
+                &completed_at,
+                &cancelled_at,
+                &state_id,
+                &now,
+                id,
+            ],
+        )?;
+
+        if let Some(ref tags) = request.tags {
+            tag_repo::set_todo_tags(conn, id, tags)?;
+        }
+        for text in &checklist {
+            insert_checklist_item(conn, id, text, false, &now)?;
+        }
+        if due_date != existing.due_date {
+            reminder_repo::reschedule_for_todo(conn, id, due_date.as_ref())?;
+        }
+
+        let updated = self.get_by_id_internal(conn, id)?;
+        history_repo::record_event(conn, ctx, TodoAction::Updated, Some(&existing), Some(&updated))?;
+
+        if list_id != existing.list_id {
+            self.move_descendants_to_list(conn, id, list_id.as_deref(), ctx)?;
+        }
+
+        // 完成重复任务后生成下一次；完成子任务或开启自动完成后，检查是否需要自动完成父任务
+        if updated.completed && !existing.completed {
+            self.create_next_occurrence(conn, &updated, ctx)?;
+            if let Some(ref parent_id) = updated.parent_id {
+                self.complete_if_children_done(conn, parent_id, ctx)?;
+            }
+        }
+        if updated.auto_complete && !existing.auto_complete {
+            return self.complete_if_children_done(conn, id, ctx);
+        }
+
+        Ok(updated)
+    }
+
+    /// 按重复规则创建 `todo` 的下一次，返回新任务的 ID。
+    /// 不是重复任务、序列已结束或下一次已经生成过（例如完成后又取消完成再完成）时返回 `None`。
+    fn create_next_occurrence(&self, conn: &rusqlite::Connection, todo: &Todo, ctx: &EventContext) -> Result<Option<String>, AppError> {
+        let (rule, series_id) = match (&todo.recurrence, &todo.series_id) {
+            (Some(rule), Some(series_id)) => (RecurrenceRule::parse(rule)?, series_id),
+            _ => return Ok(None),
+        };
+
+        let tz = settings_repo::load_timezone(conn)?;
+        let due_date = match recurrence::next_due_date(&rule, todo.due_date.as_ref(), todo.occurrence, &tz) {
+            Some(due_date) => due_date,
+            None => return Ok(None),
+        };
+
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM todos WHERE series_id = ?1 AND occurrence = ?2)",
+            (series_id, todo.occurrence + 1),
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Ok(None);
+        }
+
+        // 父任务已移入回收站或开启了自动完成时，下一次作为顶层任务创建：
+        // 留在自动完成的父任务下会使它永远有未完成的子任务
+        let parent_id = match todo.parent_id {
+            Some(ref parent_id) => match self.get_active(conn, parent_id) {
+                Ok(parent) if !parent.auto_complete => Some(parent.id),
+                _ => None,
+            },
+            None => None,
+        };
+
+        let request = CreateTodoRequest {
+            text: todo.text.clone(),
+            priority: Some(todo.priority.clone()),
+            due_date: Some(due_date.to_string()),
+            tags: Some(todo.tags.clone()),
+            parent_id,
+            auto_complete: todo.auto_complete,
+            list_id: Some(todo.list_id.clone().unwrap_or_default()),
+            recurrence: todo.recurrence.clone(),
+            notes: todo.notes.clone(),
+            // 清单随下一次一起重复，完成状态重置
+            checklist: todo.checklist.iter().map(|item| item.text.clone()).collect(),
+            estimate_minutes: todo.estimate_minutes,
+            story_points: todo.story_points,
+            series: Some((series_id.clone(), todo.occurrence + 1)),
+            ..Default::default()
+        };
+        let id = self.create_internal(conn, request, ctx, &mut Vec::new())?;
+        reminder_repo::copy_reminders(conn, &todo.id, &id, Some(&due_date))?;
+        Ok(Some(id))
+    }
+
+    /// 重复任务序列中紧接在 `todo` 之后的一次（不在回收站中），尚未生成时返回 `None`
+    pub fn get_next_occurrence(&self, todo: &Todo) -> Result<Option<Todo>, AppError> {
+        let series_id = match todo.series_id {
+            Some(ref series_id) => series_id,
+            None => return Ok(None),
+        };
+
+        self.db.with_conn(|conn| {
+            let id: Option<String> = conn
+                .query_row(
+                    "SELECT id FROM todos WHERE series_id = ?1 AND occurrence = ?2 AND deleted_at IS NULL",
+                    (series_id, todo.occurrence + 1),
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            id.map(|id| self.get_by_id_internal(conn, &id)).transpose()
+        })
+    }
+
+    /// 跳过重复任务的本次：截止日期顺延到下一次，序号加一。序列已结束时把任务标记为取消。
+    pub fn skip_occurrence(&self, id: &str, actor: &Actor) -> Result<Todo, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let before = self.get_active(&tx, id)?;
+            let rule = match before.recurrence {
+                Some(ref rule) => RecurrenceRule::parse(rule)?,
+                None => return Err(AppError::InvalidArgument(format!("Todo {} is not recurring", id))),
+            };
+
+            let ctx = EventContext::new(actor);
+            let tz = settings_repo::load_timezone(&tx)?;
+            let next_due = recurrence::next_due_date(&rule, before.due_date.as_ref(), before.occurrence, &tz);
+            let after = match next_due {
+                Some(due_date) => {
+                    let (due_date_sql, due_at) = due_columns(Some(&due_date), &tz);
+                    tx.execute(
+                        "UPDATE todos SET due_date = ?1, due_at = ?2, occurrence = occurrence + 1, updated_at = ?3 WHERE id = ?4",
+                        (&due_date_sql, &due_at, Utc::now().to_rfc3339(), id),
+                    )?;
+                    reminder_repo::reschedule_for_todo(&tx, id, Some(&due_date))?;
+                    let after = self.get_by_id_internal(&tx, id)?;
+                    history_repo::record_event(&tx, &ctx, TodoAction::Updated, Some(&before), Some(&after))?;
+                    after
+                }
+                None => {
+                    let request = UpdateTodoRequest {
+                        status: Some(TodoStatus::Cancelled),
+                        ..Default::default()
+                    };
+                    self.update_internal(&tx, id, &request, &ctx)?
+                }
+            };
+
+            tx.commit()?;
+            Ok(after)
+        })
+    }
+
+    /// 把子树中的任务（不含根本身）移动到 `list_id`，保持整棵任务树在同一个列表中
+    fn move_descendants_to_list(
+        &self,
+        conn: &rusqlite::Connection,
+        id: &str,
+        list_id: Option<&str>,
+        ctx: &EventContext,
+    ) -> Result<(), AppError> {
+        let now = Utc::now().to_rfc3339();
+        for descendant in self.descendant_ids(conn, id)? {
+            let before = self.get_by_id_internal(conn, &descendant)?;
+            if before.list_id.as_deref() == list_id {
+                continue;
+            }
+
+            conn.execute(
+                "UPDATE todos SET list_id = ?1, updated_at = ?2 WHERE id = ?3",
+                (list_id, &now, &descendant),
+            )?;
+
+            let after = self.get_by_id_internal(conn, &descendant)?;
+            history_repo::record_event(conn, ctx, TodoAction::Updated, Some(&before), Some(&after))?;
+        }
+        Ok(())
+    }
+
+    /// 如果任务开启了自动完成，且所有子任务都已完成或取消，把它标记为完成（会继续向上检查）。
+    /// 已取消的任务不会被自动完成。返回检查后的任务。
+    fn complete_if_children_done(&self, conn: &rusqlite::Connection, id: &str, ctx: &EventContext) -> Result<Todo, AppError> {
+        let todo = self.get_by_id_internal(conn, id)?;
+        if !todo.auto_complete || todo.completed || todo.status == TodoStatus::Cancelled
+            || todo.deleted_at.is_some() || todo.child_count == 0
+        {
+            return Ok(todo);
+        }
+
+        let open_children: u32 = conn.query_row(
+            "SELECT COUNT(*) FROM todos WHERE parent_id = ?1 AND deleted_at IS NULL AND completed = 0 AND status != ?2",
+            (id, TodoStatus::Cancelled.as_str()),
+            |row| row.get(0),
+        )?;
+        if open_children > 0 {
+            return Ok(todo);
+        }
+
+        let request = UpdateTodoRequest {
+            completed: Some(true),
+            status: Some(TodoStatus::Completed),
+            ..Default::default()
+        };
+        // 工作流不允许直接完成时保持原状，由用户手动移动
+        match self.update_internal(conn, id, &request, ctx) {
+            Err(AppError::StateTransitionNotAllowed { .. } | AppError::WipLimitReached { .. }) => Ok(todo),
+            result => result,
+        }
+    }
+
+    /// 读取一个不在回收站中的任务
+    fn get_active(&self, conn: &rusqlite::Connection, id: &str) -> Result<Todo, AppError> {
+        let todo = self.get_by_id_internal(conn, id)?;
+        if todo.deleted_at.is_some() {
+            return Err(AppError::TodoNotFound(id.to_string()));
+        }
+        Ok(todo)
+    }
+
+    /// 子树中所有任务的 ID（不含根本身，包括回收站中的任务），按深度由浅到深排列
+    fn descendant_ids(&self, conn: &rusqlite::Connection, id: &str) -> Result<Vec<String>, AppError> {
+        let mut stmt = conn.prepare(&format!(
+            "{} SELECT subtree.id FROM subtree WHERE subtree.depth > 0 ORDER BY subtree.depth",
+            DESCENDANTS_CTE
+        ))?;
+        let ids = stmt.query_map([id], |row| row.get(0))?;
+        Ok(ids.collect::<Result<_, _>>()?)
+    }
+
+    /// 直接子任务，按手动排序排列
+    pub fn get_children(&self, id: &str) -> Result<Vec<Todo>, AppError> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM todos WHERE todos.parent_id = ?1 AND todos.deleted_at IS NULL ORDER BY todos.position",
+                TODO_COLUMNS
+            ))?;
+
+            let todos = stmt.query_map([id], row_to_todo)?;
+
+            let mut result = Vec::new();
+            for todo in todos {
+                result.push(todo?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// 祖先任务，从顶层任务开始排列（不含任务本身）
+    pub fn get_ancestors(&self, id: &str) -> Result<Vec<Todo>, AppError> {
+        self.db.with_conn(|conn| {
+            self.get_by_id_internal(conn, id)?;
+
+            let mut stmt = conn.prepare(&format!(
+                "WITH RECURSIVE chain(id, parent_id, depth) AS (
+                    SELECT id, parent_id, 0 FROM todos WHERE id = ?1
+                    UNION ALL
+                    SELECT todos.id, todos.parent_id, chain.depth + 1 FROM todos JOIN chain ON todos.id = chain.parent_id
+                )
+                SELECT {} FROM chain JOIN todos ON todos.id = chain.id
+                WHERE chain.depth > 0 ORDER BY chain.depth DESC",
+                TODO_COLUMNS
+            ))?;
+
+            let todos = stmt.query_map([id], row_to_todo)?;
+
+            let mut result = Vec::new();
+            for todo in todos {
+                result.push(todo?);
+            }
+            Ok(result)
+        })
     }
 
-    pub fn create(&self, request: CreateTodoRequest) -> Result<Todo, AppError> {
-        let now = Utc::now().to_rfc3339();
-        let id = Uuid::new_v4().to_string();
-        let priority = request.priority.unwrap_or_default();
-        let tags = request.tags.unwrap_or_default();
-        let tags_json = serde_json::to_string(&tags)?;
-
-        self.db.with_conn(|conn| {
-            let affected_rows = conn.execute(
-                "INSERT INTO todos (id, text, completed, status, priority, due_date, tags, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                (
-                    &id,
-                    &request.text,
-                    0,
-                    TodoStatus::Pending.as_str(),
-                    priority.as_i32(),
-                    &request.due_date,
-                    &tags_json,
-                    &now,
-                    &now,
-                ),
+    /// 以任务为根的完整子树（不含回收站中的任务），同级任务按手动排序排列
+    pub fn get_tree(&self, id: &str) -> Result<TodoTree, AppError> {
+        self.db.with_conn(|conn| {
+            let root = self.get_active(conn, id)?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} SELECT {} FROM subtree JOIN todos ON todos.id = subtree.id
+                 WHERE subtree.depth > 0 AND todos.deleted_at IS NULL
+                 ORDER BY todos.position",
+                DESCENDANTS_CTE, TODO_COLUMNS
+            ))?;
+
+            let mut by_parent: HashMap<String, Vec<Todo>> = HashMap::new();
+            for todo in stmt.query_map([id], row_to_todo)? {
+                let todo = todo?;
+                if let Some(parent_id) = todo.parent_id.clone() {
+                    by_parent.entry(parent_id).or_default().push(todo);
+                }
+            }
+
+            Ok(build_tree(root, &mut by_parent))
+        })
+    }
+
+    /// 把任务移动到 `parent_id` 下，`None` 表示移为顶层任务。不允许移动到自己的子树中。
+    /// 移动到其他列表的任务下时，整棵子树随之移动到父任务的列表。
+    pub fn set_parent(&self, id: &str, parent_id: Option<&str>, actor: &Actor) -> Result<Todo, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let before = self.get_active(&tx, id)?;
+            let mut list_id = before.list_id.clone();
+            if let Some(parent_id) = parent_id {
+                let parent = self.get_active(&tx, parent_id)?;
+                if parent_id == id || self.descendant_ids(&tx, id)?.iter().any(|d| d == parent_id) {
+                    return Err(AppError::InvalidArgument(
+                        "Cannot move a todo under itself or one of its subtasks".into(),
+                    ));
+                }
+                list_id = parent.list_id;
+            }
+
+            tx.execute(
+                "UPDATE todos SET parent_id = ?1, list_id = ?2, updated_at = ?3 WHERE id = ?4",
+                (parent_id, &list_id, Utc::now().to_rfc3339(), id),
             )?;
 
-            if affected_rows != 1 {
-                return Err(AppError::InvalidArgument(format!(
-                    "Expected 1 row to be inserted, but {} were affected",
-                    affected_rows
-                )));
+            let ctx = EventContext::new(actor);
+            let after = self.get_by_id_internal(&tx, id)?;
+            history_repo::record_event(&tx, &ctx, TodoAction::Updated, Some(&before), Some(&after))?;
+
+            if list_id != before.list_id {
+                self.move_descendants_to_list(&tx, id, list_id.as_deref(), &ctx)?;
+            }
+
+            // 移走最后一个未完成的子任务后，原父任务可能满足自动完成条件
+            if let Some(ref old_parent) = before.parent_id {
+                self.complete_if_children_done(&tx, old_parent, &ctx)?;
             }
 
-            // 从数据库读取刚插入的记录以确保数据一致性
-            self.get_by_id_internal(conn, &id)
+            tx.commit()?;
+            Ok(after)
         })
     }
 
-    pub fn batch_create(&self, requests: Vec<CreateTodoRequest>) -> Result<Vec<Todo>, AppError> {
-        let mut todos = Vec::new();
-        for request in requests {
-            let todo = self.create(request)?;
-            todos.push(todo);
-        }
-        Ok(todos)
+    /// 在一个事务中对选中的所有任务应用同一组修改，返回修改后的任务。
+    /// 整批操作记录为一个撤销步骤。
+    pub fn bulk_update(&self, selector: &TodoSelector, request: UpdateTodoRequest, actor: &Actor) -> Result<Vec<Todo>, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let ctx = EventContext::new(actor);
+            let ids = self.select_ids(&tx, selector)?;
+            let mut updated = Vec::with_capacity(ids.len());
+            for id in &ids {
+                updated.push(self.update_internal(&tx, id, &request, &ctx)?);
+            }
+
+            tx.commit()?;
+            Ok(updated)
+        })
     }
 
-    pub fn get_all(&self, filter: Option<TodoFilter>) -> Result<Vec<Todo>, AppError> {
+    /// 批量设置状态，同时保持 `completed` 与状态一致
+    pub fn bulk_set_status(&self, selector: &TodoSelector, status: TodoStatus, actor: &Actor) -> Result<Vec<Todo>, AppError> {
+        let request = UpdateTodoRequest {
+            completed: Some(status == TodoStatus::Completed),
+            status: Some(status),
+            ..Default::default()
+        };
+        self.bulk_update(selector, request, actor)
+    }
+
+    /// 在一个事务中把选中的所有任务移入回收站，返回移入的数量
+    pub fn bulk_delete(&self, selector: &TodoSelector, actor: &Actor) -> Result<u32, AppError> {
         self.db.with_conn(|conn| {
-            let mut sql = "SELECT id, text, completed, status, priority, due_date, tags, created_at, updated_at FROM todos WHERE 1=1".to_string();
-            let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+            let tx = conn.unchecked_transaction()?;
 
-            if let Some(ref f) = filter {
-                if let Some(ref status) = f.status {
-                    sql.push_str(" AND status = ?");
-                    params.push(Box::new(status.as_str().to_string()));
-                }
-                if let Some(completed) = f.completed {
-                    sql.push_str(" AND completed = ?");
-                    params.push(Box::new(if completed { 1 } else { 0 }));
-                }
-                if let Some(ref priority) = f.priority {
-                    sql.push_str(" AND priority = ?");
-                    params.push(Box::new(priority.as_i32()));
-                }
-                if let Some(ref search) = f.search {
-                    sql.push_str(" AND text LIKE ?");
-                    params.push(Box::new(format!("%{}%", search)));
-                }
-                if let Some(ref tag) = f.tag {
-                    sql.push_str(" AND tags LIKE ?");
-                    params.push(Box::new(format!("%\"{}%", tag)));
+            let ctx = EventContext::new(actor);
+            let ids = self.select_ids(&tx, selector)?;
+            let mut count = 0;
+            for id in &ids {
+                // 已随父任务一起移入回收站的子任务跳过
+                if self.get_by_id_internal(&tx, id)?.deleted_at.is_none() {
+                    count += self.soft_delete_internal(&tx, id, &ctx)?;
                 }
             }
 
-            sql.push_str(" ORDER BY completed ASC, created_at DESC");
+            tx.commit()?;
+            Ok(count)
+        })
+    }
 
-            let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-            let mut stmt = conn.prepare(&sql)?;
+    /// 把 `ids` 中的任务按给定顺序整体移动到 `anchor_id` 之前或之后，返回移动后的任务。
+    /// 被移动的任务必须与目标任务属于同一清单、同一父任务；只修改被移动任务的 `position`，
+    /// 间隔不足时先重新分配该范围内的全部位置。
+    pub fn reorder(&self, ids: &[String], anchor_id: &str, placement: Placement, actor: &Actor) -> Result<Vec<Todo>, AppError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        if ids.iter().any(|id| id == anchor_id) {
+            return Err(AppError::InvalidArgument("Cannot move a todo relative to itself".into()));
+        }
 
-            let todos = stmt.query_map(params_refs.as_slice(), |row| {
-                let tags_json: String = row.get(6)?;
-                let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-
-                Ok(Todo {
-                    id: row.get(0)?,
-                    text: row.get(1)?,
-                    completed: row.get::<_, i32>(2)? != 0,
-                    status: TodoStatus::from_str(&row.get::<_, String>(3)?),
-                    priority: Priority::from_i32(row.get(4)?),
-                    due_date: row.get(5)?,
-                    tags,
-                    created_at: row.get(7)?,
-                    updated_at: row.get(8)?,
-                })
-            })?;
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
 
-            let mut result = Vec::new();
-            for todo in todos {
-                result.push(todo?);
+            let ids = self.select_ids(&tx, &TodoSelector::Ids(ids.to_vec()))?;
+            let before: Vec<Todo> = ids.iter()
+                .map(|id| self.get_by_id_internal(&tx, id))
+                .collect::<Result<_, _>>()?;
+            // 位置只在同一清单、同一父任务下比较，跨范围移动需要先修改清单或父任务
+            let anchor = self.get_by_id_internal(&tx, anchor_id)?;
+            if let Some(todo) = before.iter().find(|t| t.list_id != anchor.list_id || t.parent_id != anchor.parent_id) {
+                return Err(AppError::InvalidArgument(format!(
+                    "Cannot reorder '{}' next to a todo in another list or under another parent",
+                    todo.text
+                )));
             }
-            Ok(result)
+
+            let mut bounds = self.reorder_bounds(&tx, &ids, anchor_id, placement)?;
+            let step = (bounds.1 - bounds.0) / (ids.len() + 1) as f64;
+            if step < MIN_POSITION_GAP {
+                Self::renumber_positions(&tx, anchor_id)?;
+                bounds = self.reorder_bounds(&tx, &ids, anchor_id, placement)?;
+            }
+
+            let (lower, upper) = bounds;
+            let step = (upper - lower) / (ids.len() + 1) as f64;
+            let now = Utc::now().to_rfc3339();
+            for (i, id) in ids.iter().enumerate() {
+                let position = lower + step * (i + 1) as f64;
+                tx.execute(
+                    "UPDATE todos SET position = ?1, updated_at = ?2 WHERE id = ?3",
+                    (position, &now, id),
+                )?;
+            }
+
+            let ctx = EventContext::new(actor);
+            let mut moved = Vec::with_capacity(ids.len());
+            for todo in &before {
+                let after = self.get_by_id_internal(&tx, &todo.id)?;
+                history_repo::record_event(&tx, &ctx, TodoAction::Updated, Some(todo), Some(&after))?;
+                moved.push(after);
+            }
+
+            tx.commit()?;
+            Ok(moved)
         })
     }
 
-    pub fn get_by_id(&self, id: &str) -> Result<Todo, AppError> {
-        self.db.with_conn(|conn| self.get_by_id_internal(conn, id))
+    /// 移动后的任务应放入的位置区间（开区间），只看与目标任务同一清单、同一父任务的任务，不考虑被移动的任务本身
+    fn reorder_bounds(
+        &self,
+        conn: &rusqlite::Connection,
+        moving: &[String],
+        anchor_id: &str,
+        placement: Placement,
+    ) -> Result<(f64, f64), AppError> {
+        let anchor = self.get_by_id_internal(conn, anchor_id)?;
+        if anchor.deleted_at.is_some() {
+            return Err(AppError::TodoNotFound(anchor_id.to_string()));
+        }
+
+        let placeholders = vec!["?"; moving.len()].join(", ");
+        let (op, order) = match placement {
+            Placement::Before => ("<", "DESC"),
+            Placement::After => (">", "ASC"),
+        };
+        let sql = format!(
+            "SELECT position FROM todos
+             WHERE deleted_at IS NULL AND list_id IS ? AND parent_id IS ? AND position {} ? AND id NOT IN ({})
+             ORDER BY position {} LIMIT 1",
+            op, placeholders, order
+        );
+
+        let mut params: Vec<&dyn rusqlite::ToSql> = vec![&anchor.list_id, &anchor.parent_id, &anchor.position];
+        params.extend(moving.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+        let neighbour: Option<f64> = conn
+            .query_row(&sql, params.as_slice(), |row| row.get(0))
+            .optional()?;
+
+        // 移到最前或最后时，在目标任务外侧留出与新任务相同的间隔
+        let span = POSITION_GAP * (moving.len() + 1) as f64;
+        Ok(match placement {
+            Placement::Before => (neighbour.unwrap_or(anchor.position - span), anchor.position),
+            Placement::After => (anchor.position, neighbour.unwrap_or(anchor.position + span)),
+        })
     }
 
-    pub fn update(&self, id: &str, request: UpdateTodoRequest) -> Result<Todo, AppError> {
-        let now = Utc::now().to_rfc3339();
+    /// 按当前顺序把与 `anchor_id` 同一清单、同一父任务的任务位置重新分配为 `POSITION_GAP` 的整数倍
+    fn renumber_positions(conn: &rusqlite::Connection, anchor_id: &str) -> Result<(), AppError> {
+        let (list_id, parent_id): (Option<String>, Option<String>) = conn.query_row(
+            "SELECT list_id, parent_id FROM todos WHERE id = ?1",
+            [anchor_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        conn.execute(
+            "UPDATE todos SET position = ?1 * (
+                SELECT ordered.rn FROM (
+                    SELECT id, ROW_NUMBER() OVER (ORDER BY position, created_at, id) AS rn FROM todos
+                    WHERE list_id IS ?2 AND parent_id IS ?3
+                ) AS ordered
+                WHERE ordered.id = todos.id
+            )
+            WHERE list_id IS ?2 AND parent_id IS ?3",
+            rusqlite::params![POSITION_GAP, list_id, parent_id],
+        )?;
+        Ok(())
+    }
 
-        // 在单个连接中执行读取与更新，避免竞态
+    fn get_by_id_internal(&self, conn: &rusqlite::Connection, id: &str) -> Result<Todo, AppError> {
+        load_todo(conn, id)
+    }
+
+    /// 把任务及其所有子任务移入回收站（软删除），可通过 `restore` 恢复
+    pub fn delete(&self, id: &str, actor: &Actor) -> Result<(), AppError> {
         self.db.with_conn(|conn| {
-            // 先获取现有数据
-            let existing = self.get_by_id_internal(conn, id)?;
+            let tx = conn.unchecked_transaction()?;
+            self.soft_delete_internal(&tx, id, &EventContext::new(actor))?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// 把所有已完成的任务移入回收站
+    pub fn delete_completed(&self, actor: &Actor) -> Result<u32, AppError> {
+        let selector = TodoSelector::Filter(TodoFilter {
+            completed: Some(true),
+            ..Default::default()
+        });
+        self.bulk_delete(&selector, actor)
+    }
+
+    /// 软删除任务及其仍在列表中的子任务，它们使用相同的 `deleted_at`，恢复时据此一起恢复。
+    /// 返回移入回收站的任务数。
+    fn soft_delete_internal(&self, conn: &rusqlite::Connection, id: &str, ctx: &EventContext) -> Result<u32, AppError> {
+        let now = Utc::now().to_rfc3339();
+
+        let root = self.get_active(conn, id)?;
 
-            let text = request.text.clone().unwrap_or(existing.text);
-            let completed = request.completed.unwrap_or(existing.completed);
-            let status = request.status.unwrap_or(existing.status);
-            let priority = request.priority.unwrap_or(existing.priority);
-            let due_date = request.due_date.clone().or(existing.due_date);
-            let tags = request.tags.clone().unwrap_or(existing.tags);
-            let tags_json = serde_json::to_string(&tags)?;
+        let mut ids = vec![id.to_string()];
+        ids.extend(self.descendant_ids(conn, id)?);
+
+        let mut count = 0;
+        for todo_id in &ids {
+            let before = self.get_by_id_internal(conn, todo_id)?;
+            if before.deleted_at.is_some() {
+                continue;
+            }
 
             conn.execute(
-                "UPDATE todos SET text = ?1, completed = ?2, status = ?3, priority = ?4, due_date = ?5, tags = ?6, updated_at = ?7 WHERE id = ?8",
-                (
-                    &text,
-                    if completed { 1 } else { 0 },
-                    status.as_str(),
-                    priority.as_i32(),
-                    &due_date,
-                    &tags_json,
-                    &now,
-                    id,
-                ),
+                "UPDATE todos SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2",
+                (&now, todo_id),
             )?;
 
-            Ok(Todo {
-                id: id.to_string(),
-                text,
-                completed,
-                status,
-                priority,
-                due_date,
-                tags,
-                created_at: existing.created_at,
-                updated_at: now.clone(),
-            })
+            let after = self.get_by_id_internal(conn, todo_id)?;
+            history_repo::record_event(conn, ctx, TodoAction::Deleted, Some(&before), Some(&after))?;
+            count += 1;
+        }
+
+        // 删除最后一个未完成的子任务后，父任务可能满足自动完成条件
+        if let Some(ref parent_id) = root.parent_id {
+            self.complete_if_children_done(conn, parent_id, ctx)?;
+        }
+
+        Ok(count)
+    }
+
+    /// 回收站中的任务，最近删除的在前
+    pub fn get_trash(&self) -> Result<Vec<Todo>, AppError> {
+        self.db.with_conn(|conn| {
+            self.query_trash(conn, "1=1", &[])
         })
     }
 
-    fn get_by_id_internal(&self, conn: &rusqlite::Connection, id: &str) -> Result<Todo, AppError> {
-        let todo = conn.query_row(
-            "SELECT id, text, completed, status, priority, due_date, tags, created_at, updated_at FROM todos WHERE id = ?1",
-            [id],
-            |row| {
-                let tags_json: String = row.get(6)?;
-                let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-
-                Ok(Todo {
-                    id: row.get(0)?,
-                    text: row.get(1)?,
-                    completed: row.get::<_, i32>(2)? != 0,
-                    status: TodoStatus::from_str(&row.get::<_, String>(3)?),
-                    priority: Priority::from_i32(row.get(4)?),
-                    due_date: row.get(5)?,
-                    tags,
-                    created_at: row.get(7)?,
-                    updated_at: row.get(8)?,
-                })
-            },
-        ).map_err(|_| AppError::TodoNotFound(id.to_string()))?;
+    fn query_trash(
+        &self,
+        conn: &rusqlite::Connection,
+        condition: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<Todo>, AppError> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM todos WHERE todos.deleted_at IS NOT NULL AND {} ORDER BY todos.deleted_at DESC",
+            TODO_COLUMNS, condition
+        ))?;
 
-        Ok(todo)
+        let todos = stmt.query_map(params, row_to_todo)?;
+
+        let mut result = Vec::new();
+        for todo in todos {
+            result.push(todo?);
+        }
+        Ok(result)
     }
 
-    pub fn delete(&self, id: &str) -> Result<(), AppError> {
+    /// 从回收站恢复任务。
+    ///
+    /// 与它一起被删除的子任务（`deleted_at` 相同）会一起恢复；
+    /// 如果父任务仍在回收站中，父任务也会被恢复，以保持层级完整。
+    pub fn restore(&self, id: &str, actor: &Actor) -> Result<Todo, AppError> {
         self.db.with_conn(|conn| {
-            let rows = conn.execute("DELETE FROM todos WHERE id = ?1", [id])?;
+            let tx = conn.unchecked_transaction()?;
 
-            if rows == 0 {
-                return Err(AppError::TodoNotFound(id.to_string()));
+            let before = self.get_by_id_internal(&tx, id)?;
+            let deleted_at = match before.deleted_at {
+                Some(ref deleted_at) => deleted_at.clone(),
+                None => return Err(AppError::TodoNotFound(id.to_string())),
+            };
+
+            // 依次恢复：仍在回收站中的祖先（从顶层开始）、任务本身、同批删除的子任务
+            let mut ids = Vec::new();
+            let mut parent_id = before.parent_id.clone();
+            while let Some(pid) = parent_id {
+                let parent = self.get_by_id_internal(&tx, &pid)?;
+                if parent.deleted_at.is_none() {
+                    break;
+                }
+                ids.insert(0, pid);
+                parent_id = parent.parent_id;
+            }
+            ids.push(id.to_string());
+            for descendant in self.descendant_ids(&tx, id)? {
+                if self.get_by_id_internal(&tx, &descendant)?.deleted_at.as_deref() == Some(deleted_at.as_str()) {
+                    ids.push(descendant);
+                }
             }
 
-            Ok(())
+            let ctx = EventContext::new(actor);
+            let now = Utc::now().to_rfc3339();
+            for todo_id in &ids {
+                let before = self.get_by_id_internal(&tx, todo_id)?;
+                tx.execute(
+                    "UPDATE todos SET deleted_at = NULL, updated_at = ?1 WHERE id = ?2",
+                    (&now, todo_id),
+                )?;
+
+                let after = self.get_by_id_internal(&tx, todo_id)?;
+                history_repo::record_event(&tx, &ctx, TodoAction::Restored, Some(&before), Some(&after))?;
+            }
+
+            let restored = self.get_by_id_internal(&tx, id)?;
+            tx.commit()?;
+            Ok(restored)
         })
     }
 
-    pub fn delete_completed(&self) -> Result<u32, AppError> {
+    /// 清空回收站，永久删除其中的全部任务
+    pub fn empty_trash(&self, actor: &Actor) -> Result<u32, AppError> {
+        self.purge_where("1=1", &[], actor)
+    }
+
+    /// 永久删除在回收站中停留超过 `retention_days` 天的任务
+    pub fn purge_trash(&self, retention_days: u32, actor: &Actor) -> Result<u32, AppError> {
+        let cutoff = (Utc::now() - chrono::Duration::days(retention_days as i64)).to_rfc3339();
+        self.purge_where("todos.deleted_at < ?1", &[&cutoff], actor)
+    }
+
+    fn purge_where(&self, condition: &str, params: &[&dyn rusqlite::ToSql], actor: &Actor) -> Result<u32, AppError> {
         self.db.with_conn(|conn| {
-            let rows = conn.execute("DELETE FROM todos WHERE completed = 1", [])?;
-            Ok(rows as u32)
+            let tx = conn.unchecked_transaction()?;
+
+            // 永久删除不可撤销，见 `undo_repo::forget_todo`
+            let ctx = EventContext::untracked(actor);
+            let todos = self.query_trash(&tx, condition, params)?;
+            let mut count = 0;
+            for todo in &todos {
+                count += self.purge_subtree(&tx, &todo.id, &ctx)?;
+            }
+
+            tx.commit()?;
+            Ok(count)
         })
     }
 
-    pub fn search(&self, keyword: &str) -> Result<Vec<Todo>, AppError> {
-        self.get_all(Some(TodoFilter {
-            search: Some(keyword.to_string()),
-            ..Default::default()
-        }))
+    /// 永久删除任务及其全部子任务，先删除最深的子任务
+    fn purge_subtree(&self, conn: &rusqlite::Connection, id: &str, ctx: &EventContext) -> Result<u32, AppError> {
+        let mut ids = self.descendant_ids(conn, id)?;
+        ids.reverse();
+        ids.push(id.to_string());
+
+        let mut count = 0;
+        for todo_id in &ids {
+            // 已在本次清理中作为其他任务的子任务删除
+            let todo = match self.get_by_id_internal(conn, todo_id) {
+                Ok(todo) => todo,
+                Err(AppError::TodoNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+
+            conn.execute("DELETE FROM todos WHERE id = ?1", [todo_id])?;
+            history_repo::record_event(conn, ctx, TodoAction::Purged, Some(&todo), None)?;
+            undo_repo::forget_todo(conn, todo_id)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// 全文检索，结果按 bm25 相关度排序，并附带高亮片段。
+    /// 支持短语（`"..."`）与前缀（`rep*`）查询，语法见 `fts::build_match_query`。
+    pub fn search(&self, keyword: &str, limit: u32) -> Result<Vec<TodoSearchResult>, AppError> {
+        let query = match fts::build_match_query(keyword) {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+
+        self.db.with_conn(|conn| {
+            // 片段取自匹配最好的一列（标题、备注或清单）
+            let sql = format!(
+                "SELECT {}, {} AS rank,
+                        snippet(todos_fts, -1, '<mark>', '</mark>', '…', 16)
+                 FROM todos_fts
+                 JOIN todos ON todos.id = todos_fts.id
+                 WHERE todos_fts MATCH ?1 AND todos.deleted_at IS NULL
+                 ORDER BY rank
+                 LIMIT ?2",
+                TODO_COLUMNS, FTS_RANK_SQL
+            );
+            let mut stmt = conn.prepare(&sql)?;
+
+            let results = stmt.query_map((&query, limit), |row| {
+                Ok(TodoSearchResult {
+                    todo: row_to_todo(row)?,
+                    rank: row.get(TODO_COLUMN_COUNT)?,
+                    snippet: fts::desegment(&row.get::<_, String>(TODO_COLUMN_COUNT + 1)?),
+                })
+            })?;
+
+            let mut hits = Vec::new();
+            for hit in results {
+                hits.push(hit?);
+            }
+            Ok(hits)
+        })
     }
 
-    pub fn get_statistics(&self) -> Result<TodoStatistics, AppError> {
+    /// 任务统计，`list_id` 的含义与 `TodoFilter::list_id` 相同
+    pub fn get_statistics(&self, list_id: Option<&str>) -> Result<TodoStatistics, AppError> {
+        let filter = TodoFilter {
+            list_id: list_id.map(String::from),
+            ..Default::default()
+        };
+
         self.db.with_conn(|conn| {
-            let total: u32 = conn.query_row("SELECT COUNT(*) FROM todos", [], |row| row.get(0))?;
-            let completed: u32 = conn.query_row("SELECT COUNT(*) FROM todos WHERE completed = 1", [], |row| row.get(0))?;
-            let pending: u32 = conn.query_row("SELECT COUNT(*) FROM todos WHERE status = 'pending'", [], |row| row.get(0))?;
-            let in_progress: u32 = conn.query_row("SELECT COUNT(*) FROM todos WHERE status = 'in_progress'", [], |row| row.get(0))?;
-            let cancelled: u32 = conn.query_row("SELECT COUNT(*) FROM todos WHERE status = 'cancelled'", [], |row| row.get(0))?;
+            let tz = settings_repo::load_timezone(conn)?;
+            let filter_sql = FilterSql::new(Some(&filter), &tz)?;
+            let (total, completed, pending, in_progress, cancelled) = conn.query_row(
+                &format!(
+                    "SELECT COUNT(*),
+                        COALESCE(SUM(todos.completed = 1), 0),
+                        COALESCE(SUM(todos.status = 'pending'), 0),
+                        COALESCE(SUM(todos.status = 'in_progress'), 0),
+                        COALESCE(SUM(todos.status = 'cancelled'), 0)
+                     FROM todos WHERE todos.deleted_at IS NULL{}",
+                    filter_sql.conditions
+                ),
+                filter_sql.params_refs().as_slice(),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )?;
+
+            // 专注记录：指定列表时只统计该列表中任务的记录
+            let scope = if list_id.is_some() {
+                format!(
+                    " AND focus_sessions.todo_id IN (SELECT todos.id FROM todos WHERE todos.deleted_at IS NULL{})",
+                    filter_sql.conditions
+                )
+            } else {
+                String::new()
+            };
+            let today_start = tz
+                .from_local(tz.today().and_time(NaiveTime::MIN))
+                .map(|dt| due_date::instant_sql(&dt.with_timezone(&Utc)))
+                .unwrap_or_default();
+            let mut params: Vec<&dyn rusqlite::ToSql> = vec![&today_start];
+            if list_id.is_some() {
+                params.extend(filter_sql.params_refs());
+            }
+
+            let (focus_sessions, focus_sessions_today, focus_seconds) = conn.query_row(
+                &format!(
+                    "SELECT COALESCE(SUM(completed), 0),
+                        COALESCE(SUM(CASE WHEN completed = 1 AND started_at >= ? THEN 1 ELSE 0 END), 0),
+                        COALESCE(SUM(focused_seconds), 0)
+                     FROM focus_sessions WHERE 1 = 1{}",
+                    scope
+                ),
+                params.as_slice(),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
 
             Ok(TodoStatistics {
                 total,
@@ -230,7 +1992,582 @@ impl TodoRepository {
                 pending,
                 in_progress,
                 cancelled,
+                focus_sessions,
+                focus_sessions_today,
+                focus_seconds,
             })
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDb;
+
+    fn create(repo: &TodoRepository, text: &str) -> Todo {
+        repo.create(CreateTodoRequest { text: text.into(), ..Default::default() }, &Actor::User).unwrap()
+    }
+
+    fn create_child(repo: &TodoRepository, text: &str, parent: &Todo) -> Todo {
+        repo.create(
+            CreateTodoRequest { text: text.into(), parent_id: Some(parent.id.clone()), ..Default::default() },
+            &Actor::User,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn update_adds_checklist_items_in_the_same_transaction() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let todo = create(&repo, "写周报");
+
+        let request = UpdateTodoRequest {
+            text: Some("写月报".into()),
+            add_checklist: vec!["汇总数据".into(), " ".into()],
+            ..Default::default()
+        };
+        assert!(matches!(repo.update(&todo.id, request, &Actor::User), Err(AppError::InvalidArgument(_))));
+        let unchanged = repo.get_by_id(&todo.id).unwrap();
+        assert_eq!(unchanged.text, "写周报");
+        assert!(unchanged.checklist.is_empty());
+
+        let request = UpdateTodoRequest {
+            text: Some("写月报".into()),
+            add_checklist: vec!["汇总数据".into(), "画图表".into()],
+            ..Default::default()
+        };
+        let updated = repo.update(&todo.id, request, &Actor::User).unwrap();
+        assert_eq!(updated.text, "写月报");
+        let items: Vec<&str> = updated.checklist.iter().map(|item| item.text.as_str()).collect();
+        assert_eq!(items, vec!["汇总数据", "画图表"]);
+    }
+
+    #[test]
+    fn dependency_cycles_are_rejected() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let a = create(&repo, "a");
+        let b = create(&repo, "b");
+        let c = create(&repo, "c");
+
+        assert!(matches!(repo.add_dependency(&a.id, &a.id, &Actor::User), Err(AppError::InvalidArgument(_))));
+
+        // a -> b -> a
+        repo.add_dependency(&a.id, &b.id, &Actor::User).unwrap();
+        assert!(matches!(repo.add_dependency(&b.id, &a.id, &Actor::User), Err(AppError::DependencyCycle { .. })));
+
+        // a -> b -> c -> a
+        repo.add_dependency(&b.id, &c.id, &Actor::User).unwrap();
+        assert!(matches!(repo.add_dependency(&c.id, &a.id, &Actor::User), Err(AppError::DependencyCycle { .. })));
+
+        // 被拒绝的依赖没有写入
+        assert_eq!(repo.get_by_id(&c.id).unwrap().depends_on, Vec::<String>::new());
+        assert_eq!(repo.get_by_id(&a.id).unwrap().depends_on, vec![b.id.clone()]);
+    }
+
+    #[test]
+    fn dependency_diamond_is_allowed() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let a = create(&repo, "a");
+        let b = create(&repo, "b");
+        let c = create(&repo, "c");
+        let d = create(&repo, "d");
+
+        // a 依赖 b、c，b、c 都依赖 d
+        repo.add_dependency(&a.id, &b.id, &Actor::User).unwrap();
+        repo.add_dependency(&a.id, &c.id, &Actor::User).unwrap();
+        repo.add_dependency(&b.id, &d.id, &Actor::User).unwrap();
+        repo.add_dependency(&c.id, &d.id, &Actor::User).unwrap();
+        // 跨过中间任务的直接依赖也不构成环路
+        repo.add_dependency(&a.id, &d.id, &Actor::User).unwrap();
+
+        let mut depends_on = repo.get_by_id(&a.id).unwrap().depends_on;
+        depends_on.sort();
+        let mut expected = vec![b.id.clone(), c.id.clone(), d.id.clone()];
+        expected.sort();
+        assert_eq!(depends_on, expected);
+        assert!(repo.get_by_id(&a.id).unwrap().blocked);
+    }
+
+    #[test]
+    fn parent_cycles_are_rejected() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let a = create(&repo, "a");
+        let b = create_child(&repo, "b", &a);
+        let c = create_child(&repo, "c", &b);
+        let other = create(&repo, "other");
+
+        assert!(repo.set_parent(&a.id, Some(&a.id), &Actor::User).is_err());
+        assert!(repo.set_parent(&a.id, Some(&b.id), &Actor::User).is_err());
+        assert!(repo.set_parent(&a.id, Some(&c.id), &Actor::User).is_err());
+        assert_eq!(repo.get_by_id(&a.id).unwrap().parent_id, None);
+
+        // 移动到子树之外，以及移动到兄弟分支下都可以
+        repo.set_parent(&c.id, Some(&a.id), &Actor::User).unwrap();
+        repo.set_parent(&b.id, Some(&c.id), &Actor::User).unwrap();
+        repo.set_parent(&a.id, Some(&other.id), &Actor::User).unwrap();
+        assert_eq!(repo.get_by_id(&b.id).unwrap().parent_id.as_deref(), Some(c.id.as_str()));
+    }
+
+    /// 创建 `n` 个排序值大量相同的任务：优先级、时间、文本、截止时间、位置都只有少数几种取值
+    fn create_ties(test: &TestDb, repo: &TodoRepository, n: usize) {
+        for i in 0..n {
+            let todo = create(repo, if i % 2 == 0 { "weekly report" } else { "Weekly Report" });
+            test.db
+                .with_conn(|conn| {
+                    conn.execute(
+                        "UPDATE todos SET priority = ?1, completed = ?2, created_at = ?3, updated_at = ?4,
+                            due_date = ?5, due_at = ?6, position = ?7 WHERE id = ?8",
+                        rusqlite::params![
+                            (i % 3) as i64,
+                            i % 4 == 0,
+                            format!("2025-03-0{}T08:00:00+00:00", 1 + i % 3),
+                            format!("2025-03-1{}T08:00:00+00:00", i % 2),
+                            if i % 3 == 0 { None } else { Some("2025-04-01") },
+                            if i % 3 == 0 { None } else { Some(format!("2025-04-0{}T00:00:00Z", 1 + i % 2)) },
+                            (i / 3) as f64,
+                            todo.id,
+                        ],
+                    )?;
+                    Ok(())
+                })
+                .unwrap();
+        }
+    }
+
+    /// 以 `limit` 为页大小逐页读取全部结果（`search` 为可选的搜索词），同时检查总数
+    fn collect_pages(repo: &TodoRepository, search: Option<&str>, sort: SortKey, direction: SortDirection, limit: u32) -> Vec<String> {
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = repo
+                .get_page(
+                    Some(TodoFilter { search: search.map(String::from), ..Default::default() }),
+                    TodoPageRequest { limit: Some(limit), cursor, sort: Some(sort), direction: Some(direction) },
+                )
+                .unwrap();
+            assert!(page.items.len() <= limit as usize);
+            ids.extend(page.items.into_iter().map(|t| t.id));
+            assert!(ids.len() <= page.total as usize, "more rows than total");
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return ids,
+            }
+        }
+    }
+
+    fn assert_pages_match_full_order(repo: &TodoRepository, search: Option<&str>, expected_len: usize) {
+        let sorts = [
+            SortKey::Default,
+            SortKey::Priority,
+            SortKey::DueDate,
+            SortKey::CreatedAt,
+            SortKey::UpdatedAt,
+            SortKey::Text,
+            SortKey::Manual,
+        ];
+        for sort in sorts {
+            for direction in [SortDirection::Asc, SortDirection::Desc] {
+                let full: Vec<String> = repo
+                    .get_page(
+                        Some(TodoFilter { search: search.map(String::from), ..Default::default() }),
+                        TodoPageRequest { sort: Some(sort), direction: Some(direction), ..Default::default() },
+                    )
+                    .unwrap()
+                    .items
+                    .into_iter()
+                    .map(|t| t.id)
+                    .collect();
+                assert_eq!(full.len(), expected_len);
+
+                for limit in [1, 2, 5] {
+                    let paged = collect_pages(repo, search, sort, direction, limit);
+                    // 顺序与不分页时完全一致，既没有重复也没有遗漏
+                    assert_eq!(paged, full, "sort {:?} {:?}, limit {}", sort, direction, limit);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn keyset_pagination_handles_ties() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        create_ties(&test, &repo, 13);
+
+        assert_pages_match_full_order(&repo, None, 13);
+    }
+
+    #[test]
+    fn keyset_pagination_handles_ranked_search_ties() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        create_ties(&test, &repo, 11);
+        // 相关度不同的结果
+        create(&repo, "report");
+        create(&repo, "report on the weekly report for the weekly meeting");
+        create(&repo, "unrelated");
+
+        assert_pages_match_full_order(&repo, Some("report"), 13);
+    }
+
+    #[test]
+    fn keyset_pagination_handles_fractional_positions() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        // 非二进制小数的位置（以及其中的并列），游标必须原样还原这些值
+        for i in 0..9 {
+            let todo = create(&repo, "todo");
+            let position = 1.0 / 3.0 + (i / 2) as f64 * 0.1;
+            test.db
+                .with_conn(|conn| {
+                    conn.execute("UPDATE todos SET position = ?1 WHERE id = ?2", (position, &todo.id))?;
+                    Ok(())
+                })
+                .unwrap();
+        }
+
+        for direction in [SortDirection::Asc, SortDirection::Desc] {
+            let full: Vec<String> = repo
+                .get_page(None, TodoPageRequest { sort: Some(SortKey::Manual), direction: Some(direction), ..Default::default() })
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|t| t.id)
+                .collect();
+            for limit in [1, 2, 3] {
+                assert_eq!(collect_pages(&repo, None, SortKey::Manual, direction, limit), full);
+            }
+        }
+
+        let cursor = PageCursor {
+            sort: SortKey::Manual,
+            direction: SortDirection::Asc,
+            values: vec![sql_to_json(SqlValue::Real(0.1 + 0.2)), sql_to_json(SqlValue::Real(2.0 / 3.0))],
+        };
+        let decoded = PageCursor::decode(&serde_json::to_string(&cursor).unwrap(), SortKey::Manual, SortDirection::Asc, 2)
+            .unwrap()
+            .sql_values()
+            .unwrap();
+        assert!(matches!(decoded[0], SqlValue::Real(f) if f.to_bits() == (0.1f64 + 0.2).to_bits()));
+        assert!(matches!(decoded[1], SqlValue::Real(f) if f.to_bits() == (2.0f64 / 3.0).to_bits()));
+    }
+
+    #[test]
+    fn cursor_from_another_sort_is_rejected() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        create_ties(&test, &repo, 4);
+
+        let request = |cursor: Option<String>, sort, direction| TodoPageRequest {
+            limit: Some(2),
+            cursor,
+            sort: Some(sort),
+            direction: Some(direction),
+        };
+        let cursor = repo
+            .get_page(None, request(None, SortKey::Priority, SortDirection::Desc))
+            .unwrap()
+            .next_cursor
+            .unwrap();
+
+        assert!(repo.get_page(None, request(Some(cursor.clone()), SortKey::Priority, SortDirection::Desc)).is_ok());
+        for (sort, direction) in [
+            (SortKey::Text, SortDirection::Desc),
+            (SortKey::Priority, SortDirection::Asc),
+            (SortKey::CreatedAt, SortDirection::Desc),
+        ] {
+            assert!(matches!(
+                repo.get_page(None, request(Some(cursor.clone()), sort, direction)),
+                Err(AppError::InvalidArgument(_))
+            ));
+        }
+        assert!(matches!(
+            repo.get_page(None, request(Some("not a cursor".into()), SortKey::Priority, SortDirection::Desc)),
+            Err(AppError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn board_lists_todos_without_state() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let open = create(&repo, "open");
+        let cancelled = create(&repo, "cancelled");
+        let orphaned = create(&repo, "orphaned");
+        repo.update(&cancelled.id, UpdateTodoRequest { status: Some(TodoStatus::Cancelled), ..Default::default() }, &Actor::User)
+            .unwrap();
+        // 不经过 `WorkflowRepository::delete` 删除状态时，外键把任务的状态置空
+        test.db
+            .with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO workflow_states (id, name, category, position, created_at, updated_at)
+                     VALUES ('gone', 'Gone', 'open', 9999.0, '', '')",
+                    [],
+                )?;
+                conn.execute("UPDATE todos SET state_id = 'gone' WHERE id = ?1", [&orphaned.id])?;
+                conn.execute("DELETE FROM workflow_states WHERE id = 'gone'", [])?;
+                Ok(())
+            })
+            .unwrap();
+
+        let board = repo.get_board(None).unwrap();
+        let column = |id: &str| -> Vec<String> {
+            board.columns.iter().find(|c| c.state.id == id).unwrap().todos.iter().map(|t| t.id.clone()).collect()
+        };
+        assert_eq!(column("pending"), vec![open.id.clone()]);
+        let mut unassigned: Vec<String> = board.unassigned.iter().map(|t| t.id.clone()).collect();
+        unassigned.sort();
+        let mut expected = vec![cancelled.id.clone(), orphaned.id.clone()];
+        expected.sort();
+        assert_eq!(unassigned, expected);
+    }
+
+    #[test]
+    fn reorder_is_scoped_to_anchor_siblings() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let a = create(&repo, "a");
+        let parent = create(&repo, "parent");
+        let first = create_child(&repo, "first", &parent);
+        let b = create(&repo, "b");
+        let second = create_child(&repo, "second", &parent);
+        // 另一层级的任务紧贴在目标任务之前，不应挤占间隔或触发重新分配
+        test.db
+            .with_conn(|conn| {
+                conn.execute("UPDATE todos SET position = ?1 WHERE id = ?2", (first.position - 1e-9, &b.id))?;
+                Ok(())
+            })
+            .unwrap();
+        let b = repo.get_by_id(&b.id).unwrap();
+
+        let moved = repo.reorder(std::slice::from_ref(&second.id), &first.id, Placement::Before, &Actor::User).unwrap();
+
+        assert_ne!(moved[0].updated_at, second.updated_at);
+        let children: Vec<String> = repo.get_children(&parent.id).unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(children, vec![second.id.clone(), first.id.clone()]);
+        for todo in [&a, &parent, &first, &b] {
+            assert_eq!(repo.get_by_id(&todo.id).unwrap().position, todo.position, "{}", todo.text);
+        }
+    }
+
+    #[test]
+    fn reorder_rejects_todos_from_another_scope() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let list = crate::db::ListRepository::new(test.db.clone())
+            .create(crate::models::list::CreateListRequest { name: "工作".into(), ..Default::default() })
+            .unwrap();
+        let anchor = create(&repo, "anchor");
+        let other = repo
+            .create(CreateTodoRequest { text: "other".into(), list_id: Some(list.id.clone()), ..Default::default() }, &Actor::User)
+            .unwrap();
+        let parent = create(&repo, "parent");
+        let child = create_child(&repo, "child", &parent);
+
+        for id in [&other.id, &child.id] {
+            assert!(matches!(
+                repo.reorder(std::slice::from_ref(id), &anchor.id, Placement::After, &Actor::User),
+                Err(AppError::InvalidArgument(_))
+            ));
+        }
+        assert_eq!(repo.get_by_id(&other.id).unwrap().position, other.position);
+        assert_eq!(repo.get_by_id(&child.id).unwrap().position, child.position);
+    }
+
+    #[test]
+    fn purged_todos_leave_the_undo_stack() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let undo = crate::db::UndoRepository::new(test.db.clone());
+        let kept = create(&repo, "kept");
+        let purged = create(&repo, "purged");
+        let both = TodoSelector::Ids(vec![kept.id.clone(), purged.id.clone()]);
+        repo.bulk_update(&both, UpdateTodoRequest { priority: Some(Priority::High), ..Default::default() }, &Actor::User)
+            .unwrap();
+        repo.delete(&purged.id, &Actor::User).unwrap();
+        assert_eq!(undo.get_stack().unwrap().undo.len(), 4);
+
+        assert_eq!(repo.empty_trash(&Actor::User).unwrap(), 1);
+
+        // 永久删除本身与只涉及该任务的步骤不能再撤销；同时修改了其他任务的步骤只撤销其他任务
+        let stack = undo.get_stack().unwrap();
+        assert_eq!(stack.undo.len(), 2);
+        assert_eq!(stack.undo[0].todo_count, 1);
+        undo.undo().unwrap().unwrap();
+        assert_eq!(repo.get_by_id(&kept.id).unwrap().priority, Priority::Low);
+        assert!(matches!(repo.get_by_id(&purged.id), Err(AppError::TodoNotFound(_))));
+        undo.undo().unwrap().unwrap();
+        assert!(repo.get_by_id(&kept.id).unwrap().deleted_at.is_some());
+        assert!(undo.undo().unwrap().is_none());
+    }
+
+    #[test]
+    fn undoing_a_create_keeps_reminders_and_dependencies_for_redo() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let reminders = crate::db::ReminderRepository::new(test.db.clone());
+        let undo = crate::db::UndoRepository::new(test.db.clone());
+        let upstream = create(&repo, "upstream");
+        let todo = repo
+            .create(
+                CreateTodoRequest {
+                    text: "todo".into(),
+                    due_date: Some("2099-03-12T18:00:00Z".into()),
+                    depends_on: vec![upstream.id.clone()],
+                    ..Default::default()
+                },
+                &Actor::User,
+            )
+            .unwrap();
+        let downstream = create(&repo, "downstream");
+        repo.add_dependency(&downstream.id, &todo.id, &Actor::User).unwrap();
+        reminders
+            .create(&todo.id, crate::models::reminder::CreateReminderRequest { offset_minutes: Some(30), remind_at: None })
+            .unwrap();
+
+        for _ in 0..3 {
+            undo.undo().unwrap().unwrap();
+        }
+        assert!(repo.get_by_id(&todo.id).unwrap().deleted_at.is_some());
+        for _ in 0..3 {
+            undo.redo().unwrap().unwrap();
+        }
+
+        let todo = repo.get_by_id(&todo.id).unwrap();
+        assert!(todo.deleted_at.is_none());
+        assert_eq!(todo.depends_on, vec![upstream.id.clone()]);
+        assert_eq!(repo.get_by_id(&downstream.id).unwrap().depends_on, vec![todo.id.clone()]);
+        assert_eq!(reminders.get_for_todo(&todo.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn next_occurrence_of_child_under_auto_complete_parent_is_top_level() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let complete = |todo: &Todo| {
+            let request = UpdateTodoRequest { completed: Some(true), status: Some(TodoStatus::Completed), ..Default::default() };
+            repo.update(&todo.id, request, &Actor::User).unwrap()
+        };
+        let recurring_child = |text: &str, parent: &Todo| {
+            repo.create(
+                CreateTodoRequest {
+                    text: text.into(),
+                    parent_id: Some(parent.id.clone()),
+                    due_date: Some("2025-03-12".into()),
+                    recurrence: Some("FREQ=DAILY".into()),
+                    ..Default::default()
+                },
+                &Actor::User,
+            )
+            .unwrap()
+        };
+        let parent = repo
+            .create(CreateTodoRequest { text: "parent".into(), auto_complete: true, ..Default::default() }, &Actor::User)
+            .unwrap();
+        let daily = recurring_child("daily", &parent);
+        let once = create_child(&repo, "once", &parent);
+
+        // 下一次不挂在自动完成的父任务下，父任务仍等待其他子任务
+        let daily = complete(&daily);
+        let next = repo.get_next_occurrence(&daily).unwrap().unwrap();
+        assert_eq!(next.parent_id, None);
+        assert!(!repo.get_by_id(&parent.id).unwrap().completed);
+
+        complete(&once);
+        assert!(repo.get_by_id(&parent.id).unwrap().completed);
+        assert!(!repo.get_by_id(&next.id).unwrap().completed);
+        assert_eq!(repo.get_children(&parent.id).unwrap().len(), 2);
+
+        // 父任务未开启自动完成时，下一次仍留在父任务下
+        let manual = create(&repo, "manual");
+        let weekly = complete(&recurring_child("weekly", &manual));
+        let next = repo.get_next_occurrence(&weekly).unwrap().unwrap();
+        assert_eq!(next.parent_id.as_deref(), Some(manual.id.as_str()));
+    }
+
+    #[test]
+    fn trashed_todos_are_hidden_until_restored() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let parent = create(&repo, "parent");
+        let child = create_child(&repo, "child", &parent);
+        let other = create(&repo, "other");
+
+        repo.delete(&parent.id, &Actor::User).unwrap();
+
+        let ids = |todos: Vec<Todo>| todos.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(ids(repo.get_all(None).unwrap()), vec![other.id.clone()]);
+        assert_eq!(repo.get_statistics(None).unwrap().total, 1);
+        let mut trash = ids(repo.get_trash().unwrap());
+        trash.sort();
+        let mut expected = vec![parent.id.clone(), child.id.clone()];
+        expected.sort();
+        assert_eq!(trash, expected);
+        assert!(matches!(repo.delete(&parent.id, &Actor::User), Err(AppError::TodoNotFound(_))));
+
+        // 恢复父任务时同批删除的子任务一起恢复
+        let restored = repo.restore(&parent.id, &Actor::User).unwrap();
+        assert!(restored.deleted_at.is_none());
+        assert!(repo.get_by_id(&child.id).unwrap().deleted_at.is_none());
+        assert!(repo.get_trash().unwrap().is_empty());
+        assert!(matches!(repo.restore(&parent.id, &Actor::User), Err(AppError::TodoNotFound(_))));
+    }
+
+    #[test]
+    fn restore_brings_back_trashed_ancestors_but_not_earlier_deletions() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let parent = create(&repo, "parent");
+        let deleted_first = create_child(&repo, "deleted first", &parent);
+        let deleted_with_parent = create_child(&repo, "deleted with parent", &parent);
+
+        repo.delete(&deleted_first.id, &Actor::User).unwrap();
+        repo.delete(&parent.id, &Actor::User).unwrap();
+
+        // 单独删除过的子任务不随父任务恢复
+        repo.restore(&parent.id, &Actor::User).unwrap();
+        assert!(repo.get_by_id(&deleted_with_parent.id).unwrap().deleted_at.is_none());
+        assert!(repo.get_by_id(&deleted_first.id).unwrap().deleted_at.is_some());
+
+        // 恢复子任务时，仍在回收站中的父任务也会恢复
+        repo.delete(&parent.id, &Actor::User).unwrap();
+        repo.restore(&deleted_first.id, &Actor::User).unwrap();
+        assert!(repo.get_by_id(&parent.id).unwrap().deleted_at.is_none());
+        assert!(repo.get_by_id(&deleted_with_parent.id).unwrap().deleted_at.is_some());
+    }
+
+    #[test]
+    fn purge_trash_only_removes_todos_past_retention() {
+        let test = TestDb::new();
+        let repo = TodoRepository::new(test.db.clone());
+        let old = create(&repo, "old");
+        let old_child = create_child(&repo, "old child", &old);
+        let recent = create(&repo, "recent");
+        let active = create(&repo, "active");
+        repo.delete(&old.id, &Actor::User).unwrap();
+        repo.delete(&recent.id, &Actor::User).unwrap();
+
+        let long_ago = (Utc::now() - chrono::Duration::days(31)).to_rfc3339();
+        test.db
+            .with_conn(|conn| {
+                conn.execute("UPDATE todos SET deleted_at = ?1 WHERE id IN (?2, ?3)", (&long_ago, &old.id, &old_child.id))?;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(repo.purge_trash(30, &Actor::User).unwrap(), 2);
+        assert!(matches!(repo.get_by_id(&old.id), Err(AppError::TodoNotFound(_))));
+        assert!(matches!(repo.get_by_id(&old_child.id), Err(AppError::TodoNotFound(_))));
+        assert_eq!(repo.get_trash().unwrap().len(), 1);
+
+        assert_eq!(repo.empty_trash(&Actor::User).unwrap(), 1);
+        assert!(repo.get_trash().unwrap().is_empty());
+        assert!(repo.get_by_id(&active.id).unwrap().deleted_at.is_none());
+    }
+}
+
@@ -0,0 +1,252 @@
+use crate::db::history_repo::{self, EventContext};
+use crate::db::{todo_repo, Database};
+use crate::error::AppError;
+use crate::models::history::{Actor, TodoAction};
+use crate::models::todo::Todo;
+use crate::models::undo::{UndoStack, UndoStep};
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::sync::Arc;
+
+/// 撤销栈最多保留的步骤数
+const MAX_UNDO_STEPS: u32 = 100;
+
+/// 登记一个撤销步骤（已存在时忽略）。
+///
+/// 新步骤产生时清空重做栈，并丢弃超出 `MAX_UNDO_STEPS` 的旧步骤。
+pub(crate) fn register_step(conn: &Connection, step_id: &str, actor: &Actor, now: &str) -> Result<(), AppError> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO undo_steps (id, actor, created_at, undone_at) VALUES (?1, ?2, ?3, NULL)",
+        (step_id, serde_json::to_string(actor)?, now),
+    )?;
+
+    if inserted > 0 {
+        conn.execute("DELETE FROM undo_steps WHERE undone_at IS NOT NULL", [])?;
+        conn.execute(
+            "DELETE FROM undo_steps WHERE id NOT IN (
+                SELECT id FROM undo_steps ORDER BY created_at DESC LIMIT ?1
+            )",
+            [MAX_UNDO_STEPS],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 任务被永久删除后，把它的事件移出所在的撤销与重做步骤，移出后为空的步骤一并丢弃。
+///
+/// 永久删除会级联删除提醒、附件、计时记录与标签关联，快照中没有这些数据，
+/// 撤销只能重建出残缺的任务，因此永久删除本身不可撤销，此前对该任务的修改也不再撤销；
+/// 同一步骤中对其他任务的修改不受影响。
+pub(crate) fn forget_todo(conn: &Connection, todo_id: &str) -> Result<(), AppError> {
+    let mut stmt = conn.prepare("SELECT DISTINCT step_id FROM todo_events WHERE todo_id = ?1 AND step_id IS NOT NULL")?;
+    let step_ids = stmt
+        .query_map([todo_id], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    conn.execute("UPDATE todo_events SET step_id = NULL WHERE todo_id = ?1 AND step_id IS NOT NULL", [todo_id])?;
+    for step_id in &step_ids {
+        conn.execute(
+            "DELETE FROM undo_steps WHERE id = ?1 AND NOT EXISTS (SELECT 1 FROM todo_events WHERE step_id = ?1)",
+            [step_id],
+        )?;
+    }
+    Ok(())
+}
+
+struct StepEvent {
+    todo_id: String,
+    before: Option<Todo>,
+    after: Option<Todo>,
+}
+
+fn parse_snapshot(json: Option<String>) -> Option<Todo> {
+    json.and_then(|j| serde_json::from_str(&j).ok())
+}
+
+pub struct UndoRepository {
+    db: Arc<Database>,
+}
+
+impl UndoRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 当前的撤销栈与重做栈，最近的步骤在前
+    pub fn get_stack(&self) -> Result<UndoStack, AppError> {
+        self.db.with_conn(|conn| {
+            Ok(UndoStack {
+                undo: self.query_steps(conn, "undone_at IS NULL ORDER BY created_at DESC")?,
+                redo: self.query_steps(conn, "undone_at IS NOT NULL ORDER BY undone_at DESC")?,
+            })
+        })
+    }
+
+    /// 查询单个步骤，不存在（例如该轮 AI 对话没有修改任何任务）时返回 `None`
+    pub fn get_step(&self, step_id: &str) -> Result<Option<UndoStep>, AppError> {
+        self.db.with_conn(|conn| {
+            let step = conn
+                .query_row(&format!("{} WHERE s.id = ?1", STEP_SELECT), [step_id], row_to_step)
+                .optional()?;
+            Ok(step)
+        })
+    }
+
+    fn query_steps(&self, conn: &Connection, condition: &str) -> Result<Vec<UndoStep>, AppError> {
+        let mut stmt = conn.prepare(&format!("{} WHERE {}", STEP_SELECT, condition))?;
+        let steps = stmt.query_map([], row_to_step)?;
+
+        let mut result = Vec::new();
+        for step in steps {
+            result.push(step?);
+        }
+        Ok(result)
+    }
+
+    /// 撤销最近一步，返回被撤销的步骤；撤销栈为空时返回 `None`
+    pub fn undo(&self) -> Result<Option<UndoStep>, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let step_id: Option<String> = tx
+                .query_row(
+                    "SELECT id FROM undo_steps WHERE undone_at IS NULL ORDER BY created_at DESC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            let step_id = match step_id {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+
+            // 按相反顺序把每个任务恢复到修改前的状态
+            let events = Self::step_events(&tx, &step_id)?;
+            if events.iter().any(|event| event.before.is_some() && event.after.is_none()) {
+                return Err(AppError::InvalidArgument("Permanently deleted todos cannot be restored".into()));
+            }
+            for event in events.iter().rev() {
+                Self::apply(&tx, &event.todo_id, event.before.as_ref())?;
+            }
+
+            tx.execute(
+                "UPDATE undo_steps SET undone_at = ?1 WHERE id = ?2",
+                (Utc::now().to_rfc3339(), &step_id),
+            )?;
+
+            let step = tx.query_row(&format!("{} WHERE s.id = ?1", STEP_SELECT), [&step_id], row_to_step)?;
+            tx.commit()?;
+            Ok(Some(step))
+        })
+    }
+
+    /// 重做最近一次撤销的步骤；重做栈为空时返回 `None`
+    pub fn redo(&self) -> Result<Option<UndoStep>, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let step_id: Option<String> = tx
+                .query_row(
+                    "SELECT id FROM undo_steps WHERE undone_at IS NOT NULL ORDER BY undone_at DESC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            let step_id = match step_id {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+
+            let events = Self::step_events(&tx, &step_id)?;
+            for event in &events {
+                Self::apply(&tx, &event.todo_id, event.after.as_ref())?;
+            }
+
+            tx.execute("UPDATE undo_steps SET undone_at = NULL WHERE id = ?1", [&step_id])?;
+
+            let step = tx.query_row(&format!("{} WHERE s.id = ?1", STEP_SELECT), [&step_id], row_to_step)?;
+            tx.commit()?;
+            Ok(Some(step))
+        })
+    }
+
+    fn step_events(conn: &Connection, step_id: &str) -> Result<Vec<StepEvent>, AppError> {
+        let mut stmt = conn.prepare(
+            "SELECT todo_id, before, after FROM todo_events WHERE step_id = ?1 ORDER BY id ASC",
+        )?;
+
+        let events = stmt.query_map([step_id], |row| {
+            Ok(StepEvent {
+                todo_id: row.get(0)?,
+                before: parse_snapshot(row.get(1)?),
+                after: parse_snapshot(row.get(2)?),
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for event in events {
+            result.push(event?);
+        }
+        Ok(result)
+    }
+
+    /// 把任务写回到 `target` 快照，并记录一条不可撤销的历史事件
+    fn apply(conn: &Connection, todo_id: &str, target: Option<&Todo>) -> Result<(), AppError> {
+        let current = todo_repo::load_todo(conn, todo_id).ok();
+
+        let action = match (&current, target) {
+            (None, None) => return Ok(()),
+            (Some(cur), None) if cur.deleted_at.is_some() => return Ok(()),
+            (Some(_), None) => TodoAction::Deleted,
+            (None, Some(_)) => TodoAction::Created,
+            (Some(cur), Some(t)) => match (cur.deleted_at.is_some(), t.deleted_at.is_some()) {
+                (true, false) => TodoAction::Restored,
+                (false, true) => TodoAction::Deleted,
+                _ => TodoAction::Updated,
+            },
+        };
+
+        todo_repo::write_snapshot(conn, todo_id, target)?;
+        let after = todo_repo::load_todo(conn, todo_id)?;
+
+        history_repo::record_event(
+            conn,
+            &EventContext::untracked(&Actor::User),
+            action,
+            current.as_ref(),
+            Some(&after),
+        )
+    }
+}
+
+/// 步骤查询：标签取该步骤的第一条事件，并统计涉及的任务数
+const STEP_SELECT: &str = "SELECT s.id, s.actor, s.created_at, s.undone_at,
+    (SELECT COUNT(DISTINCT e.todo_id) FROM todo_events e WHERE e.step_id = s.id),
+    (SELECT e.action FROM todo_events e WHERE e.step_id = s.id ORDER BY e.id LIMIT 1),
+    (SELECT json_extract(COALESCE(e.after, e.before), '$.text') FROM todo_events e WHERE e.step_id = s.id ORDER BY e.id LIMIT 1)
+    FROM undo_steps s";
+
+fn row_to_step(row: &rusqlite::Row) -> rusqlite::Result<UndoStep> {
+    let actor: Actor = serde_json::from_str(&row.get::<_, String>(1)?).unwrap_or(Actor::User);
+    let todo_count: u32 = row.get(4)?;
+    let first_action = TodoAction::parse(&row.get::<_, Option<String>>(5)?.unwrap_or_default());
+    let first_text: String = row.get::<_, Option<String>>(6)?.unwrap_or_default();
+
+    let label = if todo_count <= 1 {
+        format!("{}{}任务「{}」", actor.display_name(), first_action.display_name(), first_text)
+    } else {
+        format!("{}修改了 {} 个任务", actor.display_name(), todo_count)
+    };
+
+    Ok(UndoStep {
+        id: row.get(0)?,
+        label,
+        actor,
+        todo_count,
+        created_at: row.get(2)?,
+        undone_at: row.get(3)?,
+    })
+}
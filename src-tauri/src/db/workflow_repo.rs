@@ -0,0 +1,409 @@
+use crate::db::{history_repo, tag_repo, todo_repo, Database};
+use crate::db::history_repo::EventContext;
+use crate::error::AppError;
+use crate::models::history::{Actor, TodoAction};
+use crate::models::workflow::{CreateWorkflowStateRequest, StateCategory, UpdateWorkflowStateRequest, WorkflowState};
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 相邻状态之间的位置间隔
+const STATE_POSITION_GAP: f64 = 1024.0;
+
+const STATE_COLUMNS: &str = "workflow_states.id, workflow_states.name, workflow_states.color, workflow_states.category,
+    workflow_states.position, workflow_states.wip_limit,
+    (SELECT COUNT(*) FROM todos WHERE todos.state_id = workflow_states.id AND todos.deleted_at IS NULL),
+    (SELECT json_group_array(to_state_id) FROM workflow_transitions
+        WHERE workflow_transitions.from_state_id = workflow_states.id),
+    workflow_states.created_at, workflow_states.updated_at";
+
+fn row_to_state(row: &rusqlite::Row) -> rusqlite::Result<WorkflowState> {
+    let transitions: Vec<String> = serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default();
+
+    Ok(WorkflowState {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        category: StateCategory::parse(&row.get::<_, String>(3)?),
+        position: row.get(4)?,
+        wip_limit: row.get(5)?,
+        todo_count: row.get(6)?,
+        transitions,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+/// 在给定连接上读取单个状态，供其他 repository 在事务内使用
+pub(crate) fn load_state(conn: &Connection, id: &str) -> Result<WorkflowState, AppError> {
+    conn.query_row(
+        &format!("SELECT {} FROM workflow_states WHERE workflow_states.id = ?1", STATE_COLUMNS),
+        [id],
+        row_to_state,
+    )
+    .map_err(|_| AppError::WorkflowStateNotFound(id.to_string()))
+}
+
+/// 全部状态，按看板中的顺序排列
+pub(crate) fn load_states(conn: &Connection) -> Result<Vec<WorkflowState>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workflow_states ORDER BY workflow_states.position",
+        STATE_COLUMNS
+    ))?;
+    let states = stmt.query_map([], row_to_state)?;
+    Ok(states.collect::<Result<_, _>>()?)
+}
+
+/// 类别中排在最前的状态：新建任务进入 open 类别的默认状态，只修改任务状态时进入对应类别的默认状态
+pub(crate) fn default_state(conn: &Connection, category: StateCategory) -> Result<WorkflowState, AppError> {
+    let id: Option<String> = conn
+        .query_row(
+            "SELECT id FROM workflow_states WHERE category = ?1 ORDER BY position LIMIT 1",
+            [category.as_str()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match id {
+        Some(id) => load_state(conn, &id),
+        None => Err(AppError::WorkflowStateNotFound(category.as_str().to_string())),
+    }
+}
+
+/// 检查任务能否从 `from` 移入 `to`：`from` 定义了允许的转换时 `to` 必须在其中，
+/// `to` 有 WIP 限制时其中的其他任务数必须低于限制
+pub(crate) fn check_move(conn: &Connection, from: Option<&str>, to: &WorkflowState, todo_id: &str) -> Result<(), AppError> {
+    if let Some(from) = from.and_then(|id| load_state(conn, id).ok()) {
+        if !from.transitions.is_empty() && !from.transitions.contains(&to.id) {
+            return Err(AppError::StateTransitionNotAllowed { from: from.name, to: to.name.clone() });
+        }
+    }
+
+    if let Some(limit) = to.wip_limit {
+        let count: u32 = conn.query_row(
+            "SELECT COUNT(*) FROM todos WHERE state_id = ?1 AND id != ?2 AND deleted_at IS NULL",
+            (&to.id, todo_id),
+            |row| row.get(0),
+        )?;
+        if count >= limit {
+            return Err(AppError::WipLimitReached { state: to.name.clone(), limit });
+        }
+    }
+    Ok(())
+}
+
+/// 空字符串表示清除该字段
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// WIP 限制为 0 表示不限制
+fn normalize_wip_limit(limit: Option<u32>) -> Option<u32> {
+    limit.filter(|l| *l > 0)
+}
+
+pub struct WorkflowRepository {
+    db: Arc<Database>,
+}
+
+impl WorkflowRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    pub fn get_all(&self) -> Result<Vec<WorkflowState>, AppError> {
+        self.db.with_conn(load_states)
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Result<WorkflowState, AppError> {
+        self.db.with_conn(|conn| load_state(conn, id))
+    }
+
+    /// 按名称（忽略大小写）查找状态，供 AI 通过名称指定状态
+    pub fn find_by_name(&self, name: &str) -> Result<WorkflowState, AppError> {
+        self.db.with_conn(|conn| {
+            let id: Option<String> = conn
+                .query_row("SELECT id FROM workflow_states WHERE name = ?1", [name.trim()], |row| row.get(0))
+                .optional()?;
+
+            match id {
+                Some(id) => load_state(conn, &id),
+                None => Err(AppError::WorkflowStateNotFound(name.to_string())),
+            }
+        })
+    }
+
+    /// 新建状态，排在最后
+    pub fn create(&self, request: CreateWorkflowStateRequest) -> Result<WorkflowState, AppError> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(AppError::InvalidArgument("State name cannot be empty".into()));
+        }
+        if let Some(ref color) = request.color {
+            tag_repo::validate_color(color)?;
+        }
+
+        self.db.with_conn(|conn| {
+            Self::ensure_name_available(conn, name, None)?;
+
+            let id = Uuid::new_v4().to_string();
+            let now = Utc::now().to_rfc3339();
+            conn.execute(
+                "INSERT INTO workflow_states (id, name, color, category, position, wip_limit, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(position), 0) + ?5 FROM workflow_states), ?6, ?7, ?7)",
+                (
+                    &id,
+                    name,
+                    request.color.as_deref().and_then(non_empty),
+                    request.category.as_str(),
+                    STATE_POSITION_GAP,
+                    normalize_wip_limit(request.wip_limit),
+                    &now,
+                ),
+            )?;
+
+            load_state(conn, &id)
+        })
+    }
+
+    pub fn update(&self, id: &str, request: UpdateWorkflowStateRequest) -> Result<WorkflowState, AppError> {
+        self.db.with_conn(|conn| {
+            let existing = load_state(conn, id)?;
+
+            let name = match request.name {
+                Some(ref name) => {
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(AppError::InvalidArgument("State name cannot be empty".into()));
+                    }
+                    Self::ensure_name_available(conn, name, Some(id))?;
+                    name.to_string()
+                }
+                None => existing.name,
+            };
+
+            let color = match request.color {
+                Some(ref color) => non_empty(color),
+                None => existing.color,
+            };
+            if let Some(ref color) = color {
+                tag_repo::validate_color(color)?;
+            }
+            let wip_limit = match request.wip_limit {
+                Some(limit) => normalize_wip_limit(Some(limit)),
+                None => existing.wip_limit,
+            };
+
+            conn.execute(
+                "UPDATE workflow_states SET name = ?1, color = ?2, wip_limit = ?3, updated_at = ?4 WHERE id = ?5",
+                (&name, &color, wip_limit, Utc::now().to_rfc3339(), id),
+            )?;
+
+            load_state(conn, id)
+        })
+    }
+
+    /// 删除状态，其中的任务（包括回收站中的）移入同一类别的默认状态，返回移动的任务数。
+    /// 每个类别至少要保留一个状态。其他状态允许转换到被删除状态的，改为允许转换到替代状态；
+    /// 替代状态自己只允许转换到被删除状态时拒绝删除。
+    ///
+    /// 移入替代状态不检查它的 WIP 限制，以免删除列因为限制而失败；
+    /// 超出限制时看板上该列标记为 `over_limit`，之后移入的任务仍受限制。
+    pub fn delete(&self, id: &str, actor: &Actor) -> Result<u32, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let state = load_state(&tx, id)?;
+
+            let replacement: Option<String> = tx
+                .query_row(
+                    "SELECT id FROM workflow_states WHERE category = ?1 AND id != ?2 ORDER BY position LIMIT 1",
+                    (state.category.as_str(), id),
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let replacement = replacement.ok_or_else(|| {
+                AppError::InvalidArgument(format!(
+                    "Cannot delete '{}', the last {} state",
+                    state.name,
+                    state.category.as_str()
+                ))
+            })?;
+
+            // 转换规则随状态级联删除，只允许转换到被删除状态的状态会变成不受限制，因此改为替代状态。
+            // 替代状态自己只能转换到被删除状态时无法改写（不保存到自身的转换），拒绝删除
+            let replacement_state = load_state(&tx, &replacement)?;
+            if replacement_state.transitions == [id.to_string()] {
+                return Err(AppError::InvalidArgument(format!(
+                    "Cannot delete '{}', it is the only state '{}' may move to",
+                    state.name, replacement_state.name
+                )));
+            }
+            tx.execute(
+                "INSERT OR IGNORE INTO workflow_transitions (from_state_id, to_state_id)
+                 SELECT from_state_id, ?1 FROM workflow_transitions
+                 WHERE to_state_id = ?2 AND from_state_id NOT IN (?1, ?2)",
+                (&replacement, id),
+            )?;
+
+            let todo_ids: Vec<String> = {
+                let mut stmt = tx.prepare("SELECT id FROM todos WHERE state_id = ?1")?;
+                let rows = stmt.query_map([id], |row| row.get(0))?;
+                rows.collect::<Result<_, _>>()?
+            };
+
+            let now = Utc::now().to_rfc3339();
+            let ctx = EventContext::new(actor);
+            for todo_id in &todo_ids {
+                let before = todo_repo::load_todo(&tx, todo_id)?;
+                tx.execute(
+                    "UPDATE todos SET state_id = ?1, updated_at = ?2 WHERE id = ?3",
+                    (&replacement, &now, todo_id),
+                )?;
+                let after = todo_repo::load_todo(&tx, todo_id)?;
+                history_repo::record_event(&tx, &ctx, TodoAction::Updated, Some(&before), Some(&after))?;
+            }
+
+            tx.execute("DELETE FROM workflow_states WHERE id = ?1", [id])?;
+            tx.commit()?;
+            Ok(todo_ids.len() as u32)
+        })
+    }
+
+    /// 按 `ids` 的顺序排列状态，未列出的状态保持原有顺序排在后面
+    pub fn reorder(&self, ids: &[String]) -> Result<Vec<WorkflowState>, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let states = load_states(&tx)?;
+            for id in ids {
+                if !states.iter().any(|s| &s.id == id) {
+                    return Err(AppError::WorkflowStateNotFound(id.clone()));
+                }
+            }
+
+            let rest = states.iter().map(|s| &s.id).filter(|id| !ids.contains(id));
+            for (i, id) in ids.iter().chain(rest).enumerate() {
+                tx.execute(
+                    "UPDATE workflow_states SET position = ?1 WHERE id = ?2",
+                    (STATE_POSITION_GAP * (i + 1) as f64, id),
+                )?;
+            }
+
+            let states = load_states(&tx)?;
+            tx.commit()?;
+            Ok(states)
+        })
+    }
+
+    /// 设置从该状态允许变为的状态，空列表表示不限制
+    pub fn set_transitions(&self, id: &str, to_ids: &[String]) -> Result<WorkflowState, AppError> {
+        self.db.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            load_state(&tx, id)?;
+
+            tx.execute("DELETE FROM workflow_transitions WHERE from_state_id = ?1", [id])?;
+            for to_id in to_ids.iter().filter(|to_id| *to_id != id) {
+                load_state(&tx, to_id)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO workflow_transitions (from_state_id, to_state_id) VALUES (?1, ?2)",
+                    (id, to_id),
+                )?;
+            }
+            tx.execute(
+                "UPDATE workflow_states SET updated_at = ?1 WHERE id = ?2",
+                (Utc::now().to_rfc3339(), id),
+            )?;
+
+            let state = load_state(&tx, id)?;
+            tx.commit()?;
+            Ok(state)
+        })
+    }
+
+    fn ensure_name_available(conn: &Connection, name: &str, except_id: Option<&str>) -> Result<(), AppError> {
+        let conflict: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM workflow_states WHERE name = ?1 AND id IS NOT ?2)",
+            (name, except_id),
+            |row| row.get(0),
+        )?;
+
+        if conflict {
+            return Err(AppError::InvalidArgument(format!("State '{}' already exists", name)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TestDb, TodoRepository};
+    use crate::models::todo::{CreateTodoRequest, UpdateTodoRequest};
+
+    fn create_state(repo: &WorkflowRepository, name: &str, category: StateCategory) -> WorkflowState {
+        repo.create(CreateWorkflowStateRequest { name: name.into(), color: None, category, wip_limit: None }).unwrap()
+    }
+
+    fn create_in_state(todos: &TodoRepository, text: &str, state_id: &str) -> String {
+        let todo = todos.create(CreateTodoRequest { text: text.into(), ..Default::default() }, &Actor::User).unwrap();
+        let request = UpdateTodoRequest { state_id: Some(state_id.into()), ..Default::default() };
+        todos.update(&todo.id, request, &Actor::User).unwrap().id
+    }
+
+    #[test]
+    fn delete_redirects_transitions_to_replacement() {
+        let test = TestDb::new();
+        let repo = WorkflowRepository::new(test.db.clone());
+        let review = create_state(&repo, "Review", StateCategory::Active);
+        repo.set_transitions("pending", std::slice::from_ref(&review.id)).unwrap();
+        repo.set_transitions("completed", &["pending".to_string(), review.id.clone()]).unwrap();
+
+        repo.delete(&review.id, &Actor::User).unwrap();
+
+        // 原来只能进入 Review 的状态改为只能进入替代状态，而不是变成不受限制
+        assert_eq!(repo.get_by_id("pending").unwrap().transitions, vec!["in_progress".to_string()]);
+        let mut completed = repo.get_by_id("completed").unwrap().transitions;
+        completed.sort();
+        assert_eq!(completed, vec!["in_progress".to_string(), "pending".to_string()]);
+    }
+
+    #[test]
+    fn delete_is_rejected_when_replacement_could_only_move_to_it() {
+        let test = TestDb::new();
+        let repo = WorkflowRepository::new(test.db.clone());
+        let review = create_state(&repo, "Review", StateCategory::Active);
+        repo.set_transitions("in_progress", std::slice::from_ref(&review.id)).unwrap();
+
+        assert!(matches!(repo.delete(&review.id, &Actor::User), Err(AppError::InvalidArgument(_))));
+        assert!(repo.get_by_id(&review.id).is_ok());
+        assert_eq!(repo.get_by_id("in_progress").unwrap().transitions, vec![review.id.clone()]);
+    }
+
+    #[test]
+    fn delete_moves_todos_past_the_replacement_wip_limit() {
+        let test = TestDb::new();
+        let repo = WorkflowRepository::new(test.db.clone());
+        let todos = TodoRepository::new(test.db.clone());
+        let review = create_state(&repo, "Review", StateCategory::Active);
+        create_in_state(&todos, "doing", "in_progress");
+        let reviewing = create_in_state(&todos, "reviewing", &review.id);
+        repo.update("in_progress", UpdateWorkflowStateRequest { wip_limit: Some(1), ..Default::default() }).unwrap();
+
+        assert_eq!(repo.delete(&review.id, &Actor::User).unwrap(), 1);
+
+        assert_eq!(todos.get_by_id(&reviewing).unwrap().state_id.as_deref(), Some("in_progress"));
+        let board = todos.get_board(None).unwrap();
+        let column = board.columns.iter().find(|c| c.state.id == "in_progress").unwrap();
+        assert_eq!(column.todos.len(), 2);
+        assert!(column.over_limit);
+        // 之后移入的任务仍受限制
+        let request = UpdateTodoRequest { state_id: Some("in_progress".into()), ..Default::default() };
+        let pending = todos.create(CreateTodoRequest { text: "next".into(), ..Default::default() }, &Actor::User).unwrap();
+        assert!(matches!(todos.update(&pending.id, request, &Actor::User), Err(AppError::WipLimitReached { .. })));
+    }
+}
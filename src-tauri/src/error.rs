@@ -1,6 +1,8 @@
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::models::todo::TodoStatus;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -30,6 +32,54 @@ pub enum AppError {
     #[error("Todo not found: {0}")]
     TodoNotFound(String),
 
+    #[error("Tag not found: {0}")]
+    TagNotFound(String),
+
+    #[error("List not found: {0}")]
+    ListNotFound(String),
+
+    #[error("Reminder not found: {0}")]
+    ReminderNotFound(String),
+
+    #[error("Checklist item not found: {0}")]
+    ChecklistItemNotFound(String),
+
+    #[error("Attachment not found: {0}")]
+    AttachmentNotFound(String),
+
+    #[error("Time entry not found: {0}")]
+    TimeEntryNotFound(String),
+
+    #[error("Workflow state not found: {0}")]
+    WorkflowStateNotFound(String),
+
+    #[error("Cannot move from '{from}' to '{to}'")]
+    StateTransitionNotAllowed { from: String, to: String },
+
+    #[error("'{state}' already has {limit} todos (WIP limit)")]
+    WipLimitReached { state: String, limit: u32 },
+
+    #[error("No focus session is running")]
+    FocusNotRunning,
+
+    #[error("Failed to open file: {0}")]
+    OpenFailed(String),
+
+    #[error("Database schema version {found} is newer than supported version {supported}, please upgrade the app")]
+    SchemaTooNew { found: u32, supported: u32 },
+
+    #[error("Cannot change status from {} to {}", from.as_str(), to.as_str())]
+    InvalidTransition { from: TodoStatus, to: TodoStatus },
+
+    #[error("Adding dependency {todo_id} -> {depends_on_id} would create a cycle")]
+    DependencyCycle { todo_id: String, depends_on_id: String },
+
+    #[error("Backup not found: {0}")]
+    BackupNotFound(String),
+
+    #[error("Backup failed integrity check: {0}")]
+    BackupCorrupted(String),
+
     #[error("Tauri error: {0}")]
     Tauri(#[from] tauri::Error),
 
@@ -62,6 +112,22 @@ impl AppError {
             Self::InvalidArgument(_) => "INVALID_ARGUMENT",
             Self::TooManyFunctionCalls => "TOO_MANY_FUNCTION_CALLS",
             Self::TodoNotFound(_) => "TODO_NOT_FOUND",
+            Self::TagNotFound(_) => "TAG_NOT_FOUND",
+            Self::ListNotFound(_) => "LIST_NOT_FOUND",
+            Self::ReminderNotFound(_) => "REMINDER_NOT_FOUND",
+            Self::ChecklistItemNotFound(_) => "CHECKLIST_ITEM_NOT_FOUND",
+            Self::AttachmentNotFound(_) => "ATTACHMENT_NOT_FOUND",
+            Self::TimeEntryNotFound(_) => "TIME_ENTRY_NOT_FOUND",
+            Self::WorkflowStateNotFound(_) => "WORKFLOW_STATE_NOT_FOUND",
+            Self::StateTransitionNotAllowed { .. } => "STATE_TRANSITION_NOT_ALLOWED",
+            Self::WipLimitReached { .. } => "WIP_LIMIT_REACHED",
+            Self::FocusNotRunning => "FOCUS_NOT_RUNNING",
+            Self::OpenFailed(_) => "OPEN_FAILED",
+            Self::SchemaTooNew { .. } => "SCHEMA_TOO_NEW",
+            Self::InvalidTransition { .. } => "INVALID_TRANSITION",
+            Self::DependencyCycle { .. } => "DEPENDENCY_CYCLE",
+            Self::BackupNotFound(_) => "BACKUP_NOT_FOUND",
+            Self::BackupCorrupted(_) => "BACKUP_CORRUPTED",
             Self::Tauri(_) => "TAURI_ERROR",
             Self::Io(_) => "IO_ERROR",
         }
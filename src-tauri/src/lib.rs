@@ -30,6 +30,18 @@ pub fn run() {
             let state = AppState::new(db_path.to_str().unwrap())
                 .expect("Failed to initialize app state");
 
+            // 启动后台维护任务
+            services::maintenance::spawn_trash_purge(
+                state.todo_repo.clone(),
+                state.settings_repo.clone(),
+            );
+            services::maintenance::spawn_scheduled_backup(state.backup_service.clone());
+            services::maintenance::spawn_reminder_scheduler(
+                state.reminder_repo.clone(),
+                app.handle().clone(),
+            );
+            services::maintenance::spawn_attachment_gc(state.attachment_service.clone());
+
             // 注册状态
             app.manage(state);
 
@@ -41,17 +53,97 @@ pub fn run() {
             // Todo commands
             commands::todo::create_todo,
             commands::todo::get_todos,
+            commands::todo::search_todos,
             commands::todo::get_todo,
             commands::todo::update_todo,
             commands::todo::delete_todo,
             commands::todo::batch_create_todos,
+            commands::todo::bulk_update_todos,
+            commands::todo::bulk_delete_todos,
+            commands::todo::bulk_set_status,
+            commands::todo::reorder_todos,
+            commands::todo::get_todo_children,
+            commands::todo::get_todo_ancestors,
+            commands::todo::get_todo_tree,
+            commands::todo::set_todo_parent,
+            commands::todo::add_todo_dependency,
+            commands::todo::remove_todo_dependency,
+            commands::todo::add_checklist_item,
+            commands::todo::update_checklist_item,
+            commands::todo::toggle_checklist_item,
+            commands::todo::delete_checklist_item,
+            commands::todo::skip_todo_occurrence,
             commands::todo::delete_completed_todos,
+            commands::todo::get_trash,
+            commands::todo::restore_todo,
+            commands::todo::empty_trash,
+            commands::todo::get_todo_history,
             commands::todo::get_todo_statistics,
+            commands::todo::parse_date_expression,
+            // Tag commands
+            commands::tag::get_tags,
+            commands::tag::rename_tag,
+            commands::tag::merge_tags,
+            commands::tag::set_tag_color,
+            commands::tag::delete_tag,
+            // List commands
+            commands::list::get_lists,
+            commands::list::create_list,
+            commands::list::update_list,
+            commands::list::delete_list,
+            // Workflow commands
+            commands::workflow::get_workflow_states,
+            commands::workflow::create_workflow_state,
+            commands::workflow::update_workflow_state,
+            commands::workflow::delete_workflow_state,
+            commands::workflow::reorder_workflow_states,
+            commands::workflow::set_workflow_transitions,
+            commands::workflow::get_board,
+            // Reminder commands
+            commands::reminder::get_todo_reminders,
+            commands::reminder::get_upcoming_reminders,
+            commands::reminder::add_todo_reminder,
+            commands::reminder::delete_reminder,
+            commands::reminder::snooze_reminder,
+            // Attachment commands
+            commands::attachment::add_attachment,
+            commands::attachment::get_todo_attachments,
+            commands::attachment::remove_attachment,
+            commands::attachment::open_attachment,
+            // Time tracking commands
+            commands::time_entry::start_timer,
+            commands::time_entry::stop_timer,
+            commands::time_entry::get_running_timer,
+            commands::time_entry::get_todo_time_entries,
+            commands::time_entry::add_time_entry,
+            commands::time_entry::update_time_entry,
+            commands::time_entry::delete_time_entry,
+            commands::time_entry::get_time_report,
+            // Focus commands
+            commands::focus::start_focus,
+            commands::focus::pause_focus,
+            commands::focus::resume_focus,
+            commands::focus::stop_focus,
+            commands::focus::log_focus_interruption,
+            commands::focus::get_focus_status,
+            commands::focus::get_focus_sessions,
+            // Report commands
+            commands::report::get_estimate_report,
+            commands::report::get_statistics_report,
+            // Undo commands
+            commands::undo::get_undo_stack,
+            commands::undo::undo,
+            commands::undo::redo,
             // Settings commands
             commands::settings::get_settings,
             commands::settings::save_settings,
             commands::settings::test_api_connection,
             commands::settings::test_function_calling,
+            // Database commands
+            commands::database::get_schema_status,
+            commands::database::create_backup,
+            commands::database::list_backups,
+            commands::database::restore_backup,
             // AI commands
             commands::ai::ai_chat,
             commands::ai::ai_chat_stream,
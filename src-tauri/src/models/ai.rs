@@ -113,6 +113,12 @@ pub struct PartialFunctionCall {
 pub struct AiChatRequest {
     pub message: String,
     pub history: Option<Vec<ChatMessage>>,
+    /// 前端会话 ID，用于在任务历史中关联 AI 的修改；未提供时每轮对话生成一个新 ID
+    #[serde(default, alias = "conversationId")]
+    pub conversation_id: Option<String>,
+    /// 用户正在查看的列表，系统提示词中只列出该列表的任务
+    #[serde(default, alias = "listId")]
+    pub list_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -121,6 +127,8 @@ pub struct AiChatResponse {
     pub message: String,
     pub function_results: Option<Vec<FunctionResult>>,
     pub updated_todos: Option<Vec<super::todo::Todo>>,
+    /// 本轮对话产生的撤销步骤，没有修改任何任务时为 `None`
+    pub undo_step: Option<super::undo::UndoStep>,
     pub warnings: Option<Vec<String>>,
 }
 
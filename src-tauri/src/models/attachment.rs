@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+/// 任务的附件。文件按内容的 SHA-256 保存在附件目录中，相同内容只保存一份。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    pub todo_id: String,
+    /// 添加时的原始文件名
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    /// 文件内容的 SHA-256（小写十六进制）
+    pub hash: String,
+    pub created_at: String,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// 备份的来源，决定轮换时保留的数量
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// 用户手动创建，不会被自动轮换删除
+    Manual,
+    Daily,
+    Weekly,
+    /// 恢复备份前自动保存的当前数据
+    PreRestore,
+}
+
+impl BackupKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupKind::Manual => "manual",
+            BackupKind::Daily => "daily",
+            BackupKind::Weekly => "weekly",
+            BackupKind::PreRestore => "pre_restore",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "manual" => Some(BackupKind::Manual),
+            "daily" => Some(BackupKind::Daily),
+            "weekly" => Some(BackupKind::Weekly),
+            "pre_restore" => Some(BackupKind::PreRestore),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    /// 备份目录中的文件名，恢复时使用
+    pub file_name: String,
+    pub kind: BackupKind,
+    pub created_at: String,
+    pub size_bytes: u64,
+}
@@ -0,0 +1,894 @@
+use chrono::{DateTime, Datelike, Days, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use crate::error::AppError;
+
+/// 中文或阿拉伯数字，例如 `3`、`三`、`十二`、`两`
+const CN_NUMBER: &str = "[0-9零〇一二两三四五六七八九十]+";
+
+const CN_PERIOD: &str = "凌晨|早上|早晨|上午|中午|下午|傍晚|晚上|夜里";
+
+/// 英文日期前的介词，与日期一起从任务内容中去掉
+const EN_PREFIX: &str = r"\b(?:(?:on|by|due|before|until)\s+)?";
+
+const EN_NUMBER: &str = r"\d+|an?|one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve";
+
+const EN_UNIT: &str = "minutes?|mins?|hours?|hrs?|days?|weeks?|months?|years?";
+
+const EN_MONTH: &str = "january|february|march|april|may|june|july|august|september|october|november|december\
+|sept|jan|feb|mar|apr|jun|jul|aug|sep|oct|nov|dec";
+
+const EN_WEEKDAY: &str = "monday|tuesday|wednesday|thursday|friday|saturday|sunday";
+
+/// 缩写容易与普通单词混淆（sun、sat），只在 next/this 之后识别
+const EN_WEEKDAY_SHORT: &str = "mon|tues|tue|wed|thurs|thu|fri|sat|sun";
+
+/// 从文本中识别出的截止日期
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedDate {
+    /// 可以直接保存为 `Todo::due_date`：全天为 `YYYY-MM-DD`，带时间为带时区偏移的 RFC 3339
+    pub due_date: String,
+    pub all_day: bool,
+    /// 输入中被识别为日期、时间的原文
+    pub matched: String,
+    /// 去掉日期、时间后剩余的文本
+    pub remainder: String,
+}
+
+/// 识别出的日期部分
+struct DatePart {
+    date: NaiveDate,
+    /// 没有写具体钟点时使用的时间，例如"今晚"为 20:00
+    default_time: Option<NaiveTime>,
+    /// 已经是精确的时刻（"2小时后"、带时间的 ISO 日期），不再与其他钟点组合
+    exact: bool,
+    /// 带时区的 ISO 时间，需要换算到用户时区
+    instant: Option<DateTime<FixedOffset>>,
+    /// "今晚"、"tonight" 之后不带上午/下午的钟点按晚上理解
+    evening: bool,
+}
+
+impl DatePart {
+    fn day(date: NaiveDate) -> Self {
+        Self { date, default_time: None, exact: false, instant: None, evening: false }
+    }
+
+    fn at(datetime: NaiveDateTime) -> Self {
+        Self { date: datetime.date(), default_time: Some(datetime.time()), exact: true, instant: None, evening: false }
+    }
+}
+
+/// 识别出的钟点
+#[derive(Clone, Copy)]
+struct TimePart {
+    time: NaiveTime,
+    /// 是否写明了上午/下午（am/pm）
+    has_period: bool,
+    /// 钟点在第二天，例如"晚上12点半"
+    next_day: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+type Handler<T> = fn(&Captures, &NaiveDateTime) -> Option<T>;
+
+struct Rule<T> {
+    regex: Regex,
+    handler: Handler<T>,
+}
+
+/// 忽略大小写；`\b` 按 ASCII 单词边界匹配，使"明天5pm"这样中英文相连的写法也能识别
+fn rule<T>(pattern: &str, handler: Handler<T>) -> Rule<T> {
+    let pattern = format!("(?i){}", pattern.replace(r"\b", r"(?-u:\b)"));
+    Rule {
+        regex: Regex::new(&pattern).expect("invalid date expression pattern"),
+        handler,
+    }
+}
+
+fn hm(hour: u32, minute: u32) -> Option<NaiveTime> {
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn cn_digit(c: char) -> Option<u32> {
+    match c {
+        '零' | '〇' => Some(0),
+        '一' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => c.to_digit(10),
+    }
+}
+
+/// 解析阿拉伯数字或不超过 99 的中文数字
+fn number(s: &str) -> Option<u32> {
+    if let Ok(n) = s.parse() {
+        return Some(n);
+    }
+
+    let single = |part: &str, empty: u32| {
+        let mut chars = part.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => Some(empty),
+            (Some(c), None) => cn_digit(c),
+            _ => None,
+        }
+    };
+
+    match s.split_once('十') {
+        Some((tens, units)) => Some(single(tens, 1)? * 10 + single(units, 0)?),
+        None => s.chars().try_fold(0u32, |acc, c| acc.checked_mul(10)?.checked_add(cn_digit(c)?)),
+    }
+}
+
+fn en_number(s: &str) -> Option<u32> {
+    let s = s.to_lowercase();
+    let words = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve"];
+    match s.as_str() {
+        "a" | "an" => Some(1),
+        _ => match words.iter().position(|w| *w == s) {
+            Some(index) => Some(index as u32 + 1),
+            None => s.parse().ok(),
+        },
+    }
+}
+
+fn cn_unit(s: &str) -> Option<Unit> {
+    match s {
+        "分钟" => Some(Unit::Minute),
+        "小时" | "钟头" => Some(Unit::Hour),
+        "天" | "日" => Some(Unit::Day),
+        "周" | "星期" | "礼拜" => Some(Unit::Week),
+        "月" => Some(Unit::Month),
+        "年" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+fn en_unit(s: &str) -> Option<Unit> {
+    let s = s.to_lowercase();
+    if s.starts_with("mi") {
+        Some(Unit::Minute)
+    } else if s.starts_with('h') {
+        Some(Unit::Hour)
+    } else if s.starts_with('d') {
+        Some(Unit::Day)
+    } else if s.starts_with('w') {
+        Some(Unit::Week)
+    } else if s.starts_with("mo") {
+        Some(Unit::Month)
+    } else if s.starts_with('y') {
+        Some(Unit::Year)
+    } else {
+        None
+    }
+}
+
+fn cn_weekday(s: &str) -> Option<Weekday> {
+    match s {
+        "一" | "1" => Some(Weekday::Mon),
+        "二" | "2" => Some(Weekday::Tue),
+        "三" | "3" => Some(Weekday::Wed),
+        "四" | "4" => Some(Weekday::Thu),
+        "五" | "5" => Some(Weekday::Fri),
+        "六" | "6" => Some(Weekday::Sat),
+        "日" | "天" | "7" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn en_weekday(s: &str) -> Option<Weekday> {
+    match s.to_lowercase().get(..3)? {
+        "mon" => Some(Weekday::Mon),
+        "tue" => Some(Weekday::Tue),
+        "wed" => Some(Weekday::Wed),
+        "thu" => Some(Weekday::Thu),
+        "fri" => Some(Weekday::Fri),
+        "sat" => Some(Weekday::Sat),
+        "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn en_month(s: &str) -> Option<u32> {
+    let months = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    let prefix = s.to_lowercase();
+    months.iter().position(|m| *m == prefix.get(..3).unwrap_or_default()).map(|i| i as u32 + 1)
+}
+
+/// `date` 所在周（周一开始）之后第 `weeks` 周的 `weekday`
+fn weekday_of_week(date: NaiveDate, weeks: u64, weekday: Weekday) -> Option<NaiveDate> {
+    let monday = date.checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64))?;
+    monday.checked_add_days(Days::new(weeks * 7 + weekday.num_days_from_monday() as u64))
+}
+
+/// 今天或之后第一个 `weekday`
+fn upcoming_weekday(today: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    today.checked_add_days(Days::new(days as u64))
+}
+
+/// `date` 之后第 `months` 个月的 1 日
+fn first_of_month(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    date.with_day(1)?.checked_add_months(Months::new(months))
+}
+
+fn last_of_month(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    first_of_month(date, months + 1)?.pred_opt()
+}
+
+/// 没有写年份的月日取今天或之后最近的一个
+fn month_day(today: NaiveDate, year: Option<&str>, month: u32, day: u32) -> Option<NaiveDate> {
+    if let Some(year) = year {
+        return NaiveDate::from_ymd_opt(year.parse().ok()?, month, day);
+    }
+    (0..=4)
+        .filter_map(|offset| NaiveDate::from_ymd_opt(today.year() + offset, month, day))
+        .find(|date| *date >= today)
+}
+
+/// 从 `now` 起经过 `n` 个 `unit`。按分钟、小时计算的结果是精确时刻，其余为全天日期。
+fn relative(now: &NaiveDateTime, n: u32, unit: Unit) -> Option<DatePart> {
+    let today = now.date();
+    match unit {
+        Unit::Minute | Unit::Hour => {
+            let minutes = if let Unit::Hour = unit { n as i64 * 60 } else { n as i64 };
+            let at = now.checked_add_signed(Duration::minutes(minutes))?;
+            Some(DatePart::at(at.date().and_time(hm(at.hour(), at.minute())?)))
+        }
+        Unit::Day => today.checked_add_days(Days::new(n as u64)).map(DatePart::day),
+        Unit::Week => today.checked_add_days(Days::new(n as u64 * 7)).map(DatePart::day),
+        Unit::Month => today.checked_add_months(Months::new(n)).map(DatePart::day),
+        Unit::Year => today.checked_add_months(Months::new(n.checked_mul(12)?)).map(DatePart::day),
+    }
+}
+
+/// "半小时后"、"in half an hour" 等
+fn relative_half(now: &NaiveDateTime, unit: Unit) -> Option<DatePart> {
+    match unit {
+        Unit::Hour => relative(now, 30, Unit::Minute),
+        Unit::Day => relative(now, 12, Unit::Hour),
+        Unit::Month => relative(now, 15, Unit::Day),
+        Unit::Year => relative(now, 6, Unit::Month),
+        Unit::Minute | Unit::Week => None,
+    }
+}
+
+/// 晚上 12 点：整点与 midnight 一致取当天 23:59，带分钟时为第二天凌晨
+fn late_night(minute: u32, has_period: bool) -> Option<TimePart> {
+    match minute {
+        0 => Some(TimePart { time: hm(23, 59)?, has_period, next_day: false }),
+        _ => Some(TimePart { time: hm(0, minute)?, has_period, next_day: true }),
+    }
+}
+
+/// 英文中不带 am/pm 的 "at 3" 通常指下午，1 点到 7 点按下午理解
+fn bare_hour(hour: u32) -> u32 {
+    if (1..=7).contains(&hour) { hour + 12 } else { hour }
+}
+
+/// 按上午/下午（am/pm）换算为 24 小时制
+fn clock(period: Option<&str>, hour: u32, minute: u32) -> Option<TimePart> {
+    let period = period.map(str::to_lowercase);
+    if matches!(period.as_deref(), Some("晚上" | "夜里")) && hour == 12 {
+        return late_night(minute, true);
+    }
+    let hour = match period.as_deref() {
+        Some("下午" | "傍晚" | "晚上" | "夜里" | "pm" | "p.m.") if hour < 12 => hour + 12,
+        Some("中午") if hour < 11 => hour + 12,
+        Some("凌晨" | "早上" | "早晨" | "上午" | "am" | "a.m.") if hour == 12 => 0,
+        _ => hour,
+    };
+    Some(TimePart { time: hm(hour, minute)?, has_period: period.is_some(), next_day: false })
+}
+
+/// 只写了时间段（"明天下午"、"tomorrow morning"）时使用的钟点
+fn period_time(period: &str) -> Option<TimePart> {
+    let time = match period.to_lowercase().as_str() {
+        "凌晨" => hm(6, 0),
+        "早上" | "早晨" | "上午" | "morning" => hm(9, 0),
+        "中午" | "noon" => hm(12, 0),
+        "下午" | "afternoon" => hm(15, 0),
+        "傍晚" | "evening" => hm(18, 0),
+        "晚上" | "夜里" | "night" => hm(20, 0),
+        "midnight" => hm(23, 59),
+        _ => None,
+    }?;
+    Some(TimePart { time, has_period: true, next_day: false })
+}
+
+fn date_rules() -> &'static [Rule<DatePart>] {
+    static RULES: OnceLock<Vec<Rule<DatePart>>> = OnceLock::new();
+
+    RULES.get_or_init(|| {
+        vec![
+            rule("(?P<w>大后天|后天|明天|明日|今天|今日|今晚|明晚|明早)", |c, now| {
+                let (days, default_time, evening) = match &c["w"] {
+                    "今天" | "今日" => (0, None, false),
+                    "明天" | "明日" => (1, None, false),
+                    "后天" => (2, None, false),
+                    "大后天" => (3, None, false),
+                    "今晚" => (0, hm(20, 0), true),
+                    "明晚" => (1, hm(20, 0), true),
+                    "明早" => (1, hm(9, 0), false),
+                    _ => return None,
+                };
+                let date = now.date().checked_add_days(Days::new(days))?;
+                Some(DatePart { default_time, evening, ..DatePart::day(date) })
+            }),
+            rule("(?P<p>下下|下|这|本)?个?(?:周|星期|礼拜)(?P<d>[一二三四五六日天1-7])", |c, now| {
+                let weekday = cn_weekday(&c["d"])?;
+                let date = match c.name("p").map(|p| p.as_str()) {
+                    None => upcoming_weekday(now.date(), weekday),
+                    Some("下") => weekday_of_week(now.date(), 1, weekday),
+                    Some("下下") => weekday_of_week(now.date(), 2, weekday),
+                    Some(_) => weekday_of_week(now.date(), 0, weekday),
+                };
+                date.map(DatePart::day)
+            }),
+            rule("(?P<p>下下|下|这|本)?个?周末", |c, now| {
+                let weeks = match c.name("p").map(|p| p.as_str()) {
+                    Some("下") => 1,
+                    Some("下下") => 2,
+                    _ if now.date().weekday() == Weekday::Sun => return Some(DatePart::day(now.date())),
+                    _ => 0,
+                };
+                weekday_of_week(now.date(), weeks, Weekday::Sat).map(DatePart::day)
+            }),
+            rule("(?P<p>下下|下)个?(?:周|星期|礼拜)", |c, now| {
+                let weeks = if &c["p"] == "下下" { 2 } else { 1 };
+                weekday_of_week(now.date(), weeks, Weekday::Mon).map(DatePart::day)
+            }),
+            rule("(?P<p>下|这|本)?个?月(?P<k>底|末|初)", |c, now| {
+                let months = if c.name("p").map(|p| p.as_str()) == Some("下") { 1 } else { 0 };
+                let date = match &c["k"] {
+                    "初" => first_of_month(now.date(), months)?,
+                    _ => last_of_month(now.date(), months)?,
+                };
+                // 单说"月初"指下一个月初
+                if c.name("p").is_none() && date < now.date() {
+                    return first_of_month(now.date(), 1).map(DatePart::day);
+                }
+                Some(DatePart::day(date))
+            }),
+            rule(&format!("(?P<p>下|这|本)个?月(?P<d>{})(?:号|日)", CN_NUMBER), |c, now| {
+                let months = if &c["p"] == "下" { 1 } else { 0 };
+                first_of_month(now.date(), months)?.with_day(number(&c["d"])?).map(DatePart::day)
+            }),
+            rule("下个?月", |_, now| first_of_month(now.date(), 1).map(DatePart::day)),
+            rule("(?P<p>今|明)?年(?P<k>底|末|初)", |c, now| {
+                let year = now.date().year() + if c.name("p").map(|p| p.as_str()) == Some("明") { 1 } else { 0 };
+                let date = match &c["k"] {
+                    "初" => NaiveDate::from_ymd_opt(year, 1, 1)?,
+                    _ => NaiveDate::from_ymd_opt(year, 12, 31)?,
+                };
+                if c.name("p").is_none() && date < now.date() {
+                    return NaiveDate::from_ymd_opt(year + 1, 1, 1).map(DatePart::day);
+                }
+                Some(DatePart::day(date))
+            }),
+            rule(&format!(r"(?:(?P<y>\d{{4}})年)?(?P<m>{})月(?P<d>{})(?:日|号)?", CN_NUMBER, CN_NUMBER), |c, now| {
+                let year = c.name("y").map(|y| y.as_str());
+                month_day(now.date(), year, number(&c["m"])?, number(&c["d"])?).map(DatePart::day)
+            }),
+            rule(&format!(r"(?P<d>{})号|\b(?P<d2>\d{{1,2}})日", CN_NUMBER), |c, now| {
+                let day = number(c.name("d").or_else(|| c.name("d2"))?.as_str())?;
+                (0..=2)
+                    .filter_map(|months| first_of_month(now.date(), months)?.with_day(day))
+                    .find(|date| *date >= now.date())
+                    .map(DatePart::day)
+            }),
+            rule(&format!("(?P<n>{}|半)个?(?P<u>分钟|小时|钟头|天|日|周|星期|礼拜|月|年)(?:后|之后|以后)", CN_NUMBER), |c, now| {
+                let unit = cn_unit(&c["u"])?;
+                match &c["n"] {
+                    "半" => relative_half(now, unit),
+                    n => relative(now, number(n)?, unit),
+                }
+            }),
+            rule(&format!(r"{}\b(?P<w>day after tomorrow|today|tonight|tomorrow|tmrw|tmr)\b", EN_PREFIX), |c, now| {
+                let (days, default_time, evening) = match c["w"].to_lowercase().as_str() {
+                    "today" => (0, None, false),
+                    "tonight" => (0, hm(20, 0), true),
+                    "day after tomorrow" => (2, None, false),
+                    _ => (1, None, false),
+                };
+                let date = now.date().checked_add_days(Days::new(days))?;
+                Some(DatePart { default_time, evening, ..DatePart::day(date) })
+            }),
+            rule(&format!(r"{}(?:(?P<p>next|this|coming)\s+)?\b(?P<d>{})\b", EN_PREFIX, EN_WEEKDAY), en_weekday_handler),
+            rule(&format!(r"{}(?P<p>next|this|coming)\s+(?P<d>{})\b", EN_PREFIX, EN_WEEKDAY_SHORT), en_weekday_handler),
+            rule(&format!(r"{}(?:(?P<p>next|this)\s+)?\bweekend\b", EN_PREFIX), |c, now| {
+                let next = c.name("p").map(|p| p.as_str().to_lowercase()).as_deref() == Some("next");
+                if !next && now.date().weekday() == Weekday::Sun {
+                    return Some(DatePart::day(now.date()));
+                }
+                weekday_of_week(now.date(), next as u64, Weekday::Sat).map(DatePart::day)
+            }),
+            rule(&format!(r"{}next\s+(?P<u>week|month|year)\b", EN_PREFIX), |c, now| {
+                let date = match c["u"].to_lowercase().as_str() {
+                    "week" => weekday_of_week(now.date(), 1, Weekday::Mon)?,
+                    "month" => first_of_month(now.date(), 1)?,
+                    _ => NaiveDate::from_ymd_opt(now.date().year() + 1, 1, 1)?,
+                };
+                Some(DatePart::day(date))
+            }),
+            rule(&format!(r"{}(?:the\s+)?end\s+of\s+(?:the\s+)?(?:(?P<p>this|next)\s+)?(?P<u>week|month|year)\b", EN_PREFIX), |c, now| {
+                let next = c.name("p").map(|p| p.as_str().to_lowercase()).as_deref() == Some("next");
+                let date = match c["u"].to_lowercase().as_str() {
+                    // 工作周的最后一天
+                    "week" => weekday_of_week(now.date(), next as u64, Weekday::Fri)?,
+                    "month" => last_of_month(now.date(), next as u32)?,
+                    _ => NaiveDate::from_ymd_opt(now.date().year() + next as i32, 12, 31)?,
+                };
+                Some(DatePart::day(date))
+            }),
+            rule(&format!(r"{}in\s+(?P<n>{}|half\s+an?)\s+(?P<u>{})\b", EN_PREFIX, EN_NUMBER, EN_UNIT), en_relative_handler),
+            rule(&format!(r"\b(?P<n>{})\s+(?P<u>{})\s+(?:later|from\s+now)\b", EN_NUMBER, EN_UNIT), en_relative_handler),
+            rule(&format!(r"{}\b(?P<m>{})\.?\s+(?P<d>\d{{1,2}})(?:st|nd|rd|th)?\b(?:,?\s+(?P<y>\d{{4}})\b)?", EN_PREFIX, EN_MONTH), en_month_day_handler),
+            rule(&format!(r"{}\b(?P<d>\d{{1,2}})(?:st|nd|rd|th)?\s+(?:of\s+)?(?P<m>{})\b\.?(?:,?\s+(?P<y>\d{{4}})\b)?", EN_PREFIX, EN_MONTH), en_month_day_handler),
+            rule(
+                r"\b(?P<y>\d{4})[-/.](?P<m>\d{1,2})[-/.](?P<d>\d{1,2})(?:(?:T|\s+)(?P<h>\d{1,2}):(?P<min>\d{2})(?::(?P<s>\d{2})(?:\.\d+)?)?(?P<tz>Z|[+-]\d{2}:?\d{2})?)?",
+                |c, _| {
+                    let date = NaiveDate::from_ymd_opt(c["y"].parse().ok()?, c["m"].parse().ok()?, c["d"].parse().ok()?)?;
+                    let (hour, minute) = match (c.name("h"), c.name("min")) {
+                        (Some(h), Some(m)) => (h.as_str().parse().ok()?, m.as_str().parse().ok()?),
+                        _ => return Some(DatePart::day(date)),
+                    };
+                    let second = c.name("s").map_or(Some(0), |s| s.as_str().parse().ok())?;
+                    let datetime = date.and_hms_opt(hour, minute, second)?;
+
+                    let offset = match c.name("tz").map(|tz| tz.as_str().to_uppercase()) {
+                        None => return Some(DatePart::at(datetime)),
+                        Some(tz) if tz == "Z" => FixedOffset::east_opt(0)?,
+                        Some(tz) => {
+                            let sign = if tz.starts_with('-') { -1 } else { 1 };
+                            let digits = tz[1..].replace(':', "");
+                            let hours: i32 = digits.get(..2)?.parse().ok()?;
+                            let minutes: i32 = digits.get(2..)?.parse().ok()?;
+                            FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))?
+                        }
+                    };
+                    let instant = offset.from_local_datetime(&datetime).single()?;
+                    Some(DatePart { instant: Some(instant), ..DatePart::at(datetime) })
+                },
+            ),
+        ]
+    })
+}
+
+fn en_weekday_handler(c: &Captures, now: &NaiveDateTime) -> Option<DatePart> {
+    let weekday = en_weekday(&c["d"])?;
+    // 与"下周三"一致，next 指下一周的那一天
+    let date = match c.name("p").map(|p| p.as_str().to_lowercase()).as_deref() {
+        Some("next") => weekday_of_week(now.date(), 1, weekday),
+        _ => upcoming_weekday(now.date(), weekday),
+    };
+    date.map(DatePart::day)
+}
+
+fn en_relative_handler(c: &Captures, now: &NaiveDateTime) -> Option<DatePart> {
+    let unit = en_unit(&c["u"])?;
+    let n = &c["n"];
+    if n.to_lowercase().starts_with("half") {
+        relative_half(now, unit)
+    } else {
+        relative(now, en_number(n)?, unit)
+    }
+}
+
+fn en_month_day_handler(c: &Captures, now: &NaiveDateTime) -> Option<DatePart> {
+    let year = c.name("y").map(|y| y.as_str());
+    month_day(now.date(), year, en_month(&c["m"])?, c["d"].parse().ok()?).map(DatePart::day)
+}
+
+fn time_rules() -> &'static [Rule<TimePart>] {
+    static RULES: OnceLock<Vec<Rule<TimePart>>> = OnceLock::new();
+
+    RULES.get_or_init(|| {
+        vec![
+            rule(
+                &format!("(?P<p>{})?(?P<h>{})(?:点钟?|时)(?:(?P<half>半)|(?P<q>一刻|三刻)|(?P<m>{})分?)?", CN_PERIOD, CN_NUMBER, CN_NUMBER),
+                |c, _| {
+                    let minute = if c.name("half").is_some() {
+                        30
+                    } else if let Some(q) = c.name("q") {
+                        if q.as_str() == "一刻" { 15 } else { 45 }
+                    } else {
+                        c.name("m").map_or(Some(0), |m| number(m.as_str()))?
+                    };
+                    clock(c.name("p").map(|p| p.as_str()), number(&c["h"])?, minute)
+                },
+            ),
+            rule(&format!(r"(?P<p>{})?(?P<at>\bat\s+)?\b(?P<h>\d{{1,2}})[:：](?P<m>\d{{2}})\b", CN_PERIOD), |c, _| {
+                let hour = c["h"].parse().ok()?;
+                let hour = if c.name("at").is_some() && c.name("p").is_none() { bare_hour(hour) } else { hour };
+                clock(c.name("p").map(|p| p.as_str()), hour, c["m"].parse().ok()?)
+            }),
+            rule(r"(?:\bat\s+|@\s*)?\b(?P<h>\d{1,2})(?::(?P<m>\d{2}))?\s*(?P<p>[ap]\.m\.|(?:am|pm)\b)", |c, _| {
+                let minute = c.name("m").map_or(Some(0), |m| m.as_str().parse().ok())?;
+                clock(Some(&c["p"]), c["h"].parse().ok()?, minute)
+            }),
+            rule(r"\bat\s+(?P<h>\d{1,2})\b", |c, _| clock(None, bare_hour(c["h"].parse().ok()?), 0)),
+            rule(r"(?:\bat\s+)?\b(?P<w>noon|midnight)\b", |c, _| period_time(&c["w"])),
+            rule(&format!("(?P<w>{})", CN_PERIOD), |c, _| period_time(&c["w"])),
+            rule(r"\b(?:in\s+the\s+|this\s+)?(?P<w>morning|afternoon|evening|night)\b", |c, _| period_time(&c["w"])),
+        ]
+    })
+}
+
+/// 在所有规则中找出最靠前的匹配，位置相同时取较长的一个。跳过与 `skip` 重叠的匹配。
+fn find_best<T>(rules: &[Rule<T>], input: &str, now: &NaiveDateTime, skip: Option<&Range<usize>>) -> Option<(Range<usize>, T)> {
+    let mut best: Option<(Range<usize>, T)> = None;
+
+    for rule in rules {
+        for caps in rule.regex.captures_iter(input) {
+            let range = match caps.get(0) {
+                Some(m) if !m.as_str().trim().is_empty() => m.range(),
+                _ => continue,
+            };
+            if skip.is_some_and(|skip| range.start < skip.end && skip.start < range.end) {
+                continue;
+            }
+            let value = match (rule.handler)(&caps, now) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let better = match best {
+                Some((ref current, _)) => {
+                    range.start < current.start || (range.start == current.start && range.len() > current.len())
+                }
+                None => true,
+            };
+            if better {
+                best = Some((range, value));
+            }
+            break;
+        }
+    }
+
+    best
+}
+
+/// 去掉日期后的剩余文本：合并空白，去掉首尾的标点
+fn tidy(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c.is_whitespace() || ",，。.、;；:：!！".contains(c))
+        .to_string()
+}
+
+/// 本地时间对应的时刻。夏令时开始时跳过的时间（例如 2:30）顺延到切换之后的同一钟点偏移（3:30）。
+fn resolve_local<Tz: TimeZone>(tz: &Tz, datetime: NaiveDateTime) -> Option<DateTime<Tz>> {
+    tz.from_local_datetime(&datetime)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&datetime.checked_add_signed(Duration::hours(1))?).earliest())
+}
+
+/// 在 `input` 中查找日期表达式，以 `now`（用户的当前时间与时区）为基准计算日期。
+///
+/// 支持今天/明天/后天、周三/下周三、周末、月底/下月初、3月5日/15号、3天后/2小时后、
+/// today/tomorrow/tonight、next friday、end of month、in 2 weeks、Oct 20、ISO 日期，
+/// 以及可选的钟点：下午三点、3点半、15:00、5pm、noon。
+/// 只写钟点时取今天，今天的这个时间已经过去则取明天。
+pub fn parse<Tz: TimeZone>(input: &str, now: &DateTime<Tz>) -> Option<ParsedDate>
+where
+    Tz::Offset: fmt::Display,
+{
+    let local_now = now.naive_local();
+    let date = find_best(date_rules(), input, &local_now, None);
+    let time = match date {
+        Some((_, ref part)) if part.exact => None,
+        _ => find_best(time_rules(), input, &local_now, date.as_ref().map(|(range, _)| range)),
+    };
+
+    let datetime = match (&date, &time) {
+        (None, None) => return None,
+        (Some((_, part)), _) if part.instant.is_some() => {
+            part.instant.map(|instant| instant.with_timezone(&now.timezone()).naive_local())
+        }
+        (Some((_, part)), None) => part.default_time.map(|time| part.date.and_time(time)),
+        (Some((_, part)), Some((_, clock))) => {
+            let clock = match (part.evening && !clock.has_period, clock.time.hour()) {
+                (true, 12) => late_night(clock.time.minute(), false)?,
+                (true, hour) if hour < 12 => TimePart { time: clock.time.with_hour(hour + 12)?, ..*clock },
+                _ => *clock,
+            };
+            Some(part.date.checked_add_days(Days::new(clock.next_day as u64))?.and_time(clock.time))
+        }
+        (None, Some((_, clock))) => {
+            let today = local_now.date().checked_add_days(Days::new(clock.next_day as u64))?.and_time(clock.time);
+            Some(if today <= local_now { today.checked_add_days(Days::new(1))? } else { today })
+        }
+    };
+
+    let (due_date, all_day) = match datetime {
+        Some(datetime) => (resolve_local(&now.timezone(), datetime)?.to_rfc3339(), false),
+        None => (date.as_ref()?.1.date.format("%Y-%m-%d").to_string(), true),
+    };
+
+    let mut spans: Vec<Range<usize>> = [date.map(|(range, _)| range), time.map(|(range, _)| range)]
+        .into_iter()
+        .flatten()
+        .collect();
+    spans.sort_by_key(|range| range.start);
+
+    let matched = spans.iter().map(|range| input[range.clone()].trim()).collect::<Vec<_>>().join(" ");
+    let mut remainder = String::new();
+    let mut last = 0;
+    for range in &spans {
+        remainder.push_str(&input[last..range.start]);
+        last = range.end;
+    }
+    remainder.push_str(&input[last..]);
+
+    Some(ParsedDate {
+        due_date,
+        all_day,
+        matched,
+        remainder: tidy(&remainder),
+    })
+}
+
+/// 把截止日期参数（ISO 日期或自然语言）转换为 `Todo::due_date` 的保存格式，整个参数必须都是日期表达式
+pub fn resolve_due_date<Tz: TimeZone>(value: &str, now: &DateTime<Tz>) -> Result<String, AppError>
+where
+    Tz::Offset: fmt::Display,
+{
+    match parse(value, now) {
+        Some(parsed) if parsed.remainder.is_empty() => Ok(parsed.due_date),
+        _ => Err(AppError::InvalidArgument(format!("Unrecognized due date: {}", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{America::New_York, Asia::Shanghai};
+
+    /// 2025-03-12 是周三
+    fn now() -> DateTime<chrono_tz::Tz> {
+        Shanghai.with_ymd_and_hms(2025, 3, 12, 10, 0, 0).unwrap()
+    }
+
+    /// (输入, 截止日期, 剩余文本)
+    fn check(cases: &[(&str, &str, &str)]) {
+        for (input, due_date, remainder) in cases {
+            let parsed = parse(input, &now()).unwrap_or_else(|| panic!("no date in {:?}", input));
+            assert_eq!(
+                (parsed.due_date.as_str(), parsed.remainder.as_str()),
+                (*due_date, *remainder),
+                "input: {:?}",
+                input
+            );
+            assert_eq!(parsed.all_day, !due_date.contains('T'), "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn chinese_relative_days() {
+        check(&[
+            ("今天", "2025-03-12", ""),
+            ("明天交报告", "2025-03-13", "交报告"),
+            ("后天", "2025-03-14", ""),
+            ("大后天", "2025-03-15", ""),
+            ("今晚", "2025-03-12T20:00:00+08:00", ""),
+            ("明早开会", "2025-03-13T09:00:00+08:00", "开会"),
+        ]);
+    }
+
+    #[test]
+    fn chinese_weeks() {
+        check(&[
+            ("周三", "2025-03-12", ""),
+            ("周五", "2025-03-14", ""),
+            ("星期日", "2025-03-16", ""),
+            ("下周三", "2025-03-19", ""),
+            ("下下周一", "2025-03-24", ""),
+            ("这周日", "2025-03-16", ""),
+            ("下周", "2025-03-17", ""),
+            ("下周提交", "2025-03-17", "提交"),
+            ("周末", "2025-03-15", ""),
+            ("下周末", "2025-03-22", ""),
+        ]);
+    }
+
+    #[test]
+    fn chinese_months_and_years() {
+        check(&[
+            ("月底", "2025-03-31", ""),
+            ("月初", "2025-04-01", ""),
+            ("下月初", "2025-04-01", ""),
+            ("下个月5号", "2025-04-05", ""),
+            ("下个月", "2025-04-01", ""),
+            ("年底", "2025-12-31", ""),
+            ("明年初", "2026-01-01", ""),
+            ("4月1号", "2025-04-01", ""),
+            ("三月五日", "2026-03-05", ""),
+            ("2026年1月2日", "2026-01-02", ""),
+            ("15号", "2025-03-15", ""),
+            ("5号", "2025-04-05", ""),
+        ]);
+    }
+
+    #[test]
+    fn chinese_offsets() {
+        check(&[
+            ("3天后", "2025-03-15", ""),
+            ("两周后", "2025-03-26", ""),
+            ("3个月后", "2025-06-12", ""),
+            ("一年以后", "2026-03-12", ""),
+            ("2小时后", "2025-03-12T12:00:00+08:00", ""),
+            ("半小时后提醒我", "2025-03-12T10:30:00+08:00", "提醒我"),
+            ("30分钟之后", "2025-03-12T10:30:00+08:00", ""),
+        ]);
+    }
+
+    #[test]
+    fn chinese_times() {
+        check(&[
+            ("明天下午3点", "2025-03-13T15:00:00+08:00", ""),
+            ("明天下午", "2025-03-13T15:00:00+08:00", ""),
+            ("下午三点半开会", "2025-03-12T15:30:00+08:00", "开会"),
+            ("晚上8点一刻", "2025-03-12T20:15:00+08:00", ""),
+            ("15:00", "2025-03-12T15:00:00+08:00", ""),
+            ("下周三 14:30", "2025-03-19T14:30:00+08:00", ""),
+            ("中午12点", "2025-03-12T12:00:00+08:00", ""),
+            ("上午12点", "2025-03-13T00:00:00+08:00", ""),
+            ("晚上12点", "2025-03-12T23:59:00+08:00", ""),
+            ("晚上12点半", "2025-03-13T00:30:00+08:00", ""),
+        ]);
+    }
+
+    #[test]
+    fn evening_adjustment() {
+        check(&[
+            ("今晚8点", "2025-03-12T20:00:00+08:00", ""),
+            ("今晚12点", "2025-03-12T23:59:00+08:00", ""),
+            ("明晚7点半", "2025-03-13T19:30:00+08:00", ""),
+            // 写明了时间段时不调整
+            ("今晚上午9点", "2025-03-12T09:00:00+08:00", ""),
+            ("tonight at 9", "2025-03-12T21:00:00+08:00", ""),
+        ]);
+    }
+
+    #[test]
+    fn time_already_passed_moves_to_tomorrow() {
+        check(&[
+            ("上午9点", "2025-03-13T09:00:00+08:00", ""),
+            ("3点半", "2025-03-13T03:30:00+08:00", ""),
+            ("10:00", "2025-03-13T10:00:00+08:00", ""),
+            ("10:01", "2025-03-12T10:01:00+08:00", ""),
+            ("下午3点", "2025-03-12T15:00:00+08:00", ""),
+            ("noon", "2025-03-12T12:00:00+08:00", ""),
+            ("midnight", "2025-03-12T23:59:00+08:00", ""),
+        ]);
+    }
+
+    #[test]
+    fn english_days_and_weeks() {
+        check(&[
+            ("today", "2025-03-12", ""),
+            ("Buy milk tomorrow at 5pm", "2025-03-13T17:00:00+08:00", "Buy milk"),
+            ("tmr", "2025-03-13", ""),
+            ("day after tomorrow", "2025-03-14", ""),
+            ("friday", "2025-03-14", ""),
+            ("by friday submit report", "2025-03-14", "submit report"),
+            ("next friday", "2025-03-21", ""),
+            ("this fri", "2025-03-14", ""),
+            ("next wed", "2025-03-19", ""),
+            ("weekend", "2025-03-15", ""),
+            ("next weekend", "2025-03-22", ""),
+            ("next week", "2025-03-17", ""),
+            ("next month", "2025-04-01", ""),
+            ("next year", "2026-01-01", ""),
+            ("end of month", "2025-03-31", ""),
+            ("end of next week", "2025-03-21", ""),
+            ("end of the year", "2025-12-31", ""),
+        ]);
+    }
+
+    #[test]
+    fn english_offsets_and_dates() {
+        check(&[
+            ("in 2 weeks", "2025-03-26", ""),
+            ("in three days", "2025-03-15", ""),
+            ("in half an hour", "2025-03-12T10:30:00+08:00", ""),
+            ("in an hour", "2025-03-12T11:00:00+08:00", ""),
+            ("3 days from now", "2025-03-15", ""),
+            ("2 hours later", "2025-03-12T12:00:00+08:00", ""),
+            ("Oct 20", "2025-10-20", ""),
+            ("March 1st", "2026-03-01", ""),
+            ("20th of October 2026", "2026-10-20", ""),
+            ("due Dec 5, 2025 pay rent", "2025-12-05", "pay rent"),
+        ]);
+    }
+
+    #[test]
+    fn english_times() {
+        check(&[
+            ("at 3", "2025-03-12T15:00:00+08:00", ""),
+            ("at 3:30", "2025-03-12T15:30:00+08:00", ""),
+            ("at 9", "2025-03-13T09:00:00+08:00", ""),
+            ("tomorrow 9am", "2025-03-13T09:00:00+08:00", ""),
+            ("tomorrow at 12am", "2025-03-13T00:00:00+08:00", ""),
+            ("friday 6:30 p.m.", "2025-03-14T18:30:00+08:00", ""),
+            ("tomorrow morning", "2025-03-13T09:00:00+08:00", ""),
+            ("friday evening", "2025-03-14T18:00:00+08:00", ""),
+        ]);
+    }
+
+    #[test]
+    fn iso_dates() {
+        check(&[
+            ("2025-03-20", "2025-03-20", ""),
+            ("2025/3/20", "2025-03-20", ""),
+            ("2025-03-20T15:00", "2025-03-20T15:00:00+08:00", ""),
+            ("2025-03-20 15:00:30", "2025-03-20T15:00:30+08:00", ""),
+            ("2025-03-20T07:00:00Z", "2025-03-20T15:00:00+08:00", ""),
+            ("2025-03-20T15:00:00-04:00", "2025-03-21T03:00:00+08:00", ""),
+            // 带时间的 ISO 日期已经是精确时刻，不再与其他钟点组合
+            ("2025-03-20T15:00 下午5点", "2025-03-20T15:00:00+08:00", "下午5点"),
+        ]);
+    }
+
+    #[test]
+    fn overlapping_rules_prefer_longest_at_same_position() {
+        let parsed = parse("下周三交", &now()).unwrap();
+        assert_eq!(parsed.matched, "下周三");
+        let parsed = parse("3个月后5号", &now()).unwrap();
+        assert_eq!((parsed.due_date.as_str(), parsed.matched.as_str()), ("2025-06-12", "3个月后"));
+        // 最靠前的匹配优先
+        let parsed = parse("周五 或 下周一", &now()).unwrap();
+        assert_eq!((parsed.due_date.as_str(), parsed.remainder.as_str()), ("2025-03-14", "或 下周一"));
+    }
+
+    #[test]
+    fn matched_and_remainder() {
+        let parsed = parse("明天 下午3点，交周报。", &now()).unwrap();
+        assert_eq!(parsed.matched, "明天 下午3点");
+        assert_eq!(parsed.remainder, "交周报");
+
+        let parsed = parse("call mom   on friday  at 3pm", &now()).unwrap();
+        assert_eq!(parsed.matched, "on friday at 3pm");
+        assert_eq!(parsed.remainder, "call mom");
+    }
+
+    #[test]
+    fn no_date() {
+        for input in ["", "买牛奶", "call sam", "room 101", "3月", "at"] {
+            assert_eq!(parse(input, &now()), None, "input: {:?}", input);
+        }
+        assert!(resolve_due_date("明天交报告", &now()).is_err());
+        assert_eq!(resolve_due_date("明天", &now()).unwrap(), "2025-03-13");
+    }
+
+    #[test]
+    fn dst_gap_moves_forward() {
+        // 2025-03-09 02:00 纽约开始夏令时，2:30 不存在
+        let now = New_York.with_ymd_and_hms(2025, 3, 8, 10, 0, 0).unwrap();
+        assert_eq!(parse("tomorrow 2:30am", &now).unwrap().due_date, "2025-03-09T03:30:00-04:00");
+        assert_eq!(parse("tomorrow 9am", &now).unwrap().due_date, "2025-03-09T09:00:00-04:00");
+        assert_eq!(parse("today 9pm", &now).unwrap().due_date, "2025-03-08T21:00:00-05:00");
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+use crate::error::AppError;
+use crate::models::date_expr::{self, ParsedDate};
+
+/// 全天任务的相对提醒以当天这个时间为基准
+const ALL_DAY_REMINDER_TIME: (u32, u32) = (9, 0);
+
+/// 用户时区：设置中的 IANA 时区名称（例如 `Asia/Shanghai`），未设置时使用系统时区
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserTimezone {
+    System,
+    Named(Tz),
+}
+
+impl UserTimezone {
+    /// 空字符串表示系统时区
+    pub fn parse(name: &str) -> Result<Self, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok(UserTimezone::System);
+        }
+        name.parse::<Tz>()
+            .map(UserTimezone::Named)
+            .map_err(|_| AppError::InvalidArgument(format!("Unknown timezone: {}", name)))
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.from_utc(Utc::now())
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    pub fn from_utc(&self, instant: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            UserTimezone::System => instant.with_timezone(&Local).fixed_offset(),
+            UserTimezone::Named(tz) => instant.with_timezone(tz).fixed_offset(),
+        }
+    }
+
+    /// 本地时间对应的时刻；夏令时切换造成的重复时间取较早的一个，不存在的时间返回 `None`
+    pub fn from_local(&self, datetime: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            UserTimezone::System => Local.from_local_datetime(&datetime).earliest().map(|dt| dt.fixed_offset()),
+            UserTimezone::Named(tz) => tz.from_local_datetime(&datetime).earliest().map(|dt| dt.fixed_offset()),
+        }
+    }
+
+    /// 以用户的当前时间为基准在 `input` 中查找日期表达式
+    pub fn parse_expression(&self, input: &str) -> Option<ParsedDate> {
+        match self {
+            UserTimezone::System => date_expr::parse(input, &Local::now()),
+            UserTimezone::Named(tz) => date_expr::parse(input, &Utc::now().with_timezone(tz)),
+        }
+    }
+}
+
+/// 任务的截止日期：全天（当天结束前完成）或带时区偏移的具体时刻。
+///
+/// 序列化为字符串：全天为 `YYYY-MM-DD`，具体时刻为 RFC 3339（例如 `2025-03-01T15:00:00+08:00`）。
+#[derive(Debug, Clone, PartialEq)]
+pub enum DueDate {
+    AllDay(NaiveDate),
+    At(DateTime<FixedOffset>),
+}
+
+impl DueDate {
+    /// 严格解析保存格式，不接受自然语言
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(DueDate::AllDay(date));
+        }
+        DateTime::parse_from_rfc3339(value).ok().map(DueDate::At)
+    }
+
+    /// 解析 ISO 日期或自然语言（"明天下午三点"），以用户时区的当前时间为基准，整个参数必须都是日期表达式
+    pub fn resolve(value: &str, tz: &UserTimezone) -> Result<Self, AppError> {
+        tz.parse_expression(value)
+            .filter(|parsed| parsed.remainder.is_empty())
+            .and_then(|parsed| DueDate::parse(&parsed.due_date))
+            .ok_or_else(|| AppError::InvalidArgument(format!("Unrecognized due date: {}", value)))
+    }
+
+    pub fn is_all_day(&self) -> bool {
+        matches!(self, DueDate::AllDay(_))
+    }
+
+    /// 截止的时刻，从这一刻起任务算作逾期：全天任务为当天在用户时区结束时
+    pub fn deadline(&self, tz: &UserTimezone) -> Option<DateTime<Utc>> {
+        match self {
+            DueDate::AllDay(date) => {
+                let next_day = date.checked_add_days(Days::new(1))?.and_time(NaiveTime::MIN);
+                tz.from_local(next_day).map(|dt| dt.with_timezone(&Utc))
+            }
+            DueDate::At(at) => Some(at.with_timezone(&Utc)),
+        }
+    }
+
+    /// 相对提醒的基准时刻：全天任务为当天 `ALL_DAY_REMINDER_TIME`
+    pub fn reminder_base(&self, tz: &UserTimezone) -> Option<DateTime<Utc>> {
+        match self {
+            DueDate::AllDay(date) => {
+                let (hour, minute) = ALL_DAY_REMINDER_TIME;
+                tz.from_local(date.and_hms_opt(hour, minute, 0)?).map(|dt| dt.with_timezone(&Utc))
+            }
+            DueDate::At(at) => Some(at.with_timezone(&Utc)),
+        }
+    }
+
+    /// 保存到 `todos.due_at` 的截止时刻。固定为 UTC、精确到秒，可以直接按字符串比较和排序。
+    pub fn deadline_sql(&self, tz: &UserTimezone) -> Option<String> {
+        self.deadline(tz).map(|deadline| instant_sql(&deadline))
+    }
+
+    /// 把具体时刻换算到用户时区，全天日期保持不变
+    pub fn in_timezone(&self, tz: &UserTimezone) -> Self {
+        match self {
+            DueDate::AllDay(date) => DueDate::AllDay(*date),
+            DueDate::At(at) => DueDate::At(tz.from_utc(at.with_timezone(&Utc))),
+        }
+    }
+}
+
+/// 与 `todos.due_at` 相同格式的时刻，用于在 SQL 中比较
+pub fn instant_sql(instant: &DateTime<Utc>) -> String {
+    instant.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+impl fmt::Display for DueDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DueDate::AllDay(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            DueDate::At(at) => write!(f, "{}", at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        }
+    }
+}
+
+impl Serialize for DueDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DueDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        DueDate::parse(&value).ok_or_else(|| serde::de::Error::custom(format!("invalid due date: {}", value)))
+    }
+}
+
+/// 读取旧版本保存的任务快照：无法识别的截止日期（类型化之前的自由文本）视为没有截止日期
+pub fn deserialize_lenient<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DueDate>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.as_deref().and_then(DueDate::parse))
+}
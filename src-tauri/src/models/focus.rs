@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// 专注（番茄钟）的时长设置
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusConfig {
+    pub work_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    /// 每完成这么多个专注阶段后休息一次长休息
+    pub long_break_every: u32,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+impl FocusConfig {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let fields = [
+            ("workMinutes", self.work_minutes),
+            ("shortBreakMinutes", self.short_break_minutes),
+            ("longBreakMinutes", self.long_break_minutes),
+            ("longBreakEvery", self.long_break_every),
+        ];
+        for (name, value) in fields {
+            if value == 0 {
+                return Err(AppError::InvalidArgument(format!("Focus setting {} must be positive", name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// 阶段的时长（秒）
+    pub fn phase_seconds(&self, phase: FocusPhase) -> u64 {
+        let minutes = match phase {
+            FocusPhase::Work => self.work_minutes,
+            FocusPhase::ShortBreak => self.short_break_minutes,
+            FocusPhase::LongBreak => self.long_break_minutes,
+        };
+        minutes as u64 * 60
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl FocusPhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FocusPhase::Work => "专注",
+            FocusPhase::ShortBreak => "短休息",
+            FocusPhase::LongBreak => "长休息",
+        }
+    }
+}
+
+/// 专注阶段中记录的一次打断
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusInterruption {
+    pub occurred_at: String,
+    pub note: Option<String>,
+}
+
+/// 当前专注的状态，也是 `focus-tick` 事件的内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusStatus {
+    pub todo_id: Option<String>,
+    pub todo_text: Option<String>,
+    pub phase: FocusPhase,
+    /// 本轮已完成的专注阶段数
+    pub completed_cycles: u32,
+    pub phase_seconds: u64,
+    pub remaining_seconds: u64,
+    pub paused: bool,
+    /// 当前专注阶段中的打断次数，休息阶段为 0
+    pub interruptions: u32,
+    pub config: FocusConfig,
+}
+
+/// 一个已结束的专注阶段
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+    pub id: String,
+    /// 任务被永久删除后为 `None`，记录仍计入统计
+    pub todo_id: Option<String>,
+    pub todo_text: Option<String>,
+    pub started_at: String,
+    pub ended_at: String,
+    pub planned_seconds: u64,
+    /// 实际专注的秒数，不含暂停时间
+    pub focused_seconds: u64,
+    /// 是否坚持到了计划时长，提前停止的为 `false`
+    pub completed: bool,
+    pub interruptions: Vec<FocusInterruption>,
+}
+
+/// `focus-phase-changed` 事件的内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusPhaseChange {
+    pub finished: FocusPhase,
+    /// 进入的新阶段
+    pub status: FocusStatus,
+    /// 刚结束的专注阶段，结束的是休息时为 `None`
+    pub session: Option<FocusSession>,
+}
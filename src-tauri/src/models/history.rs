@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 发起修改的一方，记录在每条任务变更事件中
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Actor {
+    /// 用户在界面上直接操作
+    User,
+    /// AI 对话中的工具调用
+    #[serde(rename_all = "camelCase")]
+    Ai {
+        conversation_id: Option<String>,
+        tool_call_id: Option<String>,
+        /// 一轮对话的 ID，同一轮中的全部修改作为一个撤销步骤
+        #[serde(default)]
+        turn_id: Option<String>,
+    },
+    /// 批量导入
+    Import,
+    /// 后台自动任务，例如回收站定期清理
+    Automation { source: String },
+}
+
+impl Actor {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Actor::User => "user",
+            Actor::Ai { .. } => "ai",
+            Actor::Import => "import",
+            Actor::Automation { .. } => "automation",
+        }
+    }
+
+    pub fn automation(source: &str) -> Self {
+        Actor::Automation {
+            source: source.to_string(),
+        }
+    }
+
+    /// 本次修改所属的撤销步骤 ID。
+    /// AI 同一轮对话共用 `turn_id`；自动任务产生的修改不可撤销，返回 `None`。
+    pub fn undo_step_id(&self) -> Option<String> {
+        match self {
+            Actor::Automation { .. } => None,
+            Actor::Ai { turn_id: Some(turn_id), .. } => Some(turn_id.clone()),
+            _ => Some(uuid::Uuid::new_v4().to_string()),
+        }
+    }
+
+    /// 用于展示的中文名称
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Actor::User => "用户",
+            Actor::Ai { .. } => "AI",
+            Actor::Import => "导入",
+            Actor::Automation { .. } => "自动任务",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoAction {
+    Created,
+    Updated,
+    /// 移入回收站
+    Deleted,
+    /// 从回收站恢复
+    Restored,
+    /// 永久删除
+    Purged,
+}
+
+impl TodoAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoAction::Created => "created",
+            TodoAction::Updated => "updated",
+            TodoAction::Deleted => "deleted",
+            TodoAction::Restored => "restored",
+            TodoAction::Purged => "purged",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "created" => TodoAction::Created,
+            "deleted" => TodoAction::Deleted,
+            "restored" => TodoAction::Restored,
+            "purged" => TodoAction::Purged,
+            _ => TodoAction::Updated,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TodoAction::Created => "创建了",
+            TodoAction::Updated => "修改了",
+            TodoAction::Deleted => "删除了",
+            TodoAction::Restored => "恢复了",
+            TodoAction::Purged => "永久删除了",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    pub before: Value,
+    pub after: Value,
+}
+
+/// 一条任务变更记录，`changes` 以字段名（camelCase）为键
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoEvent {
+    pub id: i64,
+    pub todo_id: String,
+    pub action: TodoAction,
+    pub actor: Actor,
+    pub changes: Map<String, Value>,
+    pub created_at: String,
+    /// 事件发生时任务的内容
+    pub todo_text: String,
+}
+
+impl TodoEvent {
+    /// 单行中文摘要，供 AI 上下文与工具结果使用
+    pub fn summary(&self) -> String {
+        let mut line = format!(
+            "{} {}{}任务「{}」",
+            self.created_at,
+            self.actor.display_name(),
+            self.action.display_name(),
+            self.todo_text
+        );
+
+        if self.action == TodoAction::Updated && !self.changes.is_empty() {
+            let fields: Vec<String> = self
+                .changes
+                .iter()
+                .map(|(field, change)| {
+                    let before = change.get("before").map(compact).unwrap_or_default();
+                    let after = change.get("after").map(compact).unwrap_or_default();
+                    format!("{}: {} → {}", field, before, after)
+                })
+                .collect();
+            line.push_str(&format!("（{}）", fields.join("，")));
+        }
+
+        line
+    }
+}
+
+fn compact(value: &Value) -> String {
+    match value {
+        Value::Null => "空".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 比较前后两个快照（任务序列化后的 JSON 对象），返回发生变化的字段。
+/// `updatedAt` 每次修改都会变化，不计入差异。
+pub fn diff_snapshots(before: Option<&Value>, after: Option<&Value>) -> Map<String, Value> {
+    let empty = Map::new();
+    let before_map = before.and_then(|v| v.as_object()).unwrap_or(&empty);
+    let after_map = after.and_then(|v| v.as_object()).unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    let keys = before_map.keys().chain(after_map.keys());
+
+    for key in keys {
+        if key == "updatedAt" || changes.contains_key(key) {
+            continue;
+        }
+
+        let old = before_map.get(key).cloned().unwrap_or(Value::Null);
+        let new = after_map.get(key).cloned().unwrap_or(Value::Null);
+        if old != new {
+            let change = FieldChange { before: old, after: new };
+            changes.insert(key.clone(), serde_json::to_value(change).unwrap_or(Value::Null));
+        }
+    }
+
+    changes
+}
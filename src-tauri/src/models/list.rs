@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use crate::models::todo::Priority;
+
+/// 任务列表（项目），例如"工作"、"购物"
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoList {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    /// 归档的列表不出现在列表栏中，其中的任务也不出现在未指定列表的查询里
+    pub archived: bool,
+    /// 在该列表中新建任务且未指定优先级时使用
+    pub default_priority: Priority,
+    /// 列表中的任务数（不含回收站）
+    pub todo_count: u32,
+    pub completed_count: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateListRequest {
+    pub name: String,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub default_priority: Option<Priority>,
+}
+
+/// 修改列表，未提供的字段保持不变；`color`、`icon` 传入空字符串表示清除
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateListRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub archived: Option<bool>,
+    pub default_priority: Option<Priority>,
+}
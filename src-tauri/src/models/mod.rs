@@ -1,3 +1,17 @@
 pub mod todo;
 pub mod settings;
 pub mod ai;
+pub mod tag;
+pub mod list;
+pub mod recurrence;
+pub mod date_expr;
+pub mod due_date;
+pub mod reminder;
+pub mod attachment;
+pub mod time_entry;
+pub mod focus;
+pub mod workflow;
+pub mod report;
+pub mod history;
+pub mod undo;
+pub mod backup;
@@ -0,0 +1,444 @@
+use chrono::{Datelike, Days, Duration, Months, NaiveDate, Utc, Weekday};
+use std::fmt;
+
+use crate::error::AppError;
+use crate::models::due_date::{DueDate, UserTimezone};
+
+/// 找不到下一次时最多向后搜索的周期数（例如每月第 5 个周一并不是每个月都有）
+const MAX_SEARCH_PERIODS: u32 = 400;
+
+/// `INTERVAL` 的上限，更大的间隔没有实际意义，且容易让日期计算溢出
+const MAX_INTERVAL: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// `BYDAY` 中的一项。`ordinal` 只用于 MONTHLY，例如 `1MO` 为第一个周一、`-1FR` 为最后一个周五。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByDay {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// RFC 5545 RRULE 的子集：`FREQ`（DAILY/WEEKLY/MONTHLY/YEARLY）、`INTERVAL`、`BYDAY`、`COUNT`、`UNTIL`。
+///
+/// 与 RFC 一致，不存在的日期会被跳过而不是顺延：每月 31 日的任务跳过没有 31 日的月份，
+/// 2 月 29 日的年度任务只在闰年出现。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<ByDay>,
+    /// 总次数（包括第一次）
+    pub count: Option<u32>,
+    /// 最后一次不晚于这一天
+    pub until: Option<NaiveDate>,
+}
+
+fn invalid(rule: &str, reason: &str) -> AppError {
+    AppError::InvalidArgument(format!("Invalid recurrence rule '{}': {}", rule, reason))
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    match code {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn days_in_month(first: NaiveDate) -> u32 {
+    match first.checked_add_months(Months::new(1)) {
+        Some(next) => (next - first).num_days() as u32,
+        // 可表示的最后一个月只可能是 12 月
+        None => 31,
+    }
+}
+
+impl RecurrenceRule {
+    /// 解析 RRULE，可以带 `RRULE:` 前缀，键名不区分大小写
+    pub fn parse(rule: &str) -> Result<Self, AppError> {
+        let trimmed = rule.trim();
+        let body = match trimmed.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &trimmed[6..],
+            _ => trimmed,
+        };
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut count = None;
+        let mut until = None;
+
+        for part in body.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| invalid(rule, "expected KEY=VALUE"))?;
+            let value = value.trim().to_ascii_uppercase();
+
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid(rule, "FREQ must be DAILY, WEEKLY, MONTHLY or YEARLY")),
+                    });
+                }
+                "INTERVAL" => {
+                    interval = value.parse().ok().filter(|i| (1..=MAX_INTERVAL).contains(i))
+                        .ok_or_else(|| invalid(rule, &format!("INTERVAL must be between 1 and {}", MAX_INTERVAL)))?;
+                }
+                "COUNT" => {
+                    count = Some(value.parse().ok().filter(|c| *c >= 1)
+                        .ok_or_else(|| invalid(rule, "COUNT must be a positive integer"))?);
+                }
+                "UNTIL" => {
+                    // 只取日期部分：YYYYMMDD 或 YYYYMMDDTHHMMSS[Z]
+                    let date = value.get(..8).and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok())
+                        .ok_or_else(|| invalid(rule, "UNTIL must be YYYYMMDD"))?;
+                    until = Some(date);
+                }
+                "BYDAY" => {
+                    for item in value.split(',').map(str::trim) {
+                        let split = item.len().saturating_sub(2);
+                        let weekday = item.get(split..).and_then(parse_weekday)
+                            .ok_or_else(|| invalid(rule, "BYDAY must contain MO, TU, WE, TH, FR, SA or SU"))?;
+                        let ordinal = match item.get(..split).unwrap_or_default() {
+                            "" => None,
+                            n => Some(n.parse::<i32>().ok().filter(|n| *n != 0 && n.abs() <= 5)
+                                .ok_or_else(|| invalid(rule, "BYDAY ordinal must be between -5 and 5"))?),
+                        };
+                        by_day.push(ByDay { ordinal, weekday });
+                    }
+                }
+                other => return Err(invalid(rule, &format!("{} is not supported", other))),
+            }
+        }
+
+        let frequency = frequency.ok_or_else(|| invalid(rule, "FREQ is required"))?;
+        if count.is_some() && until.is_some() {
+            return Err(invalid(rule, "COUNT and UNTIL cannot be used together"));
+        }
+        if frequency == Frequency::Yearly && !by_day.is_empty() {
+            return Err(invalid(rule, "BYDAY is not supported with FREQ=YEARLY"));
+        }
+        if frequency != Frequency::Monthly && by_day.iter().any(|d| d.ordinal.is_some()) {
+            return Err(invalid(rule, "BYDAY ordinals are only supported with FREQ=MONTHLY"));
+        }
+
+        Ok(Self { frequency, interval, by_day, count, until })
+    }
+
+    /// `occurrence` 是 `date` 在序列中的序号（从 1 开始），返回下一次的日期；序列已结束时返回 `None`
+    pub fn next_occurrence(&self, date: NaiveDate, occurrence: u32) -> Option<NaiveDate> {
+        if self.count.map(|c| occurrence >= c).unwrap_or(false) {
+            return None;
+        }
+
+        let next = self.next_after(date)?;
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+
+    /// 不早于 `date` 的第一次，用于确定新建重复任务的第一次日期
+    pub fn first_on_or_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let matches = match self.frequency {
+            Frequency::Daily | Frequency::Weekly => {
+                self.by_day.is_empty() || self.by_day.iter().any(|d| d.weekday == date.weekday())
+            }
+            Frequency::Monthly => {
+                self.by_day.is_empty() || self.monthly_candidates(first_of_month(date)).contains(&date)
+            }
+            Frequency::Yearly => true,
+        };
+
+        if matches {
+            Some(date)
+        } else {
+            self.next_after(date)
+        }
+    }
+
+    /// 严格晚于 `date` 的下一次，不考虑 `COUNT` 与 `UNTIL`；超出可表示的日期范围时返回 `None`
+    fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let interval = self.interval as u64;
+        match self.frequency {
+            Frequency::Daily => {
+                let mut next = date;
+                for _ in 0..MAX_SEARCH_PERIODS {
+                    next = next.checked_add_days(Days::new(interval))?;
+                    if self.by_day.is_empty() || self.by_day.iter().any(|d| d.weekday == next.weekday()) {
+                        return Some(next);
+                    }
+                }
+                None
+            }
+            Frequency::Weekly => {
+                if self.by_day.is_empty() {
+                    return date.checked_add_days(Days::new(interval * 7));
+                }
+
+                // 以周一为一周的开始
+                let mut offsets: Vec<i64> = self.by_day.iter()
+                    .map(|d| d.weekday.num_days_from_monday() as i64)
+                    .collect();
+                offsets.sort_unstable();
+                offsets.dedup();
+
+                let current = date.weekday().num_days_from_monday() as i64;
+                let week_start = date.checked_sub_signed(Duration::days(current))?;
+                match offsets.iter().find(|o| **o > current) {
+                    Some(offset) => week_start.checked_add_signed(Duration::days(*offset)),
+                    None => week_start
+                        .checked_add_days(Days::new(interval * 7))?
+                        .checked_add_signed(Duration::days(offsets[0])),
+                }
+            }
+            Frequency::Monthly => {
+                let mut month = first_of_month(date);
+                for _ in 0..MAX_SEARCH_PERIODS {
+                    let candidates = if self.by_day.is_empty() {
+                        month.with_day(date.day()).into_iter().collect()
+                    } else {
+                        self.monthly_candidates(month)
+                    };
+                    if let Some(next) = candidates.into_iter().find(|d| *d > date) {
+                        return Some(next);
+                    }
+                    month = month.checked_add_months(Months::new(self.interval))?;
+                }
+                None
+            }
+            Frequency::Yearly => {
+                let mut year = date.year();
+                for _ in 0..MAX_SEARCH_PERIODS {
+                    year = year.checked_add(i32::try_from(self.interval).ok()?)?;
+                    if let Some(next) = NaiveDate::from_ymd_opt(year, date.month(), date.day()) {
+                        return Some(next);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// `month`（当月 1 日）中满足 `BYDAY` 的全部日期，按日期排序
+    fn monthly_candidates(&self, month: NaiveDate) -> Vec<NaiveDate> {
+        let days = days_in_month(month);
+        let mut dates = Vec::new();
+
+        for by_day in &self.by_day {
+            let matching: Vec<NaiveDate> = (0..days)
+                .map(|offset| month + Duration::days(offset as i64))
+                .filter(|d| d.weekday() == by_day.weekday)
+                .collect();
+
+            match by_day.ordinal {
+                None => dates.extend(matching),
+                Some(n) if n > 0 => dates.extend(matching.get(n as usize - 1)),
+                Some(n) => dates.extend(matching.len().checked_sub(n.unsigned_abs() as usize).map(|i| matching[i])),
+            }
+        }
+
+        dates.sort_unstable();
+        dates.dedup();
+        dates
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    /// 规范形式，例如 `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=10`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.frequency.as_str())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self.by_day.iter()
+                .map(|d| match d.ordinal {
+                    Some(n) => format!("{}{}", n, weekday_code(d.weekday)),
+                    None => weekday_code(d.weekday).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        Ok(())
+    }
+}
+
+/// 计算下一次的截止日期。带时间的截止日期在用户时区中保持相同的钟点（跨夏令时切换也不变）。
+/// 没有截止日期时以 `today` 为基准，返回全天日期。序列已结束时返回 `None`。
+pub fn next_due_date(
+    rule: &RecurrenceRule,
+    due_date: Option<&DueDate>,
+    occurrence: u32,
+    tz: &UserTimezone,
+) -> Option<DueDate> {
+    match due_date {
+        Some(DueDate::AllDay(date)) => rule.next_occurrence(*date, occurrence).map(DueDate::AllDay),
+        Some(DueDate::At(at)) => {
+            let local = tz.from_utc(at.with_timezone(&Utc)).naive_local();
+            let next = rule.next_occurrence(local.date(), occurrence)?;
+            tz.from_local(next.and_time(local.time())).map(DueDate::At)
+        }
+        None => rule.next_occurrence(tz.today(), occurrence).map(DueDate::AllDay),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn rule(s: &str) -> RecurrenceRule {
+        RecurrenceRule::parse(s).unwrap()
+    }
+
+    /// 从 `start`（第 1 次）开始依次计算的日期，包含 `start`
+    fn series(rule: &RecurrenceRule, start: &str, n: usize) -> Vec<String> {
+        let mut dates = vec![date(start)];
+        while dates.len() < n {
+            match rule.next_occurrence(*dates.last().unwrap(), dates.len() as u32) {
+                Some(next) => dates.push(next),
+                None => break,
+            }
+        }
+        dates.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn monthly_on_31st_skips_short_months() {
+        assert_eq!(
+            series(&rule("FREQ=MONTHLY"), "2025-01-31", 4),
+            ["2025-01-31", "2025-03-31", "2025-05-31", "2025-07-31"]
+        );
+    }
+
+    #[test]
+    fn yearly_on_feb_29_only_in_leap_years() {
+        assert_eq!(series(&rule("FREQ=YEARLY"), "2024-02-29", 3), ["2024-02-29", "2028-02-29", "2032-02-29"]);
+    }
+
+    #[test]
+    fn monthly_by_day_ordinals() {
+        assert_eq!(
+            series(&rule("FREQ=MONTHLY;BYDAY=-1FR"), "2025-01-31", 3),
+            ["2025-01-31", "2025-02-28", "2025-03-28"]
+        );
+        // 4、5 月只有 4 个周一
+        assert_eq!(series(&rule("FREQ=MONTHLY;BYDAY=5MO"), "2025-03-31", 2), ["2025-03-31", "2025-06-30"]);
+        assert_eq!(rule("FREQ=MONTHLY;BYDAY=5MO").first_on_or_after(date("2025-04-01")), Some(date("2025-06-30")));
+    }
+
+    #[test]
+    fn biweekly_by_day_crosses_week_boundary() {
+        assert_eq!(
+            series(&rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE"), "2025-03-03", 5),
+            ["2025-03-03", "2025-03-05", "2025-03-17", "2025-03-19", "2025-03-31"]
+        );
+        // 周日属于从 2 月 24 日开始的一周，隔一周后的周一才是第一次
+        assert_eq!(
+            rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE").first_on_or_after(date("2025-03-02")),
+            Some(date("2025-03-10"))
+        );
+    }
+
+    #[test]
+    fn count_and_until_end_the_series() {
+        assert_eq!(series(&rule("FREQ=DAILY;COUNT=3"), "2025-01-01", 10), ["2025-01-01", "2025-01-02", "2025-01-03"]);
+        assert_eq!(
+            series(&rule("FREQ=WEEKLY;UNTIL=20250115T120000Z"), "2025-01-01", 10),
+            ["2025-01-01", "2025-01-08", "2025-01-15"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(RecurrenceRule::parse("FREQ=DAILY;COUNT=3;UNTIL=20250101").is_err());
+        assert!(RecurrenceRule::parse("INTERVAL=2").is_err());
+        assert!(RecurrenceRule::parse("FREQ=HOURLY").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;INTERVAL=0").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;INTERVAL=100000000").is_err());
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=1MO").is_err());
+        assert!(RecurrenceRule::parse("FREQ=YEARLY;BYDAY=MO").is_err());
+    }
+
+    #[test]
+    fn large_intervals_do_not_overflow() {
+        for text in ["FREQ=DAILY;INTERVAL=1000", "FREQ=WEEKLY;INTERVAL=1000", "FREQ=WEEKLY;INTERVAL=1000;BYDAY=MO",
+                     "FREQ=MONTHLY;INTERVAL=1000", "FREQ=YEARLY;INTERVAL=1000"] {
+            assert_eq!(rule(text).next_occurrence(NaiveDate::MAX, 1), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let parsed = rule("rrule:freq=monthly;interval=2;byday=-1fr,1mo;count=5");
+        assert_eq!(parsed.to_string(), "FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR,1MO;COUNT=5");
+        assert_eq!(rule(&parsed.to_string()), parsed);
+
+        let until = rule("FREQ=WEEKLY;BYDAY=TU,TH;UNTIL=20251231");
+        assert_eq!(until.to_string(), "FREQ=WEEKLY;BYDAY=TU,TH;UNTIL=20251231");
+        assert_eq!(rule(&until.to_string()), until);
+    }
+
+    #[test]
+    fn due_time_kept_across_dst() {
+        let tz = UserTimezone::parse("America/New_York").unwrap();
+        let due = DueDate::At(DateTime::parse_from_rfc3339("2025-03-08T09:00:00-05:00").unwrap());
+        let next = next_due_date(&rule("FREQ=DAILY"), Some(&due), 1, &tz).unwrap();
+        assert_eq!(next, DueDate::At(DateTime::parse_from_rfc3339("2025-03-09T09:00:00-04:00").unwrap()));
+
+        let all_day = DueDate::AllDay(date("2025-03-08"));
+        assert_eq!(next_due_date(&rule("FREQ=DAILY"), Some(&all_day), 1, &tz), Some(DueDate::AllDay(date("2025-03-09"))));
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use crate::models::todo::Todo;
+
+/// 任务的一个提醒：相对截止时间提前 `offset_minutes` 分钟，或在绝对时间 `remind_at` 触发
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub id: String,
+    pub todo_id: String,
+    /// 相对截止时间提前的分钟数，为 `None` 时是绝对时间提醒
+    pub offset_minutes: Option<i64>,
+    /// 计划触发时间（UTC），任务没有可识别的截止时间时相对提醒为 `None`
+    pub remind_at: Option<String>,
+    /// 稍后提醒推迟到的时间，优先于 `remind_at`
+    pub snoozed_until: Option<String>,
+    /// 已触发的时间，稍后提醒或截止时间推迟后重新变为 `None`
+    pub fired_at: Option<String>,
+    pub created_at: String,
+}
+
+/// 添加提醒，`offset_minutes` 与 `remind_at` 必须且只能提供一个
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReminderRequest {
+    pub offset_minutes: Option<i64>,
+    /// ISO 日期/时间或自然语言，按用户时区解释；只有日期时在当天 9:00 提醒
+    pub remind_at: Option<String>,
+}
+
+/// `todo-reminder` 事件的内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderNotification {
+    pub reminder: Reminder,
+    pub todo: Todo,
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::time_entry::TimePeriod;
+use crate::models::todo::Priority;
+
+/// 估算准确度报告的查询条件：统计在范围内完成的任务。
+/// `period` 优先于 `from`/`to`，都不提供时统计全部已完成的任务。
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateReportQuery {
+    pub period: Option<TimePeriod>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// 实际工作量的来源
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActualSource {
+    /// 计时记录的合计
+    Logged,
+    /// 没有计时记录时，从第一次开始进行到完成经过的时间
+    Elapsed,
+}
+
+/// 一个有预估且能确定实际工作量的已完成任务
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateItem {
+    pub todo_id: String,
+    pub text: String,
+    pub priority: Priority,
+    pub list_id: Option<String>,
+    pub tags: Vec<String>,
+    pub estimate_minutes: Option<u32>,
+    pub story_points: Option<f64>,
+    pub actual_minutes: f64,
+    pub source: ActualSource,
+    pub completed_at: String,
+}
+
+/// 一组任务的估算准确度。分钟预估与故事点分别统计，只有相应预估的任务计入。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateAccuracy {
+    pub key: String,
+    pub label: String,
+    /// 有分钟预估的任务数
+    pub estimated_count: u32,
+    pub estimated_minutes: f64,
+    pub actual_minutes: f64,
+    /// 实际 / 预估，大于 1 表示低估
+    pub ratio: Option<f64>,
+    /// 平均绝对百分比误差（%）
+    pub mean_abs_error_percent: Option<f64>,
+    /// 有故事点的任务数
+    pub pointed_count: u32,
+    pub story_points: f64,
+    /// 每个故事点的实际分钟数
+    pub minutes_per_point: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateReport {
+    /// 统计范围（UTC），不限制时为 `None`
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub overall: EstimateAccuracy,
+    /// 有多个标签的任务计入每个标签
+    pub by_tag: Vec<EstimateAccuracy>,
+    pub by_priority: Vec<EstimateAccuracy>,
+    /// 收件箱的 `key` 为空字符串
+    pub by_list: Vec<EstimateAccuracy>,
+    /// 最近完成的在前
+    pub items: Vec<EstimateItem>,
+}
+
+/// 趋势统计的时间粒度，周从周一开始
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+/// 统计报告的查询条件。`period` 优先于 `from`/`to`，都不提供时统计最近 30 天；
+/// `list_id` 为空时统计全部未归档的列表，空字符串表示只统计收件箱。
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsQuery {
+    pub period: Option<TimePeriod>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(default)]
+    pub granularity: Granularity,
+    pub list_id: Option<String>,
+}
+
+/// 一个时间段内新建和完成的任务数
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendPoint {
+    /// 时间段第一天（用户时区），YYYY-MM-DD
+    pub start: String,
+    pub created: u32,
+    pub completed: u32,
+}
+
+/// 按优先级、标签或列表分组的统计。`created`、`completed` 限于统计范围内，
+/// `open`、`overdue` 为当前未完成和已逾期的任务数。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsBreakdown {
+    pub key: String,
+    pub label: String,
+    pub created: u32,
+    pub completed: u32,
+    pub open: u32,
+    pub overdue: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsReport {
+    /// 统计范围 `[from, to)`（UTC）
+    pub from: String,
+    pub to: String,
+    pub granularity: Granularity,
+    /// 范围内新建的任务数
+    pub created: u32,
+    /// 范围内完成的任务数
+    pub completed: u32,
+    /// 范围内完成、但完成时已过截止时间的任务数
+    pub completed_late: u32,
+    /// 当前已逾期的任务数
+    pub overdue: u32,
+    /// 截至今天连续有任务完成的天数，今天还没有完成时从昨天算起
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// 范围内完成的任务从开始进行（没有开始记录时从创建）到完成的平均小时数
+    pub average_cycle_hours: Option<f64>,
+    /// 按时间段排列，没有任务的时间段也包含在内
+    pub trend: Vec<TrendPoint>,
+    pub by_priority: Vec<StatisticsBreakdown>,
+    /// 有多个标签的任务计入每个标签
+    pub by_tag: Vec<StatisticsBreakdown>,
+    /// 收件箱的 `key` 为空字符串
+    pub by_list: Vec<StatisticsBreakdown>,
+}
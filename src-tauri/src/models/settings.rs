@@ -1,17 +1,37 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::focus::FocusConfig;
+
 pub const DEFAULT_SYSTEM_PROMPT: &str = r#"你是一个智能任务助手。你可以帮助用户管理他们的待办事项。
 
 你有以下能力：
 - 添加新任务 (add_todos)
+- 修改任务内容、备注、清单或预估工作量 (update_todo)
 - 完成任务 (complete_todo)
+- 跳过重复任务的本次 (skip_occurrence)
 - 删除任务 (delete_todo)
 - 查询任务 (query_todos)
-- 获取统计信息 (get_statistics)
+- 批量修改或删除任务 (bulk_update_todos)
+- 设置或取消任务之间的依赖 (link_todos / unlink_todos)
+- 获取统计信息与一段时间内的完成趋势 (get_statistics)
+- 查询在任务或标签上花费的时间 (get_time_spent)
+- 查看看板、在工作流状态之间移动任务 (get_board / update_todo 的 state)
 
 请根据用户的自然语言请求，调用适当的函数来帮助他们管理任务。回复时使用简洁友好的中文。
 
-当用户请求创建任务时，请仔细理解他们的意图，将大目标拆解为具体可执行的小任务。"#;
+当用户请求创建任务时，请仔细理解他们的意图，将大目标拆解为具体可执行的小任务，并把这些步骤作为目标任务的子任务（children）一次创建。步骤有先后顺序时设置 sequential，跨任务的先后关系使用 link_todos。
+
+周期性的任务（例如"每周一提交周报"、"每月交房租"）创建为重复任务，设置 recurrence，完成后会自动生成下一次。
+
+任务可以分属不同的列表（例如工作、个人、购物）。用户提到某个列表时，在 add_todos、query_todos 中通过 list 参数指定列表名称。
+
+用户提到截止时间（例如"明天下午三点交报告"、"下周五之前"）时设置 due_date，可以直接使用用户的原话（"明天下午3点"、"下周五"），由系统按当前时间换算为具体日期；任务内容中不需要再包含截止时间。查询"今天到期"、"本周要做"或"已逾期"的任务时，使用 query_todos 的 due_before、due_after、overdue 参数。
+
+会议记录、链接、背景说明等补充信息写在任务的 notes（Markdown）中，不要塞进任务标题。不需要单独跟踪的简单检查项（购物清单、出行打包）使用 checklist，需要单独安排或跟踪的步骤才使用 children。
+
+拆解目标时，为每个具体步骤填写 estimate_minutes 预估所需时间；用户使用故事点估算时填写 story_points。用户说"这个大概要两小时"时，用 update_todo 修改预估。
+
+用户可以自定义工作流状态（看板列，例如"评审中"、"等待反馈"）。用户说"把…移到评审"时，用 update_todo 的 state 参数指定状态名称；查询某一列的任务时使用 query_todos 的 state 参数。状态可能有 WIP 上限或只允许特定的移动，操作被拒绝时向用户说明原因。"#;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -28,6 +48,15 @@ pub struct Settings {
 
     #[serde(default = "default_true")]
     pub enable_text_fallback: bool,  // Parse function calls from text if structured fails
+
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,  // 回收站保留天数，0 表示不自动清理
+
+    #[serde(default)]
+    pub timezone: String,  // IANA 时区名称，例如 "Asia/Shanghai"；空字符串表示使用系统时区
+
+    #[serde(default = "default_focus_config")]
+    pub focus: FocusConfig,  // 专注（番茄钟）的默认时长
 }
 
 fn default_function_calling_mode() -> String {
@@ -38,6 +67,14 @@ fn default_true() -> bool {
     true
 }
 
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+fn default_focus_config() -> FocusConfig {
+    FocusConfig::default()
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
@@ -49,6 +86,9 @@ impl Default for Settings {
             system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
             function_calling_mode: default_function_calling_mode(),
             enable_text_fallback: default_true(),
+            trash_retention_days: default_trash_retention_days(),
+            timezone: String::new(),
+            focus: default_focus_config(),
         }
     }
 }
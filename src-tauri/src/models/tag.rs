@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    /// 使用该标签的任务数量
+    pub todo_count: u32,
+    pub created_at: String,
+}
+
+/// 多标签过滤时的匹配方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    /// 必须包含全部标签
+    #[default]
+    All,
+    /// 包含任意一个标签即可
+    Any,
+}
@@ -0,0 +1,161 @@
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::models::due_date::{DueDate, UserTimezone};
+
+/// 任务的一段计时记录。`ended_at` 为 `None` 时计时仍在进行，同一时间最多只有一个。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntry {
+    pub id: String,
+    pub todo_id: String,
+    pub todo_text: String,
+    /// UTC 时间
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    /// 持续的秒数，进行中的计时算到当前时间
+    pub duration_seconds: i64,
+    pub note: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 手动添加计时记录，时间为 RFC 3339 或带具体时间的自然语言，按用户时区解释
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTimeEntryRequest {
+    pub started_at: String,
+    pub ended_at: String,
+    pub note: Option<String>,
+}
+
+/// 修改计时记录，未提供的字段保持不变；`note` 为空字符串表示清除。
+/// 为进行中的计时设置 `ended_at` 会结束计时。
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTimeEntryRequest {
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub note: Option<String>,
+}
+
+/// 按用户时区计算的常用时间段，一周从周一开始
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimePeriod {
+    Today,
+    Yesterday,
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+}
+
+impl TimePeriod {
+    /// 时间段的起止时刻 `[start, end)`，日期在用户时区中不存在时返回 `None`
+    pub fn bounds(&self, tz: &UserTimezone) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let today = tz.today();
+        let monday = today.checked_sub_days(Days::new(today.weekday().num_days_from_monday() as u64))?;
+        let first_of_month = today.with_day(1)?;
+
+        let (start, end) = match self {
+            TimePeriod::Today => (today, today.checked_add_days(Days::new(1))?),
+            TimePeriod::Yesterday => (today.checked_sub_days(Days::new(1))?, today),
+            TimePeriod::ThisWeek => (monday, monday.checked_add_days(Days::new(7))?),
+            TimePeriod::LastWeek => (monday.checked_sub_days(Days::new(7))?, monday),
+            TimePeriod::ThisMonth => (first_of_month, first_of_month.checked_add_months(Months::new(1))?),
+            TimePeriod::LastMonth => (first_of_month.checked_sub_months(Months::new(1))?, first_of_month),
+        };
+
+        Some((start_of_day(start, tz)?, start_of_day(end, tz)?))
+    }
+}
+
+/// 用户时区中 `date` 当天开始的时刻
+pub fn start_of_day(date: NaiveDate, tz: &UserTimezone) -> Option<DateTime<Utc>> {
+    tz.from_local(date.and_time(NaiveTime::MIN)).map(|dt| dt.with_timezone(&Utc))
+}
+
+/// 统计范围 `[from, to)`，未限制的一端为 `None`
+pub type TimeRange = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// 解析统计范围。`period` 优先；`from`/`to` 为 ISO 日期/时间或自然语言，
+/// 日期的起点为当天开始、终点为当天结束（包含当天）。未提供的一端不限制。
+pub fn resolve_range(
+    period: Option<TimePeriod>,
+    from: Option<&str>,
+    to: Option<&str>,
+    tz: &UserTimezone,
+) -> Result<TimeRange, AppError> {
+    if let Some(period) = period {
+        let (from, to) = period
+            .bounds(tz)
+            .ok_or_else(|| AppError::InvalidArgument(format!("Invalid period: {:?}", period)))?;
+        return Ok((Some(from), Some(to)));
+    }
+
+    let bound = |value: Option<&str>, end: bool| -> Result<Option<DateTime<Utc>>, AppError> {
+        let value = match value.map(str::trim).filter(|v| !v.is_empty()) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let due_date = DueDate::resolve(value, tz)?;
+        let instant = match (&due_date, end) {
+            (DueDate::AllDay(_), true) => due_date.deadline(tz),
+            (DueDate::AllDay(date), false) => start_of_day(*date, tz),
+            (DueDate::At(at), _) => Some(at.with_timezone(&Utc)),
+        };
+        instant
+            .map(Some)
+            .ok_or_else(|| AppError::InvalidArgument(format!("Unrecognized date: {}", value)))
+    };
+
+    Ok((bound(from, false)?, bound(to, true)?))
+}
+
+/// 计时汇总的分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeGroup {
+    #[default]
+    Todo,
+    /// 有多个标签的任务计入每个标签，没有标签的任务不出现在分组中
+    Tag,
+}
+
+/// 计时汇总的查询条件。`period` 优先于 `from`/`to`；
+/// `from`/`to` 为 ISO 日期/时间或自然语言，日期包含当天。都不提供时统计全部记录。
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReportQuery {
+    pub period: Option<TimePeriod>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub todo_id: Option<String>,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub group_by: TimeGroup,
+}
+
+/// 一个分组的合计：按任务分组时 `key` 为任务 ID、`label` 为任务内容，按标签分组时都是标签名称
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeTotal {
+    pub key: String,
+    pub label: String,
+    pub seconds: i64,
+    pub entry_count: u32,
+}
+
+/// 计时汇总。跨越统计范围边界的记录只计算范围内的部分。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReport {
+    /// 统计范围（UTC），不限制时为 `None`
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub total_seconds: i64,
+    /// 按合计时间从多到少排列
+    pub groups: Vec<TimeTotal>,
+}
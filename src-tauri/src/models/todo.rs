@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use super::due_date::{self, DueDate};
+use super::tag::TagMatch;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -20,6 +22,18 @@ impl TodoStatus {
         }
     }
 
+    /// 是否允许从当前状态变为 `to`：待办和进行中可以变为任意状态，已完成的任务可以重新打开（待办或进行中），
+    /// 已取消的任务只能恢复为待办。状态不变总是允许的。
+    pub fn can_transition_to(&self, to: &TodoStatus) -> bool {
+        match (self, to) {
+            (from, to) if from == to => true,
+            (TodoStatus::Pending | TodoStatus::InProgress, _) => true,
+            (TodoStatus::Completed, TodoStatus::Pending | TodoStatus::InProgress) => true,
+            (TodoStatus::Cancelled, TodoStatus::Pending) => true,
+            _ => false,
+        }
+    }
+
     pub fn from_str(s: &str) -> Self {
         match s {
             "pending" => TodoStatus::Pending,
@@ -41,6 +55,14 @@ pub enum Priority {
 }
 
 impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+
     pub fn as_i32(&self) -> i32 {
         match self {
             Priority::Low => 0,
@@ -67,30 +89,171 @@ pub struct Todo {
     pub completed: bool,
     pub status: TodoStatus,
     pub priority: Priority,
-    pub due_date: Option<String>,
+    /// 全天为 `YYYY-MM-DD`，具体时刻为带时区偏移的 RFC 3339
+    #[serde(default, deserialize_with = "due_date::deserialize_lenient")]
+    pub due_date: Option<DueDate>,
     pub tags: Vec<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// 移入回收站的时间，未删除时为 `None`
+    pub deleted_at: Option<String>,
+    /// 手动排序的位置，越小越靠前
+    #[serde(default)]
+    pub position: f64,
+    /// 父任务 ID，顶层任务为 `None`
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// 所有子任务完成后自动完成本任务；重复子任务的下一次不会生成在这样的任务下，而是作为顶层任务
+    #[serde(default)]
+    pub auto_complete: bool,
+    /// 直接子任务数（不含回收站），仅用于展示
+    #[serde(default)]
+    pub child_count: u32,
+    /// 已完成的直接子任务数
+    #[serde(default)]
+    pub completed_child_count: u32,
+    /// 依赖的任务 ID，这些任务完成后本任务才能开始
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// 尚未完成（且未取消、不在回收站）的依赖任务 ID
+    #[serde(default)]
+    pub blocked_by: Vec<String>,
+    /// 存在未完成的依赖任务
+    #[serde(default)]
+    pub blocked: bool,
+    /// 所属列表，`None` 表示在收件箱中
+    #[serde(default)]
+    pub list_id: Option<String>,
+    /// 重复规则（RRULE 子集，见 `RecurrenceRule`），完成后按规则生成下一次
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    /// 重复任务序列的 ID，即第一次任务的 ID
+    #[serde(default)]
+    pub series_id: Option<String>,
+    /// 本任务是序列中的第几次，从 1 开始
+    #[serde(default = "default_occurrence")]
+    pub occurrence: u32,
+    /// Markdown 格式的备注
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// 清单项，按顺序排列
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    /// 预估工作量（分钟），与实际计时比较以改进估算
+    #[serde(default)]
+    pub estimate_minutes: Option<u32>,
+    /// 预估的故事点数
+    #[serde(default)]
+    pub story_points: Option<f64>,
+    /// 第一次变为进行中的时间，重新打开后保留
+    #[serde(default)]
+    pub started_at: Option<String>,
+    /// 完成时间，只在状态为已完成时有值
+    #[serde(default)]
+    pub completed_at: Option<String>,
+    /// 取消时间，只在状态为已取消时有值
+    #[serde(default)]
+    pub cancelled_at: Option<String>,
+    /// 所处的工作流状态（看板列），`status` 由状态的类别决定；已取消的任务为 `None`
+    #[serde(default)]
+    pub state_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+fn default_occurrence() -> u32 {
+    1
+}
+
+/// 任务中的一个清单项：只有文本与完成状态，不是独立的任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistItem {
+    pub id: String,
+    pub text: String,
+    pub done: bool,
+}
+
+/// 修改清单项，未提供的字段保持不变
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateChecklistItemRequest {
+    pub text: Option<String>,
+    pub done: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTodoRequest {
     pub text: String,
     pub priority: Option<Priority>,
+    /// ISO 日期或自然语言（"明天下午三点"、"next friday"），保存前统一转换
     pub due_date: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub auto_complete: bool,
+    /// 同时创建的子任务，可以继续嵌套
+    #[serde(default)]
+    pub children: Vec<CreateTodoRequest>,
+    /// 子任务需按顺序完成：每个子任务依赖前一个
+    #[serde(default)]
+    pub sequential: bool,
+    /// 依赖的已有任务 ID
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// 所属列表，未指定时子任务沿用父任务的列表。未指定优先级时使用列表的默认优先级。
+    pub list_id: Option<String>,
+    /// 重复规则，例如 `FREQ=WEEKLY;BYDAY=MO`。没有截止日期时以今天起的第一次作为截止日期。
+    pub recurrence: Option<String>,
+    /// Markdown 格式的备注
+    pub notes: Option<String>,
+    /// 清单项的文本，按顺序创建
+    #[serde(default)]
+    pub checklist: Vec<String>,
+    pub estimate_minutes: Option<u32>,
+    pub story_points: Option<f64>,
+    /// 生成重复任务的下一次时使用：所属序列的 ID 与序号
+    #[serde(skip)]
+    pub series: Option<(String, u32)>,
 }
 
 #[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateTodoRequest {
     pub text: Option<String>,
+    /// 与 `status` 等价的简写：`true` 表示完成，`false` 表示把已完成的任务重新打开为待办。
+    /// 同时提供时两者必须一致。
     pub completed: Option<bool>,
+    /// 状态变化需符合 `TodoStatus::can_transition_to`
     pub status: Option<TodoStatus>,
     pub priority: Option<Priority>,
+    /// 与创建时格式相同，空字符串表示清除截止日期
     pub due_date: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub auto_complete: Option<bool>,
+    /// 移动到另一个列表（连同子任务），空字符串表示移回收件箱
+    pub list_id: Option<String>,
+    /// 设置重复规则，空字符串表示取消重复
+    pub recurrence: Option<String>,
+    /// 替换备注，空字符串表示清除
+    pub notes: Option<String>,
+    /// 预估分钟数，0 表示清除
+    pub estimate_minutes: Option<u32>,
+    /// 故事点数，0 表示清除
+    pub story_points: Option<f64>,
+    /// 移入另一个工作流状态，`status` 随状态的类别变化
+    pub state_id: Option<String>,
+    /// 追加到清单末尾的检查项，与其他修改在同一事务中写入
+    #[serde(default)]
+    pub add_checklist: Vec<String>,
+}
+
+/// 以某个任务为根的子树
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoTree {
+    #[serde(flatten)]
+    pub todo: Todo,
+    pub children: Vec<TodoTree>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -100,7 +263,137 @@ pub struct TodoFilter {
     pub completed: Option<bool>,
     pub priority: Option<Priority>,
     pub search: Option<String>,
+    /// 精确匹配单个标签（忽略大小写）
     pub tag: Option<String>,
+    /// 按多个标签过滤，匹配方式由 `tag_match` 决定
+    pub tags: Option<Vec<String>>,
+    pub tag_match: Option<TagMatch>,
+    /// 为 true 时只返回现在就可以开始的任务：未完成、没有未完成的依赖、也没有未完成的子任务
+    pub actionable: Option<bool>,
+    /// 只返回该列表中的任务，空字符串表示收件箱。
+    /// 未指定时不返回已归档列表中的任务。
+    pub list_id: Option<String>,
+    /// 截止时间不晚于该值，ISO 日期/时间或自然语言（"下周五"），按用户时区解释。
+    /// 日期表示包含当天。
+    pub due_before: Option<String>,
+    /// 截止时间不早于该值，格式同 `due_before`
+    pub due_after: Option<String>,
+    /// 为 true 时只返回已逾期的任务：截止时间已过且未完成、未取消
+    pub overdue: Option<bool>,
+    /// 只返回处于该工作流状态的任务
+    pub state_id: Option<String>,
+}
+
+impl TodoFilter {
+    /// 没有任何过滤条件（会匹配全部任务）
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.completed.is_none()
+            && self.priority.is_none()
+            && self.search.as_deref().map(|s| s.trim().is_empty()).unwrap_or(true)
+            && self.tag.is_none()
+            && self.tags.as_ref().map(|t| t.is_empty()).unwrap_or(true)
+            && self.actionable.is_none()
+            && self.list_id.is_none()
+            && self.due_before.as_deref().map(|s| s.trim().is_empty()).unwrap_or(true)
+            && self.due_after.as_deref().map(|s| s.trim().is_empty()).unwrap_or(true)
+            && self.overdue.is_none()
+            && self.state_id.is_none()
+    }
+}
+
+/// 批量操作的目标：显式的任务 ID 列表，或者一个过滤条件。
+/// JSON 形式为 `{"ids": [...]}` 或 `{"filter": {...}}`。
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TodoSelector {
+    Ids(Vec<String>),
+    Filter(TodoFilter),
+}
+
+/// 列表排序字段
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// 未完成在前、新建在前；有搜索词时按相关度
+    #[default]
+    Default,
+    Priority,
+    DueDate,
+    CreatedAt,
+    UpdatedAt,
+    Text,
+    /// 手动拖拽排序（`position`）
+    Manual,
+}
+
+impl SortKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortKey::Default => "default",
+            SortKey::Priority => "priority",
+            SortKey::DueDate => "due_date",
+            SortKey::CreatedAt => "created_at",
+            SortKey::UpdatedAt => "updated_at",
+            SortKey::Text => "text",
+            SortKey::Manual => "manual",
+        }
+    }
+
+    /// 未指定方向时使用的默认方向
+    pub fn default_direction(&self) -> SortDirection {
+        match self {
+            SortKey::DueDate | SortKey::Text | SortKey::Manual => SortDirection::Asc,
+            _ => SortDirection::Desc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// 移动任务时相对于目标任务的位置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    Before,
+    After,
+}
+
+/// 分页与排序参数。`limit` 为空时返回全部结果。
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoPageRequest {
+    pub limit: Option<u32>,
+    /// 上一页返回的 `next_cursor`，排序方式必须与上一页相同
+    pub cursor: Option<String>,
+    pub sort: Option<SortKey>,
+    pub direction: Option<SortDirection>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoPage {
+    pub items: Vec<Todo>,
+    /// 满足过滤条件的任务总数（不受分页影响）
+    pub total: u32,
+    /// 获取下一页使用的游标，没有更多结果时为 `None`
+    pub next_cursor: Option<String>,
+}
+
+/// 全文检索命中结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoSearchResult {
+    pub todo: Todo,
+    /// bm25 得分，越小越相关
+    pub rank: f64,
+    /// 命中片段，匹配词用 `<mark>` 包裹
+    pub snippet: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -111,4 +404,9 @@ pub struct TodoStatistics {
     pub pending: u32,
     pub in_progress: u32,
     pub cancelled: u32,
+    /// 坚持到计划时长的专注阶段数
+    pub focus_sessions: u32,
+    pub focus_sessions_today: u32,
+    /// 全部专注阶段（包括提前停止的）实际专注的秒数
+    pub focus_seconds: i64,
 }
@@ -0,0 +1,25 @@
+use serde::Serialize;
+use super::history::Actor;
+
+/// 一个可撤销的步骤：一次用户操作，或 AI 一轮对话中的全部修改
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoStep {
+    pub id: String,
+    /// 展示用的中文描述
+    pub label: String,
+    pub actor: Actor,
+    /// 涉及的任务数
+    pub todo_count: u32,
+    pub created_at: String,
+    pub undone_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoStack {
+    /// 可撤销的步骤，最近的在前
+    pub undo: Vec<UndoStep>,
+    /// 可重做的步骤，最近撤销的在前
+    pub redo: Vec<UndoStep>,
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use crate::models::todo::{Todo, TodoStatus};
+
+/// 工作流状态的类别，决定处于该状态的任务的 `TodoStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateCategory {
+    /// 尚未开始，对应 `Pending`
+    Open,
+    /// 正在处理，对应 `InProgress`
+    Active,
+    /// 已完成，对应 `Completed`
+    Done,
+}
+
+impl StateCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StateCategory::Open => "open",
+            StateCategory::Active => "active",
+            StateCategory::Done => "done",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "active" => StateCategory::Active,
+            "done" => StateCategory::Done,
+            _ => StateCategory::Open,
+        }
+    }
+
+    pub fn status(&self) -> TodoStatus {
+        match self {
+            StateCategory::Open => TodoStatus::Pending,
+            StateCategory::Active => TodoStatus::InProgress,
+            StateCategory::Done => TodoStatus::Completed,
+        }
+    }
+
+    /// 已取消的任务不属于任何状态
+    pub fn from_status(status: &TodoStatus) -> Option<Self> {
+        match status {
+            TodoStatus::Pending => Some(StateCategory::Open),
+            TodoStatus::InProgress => Some(StateCategory::Active),
+            TodoStatus::Completed => Some(StateCategory::Done),
+            TodoStatus::Cancelled => None,
+        }
+    }
+}
+
+/// 用户定义的工作流状态，即看板中的一列，例如"待评审"、"等待中"
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowState {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub category: StateCategory,
+    pub position: f64,
+    /// 同时处于该状态的任务数上限（WIP 限制），把任务移入该状态时检查
+    pub wip_limit: Option<u32>,
+    /// 处于该状态的任务数（不含回收站）
+    pub todo_count: u32,
+    /// 允许变为的状态 ID，为空时可以变为任意状态
+    pub transitions: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWorkflowStateRequest {
+    pub name: String,
+    pub color: Option<String>,
+    pub category: StateCategory,
+    pub wip_limit: Option<u32>,
+}
+
+/// 修改工作流状态，未提供的字段保持不变；`color` 传入空字符串、`wip_limit` 传入 0 表示清除。
+/// 类别不能修改，需要时新建状态并移动任务。
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWorkflowStateRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub wip_limit: Option<u32>,
+}
+
+/// 看板中的一列及其中的任务（按手动排序）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardColumn {
+    pub state: WorkflowState,
+    pub todos: Vec<Todo>,
+    /// 任务数超过了 WIP 限制
+    pub over_limit: bool,
+}
+
+/// 按工作流状态分列的任务，列按状态的顺序排列
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    pub columns: Vec<BoardColumn>,
+    /// 不属于任何状态的任务（已取消，或所在状态已不存在），按手动排序
+    pub unassigned: Vec<Todo>,
+}
@@ -1,57 +1,234 @@
 use tauri::{AppHandle, Emitter};
 use reqwest::Client;
 use futures::StreamExt;
+use chrono::Datelike;
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::models::ai::*;
+use crate::models::history::Actor;
+use crate::models::due_date::UserTimezone;
 use crate::models::settings::Settings;
-use crate::db::{TodoRepository, SettingsRepository};
+use crate::models::todo::{TodoFilter, TodoPage, TodoPageRequest};
+use crate::models::workflow::StateCategory;
+use crate::db::{TodoRepository, ListRepository, WorkflowRepository, HistoryRepository, UndoRepository, SettingsRepository};
 use crate::services::function_call::{FunctionExecutor, get_function_definitions, get_tools};
+use crate::services::FocusService;
 use crate::error::AppError;
 
+/// 系统提示词中最多列出的未完成任务数
+const CONTEXT_TODO_LIMIT: u32 = 10;
+
 pub struct AiService {
     http_client: Client,
     settings_repo: Arc<SettingsRepository>,
     todo_repo: Arc<TodoRepository>,
+    list_repo: Arc<ListRepository>,
+    workflow_repo: Arc<WorkflowRepository>,
+    history_repo: Arc<HistoryRepository>,
+    undo_repo: Arc<UndoRepository>,
+    focus_service: Arc<FocusService>,
     function_executor: Arc<FunctionExecutor>,
 }
 
 impl AiService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         settings_repo: Arc<SettingsRepository>,
         todo_repo: Arc<TodoRepository>,
+        list_repo: Arc<ListRepository>,
+        workflow_repo: Arc<WorkflowRepository>,
+        history_repo: Arc<HistoryRepository>,
+        undo_repo: Arc<UndoRepository>,
+        focus_service: Arc<FocusService>,
         function_executor: Arc<FunctionExecutor>,
     ) -> Self {
         Self {
             http_client: Client::new(),
             settings_repo,
             todo_repo,
+            list_repo,
+            workflow_repo,
+            history_repo,
+            undo_repo,
+            focus_service,
             function_executor,
         }
     }
 
+    fn conversation_id(request: &AiChatRequest) -> String {
+        request.conversation_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+    }
+
+    /// 系统提示词中展示的未完成任务，只取第一页。指定了当前列表时只取该列表中的任务。
+    fn pending_todos(&self, request: &AiChatRequest) -> Result<TodoPage, AppError> {
+        let filter = TodoFilter {
+            completed: Some(false),
+            list_id: request.list_id.clone(),
+            ..Default::default()
+        };
+        let page = TodoPageRequest {
+            limit: Some(CONTEXT_TODO_LIMIT),
+            ..Default::default()
+        };
+        self.todo_repo.get_page(Some(filter), page)
+    }
+
+    /// 工具调用的发起者。同一轮对话使用相同的 `turn_id`，其中的全部修改作为一个撤销步骤。
+    fn ai_actor(conversation_id: &str, turn_id: &str, tool_call_id: Option<&str>) -> Actor {
+        Actor::Ai {
+            conversation_id: Some(conversation_id.to_string()),
+            tool_call_id: tool_call_id.map(String::from),
+            turn_id: Some(turn_id.to_string()),
+        }
+    }
+
     /// 构建消息列表
-    fn build_messages(&self, settings: &Settings, request: &AiChatRequest, todos: &[crate::models::todo::Todo]) -> Vec<ChatMessage> {
+    fn build_messages(&self, settings: &Settings, request: &AiChatRequest, pending: &TodoPage) -> Vec<ChatMessage> {
         let mut messages = Vec::new();
 
+        // 未归档的列表，任务按所属列表标注，便于 AI 区分工作、购物等不同列表
+        let lists = self.list_repo.get_all(false).unwrap_or_default();
+        let list_names: HashMap<&str, &str> = lists.iter()
+            .map(|l| (l.id.as_str(), l.name.as_str()))
+            .collect();
+
+        // 工作流状态：默认的待办状态不在任务上标注
+        let states = self.workflow_repo.get_all().unwrap_or_default();
+        let state_names: HashMap<&str, &str> = states.iter()
+            .map(|s| (s.id.as_str(), s.name.as_str()))
+            .collect();
+        let default_state = states.iter().find(|s| s.category == StateCategory::Open).map(|s| s.id.as_str());
+
+        let tz = UserTimezone::parse(&settings.timezone).unwrap_or(UserTimezone::System);
+
         // 系统提示词，包含当前任务上下文
-        let todo_context = if todos.is_empty() {
+        let todo_context = if pending.items.is_empty() {
             "当前没有任何待办任务。".to_string()
         } else {
-            let pending: Vec<_> = todos.iter()
-                .filter(|t| !t.completed)
-                .take(10)
-                .map(|t| format!("- [{}] {} (ID: {})", if t.completed { "x" } else { " " }, t.text, &t.id[..8]))
+            let lines: Vec<_> = pending.items.iter()
+                .map(|t| {
+                    let progress = if t.child_count > 0 {
+                        format!(" [子任务 {}/{}]", t.completed_child_count, t.child_count)
+                    } else {
+                        String::new()
+                    };
+                    let checklist = if t.checklist.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" [清单 {}/{}]", t.checklist.iter().filter(|item| item.done).count(), t.checklist.len())
+                    };
+                    let blocked = if t.blocked {
+                        format!(" [等待 {} 个前置任务]", t.blocked_by.len())
+                    } else {
+                        String::new()
+                    };
+                    let estimate = match (t.estimate_minutes, t.story_points) {
+                        (Some(minutes), _) => format!(" [预估 {} 分钟]", minutes),
+                        (None, Some(points)) => format!(" [{} 点]", points),
+                        (None, None) => String::new(),
+                    };
+                    let state = t.state_id.as_deref()
+                        .filter(|id| Some(*id) != default_state)
+                        .and_then(|id| state_names.get(id))
+                        .map(|name| format!(" [{}]", name))
+                        .unwrap_or_default();
+                    let list = t.list_id.as_deref()
+                        .and_then(|id| list_names.get(id))
+                        .map(|name| format!(" #{}", name))
+                        .unwrap_or_default();
+                    let due_date = t.due_date.as_ref().map(|d| d.in_timezone(&tz).to_string());
+                    let schedule = match (t.recurrence.as_deref(), due_date) {
+                        (Some(rule), due_date) => format!(" [重复 {}，截止 {}]", rule, due_date.as_deref().unwrap_or("未设置")),
+                        (None, Some(due_date)) => format!(" [截止 {}]", due_date),
+                        (None, None) => String::new(),
+                    };
+                    format!("- [{}] {}{}{}{}{}{}{}{} (ID: {})", if t.completed { "x" } else { " " }, t.text, state, list, progress, checklist, blocked, schedule, estimate, &t.id[..8])
+                })
+                .collect();
+
+            let scope = request.list_id.as_deref()
+                .and_then(|id| list_names.get(id))
+                .map(|name| format!("（列表「{}」）", name))
+                .unwrap_or_default();
+            let mut context = format!("当前待办任务{}:\n{}", scope, lines.join("\n"));
+            if pending.total as usize > pending.items.len() {
+                context.push_str(&format!("\n（共 {} 个未完成任务，仅列出前 {} 个，需要时使用 query_todos 查询）", pending.total, pending.items.len()));
+            }
+            context
+        };
+
+        // 最近的修改记录，便于 AI 回答"刚才改了什么"
+        let history_context = match self.history_repo.get_recent(5) {
+            Ok(events) if !events.is_empty() => {
+                let lines: Vec<String> = events.iter().map(|e| format!("- {}", e.summary())).collect();
+                format!("\n\n最近的修改:\n{}", lines.join("\n"))
+            }
+            _ => String::new(),
+        };
+
+        let list_context = if lists.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<&str> = lists.iter().map(|l| l.name.as_str()).collect();
+            format!("\n\n任务列表: {}（未指定列表的任务在收件箱中）", names.join("、"))
+        };
+
+        let workflow_context = if states.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<String> = states.iter()
+                .map(|s| match s.wip_limit {
+                    Some(limit) => format!("{}（{}，最多 {} 个）", s.name, s.category.as_str(), limit),
+                    None => format!("{}（{}）", s.name, s.category.as_str()),
+                })
                 .collect();
+            format!("\n\n工作流状态（看板列）: {}", names.join("、"))
+        };
 
-            format!("当前待办任务:\n{}", pending.join("\n"))
+        // 专注状态与今天的专注次数，便于 AI 回答"我今天专注了多久"并避免在专注时打扰
+        let mut focus_lines = Vec::new();
+        if let Some(status) = self.focus_service.status() {
+            focus_lines.push(format!(
+                "- 正在{}{}：剩余 {} 分钟{}，本轮已完成 {} 个专注",
+                status.phase.label(),
+                status.todo_text.as_deref().map(|text| format!("「{}」", text)).unwrap_or_default(),
+                status.remaining_seconds.div_ceil(60),
+                if status.paused { "（已暂停）" } else { "" },
+                status.completed_cycles
+            ));
+        }
+        if let Ok(stats) = self.todo_repo.get_statistics(None) {
+            if stats.focus_sessions_today > 0 {
+                focus_lines.push(format!("- 今天已完成 {} 个专注", stats.focus_sessions_today));
+            }
+        }
+        let focus_context = if focus_lines.is_empty() {
+            String::new()
+        } else {
+            format!("\n\n专注:\n{}", focus_lines.join("\n"))
         };
 
+        // 当前时间，便于 AI 理解"明天"、"下周三"等相对日期
+        let now = tz.now();
+        let time_context = format!(
+            "当前时间: {} 星期{}\n\n",
+            now.format("%Y-%m-%d %H:%M"),
+            ["一", "二", "三", "四", "五", "六", "日"][now.weekday().num_days_from_monday() as usize]
+        );
+
         let system_prompt = format!(
-            "{}\n\n---\n{}",
+            "{}\n\n---\n{}{}{}{}{}{}",
             settings.system_prompt,
-            todo_context
+            time_context,
+            todo_context,
+            list_context,
+            workflow_context,
+            focus_context,
+            history_context
         );
 
         messages.push(ChatMessage {
@@ -83,13 +260,22 @@ impl AiService {
         messages
     }
 
-    /// 非流式聊天（支持 Function Call 循环）
-    pub async fn chat(&self, request: AiChatRequest) -> Result<AiChatResponse, AppError> {
+    /// 非流式聊天（支持 Function Call 循环）。
+    /// 本轮修改了任务时发出 `ai-changes-applied` 事件，中途出错时已执行的修改同样通知前端。
+    pub async fn chat(&self, app: &AppHandle, request: AiChatRequest) -> Result<AiChatResponse, AppError> {
+        let turn_id = uuid::Uuid::new_v4().to_string();
+        let result = self.chat_turn(request, &turn_id).await;
+        self.notify_changes(app, &turn_id)?;
+        result
+    }
+
+    async fn chat_turn(&self, request: AiChatRequest, turn_id: &str) -> Result<AiChatResponse, AppError> {
         log::info!("AI chat request received");
 
         let settings = self.settings_repo.get()?;
-        let todos = self.todo_repo.get_all(None)?;
-        let mut messages = self.build_messages(&settings, &request, &todos);
+        let pending = self.pending_todos(&request)?;
+        let mut messages = self.build_messages(&settings, &request, &pending);
+        let conversation_id = Self::conversation_id(&request);
         let mut function_results = Vec::new();
         let mut warnings = Vec::new();
 
@@ -109,9 +295,11 @@ impl AiService {
 
                 for tool_call in tool_calls {
                     if tool_call.tool_type == "function" {
+                        let actor = Self::ai_actor(&conversation_id, turn_id, Some(&tool_call.id));
                         let result = self.function_executor.execute(
                             &tool_call.function.name,
-                            &tool_call.function.arguments
+                            &tool_call.function.arguments,
+                            &actor,
                         )?;
 
                         function_results.push(FunctionResult {
@@ -142,7 +330,8 @@ impl AiService {
             if let Some(ref fc) = choice.message.function_call {
                 log::info!("Detected function call (legacy format): {}", fc.name);
 
-                let result = self.function_executor.execute(&fc.name, &fc.arguments)?;
+                let actor = Self::ai_actor(&conversation_id, turn_id, None);
+                let result = self.function_executor.execute(&fc.name, &fc.arguments, &actor)?;
 
                 function_results.push(FunctionResult {
                     function_name: fc.name.clone(),
@@ -179,8 +368,10 @@ impl AiService {
 
                         let mut cleaned_content = content.clone();
 
+                        let actor = Self::ai_actor(&conversation_id, turn_id, None);
+
                         for call in extracted {
-                            let result = self.function_executor.execute(&call.name, &call.arguments)?;
+                            let result = self.function_executor.execute(&call.name, &call.arguments, &actor)?;
 
                             function_results.push(FunctionResult {
                                 function_name: call.name.clone(),
@@ -221,7 +412,12 @@ impl AiService {
 
             // No function call detected - return final response
             let final_message = choice.message.content.clone().unwrap_or_default();
-            let updated_todos = self.todo_repo.get_all(None)?;
+            // 只有本轮修改了任务时才返回完整列表
+            let undo_step = self.undo_repo.get_step(turn_id)?;
+            let updated_todos = match undo_step {
+                Some(_) => Some(self.todo_repo.get_all(None)?),
+                None => None,
+            };
 
             return Ok(AiChatResponse {
                 message: final_message,
@@ -230,7 +426,8 @@ impl AiService {
                 } else {
                     Some(function_results)
                 },
-                updated_todos: Some(updated_todos),
+                updated_todos,
+                undo_step,
                 warnings: if warnings.is_empty() {
                     None
                 } else {
@@ -247,8 +444,10 @@ impl AiService {
         log::info!("AI streaming chat request received");
 
         let settings = self.settings_repo.get()?;
-        let todos = self.todo_repo.get_all(None)?;
-        let messages = self.build_messages(&settings, &request, &todos);
+        let pending = self.pending_todos(&request)?;
+        let messages = self.build_messages(&settings, &request, &pending);
+        let conversation_id = Self::conversation_id(&request);
+        let turn_id = uuid::Uuid::new_v4().to_string();
 
         let api_key = settings.api_key.as_ref()
             .ok_or(AppError::MissingApiKey)?;
@@ -323,13 +522,16 @@ impl AiService {
                     // 流结束
                     if !function_name.is_empty() {
                         // 执行 function call
-                        let result = self.function_executor.execute(&function_name, &function_args)?;
+                        let actor = Self::ai_actor(&conversation_id, &turn_id, None);
+                        let result = self.function_executor.execute(&function_name, &function_args, &actor)?;
                         app.emit("ai-stream-chunk", json!({
                             "function_call": {
                                 "name": function_name,
                                 "result": result
                             }
                         }))?;
+
+                        self.notify_changes(app, &turn_id)?;
                     }
 
                     app.emit("ai-stream-done", json!({
@@ -365,6 +567,14 @@ impl AiService {
         Ok(())
     }
 
+    /// 本轮修改了任务时通知前端可以整体撤销
+    fn notify_changes(&self, app: &AppHandle, turn_id: &str) -> Result<(), AppError> {
+        if let Some(step) = self.undo_repo.get_step(turn_id)? {
+            app.emit("ai-changes-applied", &step)?;
+        }
+        Ok(())
+    }
+
     /// 调用 OpenAI 兼容 API
     async fn call_api(
         &self,
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::db::AttachmentRepository;
+use crate::error::AppError;
+use crate::models::attachment::Attachment;
+
+/// 未被引用的文件至少保留这么久才会被清理，避免删除刚写入、元数据还未保存的文件
+pub const ORPHAN_GRACE: Duration = Duration::from_secs(60 * 60);
+
+const TMP_SUFFIX: &str = ".tmp";
+
+/// 附件文件的存取。
+///
+/// 文件按内容的 SHA-256 保存在 `dir/<哈希前两位>/<哈希>`，相同内容只保存一份；
+/// 删除附件只删除元数据，不再被引用的文件由 `collect_garbage` 统一清理。
+pub struct AttachmentService {
+    repo: Arc<AttachmentRepository>,
+    dir: PathBuf,
+}
+
+impl AttachmentService {
+    pub fn new(repo: Arc<AttachmentRepository>, dir: PathBuf) -> Self {
+        Self { repo, dir }
+    }
+
+    /// 复制 `source` 到附件目录并添加到任务
+    pub fn add(&self, todo_id: &str, source: &Path) -> Result<Attachment, AppError> {
+        let file_name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| AppError::InvalidArgument(format!("Not a file: {}", source.display())))?;
+        if !source.is_file() {
+            return Err(AppError::InvalidArgument(format!("Not a file: {}", source.display())));
+        }
+
+        let (hash, size_bytes) = store_blob(&self.dir, &mut File::open(source)?)?;
+        let mime_type = mime_guess::from_path(source).first_or_octet_stream().to_string();
+
+        let attachment = self.repo.create(todo_id, &file_name, &mime_type, size_bytes, &hash)?;
+        log::info!("Attached {} ({} bytes) to todo {}", file_name, size_bytes, todo_id);
+        Ok(attachment)
+    }
+
+    pub fn list(&self, todo_id: &str) -> Result<Vec<Attachment>, AppError> {
+        self.repo.get_for_todo(todo_id)
+    }
+
+    /// 删除附件的元数据，文件留给 `collect_garbage` 清理
+    pub fn remove(&self, id: &str) -> Result<(), AppError> {
+        self.repo.delete(id)
+    }
+
+    /// 准备用系统默认程序打开的文件。
+    ///
+    /// 附件目录中的文件没有扩展名，这里复制一份带原始文件名的副本到临时目录，返回副本的路径。
+    pub fn open_path(&self, id: &str) -> Result<PathBuf, AppError> {
+        let attachment = self.repo.get(id)?;
+        let blob = blob_path(&self.dir, &attachment.hash);
+        if !blob.is_file() {
+            return Err(AppError::AttachmentNotFound(id.to_string()));
+        }
+
+        let file_name = Path::new(&attachment.file_name)
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_else(|| "attachment".into());
+        let dir = std::env::temp_dir().join("aideo-attachments").join(&attachment.id);
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(file_name);
+        fs::copy(&blob, &path)?;
+        Ok(path)
+    }
+
+    /// 删除不再被任何附件引用的文件，返回删除的数量
+    pub fn collect_garbage(&self) -> Result<usize, AppError> {
+        let referenced = self.repo.referenced_hashes()?;
+        prune_blobs(&self.dir, &referenced, ORPHAN_GRACE)
+    }
+}
+
+/// 内容为 `hash` 的文件在附件目录中的位置
+pub fn blob_path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(&hash[..2.min(hash.len())]).join(hash)
+}
+
+/// 边计算哈希边写入临时文件，再移动到内容对应的位置。返回哈希与文件大小。
+fn store_blob(dir: &Path, source: &mut impl Read) -> Result<(String, u64), AppError> {
+    fs::create_dir_all(dir)?;
+    let tmp_path = dir.join(format!("{}{}", Uuid::new_v4(), TMP_SUFFIX));
+
+    let result = (|| {
+        let mut tmp = File::create(&tmp_path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 64 * 1024];
+        let mut size = 0u64;
+
+        loop {
+            let read = source.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            tmp.write_all(&buffer[..read])?;
+            size += read as u64;
+        }
+        tmp.sync_all()?;
+
+        let hash = format!("{:x}", hasher.finalize());
+        place_blob(&tmp_path, &blob_path(dir, &hash))?;
+        Ok::<_, io::Error>((hash, size))
+    })();
+
+    if result.is_err() || tmp_path.exists() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    Ok(result?)
+}
+
+/// 把临时文件移动到 `target`。相同内容的文件已存在时保留旧文件并刷新修改时间，
+/// 使其在清理时同样享有 `ORPHAN_GRACE` 保护期。
+fn place_blob(tmp_path: &Path, target: &Path) -> io::Result<()> {
+    if target.is_file() {
+        File::options().write(true).open(target)?.set_modified(SystemTime::now())?;
+        return fs::remove_file(tmp_path);
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(tmp_path, target)
+}
+
+/// 把 `hashes` 对应的文件从附件目录 `from` 复制到 `to`，用于备份与恢复。
+/// 源目录中缺失的文件只记录警告，返回复制的数量。
+pub fn copy_blobs(from: &Path, to: &Path, hashes: &HashSet<String>) -> Result<usize, AppError> {
+    let mut copied = 0;
+
+    for hash in hashes {
+        let source = blob_path(from, hash);
+        if !source.is_file() {
+            log::warn!("Attachment file {} is missing from {}", hash, from.display());
+            continue;
+        }
+
+        fs::create_dir_all(to)?;
+        let tmp_path = to.join(format!("{}{}", Uuid::new_v4(), TMP_SUFFIX));
+        let result = fs::copy(&source, &tmp_path).and_then(|_| place_blob(&tmp_path, &blob_path(to, hash)));
+        if result.is_err() || tmp_path.exists() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result?;
+        copied += 1;
+    }
+
+    Ok(copied)
+}
+
+/// 删除附件目录 `dir` 中不在 `keep` 里、且最近 `grace` 内没有修改过的文件（包括残留的临时文件），
+/// 返回删除的数量
+pub fn prune_blobs(dir: &Path, keep: &HashSet<String>, grace: Duration) -> Result<usize, AppError> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let now = SystemTime::now();
+    let is_stale = |path: &Path| -> io::Result<bool> {
+        let modified = fs::metadata(path)?.modified()?;
+        Ok(now.duration_since(modified).map(|age| age >= grace).unwrap_or(false))
+    };
+
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if path.is_file() && name.ends_with(TMP_SUFFIX) {
+            if is_stale(&path)? {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+            continue;
+        }
+        if !path.is_dir() || name.len() != 2 {
+            continue;
+        }
+
+        for blob in fs::read_dir(&path)? {
+            let blob = blob?;
+            let hash = blob.file_name().to_string_lossy().to_string();
+            if keep.contains(&hash) || !is_stale(&blob.path())? {
+                continue;
+            }
+            fs::remove_file(blob.path())?;
+            removed += 1;
+        }
+
+        // 目录为空时顺便删除，失败（例如仍有文件）不影响结果
+        let _ = fs::remove_dir(&path);
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TestDb, TodoRepository};
+    use crate::models::history::Actor;
+    use crate::models::todo::{CreateTodoRequest, Todo};
+
+    fn setup(test: &TestDb) -> (AttachmentService, TodoRepository) {
+        let repo = Arc::new(AttachmentRepository::new(test.db.clone()));
+        let service = AttachmentService::new(repo, test.dir().join("attachments"));
+        (service, TodoRepository::new(test.db.clone()))
+    }
+
+    fn create(todos: &TodoRepository, text: &str) -> Todo {
+        todos.create(CreateTodoRequest { text: text.into(), ..Default::default() }, &Actor::User).unwrap()
+    }
+
+    fn write_file(test: &TestDb, name: &str, content: &str) -> PathBuf {
+        let path = test.dir().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// 把文件的修改时间调到保护期之前，使其可以被清理
+    fn age(path: &Path) {
+        let old = SystemTime::now() - ORPHAN_GRACE - Duration::from_secs(60);
+        File::options().write(true).open(path).unwrap().set_modified(old).unwrap();
+    }
+
+    #[test]
+    fn identical_files_are_stored_once() {
+        let test = TestDb::new();
+        let (service, todos) = setup(&test);
+        let todo = create(&todos, "a");
+        let pdf = write_file(&test, "report.pdf", "same content");
+        let png = write_file(&test, "copy.png", "same content");
+
+        let first = service.add(&todo.id, &pdf).unwrap();
+        let second = service.add(&todo.id, &png).unwrap();
+
+        assert_eq!(first.hash, second.hash);
+        assert_eq!(first.size_bytes, 12);
+        assert_eq!(first.mime_type, "application/pdf");
+        assert_eq!(second.mime_type, "image/png");
+        assert_eq!(fs::read_to_string(blob_path(&test.dir().join("attachments"), &first.hash)).unwrap(), "same content");
+        assert_eq!(service.list(&todo.id).unwrap().len(), 2);
+
+        let opened = service.open_path(&first.id).unwrap();
+        assert_eq!(opened.file_name().unwrap(), "report.pdf");
+        assert!(matches!(service.add(&todo.id, test.dir()), Err(AppError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn garbage_collection_keeps_referenced_and_recent_files() {
+        let test = TestDb::new();
+        let (service, todos) = setup(&test);
+        let todo = create(&todos, "a");
+        let shared = write_file(&test, "shared.txt", "shared");
+        let single = write_file(&test, "single.txt", "single");
+        let dir = test.dir().join("attachments");
+
+        let first = service.add(&todo.id, &shared).unwrap();
+        service.add(&todo.id, &shared).unwrap();
+        let only = service.add(&todo.id, &single).unwrap();
+        for hash in [&first.hash, &only.hash] {
+            age(&blob_path(&dir, hash));
+        }
+
+        // 仍有另一个附件引用相同内容
+        service.remove(&first.id).unwrap();
+        service.remove(&only.id).unwrap();
+        assert_eq!(service.collect_garbage().unwrap(), 1);
+        assert!(blob_path(&dir, &first.hash).is_file());
+        assert!(!blob_path(&dir, &only.hash).exists());
+
+        // 刚写入的文件在保护期内不会被清理，即使已经没有引用
+        let fresh = service.add(&todo.id, &single).unwrap();
+        service.remove(&fresh.id).unwrap();
+        assert_eq!(service.collect_garbage().unwrap(), 0);
+        assert!(blob_path(&dir, &fresh.hash).is_file());
+    }
+
+    #[test]
+    fn purging_a_todo_releases_its_files() {
+        let test = TestDb::new();
+        let (service, todos) = setup(&test);
+        let todo = create(&todos, "a");
+        let attachment = service.add(&todo.id, &write_file(&test, "notes.txt", "notes")).unwrap();
+        let blob = blob_path(&test.dir().join("attachments"), &attachment.hash);
+        age(&blob);
+
+        // 回收站中的任务仍保留附件，永久删除后文件才可以清理
+        todos.delete(&todo.id, &Actor::User).unwrap();
+        assert_eq!(service.collect_garbage().unwrap(), 0);
+
+        todos.empty_trash(&Actor::User).unwrap();
+        assert!(matches!(service.open_path(&attachment.id), Err(AppError::AttachmentNotFound(_))));
+        assert_eq!(service.collect_garbage().unwrap(), 1);
+        assert!(!blob.exists());
+    }
+}
+
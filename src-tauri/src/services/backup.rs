@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::db::{self, migrations, Database, MigrationReport};
+use crate::error::AppError;
+use crate::models::backup::{BackupInfo, BackupKind};
+use crate::services::attachment;
+
+/// 轮换时各类备份保留的数量，手动备份不自动删除
+const DAILY_KEEP: usize = 7;
+const WEEKLY_KEEP: usize = 4;
+const PRE_RESTORE_KEEP: usize = 3;
+
+const FILE_PREFIX: &str = "aideo-";
+const FILE_SUFFIX: &str = ".db";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%3f";
+
+/// 数据库备份：在线快照、定期轮换与恢复。
+///
+/// 备份文件保存在 `dir` 中，文件名形如 `aideo-daily-20240101-120000000.db`，
+/// 类型与创建时间都从文件名解析，不需要额外的索引文件。
+/// 备份引用的附件文件复制到 `dir/attachments`，由全部备份共享，轮换时删除不再被引用的文件。
+pub struct BackupService {
+    db: Arc<Database>,
+    dir: PathBuf,
+    attachments_dir: PathBuf,
+}
+
+impl BackupService {
+    pub fn new(db: Arc<Database>, dir: PathBuf, attachments_dir: PathBuf) -> Self {
+        Self { db, dir, attachments_dir }
+    }
+
+    /// 备份中附件文件的存放位置
+    fn blob_dir(&self) -> PathBuf {
+        self.dir.join("attachments")
+    }
+
+    /// 创建一个备份。快照先写入临时文件，通过完整性检查后才会出现在备份列表中。
+    pub fn create(&self, kind: BackupKind) -> Result<BackupInfo, AppError> {
+        fs::create_dir_all(&self.dir)?;
+
+        let now = Utc::now();
+        let file_name = format!(
+            "{}{}-{}{}",
+            FILE_PREFIX,
+            kind.as_str(),
+            now.format(TIMESTAMP_FORMAT),
+            FILE_SUFFIX
+        );
+        let path = self.dir.join(&file_name);
+        let tmp_path = self.dir.join(format!("{}.tmp", file_name));
+
+        if tmp_path.exists() {
+            fs::remove_file(&tmp_path)?;
+        }
+
+        if let Err(e) = self.db.backup_to(&tmp_path) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        let problems = db::integrity_check(&tmp_path)?;
+        if !problems.is_empty() {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(AppError::BackupCorrupted(problems.join("; ")));
+        }
+
+        let copied = db::attachment_hashes_of(&tmp_path)
+            .and_then(|hashes| attachment::copy_blobs(&self.attachments_dir, &self.blob_dir(), &hashes));
+        if let Err(e) = copied {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        fs::rename(&tmp_path, &path)?;
+        log::info!("Created {} backup {}", kind.as_str(), file_name);
+
+        Ok(BackupInfo {
+            file_name,
+            kind,
+            created_at: now.to_rfc3339(),
+            size_bytes: fs::metadata(&path)?.len(),
+        })
+    }
+
+    /// 列出全部备份，最新的在前
+    pub fn list(&self) -> Result<Vec<BackupInfo>, AppError> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            if let Some((kind, created_at)) = parse_file_name(&file_name) {
+                backups.push(BackupInfo {
+                    file_name,
+                    kind,
+                    created_at: created_at.to_rfc3339(),
+                    size_bytes: entry.metadata()?.len(),
+                });
+            }
+        }
+
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(backups)
+    }
+
+    /// 用指定备份替换当前数据。
+    ///
+    /// 恢复前会校验备份的完整性与 schema 版本，并先为当前数据创建一个 `PreRestore` 备份，
+    /// 再把备份引用的附件文件复制回附件目录；恢复后自动执行迁移，返回迁移报告。
+    pub fn restore(&self, file_name: &str) -> Result<MigrationReport, AppError> {
+        let path = self.resolve(file_name)?;
+
+        let problems = db::integrity_check(&path)?;
+        if !problems.is_empty() {
+            return Err(AppError::BackupCorrupted(problems.join("; ")));
+        }
+
+        let version = db::schema_version_of(&path)?;
+        let supported = migrations::latest_version();
+        if version > supported {
+            return Err(AppError::SchemaTooNew { found: version, supported });
+        }
+
+        self.create(BackupKind::PreRestore)?;
+        let hashes = db::attachment_hashes_of(&path)?;
+        attachment::copy_blobs(&self.blob_dir(), &self.attachments_dir, &hashes)?;
+
+        let report = self.db.restore_from(&path)?;
+        log::info!("Restored database from backup {}", file_name);
+
+        self.rotate()?;
+        Ok(report)
+    }
+
+    /// 定期备份：距上一次每日/每周备份超过一天/一周时创建新的备份，然后执行轮换。
+    /// 返回本次新建的备份。
+    pub fn run_scheduled(&self) -> Result<Vec<BackupInfo>, AppError> {
+        let existing = self.list()?;
+        let now = Utc::now();
+        let mut created = Vec::new();
+
+        let schedule = [
+            (BackupKind::Daily, chrono::Duration::days(1)),
+            (BackupKind::Weekly, chrono::Duration::weeks(1)),
+        ];
+
+        for (kind, period) in schedule {
+            let latest = existing
+                .iter()
+                .filter(|b| b.kind == kind)
+                .filter_map(|b| DateTime::parse_from_rfc3339(&b.created_at).ok())
+                .max();
+
+            let due = match latest {
+                Some(time) => now.signed_duration_since(time) >= period,
+                None => true,
+            };
+
+            if due {
+                created.push(self.create(kind)?);
+            }
+        }
+
+        self.rotate()?;
+        Ok(created)
+    }
+
+    /// 按类型删除超出保留数量的旧备份，再删除剩余备份都不再引用的附件文件
+    fn rotate(&self) -> Result<(), AppError> {
+        let backups = self.list()?;
+
+        let limits = [
+            (BackupKind::Daily, DAILY_KEEP),
+            (BackupKind::Weekly, WEEKLY_KEEP),
+            (BackupKind::PreRestore, PRE_RESTORE_KEEP),
+        ];
+
+        for (kind, keep) in limits {
+            // 列表已按时间倒序排列，跳过最新的 `keep` 个
+            for backup in backups.iter().filter(|b| b.kind == kind).skip(keep) {
+                fs::remove_file(self.dir.join(&backup.file_name))?;
+                log::info!("Removed old backup {}", backup.file_name);
+            }
+        }
+
+        let mut referenced = HashSet::new();
+        for backup in self.list()? {
+            referenced.extend(db::attachment_hashes_of(&self.dir.join(&backup.file_name))?);
+        }
+        // 宽限期与附件目录的清理相同，避免删除正在创建的备份刚复制的文件
+        let removed = attachment::prune_blobs(&self.blob_dir(), &referenced, attachment::ORPHAN_GRACE)?;
+        if removed > 0 {
+            log::info!("Removed {} attachment files no longer referenced by backups", removed);
+        }
+
+        Ok(())
+    }
+
+    /// 将前端传入的文件名解析为备份目录中的路径，拒绝目录穿越和非备份文件
+    fn resolve(&self, file_name: &str) -> Result<PathBuf, AppError> {
+        let is_plain_name = Path::new(file_name).file_name().map(|n| n == file_name).unwrap_or(false);
+        if !is_plain_name || parse_file_name(file_name).is_none() {
+            return Err(AppError::BackupNotFound(file_name.to_string()));
+        }
+
+        let path = self.dir.join(file_name);
+        if !path.is_file() {
+            return Err(AppError::BackupNotFound(file_name.to_string()));
+        }
+        Ok(path)
+    }
+}
+
+/// 从文件名解析备份类型与创建时间，不是备份文件时返回 `None`
+fn parse_file_name(file_name: &str) -> Option<(BackupKind, DateTime<Utc>)> {
+    let stem = file_name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?;
+    let (kind, timestamp) = stem.split_once('-')?;
+
+    let kind = BackupKind::parse(kind)?;
+    let created_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+
+    Some((kind, created_at.and_utc()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TestDb, TodoRepository};
+    use crate::models::todo::CreateTodoRequest;
+    use crate::models::history::Actor;
+
+    fn service(test: &TestDb) -> BackupService {
+        BackupService::new(test.db.clone(), test.dir().join("backups"), test.dir().join("attachments"))
+    }
+
+    fn texts(todos: &TodoRepository) -> Vec<String> {
+        let mut texts: Vec<String> = todos.get_all(None).unwrap().into_iter().map(|t| t.text).collect();
+        texts.sort();
+        texts
+    }
+
+    fn count(backups: &[BackupInfo], kind: BackupKind) -> usize {
+        backups.iter().filter(|b| b.kind == kind).count()
+    }
+
+    #[test]
+    fn restore_brings_back_the_backed_up_data() {
+        let test = TestDb::new();
+        let backups = service(&test);
+        let todos = TodoRepository::new(test.db.clone());
+        let kept = todos.create(CreateTodoRequest { text: "kept".into(), ..Default::default() }, &Actor::User).unwrap();
+
+        let backup = backups.create(BackupKind::Manual).unwrap();
+        assert!(backup.size_bytes > 0);
+
+        todos.delete(&kept.id, &Actor::User).unwrap();
+        todos.create(CreateTodoRequest { text: "added later".into(), ..Default::default() }, &Actor::User).unwrap();
+        assert_eq!(texts(&todos), vec!["added later"]);
+
+        let report = backups.restore(&backup.file_name).unwrap();
+        assert!(report.pending.is_empty());
+        assert_eq!(texts(&todos), vec!["kept"]);
+
+        // 恢复前的数据保存在 PreRestore 备份中，可以再恢复回来
+        let listed = backups.list().unwrap();
+        assert_eq!(count(&listed, BackupKind::PreRestore), 1);
+        let pre_restore = listed.iter().find(|b| b.kind == BackupKind::PreRestore).unwrap();
+        backups.restore(&pre_restore.file_name).unwrap();
+        assert_eq!(texts(&todos), vec!["added later"]);
+    }
+
+    #[test]
+    fn restore_rejects_unknown_files_and_newer_schemas() {
+        let test = TestDb::new();
+        let backups = service(&test);
+        let backup = backups.create(BackupKind::Manual).unwrap();
+
+        for name in ["../aideo.db", "aideo-manual-20240101-000000000.db", "notes.txt"] {
+            assert!(matches!(backups.restore(name), Err(AppError::BackupNotFound(_))));
+        }
+
+        let path = test.dir().join("backups").join(&backup.file_name);
+        let future = migrations::latest_version() + 1;
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute(
+                "INSERT INTO schema_version (version, name, applied_at) VALUES (?1, 'from_the_future', ?2)",
+                (future, Utc::now().to_rfc3339()),
+            )
+            .unwrap();
+
+        match backups.restore(&backup.file_name) {
+            Err(AppError::SchemaTooNew { found, supported }) => {
+                assert_eq!(found, future);
+                assert_eq!(supported, migrations::latest_version());
+            }
+            other => panic!("expected SchemaTooNew, got {:?}", other.map(|_| ())),
+        }
+        // 被拒绝的恢复不会创建 PreRestore 备份
+        assert_eq!(count(&backups.list().unwrap(), BackupKind::PreRestore), 0);
+    }
+
+    #[test]
+    fn rotate_keeps_the_newest_backups_of_each_kind() {
+        let test = TestDb::new();
+        let backups = service(&test);
+        let dir = test.dir().join("backups");
+        let source = dir.join(backups.create(BackupKind::Manual).unwrap().file_name);
+
+        // 复制同一个快照并在文件名中写入不同的时间，避免依赖创建时的时钟精度
+        for (kind, copies) in [(BackupKind::Daily, 10), (BackupKind::Weekly, 6), (BackupKind::Manual, 3)] {
+            for day in 1..=copies {
+                let name = format!("{}{}-202401{:02}-000000000{}", FILE_PREFIX, kind.as_str(), day, FILE_SUFFIX);
+                fs::copy(&source, dir.join(name)).unwrap();
+            }
+        }
+
+        backups.rotate().unwrap();
+
+        let listed = backups.list().unwrap();
+        assert_eq!(count(&listed, BackupKind::Daily), DAILY_KEEP);
+        assert_eq!(count(&listed, BackupKind::Weekly), WEEKLY_KEEP);
+        assert_eq!(count(&listed, BackupKind::Manual), 4);
+
+        let oldest_daily = listed.iter().rfind(|b| b.kind == BackupKind::Daily).unwrap();
+        assert_eq!(oldest_daily.file_name, "aideo-daily-20240104-000000000.db");
+    }
+}
+
@@ -0,0 +1,444 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tauri::{AppHandle, Emitter};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::db::{FocusRepository, SettingsRepository, TodoRepository};
+use crate::error::AppError;
+use crate::models::due_date::instant_sql;
+use crate::models::focus::*;
+
+/// `focus-tick` 事件的间隔
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 进行中的一轮专注：专注与休息阶段交替进行，直到被停止
+struct FocusRun {
+    /// 每次开始新的一轮时递增，旧一轮的计时任务据此退出
+    id: u64,
+    todo_id: Option<String>,
+    todo_text: Option<String>,
+    config: FocusConfig,
+    phase: FocusPhase,
+    completed_cycles: u32,
+    phase_started_at: DateTime<Utc>,
+    /// 上次开始或继续时阶段剩余的时间
+    remaining: Duration,
+    /// 上次开始或继续的时刻，暂停时为 `None`
+    resumed_at: Option<Instant>,
+    interruptions: Vec<FocusInterruption>,
+}
+
+impl FocusRun {
+    fn remaining(&self, now: Instant) -> Duration {
+        match self.resumed_at {
+            Some(resumed_at) => self.remaining.saturating_sub(now - resumed_at),
+            None => self.remaining,
+        }
+    }
+
+    fn status(&self, now: Instant) -> FocusStatus {
+        FocusStatus {
+            todo_id: self.todo_id.clone(),
+            todo_text: self.todo_text.clone(),
+            phase: self.phase,
+            completed_cycles: self.completed_cycles,
+            phase_seconds: self.config.phase_seconds(self.phase),
+            remaining_seconds: self.remaining(now).as_secs(),
+            paused: self.resumed_at.is_none(),
+            interruptions: self.interruptions.len() as u32,
+            config: self.config,
+        }
+    }
+
+    fn enter(&mut self, phase: FocusPhase, now: Instant) {
+        self.phase = phase;
+        self.phase_started_at = Utc::now();
+        self.remaining = Duration::from_secs(self.config.phase_seconds(phase));
+        self.resumed_at = Some(now);
+        self.interruptions.clear();
+    }
+
+    /// 结束当前阶段时要保存的记录，只有专注阶段才有
+    fn session(&self, now: Instant, completed: bool) -> Option<FocusSession> {
+        if self.phase != FocusPhase::Work {
+            return None;
+        }
+
+        let planned_seconds = self.config.phase_seconds(FocusPhase::Work);
+        Some(FocusSession {
+            id: Uuid::new_v4().to_string(),
+            todo_id: self.todo_id.clone(),
+            todo_text: self.todo_text.clone(),
+            started_at: instant_sql(&self.phase_started_at),
+            ended_at: instant_sql(&Utc::now()),
+            planned_seconds,
+            focused_seconds: planned_seconds.saturating_sub(self.remaining(now).as_secs()),
+            completed,
+            interruptions: self.interruptions.clone(),
+        })
+    }
+
+    /// 当前阶段计时结束，进入下一阶段：专注后休息，每 `long_break_every` 次专注后长休息
+    fn advance(&mut self, now: Instant) {
+        let next = match self.phase {
+            FocusPhase::Work => {
+                self.completed_cycles += 1;
+                if self.completed_cycles.is_multiple_of(self.config.long_break_every) {
+                    FocusPhase::LongBreak
+                } else {
+                    FocusPhase::ShortBreak
+                }
+            }
+            FocusPhase::ShortBreak | FocusPhase::LongBreak => FocusPhase::Work,
+        };
+        self.enter(next, now);
+    }
+}
+
+/// 专注（番茄钟）计时。
+///
+/// 状态只保存在内存中，计时任务运行在 Tokio 运行时上，每秒发出 `focus-tick` 事件，
+/// 阶段切换时发出 `focus-phase-changed` 事件。专注阶段结束（包括提前停止）时保存为 `FocusSession`。
+pub struct FocusService {
+    repo: Arc<FocusRepository>,
+    todo_repo: Arc<TodoRepository>,
+    settings_repo: Arc<SettingsRepository>,
+    run: Arc<Mutex<Option<FocusRun>>>,
+    next_run_id: AtomicU64,
+}
+
+impl FocusService {
+    pub fn new(
+        repo: Arc<FocusRepository>,
+        todo_repo: Arc<TodoRepository>,
+        settings_repo: Arc<SettingsRepository>,
+    ) -> Self {
+        Self {
+            repo,
+            todo_repo,
+            settings_repo,
+            run: Arc::new(Mutex::new(None)),
+            next_run_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 开始新的一轮专注，从专注阶段开始。已有进行中的专注时先将其停止。
+    /// 未提供 `config` 时使用设置中的时长。
+    pub fn start(&self, app: AppHandle, todo_id: Option<&str>, config: Option<FocusConfig>) -> Result<FocusStatus, AppError> {
+        let config = match config {
+            Some(config) => config,
+            None => self.settings_repo.get()?.focus,
+        };
+        config.validate()?;
+
+        let todo = match todo_id {
+            Some(todo_id) => {
+                let todo = self.todo_repo.get_by_id(todo_id)?;
+                if todo.deleted_at.is_some() {
+                    return Err(AppError::TodoNotFound(todo_id.to_string()));
+                }
+                Some(todo)
+            }
+            None => None,
+        };
+
+        self.stop()?;
+
+        let now = Instant::now();
+        let id = self.next_run_id.fetch_add(1, Ordering::Relaxed);
+        let mut run = FocusRun {
+            id,
+            todo_id: todo.as_ref().map(|t| t.id.clone()),
+            todo_text: todo.map(|t| t.text),
+            config,
+            phase: FocusPhase::Work,
+            completed_cycles: 0,
+            phase_started_at: Utc::now(),
+            remaining: Duration::ZERO,
+            resumed_at: None,
+            interruptions: Vec::new(),
+        };
+        run.enter(FocusPhase::Work, now);
+        let status = run.status(now);
+
+        *self.run.lock().unwrap() = Some(run);
+        self.spawn_ticker(app, id);
+        Ok(status)
+    }
+
+    /// 当前专注的状态，没有进行中的专注时返回 `None`
+    pub fn status(&self) -> Option<FocusStatus> {
+        self.run.lock().unwrap().as_ref().map(|run| run.status(Instant::now()))
+    }
+
+    pub fn pause(&self) -> Result<FocusStatus, AppError> {
+        self.update_run(|run, now| {
+            run.remaining = run.remaining(now);
+            run.resumed_at = None;
+            Ok(())
+        })
+    }
+
+    pub fn resume(&self) -> Result<FocusStatus, AppError> {
+        self.update_run(|run, now| {
+            if run.resumed_at.is_none() {
+                run.resumed_at = Some(now);
+            }
+            Ok(())
+        })
+    }
+
+    /// 在当前专注阶段中记录一次打断，休息阶段不能记录
+    pub fn log_interruption(&self, note: Option<&str>) -> Result<FocusStatus, AppError> {
+        let note = note.map(str::trim).filter(|n| !n.is_empty()).map(String::from);
+
+        self.update_run(|run, _| {
+            if run.phase != FocusPhase::Work {
+                return Err(AppError::InvalidArgument("Interruptions can only be logged while focusing".into()));
+            }
+            run.interruptions.push(FocusInterruption {
+                occurred_at: instant_sql(&Utc::now()),
+                note,
+            });
+            Ok(())
+        })
+    }
+
+    /// 停止专注。处于专注阶段时保存为未完成的记录并返回，没有进行中的专注或处于休息阶段时返回 `None`。
+    pub fn stop(&self) -> Result<Option<FocusSession>, AppError> {
+        let session = match self.run.lock().unwrap().take() {
+            Some(run) => run.session(Instant::now(), false),
+            None => None,
+        };
+
+        match session {
+            Some(session) if session.focused_seconds > 0 => Ok(Some(self.repo.save(&session)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// 最近的专注记录，指定任务时只返回该任务的记录
+    pub fn sessions(&self, todo_id: Option<&str>, limit: u32) -> Result<Vec<FocusSession>, AppError> {
+        self.repo.get_recent(todo_id, limit)
+    }
+
+    fn update_run<F>(&self, change: F) -> Result<FocusStatus, AppError>
+    where
+        F: FnOnce(&mut FocusRun, Instant) -> Result<(), AppError>,
+    {
+        let mut guard = self.run.lock().unwrap();
+        let run = guard.as_mut().ok_or(AppError::FocusNotRunning)?;
+        let now = Instant::now();
+        change(run, now)?;
+        Ok(run.status(now))
+    }
+
+    /// 启动一轮专注的计时任务，专注被停止或开始了新的一轮后退出
+    fn spawn_ticker(&self, app: AppHandle, run_id: u64) {
+        let run = self.run.clone();
+        let repo = self.repo.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                let now = Instant::now();
+                let (status, finished) = {
+                    let mut guard = run.lock().unwrap();
+                    let current = match guard.as_mut() {
+                        Some(current) if current.id == run_id => current,
+                        _ => break,
+                    };
+                    if current.resumed_at.is_none() {
+                        continue;
+                    }
+
+                    if current.remaining(now).is_zero() {
+                        let finished = (current.phase, current.session(now, true));
+                        current.advance(now);
+                        (current.status(now), Some(finished))
+                    } else {
+                        (current.status(now), None)
+                    }
+                };
+
+                let (finished, session) = match finished {
+                    Some(finished) => finished,
+                    None => {
+                        if let Err(e) = app.emit("focus-tick", &status) {
+                            log::error!("Failed to emit focus tick: {}", e);
+                        }
+                        continue;
+                    }
+                };
+
+                let session = match session {
+                    Some(session) => {
+                        let repo = repo.clone();
+                        match tauri::async_runtime::spawn_blocking(move || repo.save(&session)).await {
+                            Ok(Ok(saved)) => Some(saved),
+                            Ok(Err(e)) => {
+                                log::error!("Failed to save focus session: {}", e);
+                                None
+                            }
+                            Err(e) => {
+                                log::error!("Focus session save task panicked: {}", e);
+                                None
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                log::info!("Focus phase {} finished, now {}", finished.label(), status.phase.label());
+                let change = FocusPhaseChange { finished, status, session };
+                if let Err(e) = app.emit("focus-phase-changed", &change) {
+                    log::error!("Failed to emit focus phase change: {}", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDb;
+    use crate::models::history::Actor;
+    use crate::models::todo::CreateTodoRequest;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn new_run(config: FocusConfig, now: Instant) -> FocusRun {
+        let mut run = FocusRun {
+            id: 1,
+            todo_id: None,
+            todo_text: None,
+            config,
+            phase: FocusPhase::Work,
+            completed_cycles: 0,
+            phase_started_at: Utc::now(),
+            remaining: Duration::ZERO,
+            resumed_at: None,
+            interruptions: Vec::new(),
+        };
+        run.enter(FocusPhase::Work, now);
+        run
+    }
+
+    fn service(test: &TestDb) -> (FocusService, Arc<TodoRepository>) {
+        let todo_repo = Arc::new(TodoRepository::new(test.db.clone()));
+        let service = FocusService::new(
+            Arc::new(FocusRepository::new(test.db.clone())),
+            todo_repo.clone(),
+            Arc::new(SettingsRepository::new(test.db.clone())),
+        );
+        (service, todo_repo)
+    }
+
+    #[test]
+    fn phases_alternate_with_a_long_break_every_n_cycles() {
+        let config = FocusConfig { long_break_every: 2, ..Default::default() };
+        let now = Instant::now();
+        let mut run = new_run(config, now);
+
+        let mut phases = Vec::new();
+        for _ in 0..5 {
+            run.advance(now);
+            phases.push(run.phase);
+        }
+
+        use FocusPhase::*;
+        assert_eq!(phases, vec![ShortBreak, Work, LongBreak, Work, ShortBreak]);
+        assert_eq!(run.completed_cycles, 3);
+        assert_eq!(run.status(now).remaining_seconds, 5 * 60);
+    }
+
+    #[test]
+    fn only_work_phases_produce_sessions() {
+        let start = Instant::now();
+        let mut run = new_run(FocusConfig::default(), start);
+        run.interruptions.push(FocusInterruption { occurred_at: instant_sql(&Utc::now()), note: None });
+
+        let session = run.session(start + 10 * MINUTE, false).unwrap();
+        assert_eq!(session.planned_seconds, 25 * 60);
+        assert_eq!(session.focused_seconds, 10 * 60);
+        assert_eq!(session.interruptions.len(), 1);
+
+        // 进入休息时清空打断记录，休息阶段不产生记录
+        run.advance(start + 25 * MINUTE);
+        assert!(run.interruptions.is_empty());
+        assert!(run.session(start + 26 * MINUTE, true).is_none());
+    }
+
+    #[test]
+    fn paused_runs_keep_their_remaining_time() {
+        let test = TestDb::new();
+        let (service, _) = service(&test);
+        assert!(matches!(service.pause(), Err(AppError::FocusNotRunning)));
+
+        // 模拟已经专注了 10 分钟
+        let mut run = new_run(FocusConfig::default(), Instant::now());
+        run.resumed_at = run.resumed_at.map(|at| at - 10 * MINUTE);
+        *service.run.lock().unwrap() = Some(run);
+
+        let paused = service.pause().unwrap();
+        assert!(paused.paused);
+        assert_eq!(paused.remaining_seconds / 60, 14);
+        std::thread::sleep(Duration::from_millis(1100));
+        assert_eq!(service.status().unwrap().remaining_seconds, paused.remaining_seconds);
+
+        let resumed = service.resume().unwrap();
+        assert!(!resumed.paused);
+        assert!(resumed.remaining_seconds <= paused.remaining_seconds);
+    }
+
+    #[test]
+    fn stopping_early_saves_an_incomplete_session() {
+        let test = TestDb::new();
+        let (service, todos) = service(&test);
+        let todo = todos.create(CreateTodoRequest { text: "write".into(), ..Default::default() }, &Actor::User).unwrap();
+
+        let mut run = new_run(FocusConfig::default(), Instant::now());
+        run.todo_id = Some(todo.id.clone());
+        run.resumed_at = run.resumed_at.map(|at| at - 10 * MINUTE);
+        *service.run.lock().unwrap() = Some(run);
+
+        assert_eq!(service.log_interruption(Some("  phone call ")).unwrap().interruptions, 1);
+        let session = service.stop().unwrap().unwrap();
+        assert!(!session.completed);
+        assert_eq!(session.focused_seconds / 60, 10);
+        assert_eq!(session.interruptions[0].note.as_deref(), Some("phone call"));
+        assert!(service.status().is_none());
+        assert!(service.stop().unwrap().is_none());
+
+        let saved = service.sessions(Some(&todo.id), 10).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].todo_text.as_deref(), Some("write"));
+        let stats = todos.get_statistics(None).unwrap();
+        assert_eq!(stats.focus_sessions, 0);
+        assert_eq!(stats.focus_seconds / 60, 10);
+    }
+
+    #[test]
+    fn interruptions_are_rejected_during_breaks() {
+        let test = TestDb::new();
+        let (service, _) = service(&test);
+        let now = Instant::now();
+        let mut run = new_run(FocusConfig::default(), now);
+        run.advance(now);
+        *service.run.lock().unwrap() = Some(run);
+
+        assert!(matches!(service.log_interruption(None), Err(AppError::InvalidArgument(_))));
+        // 休息阶段停止不保存记录
+        assert!(service.stop().unwrap().is_none());
+        assert!(service.sessions(None, 10).unwrap().is_empty());
+    }
+}
+
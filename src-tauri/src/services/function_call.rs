@@ -1,42 +1,197 @@
 use serde_json::{json, Value};
-use crate::db::TodoRepository;
+use crate::db::{HistoryRepository, ListRepository, ReportRepository, TimeEntryRepository, TodoRepository, WorkflowRepository};
 use crate::models::todo::*;
+use crate::models::history::Actor;
+use crate::models::report::{Granularity, StatisticsBreakdown, StatisticsQuery};
+use crate::models::time_entry::{TimeGroup, TimeReportQuery};
 use crate::models::ai::FunctionDefinition;
 use crate::error::AppError;
 use crate::commands::ai::FunctionInfo;
 use std::sync::Arc;
 
+/// `query_todos` 未指定 `limit` 时返回的任务数
+const QUERY_DEFAULT_LIMIT: u32 = 50;
+
+/// `add_todos` 参数中子任务允许嵌套的层数
+const ADD_TODOS_SCHEMA_DEPTH: u32 = 3;
+
+const RECURRENCE_DESCRIPTION: &str = "重复规则（RFC 5545 RRULE，支持 FREQ、INTERVAL、BYDAY、COUNT、UNTIL），\
+完成后自动生成下一次。例如每周一：FREQ=WEEKLY;BYDAY=MO；每天：FREQ=DAILY；工作日：FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR；\
+每两周：FREQ=WEEKLY;INTERVAL=2；每月最后一个周五：FREQ=MONTHLY;BYDAY=-1FR；每年：FREQ=YEARLY；共 10 次：COUNT=10";
+
+const DUE_DATE_DESCRIPTION: &str = "截止日期，ISO 格式（2025-03-01、2025-03-01T15:00）或自然语言，\
+例如：今天、明天下午3点、后天、下周三、周末、月底、3天后、2小时后、tomorrow 5pm、next friday、in 2 weeks";
+
+/// `add_todos` 中单个任务的 JSON Schema，`depth` 大于 1 时包含嵌套的 `children`
+fn todo_item_schema(depth: u32) -> Value {
+    let mut schema = json!({
+        "type": "object",
+        "properties": {
+            "text": {
+                "type": "string",
+                "description": "任务内容，应该具体、可执行"
+            },
+            "priority": {
+                "type": "string",
+                "enum": ["low", "medium", "high"],
+                "description": "优先级"
+            },
+            "due_date": {
+                "type": "string",
+                "description": DUE_DATE_DESCRIPTION
+            },
+            "recurrence": {
+                "type": "string",
+                "description": RECURRENCE_DESCRIPTION
+            },
+            "notes": {
+                "type": "string",
+                "description": "备注（Markdown），用于会议记录、链接、背景信息等不适合放在标题里的内容"
+            },
+            "checklist": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "清单项：任务内的简单检查项（例如购物清单、打包清单），不需要单独跟踪时使用，否则使用 children"
+            },
+            "estimate_minutes": {
+                "type": "integer",
+                "description": "预估完成需要的分钟数"
+            },
+            "story_points": {
+                "type": "number",
+                "description": "故事点数，用户使用故事点估算时填写"
+            }
+        },
+        "required": ["text"]
+    });
+
+    if depth > 1 {
+        schema["properties"]["children"] = json!({
+            "type": "array",
+            "description": "子任务（执行步骤），按执行顺序排列",
+            "items": todo_item_schema(depth - 1)
+        });
+        schema["properties"]["auto_complete"] = json!({
+            "type": "boolean",
+            "description": "所有子任务完成后自动完成此任务"
+        });
+        schema["properties"]["sequential"] = json!({
+            "type": "boolean",
+            "description": "子任务必须按顺序完成：每个子任务在前一个完成后才能开始"
+        });
+    }
+
+    schema
+}
+
+/// `link_todos` / `unlink_todos` 的参数：两个任务都可以通过 ID 或关键词指定
+fn dependency_parameters(depends_on_description: &str) -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "todo_id": {
+                "type": "string",
+                "description": "后续任务的ID（如果已知）"
+            },
+            "todo_search": {
+                "type": "string",
+                "description": "通过关键词找到后续任务（如果不知道ID）"
+            },
+            "depends_on_id": {
+                "type": "string",
+                "description": format!("{}的ID（如果已知）", depends_on_description)
+            },
+            "depends_on_search": {
+                "type": "string",
+                "description": format!("通过关键词找到{}（如果不知道ID）", depends_on_description)
+            }
+        }
+    })
+}
+
 pub fn get_function_definitions() -> Vec<FunctionDefinition> {
     vec![
         FunctionDefinition {
             name: "add_todos".to_string(),
-            description: "批量添加一个或多个待办任务。当用户说'帮我创建'、'添加任务'、'生成计划'时使用此函数。".to_string(),
+            description: "批量添加一个或多个待办任务。当用户说'帮我创建'、'添加任务'、'生成计划'时使用此函数。拆解大目标时，把目标作为任务、具体步骤放在 children 中，一次调用完成。".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
                     "todos": {
                         "type": "array",
                         "description": "要添加的任务列表",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "text": {
-                                    "type": "string",
-                                    "description": "任务内容，应该具体、可执行"
-                                },
-                                "priority": {
-                                    "type": "string",
-                                    "enum": ["low", "medium", "high"],
-                                    "description": "优先级"
-                                }
-                            },
-                            "required": ["text"]
-                        }
+                        "items": todo_item_schema(ADD_TODOS_SCHEMA_DEPTH)
+                    },
+                    "parent_id": {
+                        "type": "string",
+                        "description": "把这些任务添加为已有任务的子任务（如果已知父任务ID）"
+                    },
+                    "parent_search": {
+                        "type": "string",
+                        "description": "通过关键词找到父任务（如果不知道ID）"
+                    },
+                    "list": {
+                        "type": "string",
+                        "description": "添加到哪个列表（列表名称，例如'购物'），不填时添加到收件箱；指定父任务时沿用父任务的列表"
                     }
                 },
                 "required": ["todos"]
             }),
         },
+        FunctionDefinition {
+            name: "update_todo".to_string(),
+            description: "修改单个任务的内容、备注、优先级、截止日期、预估工作量或工作流状态，或向任务清单追加检查项。当用户说'把…改成'、'给…加个备注'、'在…的清单里加上'、'移到…列'时使用。".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "任务ID（如果已知）"
+                    },
+                    "search": {
+                        "type": "string",
+                        "description": "通过关键词搜索任务（如果不知道ID）"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "新的任务内容"
+                    },
+                    "notes": {
+                        "type": "string",
+                        "description": "替换全部备注（Markdown），空字符串表示清除"
+                    },
+                    "append_notes": {
+                        "type": "string",
+                        "description": "追加到已有备注末尾的内容（Markdown）"
+                    },
+                    "priority": {
+                        "type": "string",
+                        "enum": ["low", "medium", "high"]
+                    },
+                    "due_date": {
+                        "type": "string",
+                        "description": format!("{}；空字符串表示清除截止日期", DUE_DATE_DESCRIPTION)
+                    },
+                    "add_checklist": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "追加到任务清单末尾的检查项"
+                    },
+                    "estimate_minutes": {
+                        "type": "integer",
+                        "description": "预估分钟数，0 表示清除"
+                    },
+                    "story_points": {
+                        "type": "number",
+                        "description": "故事点数，0 表示清除"
+                    },
+                    "state": {
+                        "type": "string",
+                        "description": "移动到的工作流状态（看板列名称，例如'评审中'）"
+                    }
+                }
+            }),
+        },
         FunctionDefinition {
             name: "complete_todo".to_string(),
             description: "将指定任务标记为已完成。当用户说'完成了'、'做完了'、'标记完成'时使用。".to_string(),
@@ -54,6 +209,23 @@ pub fn get_function_definitions() -> Vec<FunctionDefinition> {
                 }
             }),
         },
+        FunctionDefinition {
+            name: "skip_occurrence".to_string(),
+            description: "跳过重复任务的本次，截止日期顺延到下一次。当用户说'这周不用做了'、'跳过这次'时使用。".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "任务ID（如果已知）"
+                    },
+                    "search": {
+                        "type": "string",
+                        "description": "通过关键词搜索任务（如果不知道ID）"
+                    }
+                }
+            }),
+        },
         FunctionDefinition {
             name: "delete_todo".to_string(),
             description: "删除指定任务。当用户说'删除'、'移除'、'不要了'时使用。".to_string(),
@@ -90,19 +262,239 @@ pub fn get_function_definitions() -> Vec<FunctionDefinition> {
                         "type": "boolean",
                         "description": "是否已完成"
                     },
+                    "priority": {
+                        "type": "string",
+                        "enum": ["low", "medium", "high"],
+                        "description": "按优先级过滤"
+                    },
                     "search": {
                         "type": "string",
                         "description": "关键词搜索"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "按标签过滤，需同时包含所有标签"
+                    },
+                    "actionable": {
+                        "type": "boolean",
+                        "description": "为 true 时只返回现在就能开始的任务（未完成、没有未完成的依赖和子任务）"
+                    },
+                    "due_before": {
+                        "type": "string",
+                        "description": "截止时间不晚于（日期包含当天），格式同 due_date，例如：今天、本周日、2025-03-01"
+                    },
+                    "due_after": {
+                        "type": "string",
+                        "description": "截止时间不早于（日期包含当天），格式同 due_date"
+                    },
+                    "overdue": {
+                        "type": "boolean",
+                        "description": "为 true 时只返回已逾期（截止时间已过且未完成）的任务"
+                    },
+                    "list": {
+                        "type": "string",
+                        "description": "只查询该列表（列表名称）中的任务"
+                    },
+                    "state": {
+                        "type": "string",
+                        "description": "只查询处于该工作流状态（看板列名称）的任务"
+                    },
+                    "sort": {
+                        "type": "string",
+                        "enum": ["default", "priority", "due_date", "created_at", "updated_at", "text", "manual"],
+                        "description": "排序字段，manual 为用户手动拖拽的顺序"
+                    },
+                    "direction": {
+                        "type": "string",
+                        "enum": ["asc", "desc"],
+                        "description": "排序方向，不填时使用该字段的默认方向"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "最多返回的任务数，默认 50"
+                    },
+                    "cursor": {
+                        "type": "string",
+                        "description": "上一次查询返回的 next_cursor，用于获取下一页"
+                    }
+                }
+            }),
+        },
+        FunctionDefinition {
+            name: "bulk_update_todos".to_string(),
+            description: "批量修改或删除多个任务，所有修改一次性生效。当用户说'把所有…都改成'、'全部标记完成'、'删除所有…'时使用，不要逐个调用 complete_todo 或 delete_todo。".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "ids": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "要修改的任务ID列表（如果已知）"
+                    },
+                    "filter": {
+                        "type": "object",
+                        "description": "按条件选择任务（不知道ID时使用），至少提供一个条件",
+                        "properties": {
+                            "status": {
+                                "type": "string",
+                                "enum": ["pending", "in_progress", "completed", "cancelled"]
+                            },
+                            "completed": { "type": "boolean" },
+                            "priority": {
+                                "type": "string",
+                                "enum": ["low", "medium", "high"]
+                            },
+                            "search": { "type": "string", "description": "关键词" },
+                            "tags": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "需同时包含的标签"
+                            },
+                            "due_before": { "type": "string", "description": "截止时间不晚于（日期包含当天）" },
+                            "due_after": { "type": "string", "description": "截止时间不早于（日期包含当天）" },
+                            "overdue": { "type": "boolean", "description": "只选择已逾期的任务" },
+                            "list": { "type": "string", "description": "列表名称" },
+                            "state": { "type": "string", "description": "工作流状态（看板列名称）" }
+                        }
+                    },
+                    "set": {
+                        "type": "object",
+                        "description": "要修改的字段",
+                        "properties": {
+                            "status": {
+                                "type": "string",
+                                "enum": ["pending", "in_progress", "completed", "cancelled"]
+                            },
+                            "priority": {
+                                "type": "string",
+                                "enum": ["low", "medium", "high"]
+                            },
+                            "due_date": {
+                                "type": "string",
+                                "description": format!("{}；空字符串表示清除截止日期", DUE_DATE_DESCRIPTION)
+                            },
+                            "state": {
+                                "type": "string",
+                                "description": "移动到的工作流状态（看板列名称），会同时设置对应的 status"
+                            }
+                        }
+                    },
+                    "delete": {
+                        "type": "boolean",
+                        "description": "为 true 时把选中的任务移入回收站，忽略 set"
+                    }
+                }
+            }),
+        },
+        FunctionDefinition {
+            name: "link_todos".to_string(),
+            description: "设置任务依赖：任务在另一个任务完成之前不能开始。当用户说'先…再…'、'…之后才能…'、'依赖于'时使用。不能形成循环依赖。".to_string(),
+            parameters: dependency_parameters("依赖的任务，需要先完成"),
+        },
+        FunctionDefinition {
+            name: "unlink_todos".to_string(),
+            description: "取消两个任务之间的依赖关系。".to_string(),
+            parameters: dependency_parameters("不再依赖的任务"),
+        },
+        FunctionDefinition {
+            name: "get_todo_history".to_string(),
+            description: "查看任务的修改历史，包括谁（用户、AI、自动任务）在什么时候改了什么。当用户问'谁改的'、'什么时候完成的'、'之前是什么'时使用。".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "任务ID（如果已知）"
+                    },
+                    "search": {
+                        "type": "string",
+                        "description": "通过关键词搜索任务（如果不知道ID）"
                     }
                 }
             }),
         },
         FunctionDefinition {
             name: "get_statistics".to_string(),
-            description: "获取任务统计信息。当用户询问'统计'、'完成了多少'、'进度如何'时使用。".to_string(),
+            description: "获取任务统计信息：当前各状态的任务数，以及一段时间内新建和完成的任务数、趋势、连续完成天数、逾期数、平均完成用时和按优先级/标签/列表的分组。当用户询问'统计'、'这周完成了多少'、'进度如何'、'最近效率怎么样'时使用。".to_string(),
             parameters: json!({
                 "type": "object",
-                "properties": {}
+                "properties": {
+                    "list": {
+                        "type": "string",
+                        "description": "只统计该列表（列表名称），不填时统计全部任务"
+                    },
+                    "period": {
+                        "type": "string",
+                        "enum": ["today", "yesterday", "this_week", "last_week", "this_month", "last_month"],
+                        "description": "统计的时间段，优先于 from/to；都不填时统计最近 30 天"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "开始日期，ISO 格式或自然语言（包含当天）"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "结束日期，ISO 格式或自然语言（包含当天）"
+                    },
+                    "granularity": {
+                        "type": "string",
+                        "enum": ["day", "week", "month"],
+                        "description": "趋势的时间粒度，默认按天"
+                    }
+                }
+            }),
+        },
+        FunctionDefinition {
+            name: "get_board".to_string(),
+            description: "按工作流状态（看板列）查看任务，包括每列的任务数和 WIP 上限。当用户问'看板'、'评审里有什么'、'每个阶段有多少任务'时使用。".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "list": {
+                        "type": "string",
+                        "description": "只查看该列表（列表名称），不填时查看全部任务"
+                    }
+                }
+            }),
+        },
+        FunctionDefinition {
+            name: "get_time_spent".to_string(),
+            description: "查询计时记录，统计在任务或标签上花了多少时间。当用户问'这周在…上花了多少时间'、'今天都忙了什么'时使用。".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "只统计该任务（如果已知ID）"
+                    },
+                    "search": {
+                        "type": "string",
+                        "description": "通过关键词找到要统计的任务（如果不知道ID）"
+                    },
+                    "tag": {
+                        "type": "string",
+                        "description": "只统计带有该标签的任务"
+                    },
+                    "period": {
+                        "type": "string",
+                        "enum": ["today", "yesterday", "this_week", "last_week", "this_month", "last_month"],
+                        "description": "统计的时间段，一周从周一开始；提供时忽略 from/to"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "统计范围的开始，ISO 日期/时间或自然语言（例如'3月1日'），日期从当天开始"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "统计范围的结束，日期包含当天"
+                    },
+                    "group_by": {
+                        "type": "string",
+                        "enum": ["todo", "tag"],
+                        "description": "按任务还是按标签分组，默认按任务"
+                    }
+                }
             }),
         },
     ]
@@ -130,56 +522,75 @@ pub fn get_function_infos() -> Vec<FunctionInfo> {
 
 pub struct FunctionExecutor {
     todo_repo: Arc<TodoRepository>,
+    list_repo: Arc<ListRepository>,
+    history_repo: Arc<HistoryRepository>,
+    time_entry_repo: Arc<TimeEntryRepository>,
+    report_repo: Arc<ReportRepository>,
+    workflow_repo: Arc<WorkflowRepository>,
 }
 
 impl FunctionExecutor {
-    pub fn new(todo_repo: Arc<TodoRepository>) -> Self {
-        Self { todo_repo }
+    pub fn new(
+        todo_repo: Arc<TodoRepository>,
+        list_repo: Arc<ListRepository>,
+        history_repo: Arc<HistoryRepository>,
+        time_entry_repo: Arc<TimeEntryRepository>,
+        report_repo: Arc<ReportRepository>,
+        workflow_repo: Arc<WorkflowRepository>,
+    ) -> Self {
+        Self { todo_repo, list_repo, history_repo, time_entry_repo, report_repo, workflow_repo }
     }
 
-    pub fn execute(&self, name: &str, arguments: &str) -> Result<Value, AppError> {
+    /// 执行一次函数调用，`actor` 标识本次调用所属的对话与工具调用，会记录到任务历史中
+    pub fn execute(&self, name: &str, arguments: &str, actor: &Actor) -> Result<Value, AppError> {
         let args: Value = serde_json::from_str(arguments)?;
 
         match name {
-            "add_todos" => self.add_todos(&args),
-            "complete_todo" => self.complete_todo(&args),
-            "delete_todo" => self.delete_todo(&args),
+            "add_todos" => self.add_todos(&args, actor),
+            "update_todo" => self.update_todo(&args, actor),
+            "complete_todo" => self.complete_todo(&args, actor),
+            "skip_occurrence" => self.skip_occurrence(&args, actor),
+            "delete_todo" => self.delete_todo(&args, actor),
             "query_todos" => self.query_todos(&args),
-            "get_statistics" => self.get_statistics(),
+            "bulk_update_todos" => self.bulk_update_todos(&args, actor),
+            "link_todos" => self.link_todos(&args, actor, true),
+            "unlink_todos" => self.link_todos(&args, actor, false),
+            "get_todo_history" => self.get_todo_history(&args),
+            "get_statistics" => self.get_statistics(&args),
+            "get_board" => self.get_board(&args),
+            "get_time_spent" => self.get_time_spent(&args),
             _ => Err(AppError::UnknownFunction(name.to_string())),
         }
     }
 
-    fn add_todos(&self, args: &Value) -> Result<Value, AppError> {
+    fn add_todos(&self, args: &Value, actor: &Actor) -> Result<Value, AppError> {
         let todos = args["todos"]
             .as_array()
             .ok_or_else(|| AppError::InvalidArgument("todos must be an array".into()))?;
 
-        let mut created = Vec::new();
-        for todo in todos {
-            let text = todo["text"]
-                .as_str()
-                .ok_or_else(|| AppError::InvalidArgument("text is required".into()))?;
-
-            let priority = todo.get("priority")
-                .and_then(|p| p.as_str())
-                .map(|p| match p {
-                    "high" => Priority::High,
-                    "medium" => Priority::Medium,
-                    _ => Priority::Low,
-                });
-
-            let request = CreateTodoRequest {
-                text: text.to_string(),
-                priority,
-                due_date: None,
-                tags: None,
-            };
+        // 可选的父任务：通过 parent_id 或 parent_search 指定
+        let parent_id = if args.get("parent_id").is_some() || args.get("parent_search").is_some() {
+            let parent_args = json!({
+                "id": args.get("parent_id"),
+                "search": args.get("parent_search"),
+            });
+            Some(self.find_todo(&parent_args, |t| !t.completed)?.id)
+        } else {
+            None
+        };
+        let list_id = self.resolve_list(args)?;
 
-            let result = self.todo_repo.create(request)?;
-            created.push(result);
+        let mut requests = Vec::new();
+        for todo in todos {
+            let mut request = parse_create_request(todo)?;
+            request.parent_id = parent_id.clone();
+            request.list_id = list_id.clone();
+            requests.push(request);
         }
 
+        // 所有任务在一个事务中创建，任何一个失败都不会留下部分结果
+        let created = self.todo_repo.batch_create(requests, actor)?;
+
         Ok(json!({
             "success": true,
             "created_count": created.len(),
@@ -188,88 +599,294 @@ impl FunctionExecutor {
         }))
     }
 
-    fn complete_todo(&self, args: &Value) -> Result<Value, AppError> {
-        // 通过 ID 或搜索关键词找到任务
-        let todo = if let Some(id) = args.get("id").and_then(|v| v.as_str()) {
-            self.todo_repo.get_by_id(id)?
-        } else if let Some(search) = args.get("search").and_then(|v| v.as_str()) {
-            let results = self.todo_repo.search(search)?;
-            results.into_iter().next()
-                .ok_or_else(|| AppError::TodoNotFound(search.to_string()))?
-        } else {
-            return Err(AppError::InvalidArgument("id or search required".into()));
-        };
+    fn complete_todo(&self, args: &Value, actor: &Actor) -> Result<Value, AppError> {
+        // 通过 ID 或搜索关键词找到任务，搜索时优先选择相关度最高的未完成任务
+        let todo = self.find_todo(args, |t| !t.completed)?;
 
         // 更新为已完成
         let updated = self.todo_repo.update(&todo.id, UpdateTodoRequest {
             completed: Some(true),
             status: Some(TodoStatus::Completed),
             ..Default::default()
-        })?;
+        }, actor)?;
+
+        // 重复任务完成后会生成下一次
+        let next = self.todo_repo.get_next_occurrence(&updated)?;
+        let message = match next {
+            Some(ref next) => format!(
+                "已完成任务: {}，下一次截止日期 {}",
+                updated.text,
+                next.due_date.as_ref().map(|d| d.to_string()).unwrap_or_else(|| "未设置".into())
+            ),
+            None => format!("已完成任务: {}", updated.text),
+        };
+
+        Ok(json!({
+            "success": true,
+            "message": message,
+            "todo": updated,
+            "next_occurrence": next
+        }))
+    }
+
+    fn update_todo(&self, args: &Value, actor: &Actor) -> Result<Value, AppError> {
+        let todo = self.find_todo(args, |t| !t.completed)?;
+
+        let notes = match (args.get("notes").and_then(|v| v.as_str()), args.get("append_notes").and_then(|v| v.as_str())) {
+            (notes, Some(appended)) => {
+                let current = notes.or(todo.notes.as_deref()).unwrap_or_default().trim_end();
+                Some(if current.is_empty() { appended.to_string() } else { format!("{}\n\n{}", current, appended) })
+            }
+            (notes, None) => notes.map(String::from),
+        };
+        let request = UpdateTodoRequest {
+            text: args.get("text").and_then(|v| v.as_str()).map(String::from),
+            priority: args.get("priority").and_then(|v| v.as_str()).map(parse_priority),
+            due_date: args.get("due_date").and_then(|v| v.as_str()).map(String::from),
+            notes,
+            estimate_minutes: args.get("estimate_minutes").and_then(|v| v.as_u64()).map(|m| m as u32),
+            story_points: args.get("story_points").and_then(|v| v.as_f64()),
+            state_id: self.resolve_state(args)?,
+            add_checklist: args.get("add_checklist")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|t| t.as_str()).map(String::from).collect())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        let updated = self.todo_repo.update(&todo.id, request, actor)?;
 
         Ok(json!({
             "success": true,
-            "message": format!("已完成任务: {}", updated.text),
+            "message": format!("已更新任务: {}", updated.text),
             "todo": updated
         }))
     }
 
-    fn delete_todo(&self, args: &Value) -> Result<Value, AppError> {
+    fn skip_occurrence(&self, args: &Value, actor: &Actor) -> Result<Value, AppError> {
+        let todo = self.find_todo(args, |t| t.recurrence.is_some() && !t.completed)?;
+        let updated = self.todo_repo.skip_occurrence(&todo.id, actor)?;
+
+        let message = if updated.status == TodoStatus::Cancelled {
+            format!("「{}」已没有后续重复，已取消", updated.text)
+        } else {
+            format!(
+                "已跳过「{}」的本次，下一次截止日期 {}",
+                updated.text,
+                updated.due_date.as_ref().map(|d| d.to_string()).unwrap_or_else(|| "未设置".into())
+            )
+        };
+
+        Ok(json!({
+            "success": true,
+            "message": message,
+            "todo": updated
+        }))
+    }
+
+    fn delete_todo(&self, args: &Value, actor: &Actor) -> Result<Value, AppError> {
         // 检查是否删除所有已完成
         if args.get("delete_all_completed").and_then(|v| v.as_bool()).unwrap_or(false) {
-            let count = self.todo_repo.delete_completed()?;
+            let count = self.todo_repo.delete_completed(actor)?;
             return Ok(json!({
                 "success": true,
                 "deleted_count": count,
-                "message": format!("已删除 {} 个已完成的任务", count)
+                "message": format!("已将 {} 个已完成的任务移入回收站", count)
             }));
         }
 
         // 通过 ID 或搜索关键词找到任务
-        let todo = if let Some(id) = args.get("id").and_then(|v| v.as_str()) {
-            self.todo_repo.get_by_id(id)?
-        } else if let Some(search) = args.get("search").and_then(|v| v.as_str()) {
-            let results = self.todo_repo.search(search)?;
-            results.into_iter().next()
-                .ok_or_else(|| AppError::TodoNotFound(search.to_string()))?
-        } else {
-            return Err(AppError::InvalidArgument("id or search required".into()));
-        };
+        let todo = self.find_todo(args, |_| true)?;
 
         let text = todo.text.clone();
-        self.todo_repo.delete(&todo.id)?;
+        self.todo_repo.delete(&todo.id, actor)?;
 
         Ok(json!({
             "success": true,
-            "message": format!("已删除任务: {}", text)
+            "message": format!("已将任务移入回收站: {}", text)
         }))
     }
 
+    /// 通过 `id` 或 `search` 参数定位任务。
+    /// 搜索时按相关度排序，返回第一个满足 `prefer` 的结果，没有则退回相关度最高的结果。
+    fn find_todo(&self, args: &Value, prefer: impl Fn(&Todo) -> bool) -> Result<Todo, AppError> {
+        if let Some(id) = args.get("id").and_then(|v| v.as_str()) {
+            return self.todo_repo.get_by_id(id);
+        }
+
+        let search = args.get("search")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| AppError::InvalidArgument("id or search required".into()))?;
+
+        let hits = self.todo_repo.search(search, 10)?;
+        let index = hits.iter().position(|h| prefer(&h.todo)).unwrap_or(0);
+
+        hits.into_iter()
+            .nth(index)
+            .map(|h| h.todo)
+            .ok_or_else(|| AppError::TodoNotFound(search.to_string()))
+    }
+
+    /// 把 `list` 参数（列表名称）解析为列表 ID，未提供时返回 `None`
+    fn resolve_list(&self, args: &Value) -> Result<Option<String>, AppError> {
+        match args.get("list").and_then(|v| v.as_str()).filter(|name| !name.trim().is_empty()) {
+            Some(name) => Ok(Some(self.list_repo.find_by_name(name)?.id)),
+            None => Ok(None),
+        }
+    }
+
+    /// 把 `state` 参数（工作流状态名称）解析为状态 ID，未提供时返回 `None`
+    fn resolve_state(&self, args: &Value) -> Result<Option<String>, AppError> {
+        match args.get("state").and_then(|v| v.as_str()).filter(|name| !name.trim().is_empty()) {
+            Some(name) => Ok(Some(self.workflow_repo.find_by_name(name)?.id)),
+            None => Ok(None),
+        }
+    }
+
     fn query_todos(&self, args: &Value) -> Result<Value, AppError> {
-        let filter = TodoFilter {
-            status: args.get("status")
-                .and_then(|v| v.as_str())
-                .map(TodoStatus::from_str),
-            completed: args.get("completed")
-                .and_then(|v| v.as_bool()),
-            priority: None,
-            search: args.get("search")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            tag: None,
+        let page = TodoPageRequest {
+            limit: Some(args.get("limit").and_then(|v| v.as_u64()).unwrap_or(QUERY_DEFAULT_LIMIT as u64) as u32),
+            cursor: args.get("cursor").and_then(|v| v.as_str()).map(String::from),
+            sort: args.get("sort").cloned().and_then(|v| serde_json::from_value(v).ok()),
+            direction: args.get("direction").cloned().and_then(|v| serde_json::from_value(v).ok()),
         };
 
-        let todos = self.todo_repo.get_all(Some(filter))?;
+        let mut filter = parse_filter(args);
+        filter.list_id = self.resolve_list(args)?;
+        filter.state_id = self.resolve_state(args)?;
+        let page = self.todo_repo.get_page(Some(filter), page)?;
 
         Ok(json!({
             "success": true,
-            "count": todos.len(),
-            "todos": todos
+            "count": page.items.len(),
+            "total": page.total,
+            "next_cursor": page.next_cursor,
+            "todos": page.items
         }))
     }
 
-    fn get_statistics(&self) -> Result<Value, AppError> {
-        let stats = self.todo_repo.get_statistics()?;
+    fn bulk_update_todos(&self, args: &Value, actor: &Actor) -> Result<Value, AppError> {
+        let selector = if let Some(ids) = args.get("ids").and_then(|v| v.as_array()) {
+            TodoSelector::Ids(ids.iter().filter_map(|id| id.as_str().map(String::from)).collect())
+        } else {
+            // 不允许空条件，避免误改全部任务
+            let filter_args = args.get("filter").cloned().unwrap_or(Value::Null);
+            let mut filter = parse_filter(&filter_args);
+            filter.list_id = self.resolve_list(&filter_args)?;
+            filter.state_id = self.resolve_state(&filter_args)?;
+            if filter.is_empty() {
+                return Err(AppError::InvalidArgument("ids or a non-empty filter required".into()));
+            }
+            TodoSelector::Filter(filter)
+        };
+
+        if args.get("delete").and_then(|v| v.as_bool()).unwrap_or(false) {
+            let count = self.todo_repo.bulk_delete(&selector, actor)?;
+            return Ok(json!({
+                "success": true,
+                "deleted_count": count,
+                "message": format!("已将 {} 个任务移入回收站", count)
+            }));
+        }
+
+        let set = args.get("set").cloned().unwrap_or(Value::Null);
+        let status = set.get("status").and_then(|v| v.as_str()).map(TodoStatus::from_str);
+        let request = UpdateTodoRequest {
+            completed: status.as_ref().map(|s| *s == TodoStatus::Completed),
+            status,
+            priority: set.get("priority").and_then(|v| v.as_str()).map(parse_priority),
+            due_date: set.get("due_date").and_then(|v| v.as_str()).map(String::from),
+            state_id: self.resolve_state(&set)?,
+            ..Default::default()
+        };
+
+        if request.status.is_none() && request.priority.is_none() && request.due_date.is_none() && request.state_id.is_none() {
+            return Err(AppError::InvalidArgument("set.status, set.priority, set.due_date or set.state required".into()));
+        }
+
+        let updated = self.todo_repo.bulk_update(&selector, request, actor)?;
+
+        Ok(json!({
+            "success": true,
+            "updated_count": updated.len(),
+            "message": format!("已修改 {} 个任务", updated.len()),
+            "todos": updated
+        }))
+    }
+
+    /// 添加（`link` 为 true）或移除任务依赖
+    fn link_todos(&self, args: &Value, actor: &Actor, link: bool) -> Result<Value, AppError> {
+        let todo = self.find_todo(&json!({
+            "id": args.get("todo_id"),
+            "search": args.get("todo_search"),
+        }), |t| !t.completed)?;
+        let depends_on = self.find_todo(&json!({
+            "id": args.get("depends_on_id"),
+            "search": args.get("depends_on_search"),
+        }), |t| t.id != todo.id)?;
+
+        let updated = if link {
+            self.todo_repo.add_dependency(&todo.id, &depends_on.id, actor)?
+        } else {
+            self.todo_repo.remove_dependency(&todo.id, &depends_on.id, actor)?
+        };
+
+        let message = if link {
+            format!("「{}」将在「{}」完成后开始", updated.text, depends_on.text)
+        } else {
+            format!("「{}」不再依赖「{}」", updated.text, depends_on.text)
+        };
+
+        Ok(json!({
+            "success": true,
+            "message": message,
+            "todo": updated
+        }))
+    }
+
+    fn get_todo_history(&self, args: &Value) -> Result<Value, AppError> {
+        let todo = self.find_todo(args, |_| true)?;
+        let events = self.history_repo.get_for_todo(&todo.id)?;
+        let summary: Vec<String> = events.iter().map(|e| e.summary()).collect();
+
+        Ok(json!({
+            "success": true,
+            "todo": todo.text,
+            "count": events.len(),
+            "history": summary
+        }))
+    }
+
+    fn get_statistics(&self, args: &Value) -> Result<Value, AppError> {
+        let list_id = self.resolve_list(args)?;
+        let stats = self.todo_repo.get_statistics(list_id.as_deref())?;
+        let text = |key: &str| args.get(key).and_then(|v| v.as_str()).map(String::from);
+
+        let query = StatisticsQuery {
+            period: args.get("period").cloned().and_then(|v| serde_json::from_value(v).ok()),
+            from: text("from"),
+            to: text("to"),
+            granularity: args.get("granularity").cloned().and_then(|v| serde_json::from_value(v).ok()).unwrap_or(Granularity::Day),
+            list_id,
+        };
+        let report = self.report_repo.statistics(&query)?;
+
+        let breakdown = |groups: &[StatisticsBreakdown]| -> Vec<Value> {
+            groups.iter().map(|g| json!({
+                "name": g.label,
+                "created": g.created,
+                "completed": g.completed,
+                "open": g.open,
+                "overdue": g.overdue
+            })).collect()
+        };
+        let trend: Vec<Value> = report.trend.iter().map(|p| json!({
+            "start": p.start,
+            "created": p.created,
+            "completed": p.completed
+        })).collect();
+        let cycle = report.average_cycle_hours
+            .map(|hours| format!("，平均 {} 完成", format_duration((hours * 3600.0) as i64)))
+            .unwrap_or_default();
 
         Ok(json!({
             "success": true,
@@ -278,14 +895,203 @@ impl FunctionExecutor {
                 "completed": stats.completed,
                 "pending": stats.pending,
                 "in_progress": stats.in_progress,
-                "cancelled": stats.cancelled
+                "cancelled": stats.cancelled,
+                "focus_sessions": stats.focus_sessions,
+                "focus_sessions_today": stats.focus_sessions_today,
+                "focus_time": format_duration(stats.focus_seconds)
             },
+            "range": {
+                "from": report.from,
+                "to": report.to,
+                "created": report.created,
+                "completed": report.completed,
+                "completed_late": report.completed_late,
+                "average_cycle_hours": report.average_cycle_hours,
+                "trend": trend,
+                "by_priority": breakdown(&report.by_priority),
+                "by_tag": breakdown(&report.by_tag),
+                "by_list": breakdown(&report.by_list)
+            },
+            "overdue": report.overdue,
+            "current_streak_days": report.current_streak,
+            "longest_streak_days": report.longest_streak,
             "message": format!(
-                "共 {} 个任务，已完成 {}，待办 {}，进行中 {}",
-                stats.total, stats.completed, stats.pending, stats.in_progress
+                "统计期间新建 {} 个、完成 {} 个（{} 个超过截止时间）{}；当前逾期 {} 个，已连续 {} 天有完成（最长 {} 天）。\
+                 共 {} 个任务，已完成 {}，待办 {}，进行中 {}；完成专注 {} 次（今天 {} 次），共专注 {}",
+                report.created, report.completed, report.completed_late, cycle,
+                report.overdue, report.current_streak, report.longest_streak,
+                stats.total, stats.completed, stats.pending, stats.in_progress,
+                stats.focus_sessions, stats.focus_sessions_today, format_duration(stats.focus_seconds)
             )
         }))
     }
+
+    fn get_board(&self, args: &Value) -> Result<Value, AppError> {
+        let list_id = self.resolve_list(args)?;
+        let board = self.todo_repo.get_board(list_id.as_deref())?;
+
+        let columns: Vec<Value> = board.columns.iter().map(|c| json!({
+            "state": c.state.name,
+            "category": c.state.category.as_str(),
+            "count": c.todos.len(),
+            "wip_limit": c.state.wip_limit,
+            "over_limit": c.over_limit,
+            "todos": c.todos.iter().take(QUERY_DEFAULT_LIMIT as usize).map(|t| json!({ "id": t.id, "text": t.text })).collect::<Vec<_>>()
+        })).collect();
+        let summary: Vec<String> = board.columns.iter().map(|c| match c.state.wip_limit {
+            Some(limit) => format!("{} {}/{}", c.state.name, c.todos.len(), limit),
+            None => format!("{} {}", c.state.name, c.todos.len()),
+        }).collect();
+
+        let unassigned: Vec<Value> = board.unassigned.iter()
+            .take(QUERY_DEFAULT_LIMIT as usize)
+            .map(|t| json!({ "id": t.id, "text": t.text, "status": t.status.as_str() }))
+            .collect();
+        let unassigned_note = match board.unassigned.len() {
+            0 => String::new(),
+            n => format!("；另有 {} 个任务不在看板列中（已取消等）", n),
+        };
+
+        Ok(json!({
+            "success": true,
+            "columns": columns,
+            "unassigned_count": board.unassigned.len(),
+            "unassigned": unassigned,
+            "message": format!("看板：{}{}", summary.join("，"), unassigned_note)
+        }))
+    }
+
+    fn get_time_spent(&self, args: &Value) -> Result<Value, AppError> {
+        let todo = match (args.get("id"), args.get("search")) {
+            (None, None) => None,
+            _ => Some(self.find_todo(args, |_| true)?),
+        };
+        let text = |key: &str| args.get(key).and_then(|v| v.as_str()).map(String::from);
+
+        let query = TimeReportQuery {
+            period: args.get("period").cloned().and_then(|v| serde_json::from_value(v).ok()),
+            from: text("from"),
+            to: text("to"),
+            todo_id: todo.as_ref().map(|t| t.id.clone()),
+            tag: text("tag"),
+            group_by: args.get("group_by").cloned().and_then(|v| serde_json::from_value(v).ok()).unwrap_or(TimeGroup::Todo),
+        };
+        let report = self.time_entry_repo.report(&query)?;
+
+        let groups: Vec<Value> = report.groups.iter().map(|g| json!({
+            "name": g.label,
+            "duration": format_duration(g.seconds),
+            "seconds": g.seconds,
+            "entries": g.entry_count
+        })).collect();
+
+        Ok(json!({
+            "success": true,
+            "from": report.from,
+            "to": report.to,
+            "total": format_duration(report.total_seconds),
+            "total_seconds": report.total_seconds,
+            "groups": groups,
+            "message": match todo {
+                Some(todo) => format!("在「{}」上共花了 {}", todo.text, format_duration(report.total_seconds)),
+                None => format!("共计时 {}", format_duration(report.total_seconds)),
+            }
+        }))
+    }
+}
+
+/// 把秒数格式化为"1 小时 25 分钟"
+fn format_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{} 分钟", m),
+        (h, 0) => format!("{} 小时", h),
+        (h, m) => format!("{} 小时 {} 分钟", h, m),
+    }
+}
+
+/// 解析 `add_todos` 中的单个任务及其嵌套子任务
+fn parse_create_request(todo: &Value) -> Result<CreateTodoRequest, AppError> {
+    let text = todo["text"]
+        .as_str()
+        .ok_or_else(|| AppError::InvalidArgument("text is required".into()))?;
+
+    let children = match todo.get("children").and_then(|c| c.as_array()) {
+        Some(children) => children.iter().map(parse_create_request).collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(CreateTodoRequest {
+        text: text.to_string(),
+        priority: todo.get("priority")
+            .and_then(|p| p.as_str())
+            .map(parse_priority),
+        due_date: todo.get("due_date")
+            .and_then(|d| d.as_str())
+            .map(String::from),
+        recurrence: todo.get("recurrence")
+            .and_then(|r| r.as_str())
+            .map(String::from),
+        notes: todo.get("notes")
+            .and_then(|n| n.as_str())
+            .map(String::from),
+        checklist: todo.get("checklist")
+            .and_then(|c| c.as_array())
+            .map(|arr| arr.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+            .unwrap_or_default(),
+        estimate_minutes: todo.get("estimate_minutes")
+            .and_then(|m| m.as_u64())
+            .map(|m| m as u32),
+        story_points: todo.get("story_points")
+            .and_then(|p| p.as_f64()),
+        auto_complete: todo.get("auto_complete").and_then(|v| v.as_bool()).unwrap_or(false),
+        sequential: todo.get("sequential").and_then(|v| v.as_bool()).unwrap_or(false),
+        children,
+        ..Default::default()
+    })
+}
+
+fn parse_priority(p: &str) -> Priority {
+    match p {
+        "high" => Priority::High,
+        "medium" => Priority::Medium,
+        _ => Priority::Low,
+    }
+}
+
+/// 从函数参数中读取任务过滤条件（status、completed、priority、search、tags、actionable、due_before、due_after、overdue）。
+/// `list`、`state` 需要查询数据库，由 `FunctionExecutor::resolve_list`、`resolve_state` 单独解析。
+fn parse_filter(args: &Value) -> TodoFilter {
+    TodoFilter {
+        status: args.get("status")
+            .and_then(|v| v.as_str())
+            .map(TodoStatus::from_str),
+        completed: args.get("completed")
+            .and_then(|v| v.as_bool()),
+        priority: args.get("priority")
+            .and_then(|v| v.as_str())
+            .map(parse_priority),
+        search: args.get("search")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        tag: None,
+        tags: args.get("tags")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|t| t.as_str().map(String::from)).collect()),
+        tag_match: None,
+        actionable: args.get("actionable")
+            .and_then(|v| v.as_bool()),
+        list_id: None,
+        due_before: args.get("due_before")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        due_after: args.get("due_after")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        overdue: args.get("overdue")
+            .and_then(|v| v.as_bool()),
+        state_id: None,
+    }
 }
 
 // ===== Text Parsing Fallback =====
@@ -332,7 +1138,7 @@ pub fn parse_function_calls_from_text(content: &str) -> Vec<ExtractedFunctionCal
 
     // Pattern 3: Function name followed by JSON
     // add_todos {"todos": [...]}
-    let func_names = ["add_todos", "complete_todo", "delete_todo", "query_todos", "get_statistics"];
+    let func_names = ["add_todos", "complete_todo", "skip_occurrence", "delete_todo", "query_todos", "bulk_update_todos", "update_todo", "link_todos", "unlink_todos", "get_todo_history", "get_statistics", "get_board", "get_time_spent"];
     for func_name in &func_names {
         if let Some(pos) = content.find(func_name) {
             // Look for JSON object after function name
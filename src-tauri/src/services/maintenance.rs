@@ -0,0 +1,135 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::{ReminderRepository, SettingsRepository, TodoRepository};
+use crate::error::AppError;
+use crate::models::history::Actor;
+use crate::services::{AttachmentService, BackupService};
+
+/// 回收站清理的检查间隔
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// 定期备份的检查间隔，是否真正创建备份由 `BackupService::run_scheduled` 决定
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 清理未被引用的附件文件的间隔
+const ATTACHMENT_GC_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// 提醒与逾期检查的间隔，决定提醒最多延迟多久触发
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 启动后台任务：启动时以及之后每隔 `TRASH_PURGE_INTERVAL`，
+/// 永久删除在回收站中超过 `Settings::trash_retention_days` 天的任务。
+pub fn spawn_trash_purge(todo_repo: Arc<TodoRepository>, settings_repo: Arc<SettingsRepository>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TRASH_PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let todo_repo = todo_repo.clone();
+            let settings_repo = settings_repo.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let retention_days = settings_repo.get()?.trash_retention_days;
+                if retention_days == 0 {
+                    return Ok(0);
+                }
+                todo_repo.purge_trash(retention_days, &Actor::automation("trash_purge"))
+            })
+            .await;
+
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(count)) => log::info!("Purged {} todos from trash", count),
+                Ok(Err(e)) => log::error!("Failed to purge trash: {}", e),
+                Err(e) => log::error!("Trash purge task panicked: {}", e),
+            }
+        }
+    });
+}
+
+/// 启动后台任务：启动时以及之后每隔 `BACKUP_CHECK_INTERVAL` 检查是否需要创建每日/每周备份
+pub fn spawn_scheduled_backup(backup_service: Arc<BackupService>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(BACKUP_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let backup_service = backup_service.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || backup_service.run_scheduled()).await;
+
+            match result {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => log::error!("Scheduled backup failed: {}", e),
+                Err(e) => log::error!("Scheduled backup task panicked: {}", e),
+            }
+        }
+    });
+}
+
+/// 启动后台任务：启动时以及之后每隔 `REMINDER_CHECK_INTERVAL`，为到期的提醒发出 `todo-reminder` 事件，
+/// 为新逾期的任务发出 `todo-overdue` 事件。提醒的状态保存在数据库中，应用关闭期间错过的提醒会在启动后补发。
+pub fn spawn_reminder_scheduler(reminder_repo: Arc<ReminderRepository>, app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REMINDER_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let reminder_repo = reminder_repo.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let now = Utc::now();
+                Ok::<_, AppError>((reminder_repo.take_due(now)?, reminder_repo.take_overdue(now)?))
+            })
+            .await;
+
+            let (reminders, overdue) = match result {
+                Ok(Ok(result)) => result,
+                Ok(Err(e)) => {
+                    log::error!("Failed to check reminders: {}", e);
+                    continue;
+                }
+                Err(e) => {
+                    log::error!("Reminder check task panicked: {}", e);
+                    continue;
+                }
+            };
+
+            for notification in &reminders {
+                if let Err(e) = app.emit("todo-reminder", notification) {
+                    log::error!("Failed to emit reminder {}: {}", notification.reminder.id, e);
+                }
+            }
+            for todo in &overdue {
+                if let Err(e) = app.emit("todo-overdue", todo) {
+                    log::error!("Failed to emit overdue todo {}: {}", todo.id, e);
+                }
+            }
+        }
+    });
+}
+
+/// 启动后台任务：启动时以及之后每隔 `ATTACHMENT_GC_INTERVAL`，删除附件目录中不再被任何附件引用的文件
+pub fn spawn_attachment_gc(attachment_service: Arc<AttachmentService>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(ATTACHMENT_GC_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let attachment_service = attachment_service.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || attachment_service.collect_garbage()).await;
+
+            match result {
+                Ok(Ok(0)) => {}
+                Ok(Ok(count)) => log::info!("Removed {} unreferenced attachment files", count),
+                Ok(Err(e)) => log::error!("Failed to collect attachment files: {}", e),
+                Err(e) => log::error!("Attachment cleanup task panicked: {}", e),
+            }
+        }
+    });
+}
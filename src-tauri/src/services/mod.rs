@@ -1,5 +1,12 @@
 pub mod function_call;
 pub mod ai_service;
+pub mod maintenance;
+pub mod backup;
+pub mod attachment;
+pub mod focus;
 
 pub use function_call::FunctionExecutor;
 pub use ai_service::AiService;
+pub use backup::BackupService;
+pub use attachment::AttachmentService;
+pub use focus::FocusService;
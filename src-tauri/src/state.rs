@@ -1,12 +1,26 @@
+use std::path::Path;
 use std::sync::Arc;
-use crate::db::{Database, TodoRepository, SettingsRepository};
-use crate::services::{AiService, FunctionExecutor};
+use crate::db::{Database, TodoRepository, TagRepository, ListRepository, WorkflowRepository, ReminderRepository, AttachmentRepository, TimeEntryRepository, FocusRepository, ReportRepository, HistoryRepository, UndoRepository, SettingsRepository};
+use crate::services::{AiService, AttachmentService, BackupService, FocusService, FunctionExecutor};
 use crate::error::AppError;
 
 pub struct AppState {
+    pub db: Arc<Database>,
     pub todo_repo: Arc<TodoRepository>,
+    pub tag_repo: Arc<TagRepository>,
+    pub list_repo: Arc<ListRepository>,
+    pub workflow_repo: Arc<WorkflowRepository>,
+    pub reminder_repo: Arc<ReminderRepository>,
+    pub attachment_repo: Arc<AttachmentRepository>,
+    pub time_entry_repo: Arc<TimeEntryRepository>,
+    pub report_repo: Arc<ReportRepository>,
+    pub history_repo: Arc<HistoryRepository>,
+    pub undo_repo: Arc<UndoRepository>,
     pub settings_repo: Arc<SettingsRepository>,
     pub ai_service: Arc<AiService>,
+    pub backup_service: Arc<BackupService>,
+    pub attachment_service: Arc<AttachmentService>,
+    pub focus_service: Arc<FocusService>,
 }
 
 impl AppState {
@@ -14,27 +28,86 @@ impl AppState {
         // 初始化数据库
         let db = Arc::new(Database::new(db_path)?);
 
-        // 初始化 Schema
-        db.init_schema()?;
+        // 执行 Schema 迁移
+        let report = db.migrate()?;
+        if !report.pending.is_empty() {
+            log::info!(
+                "Database migrated from version {} to {}",
+                report.current_version,
+                report.latest_version
+            );
+        }
 
         // 初始化 Repositories
         let todo_repo = Arc::new(TodoRepository::new(db.clone()));
+        let tag_repo = Arc::new(TagRepository::new(db.clone()));
+        let list_repo = Arc::new(ListRepository::new(db.clone()));
+        let workflow_repo = Arc::new(WorkflowRepository::new(db.clone()));
+        let reminder_repo = Arc::new(ReminderRepository::new(db.clone()));
+        let attachment_repo = Arc::new(AttachmentRepository::new(db.clone()));
+        let time_entry_repo = Arc::new(TimeEntryRepository::new(db.clone()));
+        let focus_repo = Arc::new(FocusRepository::new(db.clone()));
+        let report_repo = Arc::new(ReportRepository::new(db.clone()));
+        let history_repo = Arc::new(HistoryRepository::new(db.clone()));
+        let undo_repo = Arc::new(UndoRepository::new(db.clone()));
         let settings_repo = Arc::new(SettingsRepository::new(db.clone()));
 
         // 初始化 Function Executor
-        let function_executor = Arc::new(FunctionExecutor::new(todo_repo.clone()));
+        let function_executor = Arc::new(FunctionExecutor::new(
+            todo_repo.clone(),
+            list_repo.clone(),
+            history_repo.clone(),
+            time_entry_repo.clone(),
+            report_repo.clone(),
+            workflow_repo.clone(),
+        ));
+
+        // 专注计时只保存在内存中，阶段结束时写入 focus_repo
+        let focus_service = Arc::new(FocusService::new(
+            focus_repo,
+            todo_repo.clone(),
+            settings_repo.clone(),
+        ));
 
         // 初始化 AI Service
         let ai_service = Arc::new(AiService::new(
             settings_repo.clone(),
             todo_repo.clone(),
+            list_repo.clone(),
+            workflow_repo.clone(),
+            history_repo.clone(),
+            undo_repo.clone(),
+            focus_service.clone(),
             function_executor,
         ));
 
+        // 备份与附件分别保存在数据库所在目录的 backups、attachments 子目录中
+        let data_dir = Path::new(db_path).parent().unwrap_or_else(|| Path::new("."));
+        let attachments_dir = data_dir.join("attachments");
+        let backup_service = Arc::new(BackupService::new(
+            db.clone(),
+            data_dir.join("backups"),
+            attachments_dir.clone(),
+        ));
+        let attachment_service = Arc::new(AttachmentService::new(attachment_repo.clone(), attachments_dir));
+
         Ok(Self {
+            db,
             todo_repo,
+            tag_repo,
+            list_repo,
+            workflow_repo,
+            reminder_repo,
+            attachment_repo,
+            time_entry_repo,
+            report_repo,
+            history_repo,
+            undo_repo,
             settings_repo,
             ai_service,
+            backup_service,
+            attachment_service,
+            focus_service,
         })
     }
 }
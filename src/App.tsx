@@ -27,6 +27,24 @@ import type { AiChatRequest, ApiChatMessage, UiMessage } from "@/types/chat";
 type View = "list" | "summary" | "settings";
 type OverlayState = "chat" | null;
 
+// 后端 AppError 序列化为 { code, message }
+const describeError = (err: unknown): string => {
+  const { code, message } =
+    typeof err === "object" && err !== null
+      ? (err as { code?: string; message?: string })
+      : { code: undefined, message: String(err) };
+  switch (code) {
+    case "INVALID_TRANSITION":
+      return "当前状态不能直接改为该状态，已取消的任务请先恢复为待办。";
+    case "STATE_TRANSITION_NOT_ALLOWED":
+      return "工作流不允许这样移动任务。";
+    case "WIP_LIMIT_REACHED":
+      return "目标状态的任务数已达上限。";
+    default:
+      return message || "操作失败，请稍后重试。";
+  }
+};
+
 const App: React.FC = () => {
   // --- State ---
   const [todos, setTodos] = useState<Todo[]>([]);
@@ -61,6 +79,7 @@ const App: React.FC = () => {
     },
   ]);
   const [isTyping, setIsTyping] = useState(false);
+  const [notice, setNotice] = useState<string | null>(null);
   const chatEndRef = useRef<HTMLDivElement | null>(null);
 
   // --- Init ---
@@ -131,6 +150,8 @@ const App: React.FC = () => {
   void handleBatchAddTodos;
 
   const toggleComplete = async (todo: Todo) => {
+    // 已取消的任务不能直接完成，需先通过「恢复」改回待办
+    if (todo.status === "cancelled") return;
     try {
       const updated = await todoService.update(todo.id, {
         completed: !todo.completed,
@@ -141,6 +162,7 @@ const App: React.FC = () => {
       );
     } catch (err) {
       console.error("Error toggling todo", err);
+      setNotice(describeError(err));
     }
   };
 
@@ -154,6 +176,7 @@ const App: React.FC = () => {
       );
     } catch (err) {
       console.error("Error updating status", err);
+      setNotice(describeError(err));
     }
   };
 
@@ -323,6 +346,7 @@ const App: React.FC = () => {
           <button
             type="button"
             onClick={() => toggleComplete(todo)}
+            disabled={todo.status === "cancelled"}
             className={`relative flex-shrink-0 w-5 h-5 rounded-full border-[1.5px] flex items-center justify-center transition-all duration-300 ${
               todo.completed
                 ? "bg-black border-black text-white scale-100"
@@ -457,6 +481,20 @@ const App: React.FC = () => {
           {/* --- View: Todo List --- */}
           {view === "list" && (
             <div className="animate-in fade-in duration-500 max-w-3xl mx-auto">
+            {notice && (
+              <div className="mb-4 flex items-center gap-2 px-4 py-3 bg-red-50 border border-red-100 text-red-600 text-xs rounded-xl">
+                <AlertCircle size={14} className="flex-shrink-0" />
+                <span className="flex-1">{notice}</span>
+                <button
+                  type="button"
+                  onClick={() => setNotice(null)}
+                  className="p-1 rounded-md text-red-400 hover:text-red-600 hover:bg-red-100 transition-colors"
+                >
+                  <X size={12} />
+                </button>
+              </div>
+            )}
+
             {/* Input Area */}
             <form onSubmit={handleAddTodo} className="relative mb-8 group">
               <div className="absolute left-4 top-4 text-gray-400 transition-colors group-focus-within:text-black">
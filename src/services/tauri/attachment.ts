@@ -0,0 +1,22 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Attachment } from "@/types/todo";
+
+export const attachmentService = {
+  // path 为本地文件的绝对路径，文件会被复制到应用数据目录
+  async add(todoId: string, path: string): Promise<Attachment> {
+    return invoke("add_attachment", { todoId, path }) as Promise<Attachment>;
+  },
+
+  async getForTodo(todoId: string): Promise<Attachment[]> {
+    return invoke("get_todo_attachments", { todoId }) as Promise<Attachment[]>;
+  },
+
+  async remove(id: string): Promise<void> {
+    return invoke("remove_attachment", { id }) as Promise<void>;
+  },
+
+  // 用系统默认程序打开附件
+  async open(id: string): Promise<void> {
+    return invoke("open_attachment", { id }) as Promise<void>;
+  },
+};
@@ -0,0 +1,36 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface MigrationReport {
+  currentVersion: number;
+  latestVersion: number;
+  pending: { version: number; name: string }[];
+  dryRun: boolean;
+}
+
+export type BackupKind = "manual" | "daily" | "weekly" | "pre_restore";
+
+export interface BackupInfo {
+  fileName: string;
+  kind: BackupKind;
+  createdAt: string;
+  sizeBytes: number;
+}
+
+export const databaseService = {
+  async getSchemaStatus(): Promise<MigrationReport> {
+    return invoke("get_schema_status") as Promise<MigrationReport>;
+  },
+
+  async createBackup(): Promise<BackupInfo> {
+    return invoke("create_backup") as Promise<BackupInfo>;
+  },
+
+  async listBackups(): Promise<BackupInfo[]> {
+    return invoke("list_backups") as Promise<BackupInfo[]>;
+  },
+
+  // 恢复完成后后端会发出 database-restored 事件
+  async restoreBackup(fileName: string): Promise<MigrationReport> {
+    return invoke("restore_backup", { fileName }) as Promise<MigrationReport>;
+  },
+};
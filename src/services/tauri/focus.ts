@@ -0,0 +1,45 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { FocusConfig } from "@/types/settings";
+import type { FocusPhaseChange, FocusSession, FocusStatus } from "@/types/todo";
+
+export const focusService = {
+  // 开始新的一轮专注，已有进行中的专注会先被停止；不传 config 时使用设置中的时长
+  async start(todoId?: string, config?: FocusConfig): Promise<FocusStatus> {
+    return invoke("start_focus", { todoId, config }) as Promise<FocusStatus>;
+  },
+
+  async pause(): Promise<FocusStatus> {
+    return invoke("pause_focus") as Promise<FocusStatus>;
+  },
+
+  async resume(): Promise<FocusStatus> {
+    return invoke("resume_focus") as Promise<FocusStatus>;
+  },
+
+  // 处于专注阶段时返回保存的未完成记录
+  async stop(): Promise<FocusSession | null> {
+    return invoke("stop_focus") as Promise<FocusSession | null>;
+  },
+
+  async logInterruption(note?: string): Promise<FocusStatus> {
+    return invoke("log_focus_interruption", { note }) as Promise<FocusStatus>;
+  },
+
+  async getStatus(): Promise<FocusStatus | null> {
+    return invoke("get_focus_status") as Promise<FocusStatus | null>;
+  },
+
+  async getSessions(todoId?: string, limit?: number): Promise<FocusSession[]> {
+    return invoke("get_focus_sessions", { todoId, limit }) as Promise<FocusSession[]>;
+  },
+
+  // 专注进行中每秒触发一次，暂停时不触发
+  async onTick(handler: (status: FocusStatus) => void): Promise<UnlistenFn> {
+    return listen<FocusStatus>("focus-tick", (event) => handler(event.payload));
+  },
+
+  async onPhaseChange(handler: (change: FocusPhaseChange) => void): Promise<UnlistenFn> {
+    return listen<FocusPhaseChange>("focus-phase-changed", (event) => handler(event.payload));
+  },
+};
@@ -1,4 +1,14 @@
 export * from "./todo";
+export * from "./tag";
+export * from "./list";
+export * from "./workflow";
+export * from "./reminder";
+export * from "./attachment";
+export * from "./timeEntry";
+export * from "./focus";
+export * from "./report";
+export * from "./undo";
 export * from "./settings";
+export * from "./database";
 export * from "./ai";
 
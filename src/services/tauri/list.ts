@@ -0,0 +1,21 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { NewTodoList, TodoList, TodoListUpdate } from "@/types/todo";
+
+export const listService = {
+  async getAll(includeArchived = false): Promise<TodoList[]> {
+    return invoke("get_lists", { includeArchived }) as Promise<TodoList[]>;
+  },
+
+  async create(list: NewTodoList): Promise<TodoList> {
+    return invoke("create_list", { list }) as Promise<TodoList>;
+  },
+
+  async update(id: string, updates: TodoListUpdate): Promise<TodoList> {
+    return invoke("update_list", { id, updates }) as Promise<TodoList>;
+  },
+
+  // 列表中的任务移回收件箱，返回移动的任务数
+  async delete(id: string): Promise<number> {
+    return invoke("delete_list", { id }) as Promise<number>;
+  },
+};
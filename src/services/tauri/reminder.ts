@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { NewReminder, Reminder, ReminderNotification, Todo } from "@/types/todo";
+
+export const reminderService = {
+  async getForTodo(todoId: string): Promise<Reminder[]> {
+    return invoke("get_todo_reminders", { todoId }) as Promise<Reminder[]>;
+  },
+
+  async getUpcoming(limit?: number): Promise<Reminder[]> {
+    return invoke("get_upcoming_reminders", { limit }) as Promise<Reminder[]>;
+  },
+
+  async add(todoId: string, reminder: NewReminder): Promise<Reminder> {
+    return invoke("add_todo_reminder", { todoId, reminder }) as Promise<Reminder>;
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke("delete_reminder", { id }) as Promise<void>;
+  },
+
+  async snooze(id: string, minutes: number): Promise<Reminder> {
+    return invoke("snooze_reminder", { id, minutes }) as Promise<Reminder>;
+  },
+
+  // 提醒到期时触发，应用关闭期间错过的提醒会在启动后补发
+  async onReminder(handler: (notification: ReminderNotification) => void): Promise<UnlistenFn> {
+    return listen<ReminderNotification>("todo-reminder", (event) => handler(event.payload));
+  },
+
+  // 任务逾期时触发，同一截止日期只通知一次
+  async onOverdue(handler: (todo: Todo) => void): Promise<UnlistenFn> {
+    return listen<Todo>("todo-overdue", (event) => handler(event.payload));
+  },
+};
@@ -0,0 +1,14 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { EstimateReport, EstimateReportQuery, StatisticsQuery, StatisticsReport } from "@/types/todo";
+
+export const reportService = {
+  // 预估与实际工作量的对比，按标签、优先级和列表分组
+  async getEstimateReport(query?: EstimateReportQuery): Promise<EstimateReport> {
+    return invoke("get_estimate_report", { query }) as Promise<EstimateReport>;
+  },
+
+  // 新建与完成的趋势、连续完成天数、逾期与分组统计
+  async getStatisticsReport(query?: StatisticsQuery): Promise<StatisticsReport> {
+    return invoke("get_statistics_report", { query }) as Promise<StatisticsReport>;
+  },
+};
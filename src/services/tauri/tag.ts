@@ -0,0 +1,24 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Tag } from "@/types/todo";
+
+export const tagService = {
+  async getAll(): Promise<Tag[]> {
+    return invoke("get_tags") as Promise<Tag[]>;
+  },
+
+  async rename(id: string, name: string): Promise<Tag> {
+    return invoke("rename_tag", { id, name }) as Promise<Tag>;
+  },
+
+  async merge(sourceIds: string[], targetId: string): Promise<Tag> {
+    return invoke("merge_tags", { sourceIds, targetId }) as Promise<Tag>;
+  },
+
+  async setColor(id: string, color: string | null): Promise<Tag> {
+    return invoke("set_tag_color", { id, color }) as Promise<Tag>;
+  },
+
+  async delete(id: string): Promise<void> {
+    await invoke("delete_tag", { id });
+  },
+};
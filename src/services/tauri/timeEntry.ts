@@ -0,0 +1,37 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { NewTimeEntry, TimeEntry, TimeEntryUpdate, TimeReport, TimeReportQuery } from "@/types/todo";
+
+export const timeEntryService = {
+  // 同一时间只有一个计时，开始新的计时会停止正在进行的计时；待办任务会变为进行中
+  async startTimer(todoId: string, note?: string): Promise<TimeEntry> {
+    return invoke("start_timer", { todoId, note }) as Promise<TimeEntry>;
+  },
+
+  async stopTimer(): Promise<TimeEntry | null> {
+    return invoke("stop_timer") as Promise<TimeEntry | null>;
+  },
+
+  async getRunning(): Promise<TimeEntry | null> {
+    return invoke("get_running_timer") as Promise<TimeEntry | null>;
+  },
+
+  async getForTodo(todoId: string): Promise<TimeEntry[]> {
+    return invoke("get_todo_time_entries", { todoId }) as Promise<TimeEntry[]>;
+  },
+
+  async add(todoId: string, entry: NewTimeEntry): Promise<TimeEntry> {
+    return invoke("add_time_entry", { todoId, entry }) as Promise<TimeEntry>;
+  },
+
+  async update(id: string, entry: TimeEntryUpdate): Promise<TimeEntry> {
+    return invoke("update_time_entry", { id, entry }) as Promise<TimeEntry>;
+  },
+
+  async delete(id: string): Promise<void> {
+    return invoke("delete_time_entry", { id }) as Promise<void>;
+  },
+
+  async getReport(query?: TimeReportQuery): Promise<TimeReport> {
+    return invoke("get_time_report", { query }) as Promise<TimeReport>;
+  },
+};
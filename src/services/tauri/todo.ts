@@ -2,25 +2,58 @@ import { invoke } from "@tauri-apps/api/core";
 import type {
   Todo,
   TodoFilter,
+  TodoPage,
+  TodoPageRequest,
+  Placement,
+  TodoSearchResult,
+  TodoSelector,
+  TodoStatus,
+  TodoEvent,
   TodoStatistics,
+  TodoTree,
   TodoUpdate,
   NewTodo,
+  ParsedDate,
+  ChecklistItemUpdate,
 } from "@/types/todo";
 
 export const todoService = {
   async getAll(filter?: TodoFilter): Promise<Todo[]> {
+    const page = await todoService.getPage(filter);
+    return page.items;
+  },
+
+  // page 为空时返回全部任务
+  async getPage(filter?: TodoFilter, page?: TodoPageRequest): Promise<TodoPage> {
     return invoke("get_todos", {
       filter: filter ?? null,
-    }) as Promise<Todo[]>;
+      page: page ?? null,
+    }) as Promise<TodoPage>;
   },
 
-  async create(text: string, priority: string | null = null): Promise<Todo> {
-    // create_todo(text, priority, due_date, tags)
+  async search(query: string, limit?: number): Promise<TodoSearchResult[]> {
+    return invoke("search_todos", {
+      query,
+      limit: limit ?? null,
+    }) as Promise<TodoSearchResult[]>;
+  },
+
+  async create(
+    text: string,
+    priority: string | null = null,
+    parentId: string | null = null,
+    listId: string | null = null,
+    dueDate: string | null = null,
+  ): Promise<Todo> {
+    // create_todo(text, priority, due_date, tags, parent_id, list_id)
+    // dueDate 为空时从 text 中识别截止日期，例如"明天下午三点交报告"
     return invoke("create_todo", {
       text,
       priority,
-      due_date: null,
+      dueDate,
       tags: null,
+      parentId,
+      listId,
     }) as Promise<Todo>;
   },
 
@@ -42,8 +75,95 @@ export const todoService = {
     }) as Promise<Todo[]>;
   },
 
-  async getStatistics(): Promise<TodoStatistics> {
-    return invoke("get_todo_statistics") as Promise<TodoStatistics>;
+  async bulkUpdate(selector: TodoSelector, updates: TodoUpdate): Promise<Todo[]> {
+    return invoke("bulk_update_todos", { selector, updates }) as Promise<Todo[]>;
+  },
+
+  async bulkDelete(selector: TodoSelector): Promise<number> {
+    return invoke("bulk_delete_todos", { selector }) as Promise<number>;
+  },
+
+  async bulkSetStatus(selector: TodoSelector, status: TodoStatus): Promise<Todo[]> {
+    return invoke("bulk_set_status", { selector, status }) as Promise<Todo[]>;
+  },
+
+  // 把 ids 按顺序移动到 anchorId 之前或之后
+  async reorder(ids: string[], anchorId: string, placement: Placement): Promise<Todo[]> {
+    return invoke("reorder_todos", { ids, anchorId, placement }) as Promise<Todo[]>;
+  },
+
+  async getChildren(id: string): Promise<Todo[]> {
+    return invoke("get_todo_children", { id }) as Promise<Todo[]>;
+  },
+
+  async getAncestors(id: string): Promise<Todo[]> {
+    return invoke("get_todo_ancestors", { id }) as Promise<Todo[]>;
+  },
+
+  async getTree(id: string): Promise<TodoTree> {
+    return invoke("get_todo_tree", { id }) as Promise<TodoTree>;
+  },
+
+  // parentId 为 null 时移为顶层任务
+  async setParent(id: string, parentId: string | null): Promise<Todo> {
+    return invoke("set_todo_parent", { id, parentId }) as Promise<Todo>;
+  },
+
+  // id 在 dependsOnId 完成之前处于阻塞状态；形成循环依赖时返回 DEPENDENCY_CYCLE 错误
+  async addDependency(id: string, dependsOnId: string): Promise<Todo> {
+    return invoke("add_todo_dependency", { id, dependsOnId }) as Promise<Todo>;
+  },
+
+  async removeDependency(id: string, dependsOnId: string): Promise<Todo> {
+    return invoke("remove_todo_dependency", { id, dependsOnId }) as Promise<Todo>;
+  },
+
+  // 清单操作都返回修改后的任务
+  async addChecklistItem(todoId: string, text: string): Promise<Todo> {
+    return invoke("add_checklist_item", { todoId, text }) as Promise<Todo>;
+  },
+
+  async updateChecklistItem(id: string, updates: ChecklistItemUpdate): Promise<Todo> {
+    return invoke("update_checklist_item", { id, updates }) as Promise<Todo>;
+  },
+
+  async toggleChecklistItem(id: string): Promise<Todo> {
+    return invoke("toggle_checklist_item", { id }) as Promise<Todo>;
+  },
+
+  async deleteChecklistItem(id: string): Promise<Todo> {
+    return invoke("delete_checklist_item", { id }) as Promise<Todo>;
+  },
+
+  // 跳过重复任务的本次，截止日期顺延到下一次
+  async skipOccurrence(id: string): Promise<Todo> {
+    return invoke("skip_todo_occurrence", { id }) as Promise<Todo>;
+  },
+
+  async getTrash(): Promise<Todo[]> {
+    return invoke("get_trash") as Promise<Todo[]>;
+  },
+
+  async restore(id: string): Promise<Todo> {
+    return invoke("restore_todo", { id }) as Promise<Todo>;
+  },
+
+  async emptyTrash(): Promise<number> {
+    return invoke("empty_trash") as Promise<number>;
+  },
+
+  async getHistory(id: string): Promise<TodoEvent[]> {
+    return invoke("get_todo_history", { id }) as Promise<TodoEvent[]>;
+  },
+
+  // listId 为空字符串时只统计收件箱
+  async getStatistics(listId?: string): Promise<TodoStatistics> {
+    return invoke("get_todo_statistics", { listId: listId ?? null }) as Promise<TodoStatistics>;
+  },
+
+  // 预览日期表达式（"下周三"、"tomorrow 5pm"）的解析结果，无法识别时返回 null
+  async parseDateExpression(text: string): Promise<ParsedDate | null> {
+    return invoke("parse_date_expression", { text }) as Promise<ParsedDate | null>;
   },
 };
 
@@ -0,0 +1,16 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { UndoStack, UndoStep } from "@/types/todo";
+
+export const undoService = {
+  async getStack(): Promise<UndoStack> {
+    return invoke("get_undo_stack") as Promise<UndoStack>;
+  },
+
+  async undo(): Promise<UndoStep | null> {
+    return invoke("undo") as Promise<UndoStep | null>;
+  },
+
+  async redo(): Promise<UndoStep | null> {
+    return invoke("redo") as Promise<UndoStep | null>;
+  },
+};
@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Board, NewWorkflowState, WorkflowState, WorkflowStateUpdate } from "@/types/todo";
+
+export const workflowService = {
+  async getAll(): Promise<WorkflowState[]> {
+    return invoke("get_workflow_states") as Promise<WorkflowState[]>;
+  },
+
+  async create(workflowState: NewWorkflowState): Promise<WorkflowState> {
+    return invoke("create_workflow_state", { workflowState }) as Promise<WorkflowState>;
+  },
+
+  async update(id: string, updates: WorkflowStateUpdate): Promise<WorkflowState> {
+    return invoke("update_workflow_state", { id, updates }) as Promise<WorkflowState>;
+  },
+
+  // 状态中的任务移入同一类别的默认状态，返回移动的任务数
+  async delete(id: string): Promise<number> {
+    return invoke("delete_workflow_state", { id }) as Promise<number>;
+  },
+
+  async reorder(ids: string[]): Promise<WorkflowState[]> {
+    return invoke("reorder_workflow_states", { ids }) as Promise<WorkflowState[]>;
+  },
+
+  // 空列表表示不限制
+  async setTransitions(id: string, toIds: string[]): Promise<WorkflowState> {
+    return invoke("set_workflow_transitions", { id, toIds }) as Promise<WorkflowState>;
+  },
+
+  // 不传时包含全部未归档列表，空字符串表示收件箱
+  async getBoard(listId?: string): Promise<Board> {
+    return invoke("get_board", { listId }) as Promise<Board>;
+  },
+};
@@ -1,3 +1,5 @@
+import type { UndoStep } from "./todo";
+
 export type MessageRole = "system" | "user" | "assistant" | "function";
 
 // UI 消息
@@ -21,6 +23,9 @@ export interface ApiChatMessage {
 export interface AiChatRequest {
   message: string;
   history?: ApiChatMessage[];
+  conversation_id?: string;
+  // 用户正在查看的列表
+  list_id?: string;
 }
 
 export interface FunctionResult {
@@ -34,6 +39,7 @@ export interface AiChatResponse<TTodo = unknown> {
   message: string;
   functionResults?: FunctionResult[];
   updatedTodos?: TTodo[];
+  undoStep?: UndoStep | null;
   warnings?: string[];
 }
 
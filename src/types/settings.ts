@@ -1,3 +1,11 @@
+// 专注（番茄钟）的时长，每完成 longBreakEvery 个专注后长休息一次
+export interface FocusConfig {
+  workMinutes: number;
+  shortBreakMinutes: number;
+  longBreakMinutes: number;
+  longBreakEvery: number;
+}
+
 export interface Settings {
   apiKey: string;
   apiBaseUrl: string;
@@ -7,6 +15,10 @@ export interface Settings {
   systemPrompt: string;
   functionCallingMode?: string;
   enableTextFallback?: boolean;
+  trashRetentionDays?: number;
+  // IANA 时区名称，例如 "Asia/Shanghai"；空字符串表示使用系统时区
+  timezone?: string;
+  focus?: FocusConfig;
 }
 
 export const DEFAULT_SETTINGS: Settings = {
@@ -18,6 +30,14 @@ export const DEFAULT_SETTINGS: Settings = {
   systemPrompt: "",
   functionCallingMode: "auto",
   enableTextFallback: true,
+  trashRetentionDays: 30,
+  timezone: "",
+  focus: {
+    workMinutes: 25,
+    shortBreakMinutes: 5,
+    longBreakMinutes: 15,
+    longBreakEvery: 4,
+  },
 };
 
 export const AI_PROVIDERS = {
@@ -1,26 +1,173 @@
+import type { FocusConfig } from "./settings";
+
 export type TodoStatus = "pending" | "in_progress" | "completed" | "cancelled";
 
 export type Priority = "low" | "medium" | "high";
 
+export type TagMatch = "all" | "any";
+
+export interface Tag {
+  id: string;
+  name: string;
+  color?: string | null;
+  todoCount: number;
+  createdAt: string;
+}
+
+export interface TodoList {
+  id: string;
+  name: string;
+  color?: string | null;
+  icon?: string | null;
+  archived: boolean;
+  defaultPriority: Priority;
+  todoCount: number;
+  completedCount: number;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface NewTodoList {
+  name: string;
+  color?: string;
+  icon?: string;
+  defaultPriority?: Priority;
+}
+
+// color、icon 传入空字符串表示清除
+export interface TodoListUpdate {
+  name?: string;
+  color?: string;
+  icon?: string;
+  archived?: boolean;
+  defaultPriority?: Priority;
+}
+
+// open、active、done 分别对应 pending、in_progress、completed
+export type StateCategory = "open" | "active" | "done";
+
+export interface WorkflowState {
+  id: string;
+  name: string;
+  color?: string | null;
+  category: StateCategory;
+  position: number;
+  wipLimit?: number | null;
+  todoCount: number;
+  // 允许变为的状态 ID，为空时不限制
+  transitions: string[];
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface NewWorkflowState {
+  name: string;
+  color?: string;
+  category: StateCategory;
+  wipLimit?: number;
+}
+
+// color 传入空字符串、wipLimit 传入 0 表示清除；类别不能修改
+export interface WorkflowStateUpdate {
+  name?: string;
+  color?: string;
+  wipLimit?: number;
+}
+
+export interface BoardColumn {
+  state: WorkflowState;
+  todos: Todo[];
+  overLimit: boolean;
+}
+
+export interface Board {
+  columns: BoardColumn[];
+  // 不属于任何状态的任务（已取消，或所在状态已不存在）
+  unassigned: Todo[];
+}
+
 export interface Todo {
   id: string;
   text: string;
   completed: boolean;
   status: TodoStatus;
   priority: Priority;
+  // 全天为 YYYY-MM-DD，具体时刻为带时区偏移的 RFC 3339
   dueDate?: string | null;
   tags: string[];
   createdAt: string;
   updatedAt: string;
+  deletedAt?: string | null;
+  position: number;
+  parentId?: string | null;
+  autoComplete: boolean;
+  childCount: number;
+  completedChildCount: number;
+  dependsOn: string[];
+  // 尚未完成的依赖任务 ID
+  blockedBy: string[];
+  blocked: boolean;
+  // null 表示在收件箱中
+  listId?: string | null;
+  // RRULE，例如 "FREQ=WEEKLY;BYDAY=MO"
+  recurrence?: string | null;
+  seriesId?: string | null;
+  // 在重复序列中的序号，从 1 开始
+  occurrence: number;
+  // Markdown
+  notes?: string | null;
+  checklist: ChecklistItem[];
+  estimateMinutes?: number | null;
+  storyPoints?: number | null;
+  // 第一次变为进行中的时间，重新打开后保留
+  startedAt?: string | null;
+  // 只在已完成时有值
+  completedAt?: string | null;
+  // 只在已取消时有值
+  cancelledAt?: string | null;
+  // 工作流状态（看板列），已取消的任务为 null
+  stateId?: string | null;
+}
+
+export interface ChecklistItem {
+  id: string;
+  text: string;
+  done: boolean;
+}
+
+export interface ChecklistItemUpdate {
+  text?: string;
+  done?: boolean;
+}
+
+export interface TodoTree extends Todo {
+  children: TodoTree[];
 }
 
 export interface TodoUpdate {
   text?: string;
+  // status 的简写：true 表示完成，false 把已完成的任务重新打开为待办；同时提供时必须一致
   completed?: boolean;
+  // 已取消的任务只能恢复为待办，已完成的任务不能直接取消，否则返回 INVALID_TRANSITION
   status?: TodoStatus;
   priority?: Priority;
+  // ISO 日期或自然语言，空字符串表示清除截止日期
   dueDate?: string;
   tags?: string[];
+  autoComplete?: boolean;
+  // 空字符串表示移回收件箱
+  listId?: string;
+  // 空字符串表示取消重复
+  recurrence?: string;
+  // 空字符串表示清除备注
+  notes?: string;
+  // 0 表示清除预估
+  estimateMinutes?: number;
+  storyPoints?: number;
+  // 移动到该工作流状态，status 随状态的类别变化；受状态的允许移动和 WIP 限制约束
+  stateId?: string;
+  // 追加到清单末尾的检查项
+  addChecklist?: string[];
 }
 
 export interface NewTodo {
@@ -28,6 +175,19 @@ export interface NewTodo {
   priority?: Priority;
   dueDate?: string;
   tags?: string[];
+  parentId?: string;
+  autoComplete?: boolean;
+  children?: NewTodo[];
+  // 子任务按顺序完成，每个依赖前一个
+  sequential?: boolean;
+  dependsOn?: string[];
+  listId?: string;
+  recurrence?: string;
+  notes?: string;
+  // 清单项文本
+  checklist?: string[];
+  estimateMinutes?: number;
+  storyPoints?: number;
 }
 
 export interface TodoFilter {
@@ -36,6 +196,46 @@ export interface TodoFilter {
   priority?: Priority;
   search?: string;
   tag?: string;
+  tags?: string[];
+  tagMatch?: TagMatch;
+  // 只返回现在就能开始的任务
+  actionable?: boolean;
+  // 空字符串表示收件箱；不填时不包含已归档列表中的任务
+  listId?: string;
+  stateId?: string;
+  // ISO 日期/时间或自然语言，按设置中的时区解释；日期包含当天
+  dueBefore?: string;
+  dueAfter?: string;
+  // 截止时间已过且未完成、未取消
+  overdue?: boolean;
+}
+
+export type SortKey = "default" | "priority" | "due_date" | "created_at" | "updated_at" | "text" | "manual";
+
+export type Placement = "before" | "after";
+
+export type SortDirection = "asc" | "desc";
+
+export interface TodoPageRequest {
+  limit?: number;
+  cursor?: string | null;
+  sort?: SortKey;
+  direction?: SortDirection;
+}
+
+export interface TodoPage {
+  items: Todo[];
+  total: number;
+  nextCursor: string | null;
+}
+
+// 批量操作的目标：显式 ID 列表或过滤条件
+export type TodoSelector = { ids: string[] } | { filter: TodoFilter };
+
+export interface TodoSearchResult {
+  todo: Todo;
+  rank: number;
+  snippet: string;
 }
 
 export interface TodoStatistics {
@@ -44,5 +244,281 @@ export interface TodoStatistics {
   pending: number;
   inProgress: number;
   cancelled: number;
+  // 坚持到计划时长的专注次数
+  focusSessions: number;
+  focusSessionsToday: number;
+  // 全部专注（包括提前停止的）实际专注的秒数
+  focusSeconds: number;
+}
+
+export interface ParsedDate {
+  // 全天为 YYYY-MM-DD，带时间为带时区偏移的 RFC 3339
+  dueDate: string;
+  allDay: boolean;
+  matched: string;
+  // 去掉日期后剩余的文本
+  remainder: string;
+}
+
+export interface Reminder {
+  id: string;
+  todoId: string;
+  // 相对截止时间提前的分钟数，为 null 时是绝对时间提醒
+  offsetMinutes: number | null;
+  // UTC 时间
+  remindAt: string | null;
+  snoozedUntil: string | null;
+  firedAt: string | null;
+  createdAt: string;
+}
+
+// offsetMinutes 与 remindAt 必须且只能提供一个
+export type NewReminder = { offsetMinutes: number } | { remindAt: string };
+
+// todo-reminder 事件的内容
+export interface ReminderNotification {
+  reminder: Reminder;
+  todo: Todo;
+}
+
+// 任务的附件，文件按内容哈希保存在应用数据目录中
+export interface Attachment {
+  id: string;
+  todoId: string;
+  fileName: string;
+  mimeType: string;
+  sizeBytes: number;
+  // 文件内容的 SHA-256
+  hash: string;
+  createdAt: string;
 }
 
+// 计时记录，endedAt 为 null 时计时仍在进行
+export interface TimeEntry {
+  id: string;
+  todoId: string;
+  todoText: string;
+  // UTC 时间
+  startedAt: string;
+  endedAt: string | null;
+  // 进行中的计时算到当前时间
+  durationSeconds: number;
+  note: string | null;
+  createdAt: string;
+  updatedAt: string;
+}
+
+// 时间为 RFC 3339 或带具体时间的自然语言
+export interface NewTimeEntry {
+  startedAt: string;
+  endedAt: string;
+  note?: string;
+}
+
+// note 传入空字符串表示清除
+export interface TimeEntryUpdate {
+  startedAt?: string;
+  endedAt?: string;
+  note?: string;
+}
+
+export type TimePeriod = "today" | "yesterday" | "this_week" | "last_week" | "this_month" | "last_month";
+
+export type TimeGroup = "todo" | "tag";
+
+// period 优先于 from/to；from/to 为日期/时间或自然语言，日期包含当天
+export interface TimeReportQuery {
+  period?: TimePeriod;
+  from?: string;
+  to?: string;
+  todoId?: string;
+  tag?: string;
+  groupBy?: TimeGroup;
+}
+
+// 按任务分组时 key 为任务 ID、label 为任务内容，按标签分组时都是标签名称
+export interface TimeTotal {
+  key: string;
+  label: string;
+  seconds: number;
+  entryCount: number;
+}
+
+export interface TimeReport {
+  from: string | null;
+  to: string | null;
+  totalSeconds: number;
+  groups: TimeTotal[];
+}
+
+// period 优先于 from/to；按完成时间统计，都不提供时统计全部已完成的任务
+export interface EstimateReportQuery {
+  period?: TimePeriod;
+  from?: string;
+  to?: string;
+}
+
+// logged：计时记录的合计；elapsed：没有计时时从开始进行到完成经过的时间
+export type ActualSource = "logged" | "elapsed";
+
+export interface EstimateItem {
+  todoId: string;
+  text: string;
+  priority: Priority;
+  listId: string | null;
+  tags: string[];
+  estimateMinutes: number | null;
+  storyPoints: number | null;
+  actualMinutes: number;
+  source: ActualSource;
+  completedAt: string;
+}
+
+// 分钟预估与故事点分别统计
+export interface EstimateAccuracy {
+  key: string;
+  label: string;
+  estimatedCount: number;
+  estimatedMinutes: number;
+  actualMinutes: number;
+  // 实际 / 预估，大于 1 表示低估
+  ratio: number | null;
+  // 平均绝对百分比误差（%）
+  meanAbsErrorPercent: number | null;
+  pointedCount: number;
+  storyPoints: number;
+  minutesPerPoint: number | null;
+}
+
+export interface EstimateReport {
+  from: string | null;
+  to: string | null;
+  overall: EstimateAccuracy;
+  byTag: EstimateAccuracy[];
+  byPriority: EstimateAccuracy[];
+  // 收件箱的 key 为空字符串
+  byList: EstimateAccuracy[];
+  items: EstimateItem[];
+}
+
+// 趋势的时间粒度，周从周一开始
+export type Granularity = "day" | "week" | "month";
+
+// period 优先于 from/to，都不提供时统计最近 30 天；listId 为空字符串表示收件箱
+export interface StatisticsQuery {
+  period?: TimePeriod;
+  from?: string;
+  to?: string;
+  granularity?: Granularity;
+  listId?: string;
+}
+
+export interface TrendPoint {
+  // 时间段第一天，YYYY-MM-DD
+  start: string;
+  created: number;
+  completed: number;
+}
+
+// created、completed 限于统计范围内，open、overdue 为当前数量
+export interface StatisticsBreakdown {
+  key: string;
+  label: string;
+  created: number;
+  completed: number;
+  open: number;
+  overdue: number;
+}
+
+export interface StatisticsReport {
+  from: string;
+  to: string;
+  granularity: Granularity;
+  created: number;
+  completed: number;
+  // 完成时已过截止时间
+  completedLate: number;
+  overdue: number;
+  // 连续有任务完成的天数，今天还没有完成时从昨天算起
+  currentStreak: number;
+  longestStreak: number;
+  averageCycleHours: number | null;
+  trend: TrendPoint[];
+  byPriority: StatisticsBreakdown[];
+  byTag: StatisticsBreakdown[];
+  // 收件箱的 key 为空字符串
+  byList: StatisticsBreakdown[];
+}
+
+export type FocusPhase = "work" | "short_break" | "long_break";
+
+export interface FocusInterruption {
+  occurredAt: string;
+  note: string | null;
+}
+
+// 当前专注的状态，也是 focus-tick 事件的内容
+export interface FocusStatus {
+  todoId: string | null;
+  todoText: string | null;
+  phase: FocusPhase;
+  completedCycles: number;
+  phaseSeconds: number;
+  remainingSeconds: number;
+  paused: boolean;
+  interruptions: number;
+  config: FocusConfig;
+}
+
+// 已结束的专注阶段
+export interface FocusSession {
+  id: string;
+  todoId: string | null;
+  todoText: string | null;
+  startedAt: string;
+  endedAt: string;
+  plannedSeconds: number;
+  focusedSeconds: number;
+  // 是否坚持到了计划时长
+  completed: boolean;
+  interruptions: FocusInterruption[];
+}
+
+// focus-phase-changed 事件的内容
+export interface FocusPhaseChange {
+  finished: FocusPhase;
+  status: FocusStatus;
+  session: FocusSession | null;
+}
+
+export type Actor =
+  | { kind: "user" }
+  | { kind: "ai"; conversationId?: string | null; toolCallId?: string | null; turnId?: string | null }
+  | { kind: "import" }
+  | { kind: "automation"; source: string };
+
+export type TodoAction = "created" | "updated" | "deleted" | "restored" | "purged";
+
+export interface TodoEvent {
+  id: number;
+  todoId: string;
+  action: TodoAction;
+  actor: Actor;
+  changes: Record<string, { before: unknown; after: unknown }>;
+  createdAt: string;
+  todoText: string;
+}
+
+export interface UndoStep {
+  id: string;
+  label: string;
+  actor: Actor;
+  todoCount: number;
+  createdAt: string;
+  undoneAt: string | null;
+}
+
+export interface UndoStack {
+  undo: UndoStep[];
+  redo: UndoStep[];
+}